@@ -208,6 +208,11 @@ impl Root {
     pub fn stmts(self, tree: &SyntaxTree) -> impl Iterator<Item = Stmt> + '_ {
         nodes(self, tree)
     }
+
+    /// the definitions and global directives of the file, without descending into `#if` blocks
+    pub fn decls(self, tree: &SyntaxTree) -> impl Iterator<Item = Decl> + '_ {
+        nodes(self, tree)
+    }
 }
 
 def_ast_node!(Lambda);
@@ -217,6 +222,10 @@ impl Lambda {
         node(self, tree)
     }
 
+    pub fn capture_list(self, tree: &SyntaxTree) -> Option<CaptureList> {
+        node(self, tree)
+    }
+
     pub fn return_ty(self, tree: &SyntaxTree) -> Option<Ty> {
         node(self, tree)
     }
@@ -228,6 +237,10 @@ impl Lambda {
     pub fn r#extern(self, tree: &SyntaxTree) -> Option<Extern> {
         token(self, tree)
     }
+
+    pub fn export(self, tree: &SyntaxTree) -> Option<Export> {
+        token(self, tree)
+    }
 }
 
 def_multi_node! {
@@ -238,11 +251,69 @@ def_multi_node! {
     Break -> BreakStmt
     Continue -> ContinueStmt
     Defer -> DeferStmt
+    Destructure -> Destructure
     ;
     Define -> Define
     ;
 }
 
+def_multi_node! {
+    Decl:
+    StaticIf -> StaticIf
+    DirectiveDecl -> DirectiveDecl
+    ;
+    Define -> Define
+    ;
+}
+
+def_ast_node!(DirectiveDecl);
+
+impl DirectiveDecl {
+    pub fn name(self, tree: &SyntaxTree) -> Option<Ident> {
+        token(self, tree)
+    }
+
+    pub fn args(self, tree: &SyntaxTree) -> impl Iterator<Item = Expr> + '_ {
+        nodes(self, tree)
+    }
+}
+
+def_ast_node!(StaticIf);
+
+impl StaticIf {
+    pub fn condition(self, tree: &SyntaxTree) -> Option<Expr> {
+        node(self, tree)
+    }
+
+    pub fn body(self, tree: &SyntaxTree) -> Option<DeclBlock> {
+        node(self, tree)
+    }
+
+    pub fn else_branch(self, tree: &SyntaxTree) -> Option<StaticElse> {
+        node(self, tree)
+    }
+}
+
+def_ast_node!(StaticElse);
+
+impl StaticElse {
+    pub fn body(self, tree: &SyntaxTree) -> Option<DeclBlock> {
+        node(self, tree)
+    }
+
+    pub fn static_if(self, tree: &SyntaxTree) -> Option<StaticIf> {
+        node(self, tree)
+    }
+}
+
+def_ast_node!(DeclBlock);
+
+impl DeclBlock {
+    pub fn decls(self, tree: &SyntaxTree) -> impl Iterator<Item = Decl> + '_ {
+        nodes(self, tree)
+    }
+}
+
 def_multi_node! {
     Define:
     Binding -> Binding
@@ -295,6 +366,19 @@ impl VarDef {
     }
 }
 
+def_ast_node!(Destructure);
+
+impl Destructure {
+    /// each name is a `VarDef` without a type or value
+    pub fn names(self, tree: &SyntaxTree) -> impl Iterator<Item = VarDef> + '_ {
+        nodes(self, tree)
+    }
+
+    pub fn value(self, tree: &SyntaxTree) -> Option<Expr> {
+        node(self, tree)
+    }
+}
+
 def_ast_node!(Assign);
 
 impl Assign {
@@ -313,6 +397,10 @@ impl ParamList {
     pub fn params(self, tree: &SyntaxTree) -> impl Iterator<Item = Param> + '_ {
         nodes(self, tree)
     }
+
+    pub fn var_args(self, tree: &SyntaxTree) -> impl Iterator<Item = VarArgs> + '_ {
+        nodes(self, tree)
+    }
 }
 
 def_ast_node!(Param);
@@ -325,6 +413,21 @@ impl Param {
     pub fn ty(self, tree: &SyntaxTree) -> Option<Ty> {
         node(self, tree)
     }
+
+    pub fn default_value(self, tree: &SyntaxTree) -> Option<Expr> {
+        node(self, tree)
+    }
+}
+
+def_ast_node!(VarArgs);
+
+def_ast_node!(CaptureList);
+
+impl CaptureList {
+    /// the locals which are captured by value
+    pub fn names(self, tree: &SyntaxTree) -> impl Iterator<Item = Ident> + '_ {
+        tokens(self, tree)
+    }
 }
 
 def_ast_node!(Ty);
@@ -385,29 +488,38 @@ def_multi_node! {
     Cast -> CastExpr
     Ref -> RefExpr
     Deref -> DerefExpr
+    Unwrap -> UnwrapExpr
     Binary -> BinaryExpr
     Unary -> UnaryExpr
     IntLiteral -> IntLiteral
     FloatLiteral -> FloatLiteral
     BoolLiteral -> BoolLiteral
+    NullLiteral -> NullLiteral
+    UninitLiteral -> UninitLiteral
     CharLiteral -> CharLiteral
     StringLiteral -> StringLiteral
     StructDecl -> StructDecl
+    UnionDecl -> UnionDecl
+    InterfaceDecl -> InterfaceDecl
     StructLiteral -> StructLiteral
     ArrayDecl -> ArrayDecl
     ArrayLiteral -> ArrayLiteral
     IndexExpr -> IndexExpr
+    SliceExpr -> SliceExpr
     VarRef -> VarRef    // `foo` in `foo.bar`
     Path -> Path        // `foo.bar`
     Call -> Call
     Paren -> ParenExpr
+    Tuple -> TupleExpr
     Block -> Block
     If -> IfExpr
     While -> WhileExpr
     Distinct -> Distinct
+    OptionalDecl -> OptionalDecl
     Lambda -> Lambda
     Import -> ImportExpr
     Comptime -> ComptimeExpr
+    Directive -> Directive
     ;
     ;
 }
@@ -460,6 +572,22 @@ impl DerefExpr {
     }
 }
 
+def_ast_node!(UnwrapExpr);
+
+impl UnwrapExpr {
+    pub fn optional(self, tree: &SyntaxTree) -> Option<Expr> {
+        node(self, tree)
+    }
+}
+
+def_ast_node!(OptionalDecl);
+
+impl OptionalDecl {
+    pub fn ty(self, tree: &SyntaxTree) -> Option<Ty> {
+        node(self, tree)
+    }
+}
+
 def_ast_node!(Distinct);
 
 impl Distinct {
@@ -476,6 +604,14 @@ impl ParenExpr {
     }
 }
 
+def_ast_node!(TupleExpr);
+
+impl TupleExpr {
+    pub fn items(self, tree: &SyntaxTree) -> impl Iterator<Item = Expr> + '_ {
+        nodes(self, tree)
+    }
+}
+
 def_ast_node!(Block);
 
 impl Block {
@@ -495,11 +631,23 @@ impl Block {
 def_ast_node!(IfExpr);
 
 impl IfExpr {
+    /// `x := foo` in `if x := foo { ... }`
+    pub fn binding(self, tree: &SyntaxTree) -> Option<VarDef> {
+        node(self, tree)
+    }
+
+    /// this is `None` when there's a binding instead of a condition
     pub fn condition(self, tree: &SyntaxTree) -> Option<Expr> {
+        if self.binding(tree).is_some() {
+            return None;
+        }
         node(self, tree)
     }
 
     pub fn body(self, tree: &SyntaxTree) -> Option<Expr> {
+        if self.binding(tree).is_some() {
+            return node(self, tree);
+        }
         nodes(self, tree).nth(1)
     }
 
@@ -559,6 +707,27 @@ impl Condition {
 def_ast_node!(StructDecl);
 
 impl StructDecl {
+    /// layout directives like `#packed` or `#align(16)`
+    pub fn directives(self, tree: &SyntaxTree) -> impl Iterator<Item = Directive> + '_ {
+        nodes(self, tree)
+    }
+
+    pub fn members(self, tree: &SyntaxTree) -> impl Iterator<Item = MemberDecl> + '_ {
+        nodes(self, tree)
+    }
+}
+
+def_ast_node!(UnionDecl);
+
+impl UnionDecl {
+    pub fn members(self, tree: &SyntaxTree) -> impl Iterator<Item = MemberDecl> + '_ {
+        nodes(self, tree)
+    }
+}
+
+def_ast_node!(InterfaceDecl);
+
+impl InterfaceDecl {
     pub fn members(self, tree: &SyntaxTree) -> impl Iterator<Item = MemberDecl> + '_ {
         nodes(self, tree)
     }
@@ -567,6 +736,14 @@ impl StructDecl {
 def_ast_node!(MemberDecl);
 
 impl MemberDecl {
+    pub fn directives(self, tree: &SyntaxTree) -> impl Iterator<Item = Directive> + '_ {
+        // the default value might be a directive too, like `os: str = #os`
+        let ty_start = self.ty(tree).map(|ty| ty.range(tree).start());
+        nodes(self, tree).filter(move |directive: &Directive| {
+            ty_start.is_none_or(|start| directive.range(tree).end() <= start)
+        })
+    }
+
     pub fn name(self, tree: &SyntaxTree) -> Option<Ident> {
         token(self, tree)
     }
@@ -574,6 +751,12 @@ impl MemberDecl {
     pub fn ty(self, tree: &SyntaxTree) -> Option<Ty> {
         node(self, tree)
     }
+
+    /// directives like `#align(8)` are expressions too, but they come before the type
+    pub fn default_value(self, tree: &SyntaxTree) -> Option<Expr> {
+        let ty_end = self.ty(tree)?.range(tree).end();
+        nodes(self, tree).find(|expr: &Expr| expr.range(tree).start() >= ty_end)
+    }
 }
 
 def_ast_node!(StructLiteral);
@@ -660,6 +843,38 @@ impl Index {
     }
 }
 
+def_ast_node!(SliceExpr);
+
+impl SliceExpr {
+    pub fn array(self, tree: &SyntaxTree) -> Option<Source> {
+        node(self, tree)
+    }
+
+    pub fn start(self, tree: &SyntaxTree) -> Option<SliceStart> {
+        node(self, tree)
+    }
+
+    pub fn end(self, tree: &SyntaxTree) -> Option<SliceEnd> {
+        node(self, tree)
+    }
+}
+
+def_ast_node!(SliceStart);
+
+impl SliceStart {
+    pub fn value(self, tree: &SyntaxTree) -> Option<Expr> {
+        node(self, tree)
+    }
+}
+
+def_ast_node!(SliceEnd);
+
+impl SliceEnd {
+    pub fn value(self, tree: &SyntaxTree) -> Option<Expr> {
+        node(self, tree)
+    }
+}
+
 def_ast_node!(Source);
 
 impl Source {
@@ -711,6 +926,11 @@ impl ArgList {
 def_ast_node!(Arg);
 
 impl Arg {
+    /// only named arguments like `mode = "w"` have a name
+    pub fn name(self, tree: &SyntaxTree) -> Option<Ident> {
+        token(self, tree)
+    }
+
     pub fn value(self, tree: &SyntaxTree) -> Option<Expr> {
         node(self, tree)
     }
@@ -736,6 +956,23 @@ impl ComptimeExpr {
     }
 }
 
+def_ast_node!(Directive);
+
+impl Directive {
+    pub fn name(self, tree: &SyntaxTree) -> Option<Ident> {
+        token(self, tree)
+    }
+
+    pub fn arg_list(self, tree: &SyntaxTree) -> Option<ArgList> {
+        node(self, tree)
+    }
+
+    /// the file name given to `#embed`
+    pub fn file(self, tree: &SyntaxTree) -> Option<StringLiteral> {
+        node(self, tree)
+    }
+}
+
 def_ast_node!(IntLiteral);
 
 impl IntLiteral {
@@ -767,6 +1004,10 @@ impl BoolLiteral {
     }
 }
 
+def_ast_node!(NullLiteral);
+
+def_ast_node!(UninitLiteral);
+
 def_ast_node!(StringLiteral);
 
 impl StringLiteral {
@@ -839,6 +1080,7 @@ def_multi_token! {
 
 def_ast_token!(Mut);
 def_ast_token!(Extern);
+def_ast_token!(Export);
 def_ast_token!(Import);
 def_ast_token!(Mod);
 def_ast_token!(Colon);
@@ -1352,6 +1594,57 @@ mod tests {
         assert_eq!(index_ref.name(&tree).unwrap().text(&tree), "idx");
     }
 
+    #[test]
+    fn get_bounds_of_slice() {
+        let (tree, root) = parse("text[1..len]");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let slice_expr = match expr {
+            Some(Expr::SliceExpr(slice_expr)) => slice_expr,
+            _ => unreachable!(),
+        };
+
+        let array_ref = match slice_expr.array(&tree).unwrap().value(&tree) {
+            Some(Expr::VarRef(array_ref)) => array_ref,
+            _ => unreachable!(),
+        };
+        assert_eq!(array_ref.name(&tree).unwrap().text(&tree), "text");
+
+        let start = match slice_expr.start(&tree).unwrap().value(&tree) {
+            Some(Expr::IntLiteral(start)) => start,
+            _ => unreachable!(),
+        };
+        assert_eq!(start.value(&tree).unwrap().text(&tree), "1");
+
+        let end = match slice_expr.end(&tree).unwrap().value(&tree) {
+            Some(Expr::VarRef(end)) => end,
+            _ => unreachable!(),
+        };
+        assert_eq!(end.name(&tree).unwrap().text(&tree), "len");
+    }
+
+    #[test]
+    fn get_missing_bounds_of_slice() {
+        let (tree, root) = parse("text[..]");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let slice_expr = match expr {
+            Some(Expr::SliceExpr(slice_expr)) => slice_expr,
+            _ => unreachable!(),
+        };
+
+        assert!(slice_expr.start(&tree).is_none());
+        assert!(slice_expr.end(&tree).is_none());
+    }
+
     #[test]
     fn get_name_of_var_ref() {
         let (tree, root) = parse("idx;");
@@ -1492,6 +1785,33 @@ mod tests {
         assert!(args.next().is_none());
     }
 
+    #[test]
+    fn get_named_args_of_call() {
+        let (tree, root) = parse("open(path, mode = \"w\");");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let call = match expr {
+            Some(Expr::Call(call)) => call,
+            _ => unreachable!(),
+        };
+
+        let mut args = call.arg_list(&tree).unwrap().args(&tree);
+
+        let arg = args.next().unwrap();
+        assert!(arg.name(&tree).is_none());
+        assert_eq!(arg.value(&tree).unwrap().text(&tree), "path");
+
+        let arg = args.next().unwrap();
+        assert_eq!(arg.name(&tree).unwrap().text(&tree), "mode");
+        assert_eq!(arg.value(&tree).unwrap().text(&tree), "\"w\"");
+
+        assert!(args.next().is_none());
+    }
+
     #[test]
     fn get_value_of_decimal_literal() {
         let (tree, root) = parse("92;");
@@ -1692,6 +2012,47 @@ mod tests {
         assert!(expr.is_none());
     }
 
+    #[test]
+    fn get_tuple_items() {
+        let (tree, root) = parse("(1, true, foo);");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let tuple = match expr {
+            Some(Expr::Tuple(tuple)) => tuple,
+            _ => unreachable!(),
+        };
+
+        let mut items = tuple.items(&tree);
+
+        assert!(matches!(items.next(), Some(Expr::IntLiteral(_))));
+        assert!(matches!(items.next(), Some(Expr::BoolLiteral(_))));
+        assert!(matches!(items.next(), Some(Expr::VarRef(_))));
+        assert!(items.next().is_none());
+    }
+
+    #[test]
+    fn get_names_and_value_of_destructure() {
+        let (tree, root) = parse("x, ok := parse(s);");
+        let statement = root.stmts(&tree).next().unwrap();
+
+        let destructure = match statement {
+            Stmt::Destructure(destructure) => destructure,
+            _ => unreachable!(),
+        };
+
+        let names: Vec<_> = destructure
+            .names(&tree)
+            .map(|name| name.name(&tree).unwrap().text(&tree))
+            .collect();
+
+        assert_eq!(names, ["x", "ok"]);
+        assert!(matches!(destructure.value(&tree), Some(Expr::Call(_))));
+    }
+
     #[test]
     fn get_block_stmts() {
         let (tree, root) = parse("{ a := 10; b = a * {a - 1}; b + 5 };");
@@ -1873,6 +2234,28 @@ mod tests {
         assert!(matches!(condition, Some(Expr::BoolLiteral(_))));
     }
 
+    #[test]
+    fn get_if_binding() {
+        let (tree, root) = parse("if x := foo() { x }");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let if_expr = match expr {
+            Some(Expr::If(if_expr)) => if_expr,
+            _ => unreachable!(),
+        };
+
+        let binding = if_expr.binding(&tree).unwrap();
+
+        assert_eq!(binding.name(&tree).unwrap().text(&tree), "x");
+        assert!(matches!(binding.value(&tree), Some(Expr::Call(_))));
+        assert!(if_expr.condition(&tree).is_none());
+        assert!(matches!(if_expr.body(&tree), Some(Expr::Block(_))));
+    }
+
     #[test]
     fn get_if_body() {
         let (tree, root) = parse("if false { foo(); }");
@@ -2086,6 +2469,37 @@ mod tests {
         assert!(params.next().is_none());
     }
 
+    #[test]
+    fn get_lambda_param_defaults() {
+        let (tree, root) = parse("(path: str, mode := \"r\", retries: i32 = 3) {};");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let lambda = match expr {
+            Some(Expr::Lambda(lambda)) => lambda,
+            _ => unreachable!(),
+        };
+
+        let mut params = lambda.param_list(&tree).unwrap().params(&tree);
+
+        let param = params.next().unwrap();
+        assert_eq!(param.ty(&tree).unwrap().text(&tree), "str");
+        assert!(param.default_value(&tree).is_none());
+
+        let param = params.next().unwrap();
+        assert!(param.ty(&tree).is_none());
+        assert_eq!(param.default_value(&tree).unwrap().text(&tree), "\"r\"");
+
+        let param = params.next().unwrap();
+        assert_eq!(param.ty(&tree).unwrap().text(&tree), "i32");
+        assert_eq!(param.default_value(&tree).unwrap().text(&tree), "3");
+
+        assert!(params.next().is_none());
+    }
+
     #[test]
     fn get_lambda_return_ty() {
         let (tree, root) = parse("() -> i32 {};");
@@ -2148,6 +2562,155 @@ mod tests {
         assert!(lambda.r#extern(&tree).is_some());
     }
 
+    #[test]
+    fn get_lambda_export() {
+        let (tree, root) = parse("() export {};");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let lambda = match expr {
+            Some(Expr::Lambda(lambda)) => lambda,
+            _ => unreachable!(),
+        };
+
+        assert!(lambda.body(&tree).is_some());
+        assert!(lambda.r#extern(&tree).is_none());
+        assert!(lambda.export(&tree).is_some());
+    }
+
+    #[test]
+    fn get_struct_directives() {
+        let (tree, root) = parse("struct #packed #align(4) { a: u8, #align(8) b: u32 };");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let struct_decl = match expr {
+            Some(Expr::StructDecl(struct_decl)) => struct_decl,
+            _ => unreachable!(),
+        };
+
+        let directives = struct_decl
+            .directives(&tree)
+            .map(|directive| directive.name(&tree).unwrap().text(&tree))
+            .collect::<Vec<_>>();
+        assert_eq!(directives, ["packed", "align"]);
+
+        let mut members = struct_decl.members(&tree);
+        assert_eq!(members.next().unwrap().directives(&tree).count(), 0);
+
+        let b = members.next().unwrap();
+        assert_eq!(b.name(&tree).unwrap().text(&tree), "b");
+        assert_eq!(b.directives(&tree).count(), 1);
+    }
+
+    #[test]
+    fn get_union_members() {
+        let (tree, root) = parse("union { i: i32, f: f32 };");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let union_decl = match expr {
+            Some(Expr::UnionDecl(union_decl)) => union_decl,
+            _ => unreachable!(),
+        };
+
+        let names = union_decl
+            .members(&tree)
+            .map(|member| member.name(&tree).unwrap().text(&tree))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["i", "f"]);
+    }
+
+    #[test]
+    fn get_interface_members() {
+        let (tree, root) = parse("interface { area: () -> f32, scale: (by: f32) -> void };");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let interface_decl = match expr {
+            Some(Expr::InterfaceDecl(interface_decl)) => interface_decl,
+            _ => unreachable!(),
+        };
+
+        let names = interface_decl
+            .members(&tree)
+            .map(|member| member.name(&tree).unwrap().text(&tree))
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["area", "scale"]);
+    }
+
+    #[test]
+    fn get_struct_member_defaults() {
+        let (tree, root) =
+            parse("struct { retries: i32 = 3, name: str, #align(8) flags: u8 = #os };");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let struct_decl = match expr {
+            Some(Expr::StructDecl(struct_decl)) => struct_decl,
+            _ => unreachable!(),
+        };
+
+        let mut members = struct_decl.members(&tree);
+
+        let retries = members.next().unwrap();
+        assert!(matches!(
+            retries.default_value(&tree),
+            Some(Expr::IntLiteral(_))
+        ));
+        assert!(retries.ty(&tree).is_some());
+
+        let name = members.next().unwrap();
+        assert!(name.default_value(&tree).is_none());
+        assert!(name.ty(&tree).is_some());
+
+        // the directive before the name isn't the default value
+        let flags = members.next().unwrap();
+        assert_eq!(flags.directives(&tree).count(), 1);
+        let default = flags.default_value(&tree).unwrap();
+        assert_eq!(default.text(&tree), "#os");
+
+        assert!(members.next().is_none());
+    }
+
+    #[test]
+    fn get_lambda_var_args() {
+        let (tree, root) = parse("(fmt: cstr, ..) extern;");
+        let statement = root.stmts(&tree).next().unwrap();
+        let expr = match statement {
+            Stmt::Expr(expr_stmt) => expr_stmt.expr(&tree),
+            _ => unreachable!(),
+        };
+
+        let lambda = match expr {
+            Some(Expr::Lambda(lambda)) => lambda,
+            _ => unreachable!(),
+        };
+
+        let param_list = lambda.param_list(&tree).unwrap();
+
+        assert_eq!(param_list.params(&tree).count(), 1);
+
+        let mut var_args = param_list.var_args(&tree);
+        assert_eq!(var_args.next().unwrap().text(&tree), "..");
+        assert!(var_args.next().is_none());
+    }
+
     #[test]
     fn no_lambda_body() {
         let (tree, root) = parse("() -> void;");
@@ -0,0 +1,168 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    Ident,
+    Int,
+    Float,
+    String,
+    Char,
+    Punct,
+    /// a whole preprocessor line, including any lines that were continued with `\`
+    Directive,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Token {
+    pub(crate) kind: TokenKind,
+    /// for numbers this doesn't include suffixes like `u` or `f`
+    pub(crate) text: String,
+    pub(crate) range: Range<usize>,
+}
+
+const PUNCTS: &[&str] = &[
+    "...", "<<=", ">>=", "->", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "++", "--", "##",
+];
+
+/// C doesn't need to be lexed perfectly here, just well enough to read declarations
+pub(crate) fn lex(source: &str) -> Vec<Token> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    // preprocessor directives have to be the first thing on a line
+    let mut line_start = true;
+
+    while idx < bytes.len() {
+        let start = idx;
+        let ch = bytes[idx];
+
+        if ch == b'\n' {
+            line_start = true;
+            idx += 1;
+            continue;
+        }
+        if ch.is_ascii_whitespace() {
+            idx += 1;
+            continue;
+        }
+
+        if source[idx..].starts_with("//") {
+            while idx < bytes.len() && bytes[idx] != b'\n' {
+                idx += 1;
+            }
+            continue;
+        }
+        if source[idx..].starts_with("/*") {
+            idx = source[idx + 2..]
+                .find("*/")
+                .map(|end| idx + 2 + end + 2)
+                .unwrap_or(bytes.len());
+            continue;
+        }
+
+        if ch == b'#' && line_start {
+            while idx < bytes.len() && bytes[idx] != b'\n' {
+                if bytes[idx] == b'\\' && bytes.get(idx + 1) == Some(&b'\n') {
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            let text = source[start..idx]
+                .replace("\\\r\n", " ")
+                .replace("\\\n", " ");
+            tokens.push(Token {
+                kind: TokenKind::Directive,
+                text,
+                range: start..idx,
+            });
+            continue;
+        }
+        line_start = false;
+
+        let kind = if ch.is_ascii_alphabetic() || ch == b'_' {
+            while idx < bytes.len() && (bytes[idx].is_ascii_alphanumeric() || bytes[idx] == b'_') {
+                idx += 1;
+            }
+            TokenKind::Ident
+        } else if ch.is_ascii_digit()
+            || (ch == b'.' && bytes.get(idx + 1).is_some_and(u8::is_ascii_digit))
+        {
+            lex_number(source, &mut idx, &mut tokens);
+            continue;
+        } else if ch == b'"' || ch == b'\'' {
+            idx += 1;
+            while idx < bytes.len() && bytes[idx] != ch && bytes[idx] != b'\n' {
+                if bytes[idx] == b'\\' {
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            idx = (idx + 1).min(bytes.len());
+            if ch == b'"' {
+                TokenKind::String
+            } else {
+                TokenKind::Char
+            }
+        } else {
+            idx += PUNCTS
+                .iter()
+                .find(|punct| source[idx..].starts_with(**punct))
+                .map(|punct| punct.len())
+                .unwrap_or_else(|| source[idx..].chars().next().unwrap().len_utf8());
+            TokenKind::Punct
+        };
+
+        tokens.push(Token {
+            kind,
+            text: source[start..idx].to_string(),
+            range: start..idx,
+        });
+    }
+
+    tokens
+}
+
+fn lex_number(source: &str, idx: &mut usize, tokens: &mut Vec<Token>) {
+    let bytes = source.as_bytes();
+    let start = *idx;
+
+    let is_hex = source[start..].starts_with("0x") || source[start..].starts_with("0X");
+    if is_hex {
+        *idx += 2;
+    }
+
+    let mut is_float = false;
+    while *idx < bytes.len() {
+        let ch = bytes[*idx];
+        if ch.is_ascii_digit() || (is_hex && ch.is_ascii_hexdigit()) {
+            *idx += 1;
+        } else if ch == b'.' {
+            is_float = true;
+            *idx += 1;
+        } else if !is_hex && (ch == b'e' || ch == b'E') {
+            is_float = true;
+            *idx += 1;
+            if matches!(bytes.get(*idx), Some(b'+' | b'-')) {
+                *idx += 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let end_of_digits = *idx;
+
+    // suffixes like `10ull` or `1.5f`
+    while *idx < bytes.len() && bytes[*idx].is_ascii_alphabetic() {
+        *idx += 1;
+    }
+
+    tokens.push(Token {
+        kind: if is_float {
+            TokenKind::Float
+        } else {
+            TokenKind::Int
+        },
+        text: source[start..end_of_digits].to_string(),
+        range: start..*idx,
+    });
+}
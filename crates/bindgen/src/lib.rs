@@ -0,0 +1,756 @@
+//! Turns the declarations in a C header into capy `extern` functions, `struct`s, and `distinct`
+//! types. Only a practical subset of C is understood, everything else is written out as a comment.
+//!
+//! The preprocessor isn't run, so `#include`s aren't followed, every branch of an `#if` is read,
+//! and the only macros that come through are the ones defined as constants.
+
+mod lexer;
+mod parser;
+
+use rustc_hash::FxHashSet;
+
+use parser::{is_anon, CType, FunctionSig, Item, Parser};
+
+/// declarations longer than this get cut off when they're written into a comment
+const MAX_SOURCE_LEN: usize = 100;
+
+/// `long_bit_width` is the size of a C `long` on the target
+pub fn bindgen(header: &str, header_name: &str, long_bit_width: u32) -> String {
+    let mut parser = Parser::new(header);
+    parser.parse();
+
+    let items = parser.items;
+
+    let mut known_names = FxHashSet::default();
+    let mut defined_names = FxHashSet::default();
+    for item in &items {
+        match item {
+            Item::Struct { name, .. } | Item::Enum { name } => {
+                known_names.insert(name.clone());
+                defined_names.insert(name.clone());
+            }
+            Item::Distinct { name, .. } | Item::Alias { name, .. } | Item::Opaque { name } => {
+                known_names.insert(name.clone());
+            }
+            _ => {}
+        }
+    }
+
+    let emitter = Emitter {
+        known_names,
+        long_bit_width,
+    };
+
+    let mut output = format!("// generated by `capy bindgen` from `{header_name}`\n");
+    let mut last_group = None;
+    let mut emitted_opaque = FxHashSet::default();
+
+    for item in &items {
+        if let Item::Opaque { name } = item {
+            if defined_names.contains(name) || !emitted_opaque.insert(name.clone()) {
+                continue;
+            }
+        }
+
+        let text = emitter.item(item);
+        if text.is_empty() {
+            continue;
+        }
+
+        // things like functions and constants are grouped together
+        let group = emitter.group(item);
+        if last_group.as_ref() != Some(&group) || text.contains('\n') {
+            output.push('\n');
+        }
+        last_group = if text.contains('\n') {
+            None
+        } else {
+            Some(group)
+        };
+
+        output.push_str(&text);
+        output.push('\n');
+    }
+
+    output
+}
+
+struct Emitter {
+    known_names: FxHashSet<String>,
+    long_bit_width: u32,
+}
+
+impl Emitter {
+    fn group(&self, item: &Item) -> String {
+        match item {
+            Item::Function { .. } => "function".to_string(),
+            Item::Enum { name }
+            | Item::EnumConst {
+                enum_name: name, ..
+            } => format!("enum {name}"),
+            Item::IntConst { .. } | Item::FloatConst { .. } | Item::StringConst { .. } => {
+                "constant".to_string()
+            }
+            Item::Distinct { .. } | Item::Alias { .. } | Item::Opaque { .. } => "type".to_string(),
+            Item::Struct { .. } => "struct".to_string(),
+            Item::Unsupported { .. } => "unsupported".to_string(),
+        }
+    }
+
+    fn item(&self, item: &Item) -> String {
+        let result: Result<String, String> = match item {
+            // extern functions are linked by their capy name, so they can't be renamed
+            Item::Function { name, .. } if CAPY_KEYWORDS.contains(&name.as_str()) => {
+                Err("keyword name".to_string())
+            }
+            Item::Function { name, sig } => self
+                .signature(sig, false)
+                .map(|sig| format!("{} :: {sig} extern;", ident(name))),
+            Item::Struct {
+                name,
+                fields,
+                is_union,
+            } => {
+                let keyword = if *is_union { "union" } else { "struct" };
+                fields
+                    .iter()
+                    .map(|(field, ty)| Ok(format!("    {}: {},\n", ident(field), self.ty(ty)?)))
+                    .collect::<Result<String, String>>()
+                    .map(|fields| {
+                        if fields.is_empty() {
+                            format!("{} :: {keyword} {{}};", ident(name))
+                        } else {
+                            format!("{} :: {keyword} {{\n{fields}}};", ident(name))
+                        }
+                    })
+            }
+            Item::Opaque { name } => Ok(format!("{} :: struct {{}};", ident(name))),
+            Item::Distinct { name, ty } => self
+                .ty(ty)
+                .map(|ty| format!("{} :: distinct {ty};", ident(name))),
+            Item::Alias { name, tag } => Ok(format!("{} :: {};", ident(name), ident(tag))),
+            // C enums are really just integers
+            Item::Enum { name } if is_anon(name) => return String::new(),
+            Item::Enum { name } => Ok(format!("{} :: distinct i32;", ident(name))),
+            Item::EnumConst {
+                name,
+                enum_name,
+                value,
+            } => {
+                let ty = if is_anon(enum_name) {
+                    "i32".to_string()
+                } else {
+                    ident(enum_name)
+                };
+                Ok(format!("{} : {ty} : {value};", ident(name)))
+            }
+            Item::IntConst { name, value } => {
+                let ty = if i32::try_from(*value).is_ok() {
+                    "i32"
+                } else if u32::try_from(*value).is_ok() {
+                    "u32"
+                } else {
+                    "i64"
+                };
+                Ok(format!("{} : {ty} : {value};", ident(name)))
+            }
+            Item::FloatConst { name, value } => {
+                // capy needs digits on both sides of the `.`
+                let value = if value.starts_with('.') {
+                    format!("0{value}")
+                } else {
+                    value.clone()
+                };
+                let value = value.replace(".e", ".0e").replace(".E", ".0E");
+                let value = if value.ends_with('.') {
+                    format!("{value}0")
+                } else {
+                    value
+                };
+                Ok(format!("{} : f64 : {value};", ident(name)))
+            }
+            // string macros are almost always given to C functions
+            Item::StringConst { name, value } => Ok(format!("{} :: {value} as cstr;", ident(name))),
+            Item::Unsupported { reason, source } => {
+                let source = match source.char_indices().nth(MAX_SOURCE_LEN) {
+                    Some((end, _)) => format!("{} ...", &source[..end]),
+                    None => source.clone(),
+                };
+                return format!("// unsupported ({reason}): {source}");
+            }
+        };
+
+        result.unwrap_or_else(|reason| {
+            let name = match item {
+                Item::Function { name, .. }
+                | Item::Struct { name, .. }
+                | Item::Distinct { name, .. } => name.trim_start_matches('$'),
+                _ => unreachable!("only functions and types can fail"),
+            };
+            format!("// unsupported ({reason}): {name}")
+        })
+    }
+
+    /// function types always need a return type, but lambdas don't
+    fn signature(&self, sig: &FunctionSig, is_ty: bool) -> Result<String, String> {
+        let mut params = sig
+            .params
+            .iter()
+            .enumerate()
+            .map(|(idx, (name, ty))| {
+                let name = match name {
+                    Some(name) => ident(name),
+                    None => format!("arg{idx}"),
+                };
+                Ok(format!("{name}: {}", self.ty(ty)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        if sig.variadic {
+            params.push("..".to_string());
+        }
+
+        let params = params.join(", ");
+
+        Ok(match sig.return_ty.as_ref() {
+            CType::Void if is_ty => format!("({params}) -> void"),
+            CType::Void => format!("({params})"),
+            return_ty => format!("({params}) -> {}", self.ty(return_ty)?),
+        })
+    }
+
+    fn ty(&self, ty: &CType) -> Result<String, String> {
+        Ok(match ty {
+            CType::Void => return Err("`void` values".to_string()),
+            CType::Capy(ty) => ty.to_string(),
+            CType::Long { unsigned: false } => format!("i{}", self.long_bit_width),
+            CType::Long { unsigned: true } => format!("u{}", self.long_bit_width),
+            CType::Named(name) if self.known_names.contains(name) => ident(name),
+            CType::Named(name) => return Err(format!("unknown type `{name}`")),
+            CType::Tag(name) => ident(name),
+            CType::Pointer { pointee, mutable } => {
+                let mutability = if *mutable { "mut " } else { "" };
+                match pointee.as_ref() {
+                    CType::Void => format!("^{mutability}any"),
+                    CType::Capy("char") if !mutable => "cstr".to_string(),
+                    // capy functions are already pointers
+                    CType::Function(sig) => self.signature(sig, true)?,
+                    pointee => format!("^{mutability}{}", self.ty(pointee)?),
+                }
+            }
+            CType::Array {
+                len: Some(len),
+                sub_ty,
+            } => format!("[{len}]{}", self.ty(sub_ty)?),
+            CType::Array { len: None, .. } => return Err("arrays without a length".to_string()),
+            CType::Function(_) => return Err("functions that aren't pointers".to_string()),
+        })
+    }
+}
+
+/// this has to be kept in sync with `tokenizer.txt`
+const CAPY_KEYWORDS: &[&str] = &[
+    "as",
+    "if",
+    "else",
+    "while",
+    "loop",
+    "distinct",
+    "mut",
+    "extern",
+    "export",
+    "struct",
+    "union",
+    "interface",
+    "import",
+    "mod",
+    "comptime",
+    "return",
+    "break",
+    "continue",
+    "defer",
+    "null",
+    "true",
+    "false",
+];
+
+/// C names that are keywords in capy get an `_` at the end.
+/// this is only fine for names that don't have to match a symbol, which rules out functions
+fn ident(name: &str) -> String {
+    let name = name.trim_start_matches('$');
+    if CAPY_KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use expect_test::{expect, Expect};
+
+    use super::*;
+
+    #[track_caller]
+    fn check(header: &str, expect: Expect) {
+        check_with_long(header, 64, expect);
+    }
+
+    #[track_caller]
+    fn check_with_long(header: &str, long_bit_width: u32, expect: Expect) {
+        let capy = bindgen(header, "test.h", long_bit_width);
+
+        // whatever is generated should always be valid capy
+        let tokens = ::lexer::lex(&capy);
+        let parse = ::parser::parse_source_file(&tokens, &capy);
+        assert_eq!(parse.errors(), &[], "{capy}");
+
+        expect.assert_eq(&capy);
+    }
+
+    #[test]
+    fn functions() {
+        check(
+            r#"
+int add(int a, int b);
+void say_hello(void);
+unsigned long long big(short x, unsigned char y, signed char z);
+double sqrt(double);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                add :: (a: i32, b: i32) -> i32 extern;
+                say_hello :: () extern;
+                big :: (x: i16, y: u8, z: i8) -> u64 extern;
+                sqrt :: (arg0: f64) -> f64 extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn pointers() {
+        check(
+            r#"
+size_t strlen(const char *s);
+char *strcpy(char *dst, const char *src);
+void *memcpy(void *dst, const void *src, size_t n);
+void qsort(void *base, size_t len, size_t size, int (*compare)(const void *, const void *));
+int main(int argc, char **argv);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                strlen :: (s: cstr) -> usize extern;
+                strcpy :: (dst: ^mut char, src: cstr) -> ^mut char extern;
+                memcpy :: (dst: ^mut any, src: ^any, n: usize) -> ^mut any extern;
+                qsort :: (base: ^mut any, len: usize, size: usize, compare: (arg0: ^any, arg1: ^any) -> i32) extern;
+                main :: (argc: i32, argv: ^mut ^mut char) -> i32 extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn variadic_function() {
+        check(
+            r#"
+int printf(const char *fmt, ...);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                printf :: (fmt: cstr, ..) -> i32 extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn structs() {
+        check(
+            r#"
+struct vec2 {
+    float x, y;
+};
+
+typedef struct {
+    struct vec2 pos;
+    struct { int r, g, b; } color;
+    const char *name;
+    uint8_t flags[4];
+    struct node *next;
+} sprite_t;
+
+typedef struct list_s {
+    int value;
+    struct list_s *next;
+} list_t;
+
+void draw(sprite_t *sprite, const list_t *list);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                vec2 :: struct {
+                    x: f32,
+                    y: f32,
+                };
+
+                sprite_t_color :: struct {
+                    r: i32,
+                    g: i32,
+                    b: i32,
+                };
+
+                node :: struct {};
+
+                sprite_t :: struct {
+                    pos: vec2,
+                    color: sprite_t_color,
+                    name: cstr,
+                    flags: [4]u8,
+                    next: ^mut node,
+                };
+
+                list_t :: struct {
+                    value: i32,
+                    next: ^mut list_t,
+                };
+
+                draw :: (sprite: ^mut sprite_t, list: ^list_t) extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn opaque_structs() {
+        check(
+            r#"
+typedef struct window window;
+struct context;
+
+window *create_window(int width, int height);
+void destroy(struct context *ctx);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                window :: struct {};
+                context :: struct {};
+
+                create_window :: (width: i32, height: i32) -> ^mut window extern;
+                destroy :: (ctx: ^mut context) extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn typedefs() {
+        check(
+            r#"
+typedef int file_desc;
+typedef unsigned int flags_t, *flags_ptr;
+typedef void (*callback)(int code, void *user_data);
+typedef struct point { int x; int y; } point_t;
+typedef point_t position;
+
+file_desc open_file(const char *path, flags_t flags);
+void on_event(callback cb);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                file_desc :: distinct i32;
+                flags_t :: distinct u32;
+                flags_ptr :: distinct ^mut u32;
+                callback :: distinct (code: i32, user_data: ^mut any) -> void;
+
+                point_t :: struct {
+                    x: i32,
+                    y: i32,
+                };
+
+                position :: distinct point_t;
+
+                open_file :: (path: cstr, flags: flags_t) -> file_desc extern;
+                on_event :: (cb: callback) extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn enums() {
+        check(
+            r#"
+enum color { RED, GREEN = 5, BLUE };
+
+typedef enum {
+    MODE_READ = 1 << 0,
+    MODE_WRITE = 1 << 1,
+    MODE_BOTH = MODE_READ | MODE_WRITE,
+} mode;
+
+enum { LETTER = 'a', NEGATIVE = -1 };
+
+void set_mode(mode m, enum color c);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                color :: distinct i32;
+                RED : color : 0;
+                GREEN : color : 5;
+                BLUE : color : 6;
+
+                mode :: distinct i32;
+                MODE_READ : mode : 1;
+                MODE_WRITE : mode : 2;
+                MODE_BOTH : mode : 3;
+
+                LETTER : i32 : 97;
+                NEGATIVE : i32 : -1;
+
+                set_mode :: (m: mode, c: color) extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn long_sizes() {
+        let header = r#"
+long offset(unsigned long len, long long big);
+"#;
+
+        check(
+            header,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                offset :: (len: u64, big: i64) -> i64 extern;
+            "#]],
+        );
+
+        // `long` is only 32 bits on Windows
+        check_with_long(
+            header,
+            32,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                offset :: (len: u32, big: i64) -> i32 extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn defines() {
+        check(
+            r#"
+#ifndef TEST_H
+#define TEST_H
+
+#include <stdint.h>
+
+#define BUFFER_SIZE 4096
+#define FLAG_A 0x1u
+#define FLAG_B (FLAG_A << 4)
+#define MAX_U32 0xFFFFFFFF
+#define PI 3.14159f
+#define VERSION "1.0.2"
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+#define CAST ((int)5)
+
+#endif
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                // unsupported (includes): #include <stdint.h>
+
+                BUFFER_SIZE : i32 : 4096;
+                FLAG_A : i32 : 1;
+                FLAG_B : i32 : 16;
+                MAX_U32 : u32 : 4294967295;
+                PI : f64 : 3.14159;
+                VERSION :: "1.0.2" as cstr;
+
+                // unsupported (function-like macros): #define MAX(a, b) ((a) > (b) ? (a) : (b))
+                // unsupported (non-constant macros): #define CAST ((int)5)
+            "#]],
+        );
+    }
+
+    #[test]
+    fn unsupported() {
+        check(
+            r#"
+struct flags { unsigned a : 1; unsigned b : 1; };
+extern int errno;
+static inline int square(int x) { return x * x; }
+long double precise(void);
+FILE *fopen(const char *path, const char *mode);
+int after(void);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                // unsupported (bitfields): struct flags { unsigned a : 1; unsigned b : 1; };
+                // unsupported (global variables): extern int errno;
+                // unsupported (function bodies): static inline int square(int x) { return x * x; }
+                // unsupported (`long double`): long double precise(void);
+
+                // unsupported (unknown type `FILE`): fopen
+                after :: () -> i32 extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn unions() {
+        check(
+            r#"
+typedef union epoll_data {
+    void *ptr;
+    int fd;
+    uint32_t u32;
+    uint64_t u64;
+} epoll_data_t;
+
+struct epoll_event {
+    uint32_t events;
+    epoll_data_t data;
+};
+
+struct value {
+    int kind;
+    union { int i; float f; } as;
+};
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                epoll_data_t :: union {
+                    ptr: ^mut any,
+                    fd: i32,
+                    u32: u32,
+                    u64: u64,
+                };
+
+                epoll_event :: struct {
+                    events: u32,
+                    data: epoll_data_t,
+                };
+
+                value_as :: union {
+                    i: i32,
+                    f: f32,
+                };
+
+                value :: struct {
+                    kind: i32,
+                    as_: value_as,
+                };
+            "#]],
+        );
+    }
+
+    #[test]
+    fn keywords() {
+        check(
+            r#"
+struct loop { int mut; int as; };
+void visit(struct loop *if_, int distinct);
+void defer(int x);
+int interface(void *null);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                loop_ :: struct {
+                    mut_: i32,
+                    as_: i32,
+                };
+
+                visit :: (if_: ^mut loop_, distinct_: i32) extern;
+                // unsupported (keyword name): defer
+                // unsupported (keyword name): interface
+            "#]],
+        );
+    }
+
+    #[test]
+    fn keywords_match_tokenizer() {
+        // the keywords are the tokens in `tokenizer.txt` which only match words,
+        // like `If = 'if'` or `Bool = /true|false/`
+        let mut keywords: Vec<_> = include_str!("../../../tokenizer.txt")
+            .lines()
+            .filter_map(|line| {
+                let (_, token) = line.split_once(" = ")?;
+
+                token
+                    .strip_prefix('\'')
+                    .and_then(|token| token.strip_suffix('\''))
+                    .or_else(|| token.strip_prefix('/')?.strip_suffix('/'))
+            })
+            .flat_map(|pattern| pattern.split('|'))
+            .filter(|word| !word.is_empty() && word.chars().all(|ch| ch.is_ascii_alphabetic()))
+            .collect();
+
+        keywords.sort();
+        let mut expected = CAPY_KEYWORDS.to_vec();
+        expected.sort();
+
+        assert_eq!(keywords, expected);
+    }
+
+    #[test]
+    fn extensions() {
+        check(
+            r#"
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+extern void exit(int status) __attribute__((noreturn));
+__extension__ typedef long long wide_t;
+int *restrict_ptr(int *__restrict p) __asm__("restrict_ptr_v2");
+
+#ifdef __cplusplus
+}
+#endif
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                exit :: (status: i32) extern;
+
+                wide_t :: distinct i64;
+
+                restrict_ptr :: (p: ^mut i32) -> ^mut i32 extern;
+            "#]],
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        check(
+            r#"
+#define N 3
+struct matrix { float cells[N][N + 1]; };
+int (*row(struct matrix *m, int idx))[4];
+void fill(int values[16], size_t len);
+"#,
+            expect![[r#"
+                // generated by `capy bindgen` from `test.h`
+
+                N : i32 : 3;
+
+                matrix :: struct {
+                    cells: [3][4]f32,
+                };
+
+                row :: (m: ^mut matrix, idx: i32) -> ^mut [4]i32 extern;
+                fill :: (values: ^mut i32, len: usize) extern;
+            "#]],
+        );
+    }
+}
@@ -0,0 +1,1071 @@
+use std::mem;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::lexer::{self, Token, TokenKind};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CType {
+    Void,
+    /// a type that maps directly onto a capy type, like `int` -> `i32`
+    Capy(&'static str),
+    /// `long`, which is 32 bits on Windows and the size of a pointer everywhere else
+    Long {
+        unsigned: bool,
+    },
+    /// a `typedef` name
+    Named(String),
+    /// the tag of a `struct`, `union` or `enum`
+    Tag(String),
+    Pointer {
+        pointee: Box<CType>,
+        mutable: bool,
+    },
+    Array {
+        len: Option<u64>,
+        sub_ty: Box<CType>,
+    },
+    Function(FunctionSig),
+}
+
+/// parameters in C don't need names
+pub(crate) type Params = Vec<(Option<String>, CType)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FunctionSig {
+    pub(crate) params: Params,
+    pub(crate) return_ty: Box<CType>,
+    pub(crate) variadic: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Item {
+    Function {
+        name: String,
+        sig: FunctionSig,
+    },
+    Struct {
+        name: String,
+        fields: Vec<(String, CType)>,
+        is_union: bool,
+    },
+    /// `typedef`s of anything that isn't a `struct` or `enum` become `distinct` types
+    Distinct {
+        name: String,
+        ty: CType,
+    },
+    /// `typedef`s of `struct`s or `enum`s that already have a name
+    Alias {
+        name: String,
+        tag: String,
+    },
+    Enum {
+        name: String,
+    },
+    /// a `struct` that's used but might never be defined, like `struct foo *`
+    Opaque {
+        name: String,
+    },
+    EnumConst {
+        name: String,
+        enum_name: String,
+        value: i64,
+    },
+    IntConst {
+        name: String,
+        value: i64,
+    },
+    FloatConst {
+        name: String,
+        value: String,
+    },
+    StringConst {
+        name: String,
+        value: String,
+    },
+    Unsupported {
+        reason: String,
+        source: String,
+    },
+}
+
+type ParseResult<T> = Result<T, String>;
+
+/// anonymous `struct`s and `enum`s get these names until they're given a proper one
+const ANON_PREFIX: &str = "$anon";
+
+pub(crate) fn is_anon(name: &str) -> bool {
+    name.starts_with(ANON_PREFIX)
+}
+
+struct Specifiers {
+    ty: CType,
+    is_const: bool,
+    is_typedef: bool,
+    /// the `struct` or `enum` whose body was written out in these specifiers
+    defined_tag: Option<String>,
+}
+
+pub(crate) struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    /// directives are read as soon as the parser gets to them,
+    /// but the parser sometimes backtracks over the same tokens
+    directives_read: usize,
+    pub(crate) items: Vec<Item>,
+    type_names: FxHashSet<String>,
+    constants: FxHashMap<String, i64>,
+    /// `typedef struct foo_s { .. } foo_t;` renames `foo_s` to `foo_t`
+    tag_renames: FxHashMap<String, String>,
+    anon_count: u32,
+    extern_c_depth: u32,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            tokens: lexer::lex(source),
+            pos: 0,
+            directives_read: 0,
+            items: Vec::new(),
+            type_names: FxHashSet::default(),
+            constants: FxHashMap::default(),
+            tag_renames: FxHashMap::default(),
+            anon_count: 0,
+            extern_c_depth: 0,
+        }
+    }
+
+    pub(crate) fn parse(&mut self) {
+        self.read_directives();
+
+        while self.pos < self.tokens.len() {
+            if self.eat(";") {
+                continue;
+            }
+
+            // `extern "C" {` from headers that are meant to be used in C++
+            if self.at("extern") && self.nth(1).is_some_and(|t| t.kind == TokenKind::String) {
+                self.bump();
+                self.bump();
+                if self.eat("{") {
+                    self.extern_c_depth += 1;
+                }
+                continue;
+            }
+            if self.extern_c_depth > 0 && self.eat("}") {
+                self.extern_c_depth -= 1;
+                continue;
+            }
+
+            let start = self.pos;
+            let items_len = self.items.len();
+            if let Err(reason) = self.declaration() {
+                // anything the declaration defined before the error is half-finished
+                self.items.truncate(items_len);
+                self.recover(start);
+                let source = self.source_text(start, self.pos);
+                self.items.push(Item::Unsupported { reason, source });
+            }
+        }
+    }
+
+    fn nth(&self, n: usize) -> Option<&Token> {
+        self.tokens[self.pos..]
+            .iter()
+            .filter(|t| t.kind != TokenKind::Directive)
+            .nth(n)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.nth(0)
+    }
+
+    fn at(&self, text: &str) -> bool {
+        self.peek()
+            .is_some_and(|t| t.text == text && t.kind != TokenKind::String)
+    }
+
+    fn at_ident(&self) -> bool {
+        self.peek().is_some_and(|t| t.kind == TokenKind::Ident)
+    }
+
+    fn bump(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        self.pos += 1;
+        self.read_directives();
+        token
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.at(text) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> ParseResult<()> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(token) => format!("expected `{}` but found `{}`", text, token.text),
+                None => format!("expected `{}`", text),
+            })
+        }
+    }
+
+    fn read_directives(&mut self) {
+        while let Some(token) = self.tokens.get(self.pos) {
+            if token.kind != TokenKind::Directive {
+                break;
+            }
+            if self.pos >= self.directives_read {
+                let token = token.clone();
+                self.directive(&token);
+                self.directives_read = self.pos + 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn source_text(&self, start: usize, end: usize) -> String {
+        let tokens = self.tokens[start..end]
+            .iter()
+            .filter(|t| t.kind != TokenKind::Directive)
+            .collect::<Vec<_>>();
+
+        let (Some(first), Some(last)) = (tokens.first(), tokens.last()) else {
+            return String::new();
+        };
+
+        self.source[first.range.start..last.range.end]
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// skips to the end of a declaration that couldn't be read
+    fn recover(&mut self, start: usize) {
+        self.pos = start;
+        let mut depth = 0;
+        let mut last = String::new();
+
+        while let Some(token) = self.peek() {
+            let text = token.text.clone();
+            let is_punct = token.kind == TokenKind::Punct;
+            self.bump();
+
+            if !is_punct {
+                last = text;
+                continue;
+            }
+
+            match text.as_str() {
+                "(" | "[" => depth += 1,
+                ")" | "]" => depth -= 1,
+                // a `{` right after a `)` is the body of a function, which doesn't end with `;`
+                "{" if depth == 0 && last == ")" => {
+                    self.skip_until_closing("{", "}");
+                    return;
+                }
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                ";" if depth == 0 => return,
+                _ => {}
+            }
+            last = text;
+        }
+    }
+
+    /// expects the opening token to have already been bumped
+    fn skip_until_closing(&mut self, open: &str, close: &str) {
+        let mut depth = 1;
+        while depth > 0 && self.peek().is_some() {
+            let token = self.bump();
+            if token.kind != TokenKind::Punct {
+                continue;
+            }
+            if token.text == open {
+                depth += 1;
+            } else if token.text == close {
+                depth -= 1;
+            }
+        }
+    }
+
+    /// skips over things like `__attribute__((noreturn))` which don't matter to capy
+    fn skip_extensions(&mut self) {
+        loop {
+            if self.eat("__attribute__")
+                || self.eat("__attribute")
+                || self.eat("__declspec")
+                || self.eat("__asm__")
+                || self.eat("__asm")
+                || self.eat("asm")
+            {
+                if self.eat("(") {
+                    self.skip_until_closing("(", ")");
+                }
+            } else if !(self.eat("__extension__")
+                || self.eat("__nonnull")
+                || self.eat("__wur")
+                || self.eat("__THROW"))
+            {
+                return;
+            }
+        }
+    }
+
+    fn anon_name(&mut self) -> String {
+        self.anon_count += 1;
+        format!("{ANON_PREFIX}{}", self.anon_count)
+    }
+
+    fn declaration(&mut self) -> ParseResult<()> {
+        let specifiers = self.specifiers()?;
+
+        if self.eat(";") {
+            return Ok(());
+        }
+
+        loop {
+            let (name, ty) = self.declarator(specifiers.ty.clone(), specifiers.is_const)?;
+            let name = name.ok_or("declarations need a name")?;
+            self.skip_extensions();
+
+            if specifiers.is_typedef {
+                self.typedef(name, ty, &specifiers);
+            } else {
+                match ty {
+                    CType::Function(sig) => {
+                        if self.at("{") {
+                            return Err("function bodies".to_string());
+                        }
+                        self.items.push(Item::Function { name, sig })
+                    }
+                    _ => return Err("global variables".to_string()),
+                }
+            }
+
+            if !self.eat(",") {
+                break;
+            }
+        }
+
+        self.expect(";")
+    }
+
+    fn typedef(&mut self, name: String, ty: CType, specifiers: &Specifiers) {
+        self.type_names.insert(name.clone());
+
+        match ty {
+            CType::Tag(tag) if tag == name => {}
+            CType::Tag(tag) if specifiers.defined_tag.as_ref() == Some(&tag) => {
+                self.rename(&tag, &name);
+                if !is_anon(&tag) {
+                    self.tag_renames.insert(tag, name);
+                }
+            }
+            CType::Tag(tag) => self.items.push(Item::Alias { name, tag }),
+            ty => self.items.push(Item::Distinct { name, ty }),
+        }
+    }
+
+    /// gives a `struct` or `enum` a new name everywhere it's been used so far
+    fn rename(&mut self, old: &str, new: &str) {
+        fn rename_ty(ty: &mut CType, old: &str, new: &str) {
+            match ty {
+                CType::Tag(tag) if tag == old => *tag = new.to_string(),
+                CType::Pointer { pointee: sub, .. } | CType::Array { sub_ty: sub, .. } => {
+                    rename_ty(sub, old, new)
+                }
+                CType::Function(sig) => rename_sig(sig, old, new),
+                _ => {}
+            }
+        }
+
+        fn rename_sig(sig: &mut FunctionSig, old: &str, new: &str) {
+            for (_, ty) in &mut sig.params {
+                rename_ty(ty, old, new);
+            }
+            rename_ty(&mut sig.return_ty, old, new);
+        }
+
+        // anonymous structs inside of this one were named after it
+        if is_anon(old) {
+            let prefix = format!("{}_", old.trim_start_matches('$'));
+            let nested = self
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Struct { name, .. } => name.strip_prefix(&prefix),
+                    _ => None,
+                })
+                .map(|field| field.to_string())
+                .collect::<Vec<_>>();
+
+            for field in nested {
+                self.rename(&format!("{prefix}{field}"), &format!("{new}_{field}"));
+            }
+        }
+
+        for item in &mut self.items {
+            match item {
+                Item::Struct { name, fields, .. } => {
+                    if name == old {
+                        *name = new.to_string();
+                    }
+                    for (_, ty) in fields {
+                        rename_ty(ty, old, new);
+                    }
+                }
+                Item::Enum { name }
+                | Item::Opaque { name }
+                | Item::EnumConst {
+                    enum_name: name, ..
+                } if name == old => *name = new.to_string(),
+                Item::Function { sig, .. } => rename_sig(sig, old, new),
+                Item::Distinct { ty, .. } => rename_ty(ty, old, new),
+                Item::Alias { tag, .. } if tag == old => *tag = new.to_string(),
+                _ => {}
+            }
+        }
+    }
+
+    fn specifiers(&mut self) -> ParseResult<Specifiers> {
+        let mut specifiers = Specifiers {
+            ty: CType::Void,
+            is_const: false,
+            is_typedef: false,
+            defined_tag: None,
+        };
+
+        let mut ty = None;
+        let mut keywords = Vec::new();
+
+        loop {
+            self.skip_extensions();
+
+            let Some(token) = self.peek() else {
+                break;
+            };
+            if token.kind != TokenKind::Ident {
+                break;
+            }
+
+            match token.text.as_str() {
+                "const" | "__const" => specifiers.is_const = true,
+                "volatile" | "__volatile__" | "restrict" | "__restrict" | "__restrict__"
+                | "extern" | "static" | "inline" | "register" | "auto" | "_Noreturn"
+                | "__inline" | "__inline__" => {}
+                "typedef" => specifiers.is_typedef = true,
+                "void" | "char" | "short" | "int" | "long" | "float" | "double" | "signed"
+                | "unsigned" | "_Bool" | "__signed__" => {
+                    keywords.push(token.text.clone());
+                }
+                "_Complex" | "__int128" => return Err(format!("`{}`", token.text)),
+                "struct" | "union" | "enum" if ty.is_none() && keywords.is_empty() => {
+                    let keyword = self.bump().text;
+                    let (tag_ty, defined) = self.tagged(&keyword)?;
+                    if defined {
+                        if let CType::Tag(tag) = &tag_ty {
+                            specifiers.defined_tag = Some(tag.clone());
+                        }
+                    }
+                    ty = Some(tag_ty);
+                    continue;
+                }
+                name if ty.is_none() && keywords.is_empty() => {
+                    ty = Some(match builtin_typedef(name) {
+                        Some(capy) => CType::Capy(capy),
+                        None => CType::Named(name.to_string()),
+                    });
+                }
+                _ => break,
+            }
+
+            self.bump();
+        }
+
+        specifiers.ty = match ty {
+            Some(ty) => ty,
+            None if keywords.is_empty() => {
+                return Err(match self.peek() {
+                    Some(token) => format!("expected a type but found `{}`", token.text),
+                    None => "expected a type".to_string(),
+                })
+            }
+            None => keywords_to_ty(&keywords)?,
+        };
+
+        Ok(specifiers)
+    }
+
+    /// parses what comes after `struct`, `union` or `enum`.
+    /// also returns whether or not the body was written out
+    fn tagged(&mut self, keyword: &str) -> ParseResult<(CType, bool)> {
+        self.skip_extensions();
+
+        let tag = if self.at_ident() {
+            let tag = self.bump().text;
+            Some(self.tag_renames.get(&tag).cloned().unwrap_or(tag))
+        } else {
+            None
+        };
+
+        self.skip_extensions();
+
+        if !self.eat("{") {
+            let tag = tag.ok_or("expected a name or a body")?;
+            self.items.push(Item::Opaque { name: tag.clone() });
+            return Ok((CType::Tag(tag), false));
+        }
+
+        let name = match tag {
+            Some(tag) => tag,
+            None => self.anon_name(),
+        };
+
+        if keyword == "enum" {
+            self.enum_body(&name)?;
+        } else {
+            let fields = self.struct_body(&name)?;
+            self.items.push(Item::Struct {
+                name: name.clone(),
+                fields,
+                is_union: keyword == "union",
+            });
+        }
+
+        Ok((CType::Tag(name), true))
+    }
+
+    fn struct_body(&mut self, struct_name: &str) -> ParseResult<Vec<(String, CType)>> {
+        let mut fields = Vec::new();
+
+        while !self.eat("}") {
+            if self.peek().is_none() {
+                return Err("expected `}`".to_string());
+            }
+            if self.eat(";") {
+                continue;
+            }
+
+            let specifiers = self.specifiers()?;
+            if self.at(";") {
+                return Err("anonymous members".to_string());
+            }
+
+            loop {
+                let (name, ty) = self.declarator(specifiers.ty.clone(), specifiers.is_const)?;
+                let name = name.ok_or("fields need a name")?;
+
+                if self.at(":") {
+                    return Err("bitfields".to_string());
+                }
+
+                // `struct { int x; } pos;` gets named after the field it's in
+                let ty = match ty {
+                    CType::Tag(tag) if is_anon(&tag) => {
+                        let new = format!("{}_{}", struct_name.trim_start_matches('$'), name);
+                        self.rename(&tag, &new);
+                        CType::Tag(new)
+                    }
+                    ty => ty,
+                };
+
+                fields.push((name, ty));
+
+                self.skip_extensions();
+                if !self.eat(",") {
+                    break;
+                }
+            }
+
+            self.expect(";")?;
+        }
+
+        Ok(fields)
+    }
+
+    fn enum_body(&mut self, enum_name: &str) -> ParseResult<()> {
+        self.items.push(Item::Enum {
+            name: enum_name.to_string(),
+        });
+
+        let mut next = 0;
+        while !self.eat("}") {
+            if !self.at_ident() {
+                return Err("expected the name of an enum variant".to_string());
+            }
+            let name = self.bump().text;
+            self.skip_extensions();
+
+            let value = if self.eat("=") {
+                self.const_expr()?
+            } else {
+                next
+            };
+            next = value.wrapping_add(1);
+
+            self.constants.insert(name.clone(), value);
+            self.items.push(Item::EnumConst {
+                name,
+                enum_name: enum_name.to_string(),
+                value,
+            });
+
+            if !self.eat(",") {
+                self.expect("}")?;
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// C declarators are read inside-out, so `int *(*x)[3]` is
+    /// "a pointer to an array of 3 pointers to int"
+    fn declarator(
+        &mut self,
+        mut ty: CType,
+        mut is_const: bool,
+    ) -> ParseResult<(Option<String>, CType)> {
+        self.skip_extensions();
+
+        while self.eat("*") {
+            ty = CType::Pointer {
+                pointee: Box::new(ty),
+                mutable: !is_const,
+            };
+            is_const = false;
+            // qualifiers after the `*` are about the pointer itself
+            while let Some(token) = self.peek() {
+                match token.text.as_str() {
+                    "const" | "__const" => is_const = true,
+                    "volatile" | "restrict" | "__restrict" | "__restrict__" => {}
+                    _ => break,
+                }
+                self.bump();
+            }
+            self.skip_extensions();
+        }
+
+        if self.at("(") && self.is_nested_declarator() {
+            // everything after the parentheses applies first
+            let open = self.pos;
+            self.bump();
+            self.skip_until_closing("(", ")");
+            let ty = self.suffixes(ty)?;
+            let end = self.pos;
+
+            self.pos = open;
+            self.bump();
+            let declarator = self.declarator(ty, false)?;
+            self.expect(")")?;
+            self.pos = end;
+
+            return Ok(declarator);
+        }
+
+        let name = if self.at_ident() {
+            Some(self.bump().text)
+        } else {
+            None
+        };
+
+        Ok((name, self.suffixes(ty)?))
+    }
+
+    fn is_nested_declarator(&self) -> bool {
+        match self.nth(1) {
+            Some(token) if token.kind == TokenKind::Punct => {
+                token.text == "*" || token.text == "(" || token.text == "["
+            }
+            Some(token) if token.kind == TokenKind::Ident => {
+                !self.type_names.contains(&token.text)
+                    && builtin_typedef(&token.text).is_none()
+                    && !is_type_keyword(&token.text)
+            }
+            _ => false,
+        }
+    }
+
+    fn suffixes(&mut self, ty: CType) -> ParseResult<CType> {
+        enum Suffix {
+            Array(Option<u64>),
+            Function(Params, bool),
+        }
+
+        let mut suffixes = Vec::new();
+        loop {
+            if self.eat("[") {
+                while self.eat("static") || self.eat("const") || self.eat("restrict") {}
+
+                if self.eat("]") {
+                    suffixes.push(Suffix::Array(None));
+                    continue;
+                }
+
+                let len = self.const_expr()?;
+                self.expect("]")?;
+
+                let len = u64::try_from(len).map_err(|_| "negative array lengths")?;
+                suffixes.push(Suffix::Array(Some(len)));
+            } else if self.eat("(") {
+                let (params, variadic) = self.params()?;
+                suffixes.push(Suffix::Function(params, variadic));
+            } else {
+                break;
+            }
+        }
+
+        Ok(suffixes
+            .into_iter()
+            .rev()
+            .fold(ty, |ty, suffix| match suffix {
+                Suffix::Array(len) => CType::Array {
+                    len,
+                    sub_ty: Box::new(ty),
+                },
+                Suffix::Function(params, variadic) => CType::Function(FunctionSig {
+                    params,
+                    return_ty: Box::new(ty),
+                    variadic,
+                }),
+            }))
+    }
+
+    fn params(&mut self) -> ParseResult<(Params, bool)> {
+        let mut params = Vec::new();
+
+        if self.eat(")") {
+            return Ok((params, false));
+        }
+        if self.at("void") && self.nth(1).is_some_and(|t| t.text == ")") {
+            self.bump();
+            self.bump();
+            return Ok((params, false));
+        }
+
+        loop {
+            if self.eat("...") {
+                self.expect(")")?;
+                return Ok((params, true));
+            }
+
+            let specifiers = self.specifiers()?;
+            let (name, ty) = self.declarator(specifiers.ty, specifiers.is_const)?;
+            self.skip_extensions();
+
+            // arrays decay into pointers when they're passed to functions
+            let ty = match ty {
+                CType::Array { sub_ty, .. } => CType::Pointer {
+                    pointee: sub_ty,
+                    mutable: !specifiers.is_const,
+                },
+                ty => ty,
+            };
+
+            params.push((name, ty));
+
+            if !self.eat(",") {
+                break;
+            }
+        }
+
+        self.expect(")")?;
+
+        Ok((params, false))
+    }
+
+    fn directive(&mut self, token: &Token) {
+        let text = token.text.trim_start_matches('#').trim_start();
+        let (keyword, rest) = text
+            .split_once(|ch: char| ch.is_whitespace())
+            .unwrap_or((text, ""));
+        let rest = rest.trim();
+
+        match keyword {
+            "define" => {
+                if let Err(reason) = self.define(rest) {
+                    self.items.push(Item::Unsupported {
+                        reason,
+                        source: token.text.split_whitespace().collect::<Vec<_>>().join(" "),
+                    });
+                }
+            }
+            "include" => self.items.push(Item::Unsupported {
+                reason: "includes".to_string(),
+                source: format!("#include {rest}"),
+            }),
+            // every branch of an `#if` gets read,
+            // which is fine for include guards and `__cplusplus` checks
+            _ => {}
+        }
+    }
+
+    fn define(&mut self, text: &str) -> ParseResult<()> {
+        let name_len = text
+            .find(|ch: char| !ch.is_ascii_alphanumeric() && ch != '_')
+            .unwrap_or(text.len());
+        let (name, body) = text.split_at(name_len);
+
+        if body.starts_with('(') {
+            return Err("function-like macros".to_string());
+        }
+
+        let body = lexer::lex(body);
+        match body.as_slice() {
+            // include guards and flags
+            [] => return Ok(()),
+            [token] if token.kind == TokenKind::String => {
+                self.items.push(Item::StringConst {
+                    name: name.to_string(),
+                    value: token.text.clone(),
+                });
+                return Ok(());
+            }
+            [token] if token.kind == TokenKind::Float => {
+                self.items.push(Item::FloatConst {
+                    name: name.to_string(),
+                    value: token.text.clone(),
+                });
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        // the constant expression parser works on the main token list
+        let old_tokens = mem::replace(&mut self.tokens, body);
+        let old_pos = mem::replace(&mut self.pos, 0);
+        let old_directives_read = mem::replace(&mut self.directives_read, usize::MAX);
+
+        let value = self.const_expr().and_then(|value| match self.peek() {
+            Some(_) => Err("non-constant macros".to_string()),
+            None => Ok(value),
+        });
+
+        self.tokens = old_tokens;
+        self.pos = old_pos;
+        self.directives_read = old_directives_read;
+
+        let value = value.map_err(|_| "non-constant macros".to_string())?;
+
+        self.constants.insert(name.to_string(), value);
+        self.items.push(Item::IntConst {
+            name: name.to_string(),
+            value,
+        });
+
+        Ok(())
+    }
+
+    fn const_expr(&mut self) -> ParseResult<i64> {
+        self.binary_expr(0)
+    }
+
+    fn binary_expr(&mut self, min_precedence: u8) -> ParseResult<i64> {
+        let mut lhs = self.unary_expr()?;
+
+        while let Some(token) = self.peek() {
+            if token.kind != TokenKind::Punct {
+                break;
+            }
+
+            let precedence = match token.text.as_str() {
+                "|" => 1,
+                "^" => 2,
+                "&" => 3,
+                "<<" | ">>" => 4,
+                "+" | "-" => 5,
+                "*" | "/" | "%" => 6,
+                _ => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+
+            let op = self.bump().text;
+            let rhs = self.binary_expr(precedence + 1)?;
+
+            lhs = match op.as_str() {
+                "|" => lhs | rhs,
+                "^" => lhs ^ rhs,
+                "&" => lhs & rhs,
+                "<<" => lhs.wrapping_shl(rhs as u32),
+                ">>" => lhs.wrapping_shr(rhs as u32),
+                "+" => lhs.wrapping_add(rhs),
+                "-" => lhs.wrapping_sub(rhs),
+                "*" => lhs.wrapping_mul(rhs),
+                "/" => lhs.checked_div(rhs).ok_or("division by zero")?,
+                "%" => lhs.checked_rem(rhs).ok_or("division by zero")?,
+                _ => unreachable!(),
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary_expr(&mut self) -> ParseResult<i64> {
+        if self.eat("-") {
+            return Ok(self.unary_expr()?.wrapping_neg());
+        }
+        if self.eat("+") {
+            return self.unary_expr();
+        }
+        if self.eat("~") {
+            return Ok(!self.unary_expr()?);
+        }
+        if self.eat("!") {
+            return Ok((self.unary_expr()? == 0) as i64);
+        }
+        if self.eat("(") {
+            let value = self.const_expr()?;
+            self.expect(")")?;
+            return Ok(value);
+        }
+
+        let Some(token) = self.peek().cloned() else {
+            return Err("expected a constant".to_string());
+        };
+
+        let value = match token.kind {
+            TokenKind::Int => parse_int(&token.text),
+            TokenKind::Char => parse_char(&token.text),
+            TokenKind::Ident => self.constants.get(&token.text).copied(),
+            _ => None,
+        }
+        .ok_or_else(|| format!("`{}` isn't a constant", token.text))?;
+
+        self.bump();
+
+        Ok(value)
+    }
+}
+
+fn parse_int(text: &str) -> Option<i64> {
+    let (digits, radix) = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(bin) = text.strip_prefix("0b").or(text.strip_prefix("0B")) {
+        (bin, 2)
+    } else if text.len() > 1 && text.starts_with('0') {
+        (&text[1..], 8)
+    } else {
+        (text, 10)
+    };
+
+    u64::from_str_radix(digits, radix).ok().map(|n| n as i64)
+}
+
+fn parse_char(text: &str) -> Option<i64> {
+    let inner = text.strip_prefix('\'')?.strip_suffix('\'')?;
+
+    let ch = match inner.strip_prefix('\\') {
+        Some("n") => '\n',
+        Some("t") => '\t',
+        Some("r") => '\r',
+        Some("0") => '\0',
+        Some("\\") => '\\',
+        Some("'") => '\'',
+        Some("\"") => '"',
+        Some(_) => return None,
+        None => {
+            let mut chars = inner.chars();
+            let ch = chars.next()?;
+            if chars.next().is_some() {
+                return None;
+            }
+            ch
+        }
+    };
+
+    Some(ch as i64)
+}
+
+fn is_type_keyword(text: &str) -> bool {
+    matches!(
+        text,
+        "void"
+            | "char"
+            | "short"
+            | "int"
+            | "long"
+            | "float"
+            | "double"
+            | "signed"
+            | "unsigned"
+            | "_Bool"
+            | "struct"
+            | "union"
+            | "enum"
+            | "const"
+            | "volatile"
+    )
+}
+
+/// the common typedefs from `<stdint.h>`, `<stddef.h>` and `<stdbool.h>`
+fn builtin_typedef(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "bool" => "bool",
+        "int8_t" => "i8",
+        "int16_t" => "i16",
+        "int32_t" => "i32",
+        "int64_t" => "i64",
+        "uint8_t" => "u8",
+        "uint16_t" => "u16",
+        "uint32_t" => "u32",
+        "uint64_t" => "u64",
+        "size_t" | "uintptr_t" => "usize",
+        "ssize_t" | "ptrdiff_t" | "intptr_t" => "isize",
+        _ => return None,
+    })
+}
+
+fn keywords_to_ty(keywords: &[String]) -> ParseResult<CType> {
+    let count = |keyword: &str| keywords.iter().filter(|k| *k == keyword).count();
+
+    let unsigned = count("unsigned") > 0;
+    let signed = count("signed") > 0 || count("__signed__") > 0;
+    let longs = count("long");
+
+    let ty = if count("void") > 0 {
+        return Ok(CType::Void);
+    } else if count("_Bool") > 0 {
+        "bool"
+    } else if count("float") > 0 {
+        "f32"
+    } else if count("double") > 0 {
+        if longs > 0 {
+            return Err("`long double`".to_string());
+        }
+        "f64"
+    } else if count("char") > 0 {
+        match (signed, unsigned) {
+            (true, _) => "i8",
+            (_, true) => "u8",
+            _ => "char",
+        }
+    } else if count("short") > 0 {
+        if unsigned {
+            "u16"
+        } else {
+            "i16"
+        }
+    } else {
+        match (longs, unsigned) {
+            (0, false) => "i32",
+            (0, true) => "u32",
+            (1, unsigned) => return Ok(CType::Long { unsigned }),
+            (_, false) => "i64",
+            (_, true) => "u64",
+        }
+    };
+
+    Ok(CType::Capy(ty))
+}
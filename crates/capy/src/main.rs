@@ -2,8 +2,16 @@ mod git;
 mod source;
 
 use std::{
-    cell::RefCell, env, io, mem, panic::AssertUnwindSafe, path::PathBuf, process::exit, rc::Rc,
-    str::FromStr, time::Instant,
+    cell::RefCell,
+    env,
+    ffi::CString,
+    io, mem,
+    panic::AssertUnwindSafe,
+    path::PathBuf,
+    process::{exit, ExitStatus},
+    rc::Rc,
+    str::FromStr,
+    time::Instant,
 };
 
 use clap::{Parser, Subcommand};
@@ -17,7 +25,7 @@ use path_clean::PathClean;
 use platform_dirs::AppDirs;
 use rustc_hash::FxHashMap;
 use std::fs;
-use target_lexicon::Triple;
+use target_lexicon::{Endianness, OperatingSystem, Triple};
 use uid_gen::UIDGenerator;
 
 use crate::source::SourceFile;
@@ -70,6 +78,17 @@ enum BuildAction {
         /// this literally works by passing the args to gcc with "-l"
         #[arg(long)]
         libs: Vec<String>,
+
+        /// Defines a value which can be read with `#def(NAME)` and checked with `#defined(NAME)`.
+        /// Leaving out the value defines it as `true`
+        #[arg(short = 'D', value_name = "NAME[=VALUE]")]
+        define: Vec<String>,
+
+        /// Writes a C header declaring all the `export`ed functions next to the output.
+        /// If there is no entry point, the output is an object file which can be linked into
+        /// a C program
+        #[arg(long)]
+        emit_header: bool,
     },
     /// Takes in one or more .capy files, compiles them, and runs the compiled executable
     Run {
@@ -107,6 +126,31 @@ enum BuildAction {
         /// this literally works by passing the args to gcc with "-l"
         #[arg(long)]
         libs: Vec<String>,
+
+        /// Defines a value which can be read with `#def(NAME)` and checked with `#defined(NAME)`.
+        /// Leaving out the value defines it as `true`
+        #[arg(short = 'D', value_name = "NAME[=VALUE]")]
+        define: Vec<String>,
+
+        /// The arguments to pass to the program, given after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+    /// Reads the declarations in a C header and writes them out as capy `extern` functions,
+    /// `struct`s, and `distinct` types
+    Bindgen {
+        /// The C header to read
+        #[arg(required = true)]
+        file: String,
+
+        /// The .capy file to write. Defaults to the name of the header
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// The target whose C type sizes are used, like the size of `long`.
+        /// Defaults to the host
+        #[arg(long)]
+        target: Option<String>,
     },
 }
 
@@ -116,11 +160,12 @@ macro_rules! get_build_config {
     ) => {
         match $action {
             BuildAction::Build {
-                $($property,)+ target
-            } => ($($property,)+ CompilationConfig::Compile(target)),
+                $($property,)+ target, emit_header
+            } => ($($property,)+ CompilationConfig::Compile { target, emit_header }),
             BuildAction::Run {
-                $($property,)+ jit
-            } => ($($property,)+ if jit { CompilationConfig::Jit } else { CompilationConfig::Run })
+                $($property,)+ jit, args
+            } => ($($property,)+ if jit { CompilationConfig::Jit(args) } else { CompilationConfig::Run(args) }),
+            BuildAction::Bindgen { .. } => unreachable!("`bindgen` doesn't compile anything"),
         }
     };
 }
@@ -128,7 +173,16 @@ macro_rules! get_build_config {
 fn main() -> io::Result<()> {
     let config = CompilerConfig::parse();
 
-    let (file, entry_point, output, verbose, mod_dir, redownload_core, libs, config) = get_build_config!(config.action => file, entry_point, output, verbose, mod_dir, redownload_core, libs);
+    if let BuildAction::Bindgen {
+        file,
+        output,
+        target,
+    } = config.action
+    {
+        return bindgen(file, output, target);
+    }
+
+    let (file, entry_point, output, verbose, mod_dir, redownload_core, libs, define, config) = get_build_config!(config.action => file, entry_point, output, verbose, mod_dir, redownload_core, libs, define);
 
     let file = env::current_dir()
         .unwrap()
@@ -153,14 +207,83 @@ fn main() -> io::Result<()> {
         config,
         verbose,
         &libs,
+        &define,
     )
 }
 
+fn bindgen(file: String, output: Option<String>, target: Option<String>) -> io::Result<()> {
+    let with_color = supports_color::on(supports_color::Stream::Stdout).is_some();
+    let (ansi_green, ansi_reset) = if with_color {
+        (ANSI_GREEN, ANSI_RESET)
+    } else {
+        ("", "")
+    };
+
+    let start = Instant::now();
+
+    let file = env::current_dir()
+        .unwrap()
+        .join(file.replace(['/', '\\'], std::path::MAIN_SEPARATOR_STR))
+        .clean();
+
+    let contents = match fs::read_to_string(&file) {
+        Ok(contents) => contents,
+        Err(why) => {
+            println!("{}: {}", file.display(), why);
+            exit(1)
+        }
+    };
+
+    let target = target
+        .map(|target| {
+            Triple::from_str(&target).unwrap_or_else(|msg| {
+                println!("invalid target: {}", msg);
+                exit(1);
+            })
+        })
+        .unwrap_or_else(Triple::host);
+
+    // `long` is 32 bits on Windows, and the size of a pointer everywhere else
+    let long_bit_width = if target.operating_system == OperatingSystem::Windows {
+        32
+    } else {
+        target.pointer_width().unwrap().bits() as u32
+    };
+
+    let header_name = file.file_name().unwrap().to_string_lossy();
+    let bindings = bindgen::bindgen(&contents, &header_name, long_bit_width);
+
+    let output = match output {
+        Some(output) => env::current_dir().unwrap().join(output).clean(),
+        None => env::current_dir()
+            .unwrap()
+            .join(file.file_stem().unwrap())
+            .with_extension("capy"),
+    };
+
+    fs::write(&output, bindings).unwrap_or_else(|why| {
+        println!("{}: {why}", output.display());
+        exit(1);
+    });
+
+    println!(
+        "{ansi_green}Finished{ansi_reset}   {} in {:.2}s",
+        output.display(),
+        start.elapsed().as_secs_f32(),
+    );
+
+    Ok(())
+}
+
 #[derive(Clone, PartialEq)]
 enum CompilationConfig {
-    Compile(Option<String>),
-    Run,
-    Jit,
+    Compile {
+        target: Option<String>,
+        emit_header: bool,
+    },
+    /// holds the arguments to pass to the program
+    Run(Vec<String>),
+    Jit(Vec<String>),
 }
 
 const ANSI_RED: &str = "\x1B[1;91m";
@@ -179,6 +302,7 @@ fn compile_file(
     config: CompilationConfig,
     verbose: u8,
     libs: &[String],
+    defines: &[String],
 ) -> io::Result<()> {
     let with_color = supports_color::on(supports_color::Stream::Stdout).is_some();
     let (ansi_red, ansi_green, ansi_white, ansi_reset) = if with_color {
@@ -231,7 +355,7 @@ fn compile_file(
     }
 
     let target = match &config {
-        CompilationConfig::Compile(target) => target.as_deref(),
+        CompilationConfig::Compile { target, .. } => target.as_deref(),
         _ => None,
     }
     .map(|target| {
@@ -242,6 +366,8 @@ fn compile_file(
     })
     .unwrap_or_else(Triple::host);
 
+    let build_config = build_config(&target, defines);
+
     println!("{ansi_green}Compiling{ansi_reset}  ...");
     let compilation_start = Instant::now();
 
@@ -265,12 +391,14 @@ fn compile_file(
         world_index.clone(),
         world_bodies.clone(),
         &mod_dir,
+        &build_config,
         verbose,
     );
 
     line_indexes.insert(source_file.module, LineIndex::new(&file_contents));
 
-    let mut current_imports = source_file.build_bodies(&mod_dir);
+    let root_file = source_file.module;
+    let mut current_imports = source_file.build_bodies(&mod_dir, &build_config);
     source_files.insert(source_file.module, source_file);
 
     // find all imports in the source file, compile them, then do the same for their imports
@@ -302,12 +430,13 @@ fn compile_file(
                 world_index.clone(),
                 world_bodies.clone(),
                 &mod_dir,
+                &build_config,
                 verbose,
             );
 
             line_indexes.insert(source_file.module, LineIndex::new(&file_contents));
 
-            let imports = source_file.build_bodies(&mod_dir);
+            let imports = source_file.build_bodies(&mod_dir, &build_config);
             current_imports.extend(imports);
 
             source_files.insert(source_file.module, source_file);
@@ -438,7 +567,13 @@ fn compile_file(
         target.pointer_width().unwrap().bits(),
     );
 
+    // libraries don't need an entry point, they just have to export something
+    let is_library = main_files.is_empty()
+        && matches!(config, CompilationConfig::Compile { .. })
+        && !world_bodies.borrow().find_exports().is_empty();
+
     match main_files.len().cmp(&1) {
+        std::cmp::Ordering::Less if is_library => {}
         std::cmp::Ordering::Less => {
             println!(
                 "{ansi_red}error{ansi_white}: there is no `{}` function{ansi_reset}",
@@ -480,7 +615,7 @@ fn compile_file(
         Verbosity::None
     };
 
-    if config == CompilationConfig::Jit {
+    if let CompilationConfig::Jit(args) = &config {
         let jit_fn = codegen::compile_jit(
             comp_verbosity,
             entry_point.unwrap(),
@@ -500,15 +635,56 @@ fn compile_file(
             "{ansi_green}Running{ansi_reset}    `{}`\n",
             main_file.unwrap().to_string(&mod_dir, &interner)
         );
-        let status = jit_fn(0, 0);
+
+        // the first argument is always the program itself, just like with a real executable
+        let args = std::iter::once(main_file.unwrap().to_string(&mod_dir, &interner))
+            .chain(args.iter().cloned())
+            .map(|arg| CString::new(arg).expect("arguments cannot contain null bytes"))
+            .collect_vec();
+        let argv = args
+            .iter()
+            .map(|arg| arg.as_ptr())
+            .chain(std::iter::once(std::ptr::null()))
+            .collect_vec();
+
+        let status = jit_fn(args.len() as i32, argv.as_ptr() as usize);
         println!("\nProcess exited with {}", status);
 
-        return Ok(());
+        exit(status as i32);
     }
 
+    let main_file = main_file.copied().unwrap_or(root_file);
+
+    let output = output.unwrap_or_else(|| {
+        let main_file = std::path::PathBuf::from(interner.lookup(main_file.0));
+        main_file.file_stem().unwrap().to_string_lossy().to_string()
+    });
+
+    let header = match config {
+        CompilationConfig::Compile {
+            emit_header: true, ..
+        } => match codegen::generate_header(
+            &output,
+            is_library,
+            &mod_dir,
+            &interner,
+            &world_bodies.borrow(),
+            &tys,
+            target.pointer_width().unwrap().bits() as u32,
+        ) {
+            Ok(header) => Some(header),
+            Err(why) => {
+                println!("{ansi_red}error{ansi_white}: {why}{ansi_reset}");
+                exit(1);
+            }
+        },
+        _ => None,
+    };
+
     let bytes = match codegen::compile_obj(
         comp_verbosity,
-        entry_point.unwrap(),
+        main_file,
+        entry_point,
         &mod_dir,
         &interner,
         &world_bodies.borrow(),
@@ -527,10 +703,6 @@ fn compile_file(
 
     let _ = fs::create_dir(&output_folder);
 
-    let output = output.unwrap_or_else(|| {
-        let main_file = std::path::PathBuf::from(interner.lookup(main_file.unwrap().0));
-        main_file.file_stem().unwrap().to_string_lossy().to_string()
-    });
     let mut object_file = output_folder.join(&output);
     object_file.set_extension("o");
     fs::write(&object_file, bytes.as_slice()).unwrap_or_else(|why| {
@@ -538,7 +710,20 @@ fn compile_file(
         exit(1);
     });
 
-    if let CompilationConfig::Compile(Some(target)) = config {
+    if let Some(header) = header {
+        let mut header_file = output_folder.join(&output);
+        header_file.set_extension("h");
+        fs::write(&header_file, header).unwrap_or_else(|why| {
+            println!("{}: {why}", header_file.display());
+            exit(1);
+        });
+    }
+
+    if let CompilationConfig::Compile {
+        target: Some(target),
+        ..
+    } = config
+    {
         println!(
             "{ansi_green}Finished{ansi_reset}   {} ({}) in {:.2}s",
             object_file.display(),
@@ -548,6 +733,15 @@ fn compile_file(
         return Ok(());
     }
 
+    if is_library {
+        println!(
+            "{ansi_green}Finished{ansi_reset}   {} (library) in {:.2}s",
+            object_file.display(),
+            compilation_start.elapsed().as_secs_f32(),
+        );
+        return Ok(());
+    }
+
     let exec = codegen::link_to_exec(&object_file, target, libs);
     println!(
         "{ansi_green}Finished{ansi_reset}   {} ({}) in {:.2}s",
@@ -556,19 +750,58 @@ fn compile_file(
         compilation_start.elapsed().as_secs_f32(),
     );
 
-    if matches!(config, CompilationConfig::Compile(_)) {
+    let CompilationConfig::Run(args) = config else {
         return Ok(());
-    }
+    };
 
     println!("{ansi_green}Running{ansi_reset}    `{}`\n", exec.display());
-    match std::process::Command::new(exec).status() {
+    match std::process::Command::new(exec).args(args).status() {
         Ok(status) => {
             println!("\nProcess exited with {}", status);
+            exit(exit_code(status));
         }
         Err(why) => {
             println!("\nProcess exited early: {}", why);
+            exit(1);
+        }
+    }
+}
+
+/// the code to exit `capy` with so that scripts can tell how the program went
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        // mimic how shells report programs killed by a signal
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
         }
     }
 
-    Ok(())
+    status.code().unwrap_or(1)
+}
+
+/// the information available to `#if` and directives like `#os`
+fn build_config(target: &Triple, defines: &[String]) -> hir::BuildConfig {
+    let os = match target.operating_system {
+        OperatingSystem::Darwin | OperatingSystem::MacOSX { .. } => "macos".to_string(),
+        os => os.to_string(),
+    };
+
+    let defines = defines
+        .iter()
+        .map(|define| match define.split_once('=') {
+            Some((name, value)) => (name.to_string(), hir::ConfigValue::parse(value)),
+            None => (define.to_string(), hir::ConfigValue::Bool(true)),
+        })
+        .collect();
+
+    hir::BuildConfig {
+        os,
+        arch: target.architecture.to_string(),
+        pointer_bit_width: target.pointer_width().unwrap().bits() as u32,
+        big_endian: target.endianness() == Ok(Endianness::Big),
+        defines,
+    }
 }
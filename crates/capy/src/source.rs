@@ -34,6 +34,7 @@ impl SourceFile {
         world_index: Rc<RefCell<hir::WorldIndex>>,
         world_bodies: Rc<RefCell<hir::WorldBodies>>,
         mod_dir: &std::path::Path,
+        config: &hir::BuildConfig,
         verbose: u8,
     ) -> SourceFile {
         let module = hir::FileName(interner.borrow_mut().intern(&file_name.to_string_lossy()));
@@ -54,7 +55,8 @@ impl SourceFile {
 
         let validation_diagnostics = ast::validation::validate(root, tree);
 
-        let (index, indexing_diagnostics) = hir::index(root, tree, &mut interner.borrow_mut());
+        let (index, indexing_diagnostics) =
+            hir::index(root, tree, &mut interner.borrow_mut(), config);
 
         let mut res = Self {
             file_name,
@@ -94,7 +96,11 @@ impl SourceFile {
         res
     }
 
-    pub(crate) fn build_bodies(&mut self, mod_dir: &std::path::Path) -> FxHashSet<FileName> {
+    pub(crate) fn build_bodies(
+        &mut self,
+        mod_dir: &std::path::Path,
+        config: &hir::BuildConfig,
+    ) -> FxHashSet<FileName> {
         let tree = self.parse.syntax_tree();
 
         let (bodies, lowering_diagnostics) = hir::lower(
@@ -105,6 +111,7 @@ impl SourceFile {
             &mut self.uid_gen.borrow_mut(),
             &mut self.interner.borrow_mut(),
             mod_dir,
+            config,
             false,
         );
 
@@ -11,11 +11,18 @@ pub(crate) enum BuiltinGlobal {
     ArrayLayout,
     DistinctLayout,
     StructLayout,
+    UnionLayout,
+    OptionalLayout,
+    TupleLayout,
     ArrayInfo,
     SliceInfo,
     PointerInfo,
     DistinctInfo,
     StructInfo,
+    UnionInfo,
+    OptionalInfo,
+    TupleInfo,
+    FunctionInfo,
     PointerLayout,
 }
 
@@ -79,11 +86,18 @@ pub(crate) fn as_compiler_defined_global(
         ("meta.capy", "array_layouts") => BuiltinGlobal::ArrayLayout,
         ("meta.capy", "distinct_layouts") => BuiltinGlobal::DistinctLayout,
         ("meta.capy", "struct_layouts") => BuiltinGlobal::StructLayout,
+        ("meta.capy", "union_layouts") => BuiltinGlobal::UnionLayout,
+        ("meta.capy", "optional_layouts") => BuiltinGlobal::OptionalLayout,
+        ("meta.capy", "tuple_layouts") => BuiltinGlobal::TupleLayout,
         ("meta.capy", "array_infos") => BuiltinGlobal::ArrayInfo,
         ("meta.capy", "slice_infos") => BuiltinGlobal::SliceInfo,
         ("meta.capy", "pointer_infos") => BuiltinGlobal::PointerInfo,
         ("meta.capy", "distinct_infos") => BuiltinGlobal::DistinctInfo,
         ("meta.capy", "struct_infos") => BuiltinGlobal::StructInfo,
+        ("meta.capy", "union_infos") => BuiltinGlobal::UnionInfo,
+        ("meta.capy", "optional_infos") => BuiltinGlobal::OptionalInfo,
+        ("meta.capy", "tuple_infos") => BuiltinGlobal::TupleInfo,
+        ("meta.capy", "function_infos") => BuiltinGlobal::FunctionInfo,
         ("meta.capy", "pointer_layout") => BuiltinGlobal::PointerLayout,
         _ => return None,
     })
@@ -151,9 +151,13 @@ pub fn eval_comptime_blocks<'a>(
         meta_tys: MetaTyData::default(),
         functions: FxHashMap::default(),
         compiler_defined_functions: FxHashMap::default(),
+        var_args_trampolines: FxHashMap::default(),
+        vtables: FxHashMap::default(),
         data: FxHashMap::default(),
+        global_inits: FxHashMap::default(),
         str_id_gen: UIDGenerator::default(),
         i128_id_gen: UIDGenerator::default(),
+        embeds: FxHashMap::default(),
         comptime_results: results,
         comptime_data: FxHashMap::default(),
         ptr_ty: match target_pointer_bit_width {
@@ -167,6 +171,7 @@ pub fn eval_comptime_blocks<'a>(
     };
 
     compiler.finalize_tys();
+    compiler.declare_libc_externs();
 
     let mut already_done: FxHashSet<_> = to_eval
         .iter()
@@ -191,6 +196,7 @@ pub fn eval_comptime_blocks<'a>(
             ctc.expr,
             vec![],
             return_ty,
+            None,
         );
 
         let extra: Vec<_> = compiler
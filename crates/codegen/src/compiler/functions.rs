@@ -1,7 +1,7 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, path::Path};
 
 use cranelift::{
-    codegen::ir::{Endianness, FuncRef},
+    codegen::ir::{Endianness, FuncRef, Function, Signature, UserFuncName},
     prelude::{
         types, Block, FloatCC, FunctionBuilder, InstBuilder, IntCC, MemFlags, StackSlotData,
         StackSlotKind, TrapCode, Value, Variable,
@@ -9,7 +9,7 @@ use cranelift::{
 };
 use cranelift_module::{DataDescription, DataId, FuncId, Linkage, Module};
 use hir::{FQComptime, LocalDef, ScopeId};
-use hir_ty::{ComptimeResult, Ty};
+use hir_ty::{CallArg, ComptimeResult, DefaultValue, InternTyExt, MethodCall, Ty};
 use interner::Interner;
 use internment::Intern;
 use la_arena::Idx;
@@ -19,18 +19,24 @@ use uid_gen::UIDGenerator;
 use crate::{
     builtin::{self, BuiltinFunction},
     convert::{GetFinalTy, ToTyId},
-    layout::GetLayoutInfo,
+    layout::{padding_needed_for, GetLayoutInfo},
     mangle::Mangle,
 };
 
 use super::{
-    abi::{Abi, FnAbi},
+    abi::{x86_64::Trampoline, Abi, FnAbi},
+    closure_env_ty,
     comptime::{ComptimeBytes, IntBytes},
     ComptimeData, FunctionToCompile, MemoryLoc, MetaTyData, MetaTyInfoArrays, MetaTyLayoutArrays,
 };
 
 struct UnfinishedComptimeErr;
 
+enum Callee {
+    Direct(FuncRef),
+    Indirect(Value),
+}
+
 // represents a single block containing multiple defer statements
 #[derive(Debug, Clone)]
 pub(crate) struct DeferFrame {
@@ -62,9 +68,14 @@ pub(crate) struct FunctionCompiler<'a> {
     // globals
     pub(crate) functions: &'a mut FxHashMap<hir::Fqn, FuncId>,
     pub(crate) compiler_defined_functions: &'a mut FxHashMap<BuiltinFunction, FuncId>,
+    pub(crate) var_args_trampolines: &'a mut FxHashMap<Trampoline, FuncId>,
+    /// the function tables of interfaces, keyed by the functions inside them
+    pub(crate) vtables: &'a mut FxHashMap<Vec<hir::Fqn>, DataId>,
     pub(crate) globals: &'a mut FxHashMap<hir::Fqn, DataId>,
     pub(crate) str_id_gen: &'a mut UIDGenerator,
     pub(crate) i128_id_gen: &'a mut UIDGenerator,
+    /// the contents of `#embed`s, keyed by the expression they come from
+    pub(crate) embeds: &'a mut FxHashMap<(hir::FileName, Idx<hir::Expr>), DataId>,
     pub(crate) comptime_results: &'a FxHashMap<FQComptime, ComptimeResult>,
     pub(crate) comptime_data: &'a mut FxHashMap<FQComptime, ComptimeData>,
 
@@ -72,6 +83,8 @@ pub(crate) struct FunctionCompiler<'a> {
     pub(crate) var_id_gen: UIDGenerator,
     pub(crate) locals: FxHashMap<Idx<LocalDef>, Value>,
     pub(crate) params: FxHashMap<u64, Variable>,
+    /// the closure being compiled, if any. its environment is the last parameter
+    pub(crate) closure: Option<Idx<hir::Lambda>>,
 
     // for control flow (breaks and continues)
     pub(crate) exits: FxHashMap<ScopeId, Block>,
@@ -96,6 +109,34 @@ impl FunctionCompiler<'_> {
         self.builder.finalize();
     }
 
+    /// Builds a function which computes the value of a runtime global and stores it
+    pub(crate) fn finish_global_init(
+        mut self,
+        global: DataId,
+        global_ty: Intern<Ty>,
+        body: Idx<hir::Expr>,
+        debug_print: bool,
+    ) {
+        let entry_block = self.builder.create_block();
+
+        self.builder.switch_to_block(entry_block);
+        self.builder.seal_block(entry_block);
+
+        let global_ptr = self.module.declare_data_in_func(global, self.builder.func);
+        let global_ptr = self.builder.ins().symbol_value(self.ptr_ty, global_ptr);
+
+        self.store_expr_in_memory(body, global_ty, MemoryLoc::from_addr(global_ptr, 0));
+
+        self.builder.ins().return_(&[]);
+        self.builder.seal_all_blocks();
+
+        if debug_print {
+            println!("{}", self.builder.func);
+        }
+
+        self.builder.finalize();
+    }
+
     /// Returns `None` if any inner comptime blocks haven't been evaluated yet
     fn expr_to_const_data(
         &mut self,
@@ -154,10 +195,14 @@ impl FunctionCompiler<'_> {
                 _ => unreachable!(),
             },
             hir::Expr::BoolLiteral(b) => Box::new([b as u8]),
-            hir::Expr::StringLiteral(mut text) => {
-                text.push('\0');
-                text.into_bytes().into()
+            // null pointers and empty optionals are both all zeroes
+            hir::Expr::NullLiteral => {
+                vec![0; self.tys[file_name][expr].size() as usize].into_boxed_slice()
             }
+            // a str points to its bytes, which can't be represented as plain data,
+            // so it gets compiled wherever it's used instead
+            hir::Expr::StringLiteral(_) => return Err(UnfinishedComptimeErr),
+            hir::Expr::Embed(contents) => contents.as_ref().into(),
             hir::Expr::ArrayLiteral { items, .. } => {
                 assert_ne!(items.len(), 0);
 
@@ -279,6 +324,24 @@ impl FunctionCompiler<'_> {
                             .get_or_insert_with(|| MetaTyLayoutArrays::new(self.module))
                             .struct_layout_slice
                     }
+                    builtin::BuiltinGlobal::UnionLayout => {
+                        self.meta_tys
+                            .layout_arrays
+                            .get_or_insert_with(|| MetaTyLayoutArrays::new(self.module))
+                            .union_layout_slice
+                    }
+                    builtin::BuiltinGlobal::OptionalLayout => {
+                        self.meta_tys
+                            .layout_arrays
+                            .get_or_insert_with(|| MetaTyLayoutArrays::new(self.module))
+                            .optional_layout_slice
+                    }
+                    builtin::BuiltinGlobal::TupleLayout => {
+                        self.meta_tys
+                            .layout_arrays
+                            .get_or_insert_with(|| MetaTyLayoutArrays::new(self.module))
+                            .tuple_layout_slice
+                    }
                     builtin::BuiltinGlobal::PointerLayout => {
                         self.meta_tys
                             .layout_arrays
@@ -315,6 +378,30 @@ impl FunctionCompiler<'_> {
                             .get_or_insert_with(|| MetaTyInfoArrays::new(self.module))
                             .struct_info_slice
                     }
+                    builtin::BuiltinGlobal::UnionInfo => {
+                        self.meta_tys
+                            .info_arrays
+                            .get_or_insert_with(|| MetaTyInfoArrays::new(self.module))
+                            .union_info_slice
+                    }
+                    builtin::BuiltinGlobal::OptionalInfo => {
+                        self.meta_tys
+                            .info_arrays
+                            .get_or_insert_with(|| MetaTyInfoArrays::new(self.module))
+                            .optional_info_slice
+                    }
+                    builtin::BuiltinGlobal::TupleInfo => {
+                        self.meta_tys
+                            .info_arrays
+                            .get_or_insert_with(|| MetaTyInfoArrays::new(self.module))
+                            .tuple_info_slice
+                    }
+                    builtin::BuiltinGlobal::FunctionInfo => {
+                        self.meta_tys
+                            .info_arrays
+                            .get_or_insert_with(|| MetaTyInfoArrays::new(self.module))
+                            .function_info_slice
+                    }
                 });
             }
 
@@ -333,15 +420,51 @@ impl FunctionCompiler<'_> {
             return Ok(global);
         }
 
+        let ty = self.tys[fqn].0;
+
+        if self.tys.is_runtime_global(fqn) {
+            // the actual value gets stored by the global's initializer
+            let global = self
+                .module
+                .declare_data(
+                    &fqn.to_mangled_name(self.mod_dir, self.interner),
+                    Linkage::Export,
+                    true,
+                    false,
+                )
+                .expect("error declaring data");
+
+            self.data_description.define_zeroinit(ty.size() as usize);
+            self.data_description.set_align(ty.align() as u64);
+            self.module
+                .define_data(global, self.data_description)
+                .expect("error defining data");
+            self.data_description.clear();
+
+            self.globals.insert(fqn, global);
+
+            return Ok(global);
+        }
+
         let value = self.world_bodies.body(fqn);
 
-        let bytes = self.expr_to_const_data(fqn.file, value)?;
+        let mut bytes = self.expr_to_const_data(fqn.file, value)?;
+
+        // `foo : ?i32 : 5` has to be wrapped
+        if let Some(sub_ty) = ty.as_optional() {
+            if !sub_ty.has_null_niche() && !self.tys[fqn.file][value].is_optional() {
+                let mut wrapped = bytes.into_vec();
+                wrapped.resize(sub_ty.size() as usize, 0);
+                wrapped.push(1);
+                bytes = wrapped.into_boxed_slice();
+            }
+        }
 
         let global = self.create_global_data(
             &fqn.to_mangled_name(self.mod_dir, self.interner),
             true,
             bytes,
-            self.tys[fqn].0.align() as u64,
+            ty.align() as u64,
         );
 
         self.globals.insert(fqn, global);
@@ -383,11 +506,25 @@ impl FunctionCompiler<'_> {
     }
 
     fn create_global_str(&mut self, mut text: String) -> DataId {
+        // the null isn't part of the str's len, but it lets the str be casted to a cstr
         text.push('\0');
         let name = format!(".str_{}", self.str_id_gen.generate_unique_id());
         self.create_global_data(&name, false, text.into_bytes().into_boxed_slice(), 1)
     }
 
+    fn get_embed(&mut self, expr: Idx<hir::Expr>, contents: &[u8]) -> DataId {
+        if let Some(data) = self.embeds.get(&(self.file_name, expr)) {
+            return *data;
+        }
+
+        let name = format!(".embed_{}", self.embeds.len());
+        let data = self.create_global_data(&name, false, contents.into(), 1);
+
+        self.embeds.insert((self.file_name, expr), data);
+
+        data
+    }
+
     fn create_global_i128(&mut self, num: u64) -> DataId {
         let name = format!(".i128_{}", self.i128_id_gen.generate_unique_id());
         self.create_global_data(
@@ -442,19 +579,92 @@ impl FunctionCompiler<'_> {
         local_func
     }
 
+    /// the vtable of an interface is just the addresses of its functions, one after the other
+    fn get_vtable(&mut self, impls: Vec<hir::Fqn>) -> DataId {
+        if let Some(vtable) = self.vtables.get(&impls) {
+            return *vtable;
+        }
+
+        let vtable = self
+            .module
+            .declare_anonymous_data(false, false)
+            .expect("error declaring vtable");
+
+        // `define_zeroinit` would put the vtable in `.bss`, which can't hold relocations
+        self.data_description
+            .define(vec![0; impls.len() * self.ptr_ty.bytes() as usize].into_boxed_slice());
+        self.data_description.set_align(self.ptr_ty.bytes() as u64);
+
+        for (idx, fqn) in impls.iter().enumerate() {
+            let func_id = self.get_func_id(*fqn);
+            let func_ref = self
+                .module
+                .declare_func_in_data(func_id, self.data_description);
+            self.data_description
+                .write_function_addr(idx as u32 * self.ptr_ty.bytes(), func_ref);
+        }
+
+        self.module
+            .define_data(vtable, self.data_description)
+            .expect("error defining vtable");
+        self.data_description.clear();
+
+        self.vtables.insert(impls, vtable);
+
+        vtable
+    }
+
+    fn get_var_args_trampoline(&mut self, trampoline: Trampoline) -> FuncRef {
+        let func_id = match self.var_args_trampolines.get(&trampoline) {
+            Some(func_id) => *func_id,
+            None => {
+                // the trampoline never touches its arguments, so the signature doesn't matter
+                let sig = Signature::new(self.module.target_config().default_call_conv);
+
+                let func_id = self
+                    .module
+                    .declare_anonymous_function(&sig)
+                    .expect("error declaring variadic trampoline");
+
+                self.module
+                    .define_function_bytes(
+                        func_id,
+                        &Function::with_name_signature(UserFuncName::default(), sig),
+                        16,
+                        &trampoline.to_bytes(),
+                        &[],
+                    )
+                    .expect("error defining variadic trampoline");
+
+                self.var_args_trampolines.insert(trampoline, func_id);
+
+                func_id
+            }
+        };
+
+        self.module.declare_func_in_func(func_id, self.builder.func)
+    }
+
     fn compile_global(&mut self, fqn: hir::Fqn, no_load: bool) -> Option<Value> {
         let ty = &self.tys[fqn].0;
 
         if ty.is_zero_sized() {
             return None;
-        } else if ty.is_function() {
+        } else if ty.is_function() && !self.tys.is_runtime_global(fqn) {
             let local_func = self.get_local_func(fqn);
 
             return Some(self.builder.ins().func_addr(self.ptr_ty, local_func));
         }
 
+        // runtime globals get initialized by the generated `main` function,
+        // which never runs when evaluating comptime blocks
+        assert!(
+            self.final_binary || !self.tys.is_runtime_global(fqn),
+            "hir_ty should've reported the runtime global being used at compile time"
+        );
+
         let Ok(global_data) = self.compile_global_binding_data(fqn) else {
-            // there was an unfinished comptime
+            // there was an unfinished comptime, or the value isn't available yet
             let body = self.world_bodies.body(fqn);
 
             // todo: could this cause issues?
@@ -473,7 +683,11 @@ impl FunctionCompiler<'_> {
 
         let final_ty = ty.get_final_ty();
 
-        if no_load || final_ty.is_pointer_type() {
+        // the data of a constant string (for example) is the string itself, but the data
+        // of a runtime global always holds the actual value, even if it's a pointer
+        let holds_pointer = self.tys.is_runtime_global(fqn) && !ty.is_aggregate();
+
+        if no_load || (final_ty.is_pointer_type() && !holds_pointer) {
             Some(global_ptr)
         } else {
             Some(self.builder.ins().load(
@@ -498,12 +712,14 @@ impl FunctionCompiler<'_> {
             hir::Stmt::LocalDef(local_def) => {
                 let ty = self.tys[self.file_name][local_def];
 
-                let value = self.world_bodies[self.file_name][local_def].value;
+                let local_def_body = &self.world_bodies[self.file_name][local_def];
+                let value = local_def_body.value;
+                let uninit = local_def_body.uninit;
 
                 let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
                     kind: StackSlotKind::ExplicitSlot,
                     size: ty.size(),
-                    align_shift: ty.align() as u8,
+                    align_shift: ty.align().trailing_zeros() as u8,
                 });
 
                 let memory = MemoryLoc::from_stack(stack_slot, 0);
@@ -512,7 +728,7 @@ impl FunctionCompiler<'_> {
                     // the type of the value might not be the same as the type annotation of the
                     // declaration
                     self.compile_and_cast_into_memory(value, ty, memory);
-                } else {
+                } else if !uninit {
                     self.store_default_in_memory(ty, memory);
                 }
 
@@ -626,6 +842,7 @@ impl FunctionCompiler<'_> {
             },
             Ty::Bool => self.builder.ins().iconst(types::I8, 0),
             Ty::String => unreachable!("str does not have a default value"),
+            Ty::CString => unreachable!("cstr does not have a default value"),
             Ty::Char => self.builder.ins().iconst(types::I8, 0),
             Ty::Array { size, sub_ty, .. } => {
                 let inner_stride = sub_ty.stride();
@@ -646,19 +863,49 @@ impl FunctionCompiler<'_> {
             Ty::Any => unreachable!("any does not have a default value"),
             Ty::File(_) => unreachable!("files do not have default values"),
             Ty::Function { .. } => unreachable!("functions do not have default values"),
-            Ty::Struct { members, .. } => {
+            Ty::Closure { .. } => unreachable!("closures do not have default values"),
+            Ty::Interface { .. } => unreachable!("interfaces do not have default values"),
+            Ty::Struct {
+                members, defaults, ..
+            } => {
                 let struct_mem = expected_ty.struct_layout().unwrap();
 
                 for (idx, (_, ty)) in members.iter().enumerate() {
-                    self.store_default_in_memory(
-                        *ty,
-                        memory.with_offset(struct_mem.offsets()[idx]),
-                    );
+                    let memory = memory.with_offset(struct_mem.offsets()[idx]);
+                    match defaults.get(idx) {
+                        Some(Some(default)) => self.store_member_default(*default, *ty, memory),
+                        _ => self.store_default_in_memory(*ty, memory),
+                    }
+                }
+                return;
+            }
+            Ty::Tuple { item_tys } => {
+                let tuple_mem = expected_ty.struct_layout().unwrap();
+
+                for (idx, ty) in item_tys.iter().enumerate() {
+                    self.store_default_in_memory(*ty, memory.with_offset(tuple_mem.offsets()[idx]));
+                }
+                return;
+            }
+            Ty::Union { members, .. } => {
+                // the biggest member will zero out the whole union
+                for (_, ty) in members {
+                    self.store_default_in_memory(*ty, memory);
                 }
                 return;
             }
+            // optionals start out as null
+            Ty::Optional { sub_ty } if sub_ty.has_null_niche() => {
+                self.builder.ins().iconst(self.ptr_ty, 0)
+            }
+            Ty::Optional { sub_ty } => {
+                let tag = self.builder.ins().iconst(types::I8, 0);
+                memory.store(&mut self.builder, tag, sub_ty.size() as i32);
+                return;
+            }
             // void is just a no-op
             Ty::Void => return,
+            Ty::Null => return,
             Ty::NoEval => return,
         };
 
@@ -693,6 +940,9 @@ impl FunctionCompiler<'_> {
                 members: member_values,
                 ..
             } => self.store_struct_fields(expected_ty, member_values, memory),
+            hir::Expr::Tuple { items } => {
+                self.store_tuple_items(expected_ty, items.iter().copied(), memory)
+            }
             _ => {
                 let val = self.compile_expr(expr);
 
@@ -707,10 +957,16 @@ impl FunctionCompiler<'_> {
         field_values: &[(Option<hir::NameWithRange>, Idx<hir::Expr>)],
         memory: MemoryLoc,
     ) {
-        assert!(struct_ty.is_struct());
-
-        let field_tys = struct_ty.as_struct().unwrap();
-        let struct_mem = struct_ty.struct_layout().unwrap();
+        assert!(struct_ty.is_struct() || struct_ty.is_union());
+
+        // union literals have a single member, which always goes at the start
+        let (field_tys, offsets) = match struct_ty.as_union() {
+            Some(members) => (members, Vec::new()),
+            None => (
+                struct_ty.as_struct().unwrap(),
+                struct_ty.struct_layout().unwrap().offsets().to_vec(),
+            ),
+        };
 
         for (name, value) in field_values {
             let field = field_tys
@@ -722,7 +978,91 @@ impl FunctionCompiler<'_> {
             self.store_expr_in_memory(
                 *value,
                 field.1 .1,
-                memory.with_offset(struct_mem.offsets()[field.0]),
+                memory.with_offset(offsets.get(field.0).copied().unwrap_or(0)),
+            );
+        }
+
+        // the fields which weren't given get their default values
+        let defaults = struct_ty.struct_defaults().unwrap_or_default();
+        for (idx, (field_name, field_ty)) in field_tys.iter().enumerate() {
+            let Some(Some(default)) = defaults.get(idx) else {
+                continue;
+            };
+
+            if field_values
+                .iter()
+                .any(|(name, _)| name.is_some_and(|name| name.name == *field_name))
+            {
+                continue;
+            }
+
+            self.store_member_default(*default, *field_ty, memory.with_offset(offsets[idx]));
+        }
+    }
+
+    /// The default value of a struct member might've been declared in another file,
+    /// so it gets compiled as if we were in that file
+    fn store_member_default(
+        &mut self,
+        default: DefaultValue,
+        member_ty: Intern<Ty>,
+        memory: MemoryLoc,
+    ) {
+        let expr = self.default_value_expr(default);
+
+        let old_file_name = std::mem::replace(&mut self.file_name, default.file);
+        self.store_expr_in_memory(expr, member_ty, memory);
+        self.file_name = old_file_name;
+    }
+
+    /// Same as `store_member_default` but for the default value of a parameter
+    fn compile_param_default(
+        &mut self,
+        default: DefaultValue,
+        param_ty: Intern<Ty>,
+    ) -> Option<Value> {
+        let expr = self.default_value_expr(default);
+
+        let old_file_name = std::mem::replace(&mut self.file_name, default.file);
+        let value = self.compile_and_cast(expr, param_ty);
+        self.file_name = old_file_name;
+
+        value
+    }
+
+    fn default_value_expr(&self, default: DefaultValue) -> Idx<hir::Expr> {
+        let mut expr = default.expr;
+
+        // locals only exist within the function that declared the default,
+        // but since they're constant we can just use their values
+        while let hir::Expr::Local(local) = &self.world_bodies[default.file][expr] {
+            let local_def = &self.world_bodies[default.file][*local];
+            if local_def.mutable {
+                break;
+            }
+            let Some(value) = local_def.value else {
+                break;
+            };
+            expr = value;
+        }
+
+        expr
+    }
+
+    fn store_tuple_items(
+        &mut self,
+        tuple_ty: Intern<Ty>,
+        items: impl Iterator<Item = Idx<hir::Expr>>,
+        memory: MemoryLoc,
+    ) {
+        let item_tys = tuple_ty.as_tuple().unwrap();
+        let tuple_mem = tuple_ty.struct_layout().unwrap();
+
+        for (idx, item) in items.enumerate() {
+            self.store_expr_in_memory(
+                item,
+                item_tys[idx],
+                memory.with_offset(tuple_mem.offsets()[idx]),
             );
         }
     }
@@ -801,11 +1141,45 @@ impl FunctionCompiler<'_> {
                 }
             }
             hir::Expr::BoolLiteral(b) => Some(self.builder.ins().iconst(types::I8, b as i64)),
+            hir::Expr::NullLiteral => {
+                let ty = self.tys[self.file_name][expr];
+
+                self.cast(None, Ty::Null.into(), ty)
+            }
             hir::Expr::StringLiteral(text) => {
+                let len = text.len();
+
                 let data = self.create_global_str(text);
 
                 let local_id = self.module.declare_data_in_func(data, self.builder.func);
 
+                let ptr = self.builder.ins().symbol_value(self.ptr_ty, local_id);
+
+                // a str is laid out exactly like a `[]u8`
+                let ty = self.tys[self.file_name][expr];
+                let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+                    kind: StackSlotKind::ExplicitSlot,
+                    size: ty.size(),
+                    align_shift: ty.align().trailing_zeros() as u8,
+                });
+
+                let len = self.builder.ins().iconst(self.ptr_ty, len as i64);
+                self.builder.ins().stack_store(len, stack_slot, 0);
+                self.builder
+                    .ins()
+                    .stack_store(ptr, stack_slot, self.ptr_ty.bytes() as i32);
+
+                Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0))
+            }
+            hir::Expr::Embed(contents) => {
+                if contents.is_empty() {
+                    return None;
+                }
+
+                let data = self.get_embed(expr, &contents);
+
+                let local_id = self.module.declare_data_in_func(data, self.builder.func);
+
                 Some(self.builder.ins().symbol_value(self.ptr_ty, local_id))
             }
             hir::Expr::CharLiteral(char) => Some(self.builder.ins().iconst(types::I8, char as i64)),
@@ -822,7 +1196,7 @@ impl FunctionCompiler<'_> {
                 let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
                     kind: StackSlotKind::ExplicitSlot,
                     size: ty.size(),
-                    align_shift: ty.align() as u8,
+                    align_shift: ty.align().trailing_zeros() as u8,
                 });
 
                 let memory = MemoryLoc::from_stack(stack_slot, 0);
@@ -832,6 +1206,10 @@ impl FunctionCompiler<'_> {
                 Some(memory.into_value(&mut self.builder, self.ptr_ty))
             }
             hir::Expr::Index { source, index } => {
+                if let Some(method) = self.tys[self.file_name].get_method_call(expr) {
+                    return self.compile_operator_call(method, &[source, index]);
+                }
+
                 if self.tys[self.file_name][expr].is_zero_sized() {
                     return None;
                 }
@@ -921,58 +1299,152 @@ impl FunctionCompiler<'_> {
                     ))
                 }
             }
-            hir::Expr::Cast {
-                expr: inner_expr, ..
-            } => {
-                let cast_to = self.tys[self.file_name][expr];
+            hir::Expr::Slice { source, start, end } => {
+                let slice_ty = self.tys[self.file_name][expr];
 
-                self.compile_and_cast(inner_expr, cast_to)
-            }
-            hir::Expr::Ref { expr, .. } => {
-                if self.tys[self.file_name][expr].is_aggregate()
-                    || matches!(
-                        self.world_bodies[self.file_name][expr],
-                        hir::Expr::Local(_)
-                            | hir::Expr::LocalGlobal(_)
-                            | hir::Expr::Index { .. }
-                            | hir::Expr::Member { .. }
-                    )
-                {
-                    // references to locals or globals should return the actual memory address of the local or global
-                    let res = self.compile_expr_with_args(expr, true);
+                let mut source_ty = self.tys[self.file_name][source];
+                let mut source = self.compile_expr(source).unwrap();
 
-                    if res.is_some() {
-                        res
-                    } else {
-                        // even though the expression is void, we still need to get some
-                        // result
+                let mut required_derefs = 0;
+                while let Some((_, sub_ty)) = source_ty.as_pointer() {
+                    source_ty = sub_ty;
+                    required_derefs += 1;
+                }
+                debug_assert!(source_ty.is_array() || source_ty.is_slice());
 
-                        let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
-                            kind: StackSlotKind::ExplicitSlot,
-                            size: 0,
-                            align_shift: 1,
-                        });
+                for _ in 1..required_derefs {
+                    source = self
+                        .builder
+                        .ins()
+                        .load(self.ptr_ty, MemFlags::trusted(), source, 0);
+                }
 
-                        Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0))
-                    }
+                let (len, source) = if let Some((len, _)) = source_ty.as_array() {
+                    (self.builder.ins().iconst(self.ptr_ty, len as i64), source)
                 } else {
-                    let inner_ty = self.tys[self.file_name][expr];
+                    let len = self
+                        .builder
+                        .ins()
+                        .load(self.ptr_ty, MemFlags::trusted(), source, 0);
+                    let source = self.builder.ins().load(
+                        self.ptr_ty,
+                        MemFlags::trusted(),
+                        source,
+                        self.ptr_ty.bytes() as i32,
+                    );
+
+                    (len, source)
+                };
+
+                let compile_bound = |this: &mut Self, bound: Idx<hir::Expr>| {
+                    let bound_ty = this.tys[this.file_name][bound];
+                    let bound = this.compile_expr(bound).unwrap();
+
+                    super::cast_ty_to_cranelift(&mut this.builder, bound, bound_ty, this.ptr_ty)
+                };
+
+                // `foo[..hi]` starts at zero and `foo[lo..]` goes to the end
+                let start = match start {
+                    Some(start) => compile_bound(self, start),
+                    None => self.builder.ins().iconst(self.ptr_ty, 0),
+                };
+                let end = match end {
+                    Some(end) => compile_bound(self, end),
+                    None => len,
+                };
+
+                let good_bounds_block = self.builder.create_block();
+                let bad_bounds_block = self.builder.create_block();
+
+                let start_before_end =
+                    self.builder
+                        .ins()
+                        .icmp(IntCC::UnsignedLessThanOrEqual, start, end);
+                let end_in_bounds =
+                    self.builder
+                        .ins()
+                        .icmp(IntCC::UnsignedLessThanOrEqual, end, len);
+                let are_good_bounds = self.builder.ins().band(start_before_end, end_in_bounds);
+                self.builder.ins().brif(
+                    are_good_bounds,
+                    good_bounds_block,
+                    &[],
+                    bad_bounds_block,
+                    &[],
+                );
+
+                self.builder.switch_to_block(bad_bounds_block);
+                self.builder.set_cold_block(bad_bounds_block);
+                self.builder.seal_block(bad_bounds_block);
+
+                self.builder.ins().trap(TrapCode::UnreachableCodeReached);
+
+                self.builder.switch_to_block(good_bounds_block);
+                self.builder.seal_block(good_bounds_block);
 
-                    // println!("{:?} = {inner_size}", self.tys[self.fqn.module][expr]);
+                let element_ty = slice_ty.as_slice().unwrap();
+
+                let byte_offset = self
+                    .builder
+                    .ins()
+                    .imul_imm(start, element_ty.stride() as i64);
+                let new_source = self.builder.ins().iadd(source, byte_offset);
+                let new_len = self.builder.ins().isub(end, start);
+
+                let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+                    kind: StackSlotKind::ExplicitSlot,
+                    size: slice_ty.size(),
+                    align_shift: slice_ty.align().trailing_zeros() as u8,
+                });
+
+                self.builder.ins().stack_store(new_len, stack_slot, 0);
+                self.builder
+                    .ins()
+                    .stack_store(new_source, stack_slot, self.ptr_ty.bytes() as i32);
+
+                Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0))
+            }
+            hir::Expr::Cast {
+                expr: inner_expr, ..
+            } => {
+                let cast_to = self.tys[self.file_name][expr];
+
+                if let Some(impls) = self.tys[self.file_name].get_interface_impls(expr) {
+                    let data = self.compile_expr(inner_expr).unwrap();
+
+                    let vtable = self.get_vtable(impls.to_vec());
+                    let vtable = self.module.declare_data_in_func(vtable, self.builder.func);
+                    let vtable = self.builder.ins().symbol_value(self.ptr_ty, vtable);
 
                     let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
                         kind: StackSlotKind::ExplicitSlot,
-                        size: inner_ty.size(),
-                        align_shift: inner_ty.align() as u8,
+                        size: cast_to.size(),
+                        align_shift: cast_to.align().trailing_zeros() as u8,
                     });
 
-                    let expr = self.compile_expr(expr).unwrap();
+                    self.builder.ins().stack_store(data, stack_slot, 0);
+                    self.builder
+                        .ins()
+                        .stack_store(vtable, stack_slot, self.ptr_ty.bytes() as i32);
+
+                    return Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0));
+                }
 
-                    self.builder.ins().stack_store(expr, stack_slot, 0);
+                // string literals always end with a null, so the `cstr` can point straight at
+                // the literal's bytes
+                if let (hir::Expr::StringLiteral(text), Ty::CString) = (
+                    &self.world_bodies[self.file_name][inner_expr],
+                    cast_to.remove_distinct().as_ref(),
+                ) {
+                    let data = self.create_global_str(text.clone());
+                    let data = self.module.declare_data_in_func(data, self.builder.func);
 
-                    Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0))
+                    return Some(self.builder.ins().symbol_value(self.ptr_ty, data));
                 }
+
+                self.compile_and_cast(inner_expr, cast_to)
             }
+            hir::Expr::Ref { expr, .. } => self.compile_ref(expr),
             hir::Expr::Deref { pointer } => {
                 let self_ty = self.tys[self.file_name][expr];
 
@@ -1000,13 +1472,70 @@ impl FunctionCompiler<'_> {
                     )
                 }
             }
-            hir::Expr::Binary {
-                lhs: lhs_expr,
-                rhs: rhs_expr,
-                op,
+            hir::Expr::Unwrap {
+                optional,
+                line,
+                col,
             } => {
-                match op {
-                    hir::BinaryOp::LAnd => {
+                let sub_ty = self.tys[self.file_name][optional].as_optional().unwrap();
+                let optional = self.compile_expr(optional)?;
+
+                let has_value = self.optional_has_value(sub_ty, optional);
+
+                let some_block = self.builder.create_block();
+                let null_block = self.builder.create_block();
+
+                self.builder
+                    .ins()
+                    .brif(has_value, some_block, &[], null_block, &[]);
+
+                self.builder.switch_to_block(null_block);
+                self.builder.set_cold_block(null_block);
+                self.builder.seal_block(null_block);
+
+                self.compile_null_unwrap_panic(line, col);
+
+                self.builder.switch_to_block(some_block);
+                self.builder.seal_block(some_block);
+
+                self.optional_payload(sub_ty, optional)
+            }
+            hir::Expr::Binary {
+                lhs: lhs_expr,
+                rhs: rhs_expr,
+                op,
+            } => {
+                if let Some(method) = self.tys[self.file_name].get_method_call(expr) {
+                    let result = self.compile_operator_call(method, &[lhs_expr, rhs_expr]);
+
+                    return if op == hir::BinaryOp::Ne {
+                        result.map(|result| self.builder.ins().icmp_imm(IntCC::Equal, result, 0))
+                    } else {
+                        result
+                    };
+                }
+
+                let lhs_ty = self.tys[self.file_name][lhs_expr];
+
+                if matches!(op, hir::BinaryOp::Eq | hir::BinaryOp::Ne) && lhs_ty.is_aggregate() {
+                    let lhs = self.compile_expr(lhs_expr);
+                    let rhs = self.compile_expr(rhs_expr);
+
+                    let result = match (lhs, rhs) {
+                        (Some(lhs), Some(rhs)) => self.compile_structural_eq(lhs_ty, lhs, rhs),
+                        // zero-sized values are always equal
+                        _ => self.builder.ins().iconst(types::I8, 1),
+                    };
+
+                    return Some(if op == hir::BinaryOp::Ne {
+                        self.builder.ins().icmp_imm(IntCC::Equal, result, 0)
+                    } else {
+                        result
+                    });
+                }
+
+                match op {
+                    hir::BinaryOp::LAnd => {
                         let rhs_block = self.builder.create_block();
                         let exit_block = self.builder.create_block();
 
@@ -1108,7 +1637,7 @@ impl FunctionCompiler<'_> {
                         hir::BinaryOp::LAnd | hir::BinaryOp::LOr => unreachable!(),
                     })
                 } else {
-                    Some(match op {
+                    let result = match op {
                         hir::BinaryOp::Add => self.builder.ins().iadd(lhs, rhs),
                         hir::BinaryOp::Sub => self.builder.ins().isub(lhs, rhs),
                         hir::BinaryOp::Mul => self.builder.ins().imul(lhs, rhs),
@@ -1178,16 +1707,26 @@ impl FunctionCompiler<'_> {
                             }
                         }
                         hir::BinaryOp::LAnd | hir::BinaryOp::LOr => unreachable!(),
-                    })
+                    };
+
+                    Some(super::wrap_int(
+                        &mut self.builder,
+                        result,
+                        self.tys[self.file_name][expr],
+                    ))
                 }
             }
-            hir::Expr::Unary { expr, op } => {
-                let expr_ty = self.tys[self.file_name][expr]
+            hir::Expr::Unary { expr: inner, op } => {
+                if let Some(method) = self.tys[self.file_name].get_method_call(expr) {
+                    return self.compile_operator_call(method, &[inner]);
+                }
+
+                let expr_ty = self.tys[self.file_name][inner]
                     .get_final_ty()
                     .into_number_type()
                     .unwrap();
 
-                let expr = self.compile_expr(expr).unwrap();
+                let expr = self.compile_expr(inner).unwrap();
 
                 if expr_ty.float {
                     match op {
@@ -1197,10 +1736,18 @@ impl FunctionCompiler<'_> {
                         hir::UnaryOp::LNot => unreachable!(),
                     }
                 } else {
+                    let inner_ty = self.tys[self.file_name][inner];
+
                     match op {
                         hir::UnaryOp::Pos => Some(expr),
-                        hir::UnaryOp::Neg => Some(self.builder.ins().ineg(expr)),
-                        hir::UnaryOp::BNot => Some(self.builder.ins().bnot(expr)),
+                        hir::UnaryOp::Neg => {
+                            let result = self.builder.ins().ineg(expr);
+                            Some(super::wrap_int(&mut self.builder, result, inner_ty))
+                        }
+                        hir::UnaryOp::BNot => {
+                            let result = self.builder.ins().bnot(expr);
+                            Some(super::wrap_int(&mut self.builder, result, inner_ty))
+                        }
                         hir::UnaryOp::LNot => {
                             let zero = self.builder.ins().iconst(expr_ty.ty, 0);
                             Some(self.builder.ins().icmp(IntCC::Equal, expr, zero))
@@ -1208,36 +1755,177 @@ impl FunctionCompiler<'_> {
                     }
                 }
             }
-            hir::Expr::Call { callee, args } => {
-                let (param_tys, return_ty) = self.tys[self.file_name][callee]
-                    .clone()
+            hir::Expr::Call { callee, args, .. } => {
+                // named arguments and default values have already been sorted out by hir_ty
+                let args = match self.tys[self.file_name].get_call_args(expr) {
+                    Some(call_args) => call_args.to_vec(),
+                    None => args.into_iter().map(CallArg::Given).collect(),
+                };
+
+                // `a.f(b)` gets called exactly like `f(a, b)`
+                let method = match self.world_bodies[self.file_name][callee] {
+                    hir::Expr::Member { previous, .. } => self.tys[self.file_name]
+                        .get_method_call(callee)
+                        .map(|method| (previous, method)),
+                    _ => None,
+                };
+
+                // `shape.area()` gets called through the vtable of `shape`
+                let interface_call = match self.world_bodies[self.file_name][callee] {
+                    hir::Expr::Member { previous, field } if method.is_none() => {
+                        let mut deref_ty = self.tys[self.file_name][previous];
+                        while let Some((_, sub_ty)) = deref_ty.as_pointer() {
+                            deref_ty = sub_ty;
+                        }
+
+                        deref_ty.as_interface().map(|methods| {
+                            let idx = methods
+                                .iter()
+                                .position(|(name, _)| *name == field.name)
+                                .unwrap();
+
+                            (previous, idx)
+                        })
+                    }
+                    _ => None,
+                };
+
+                let callee_ty = match method {
+                    Some((_, method)) => self.tys[method.fqn].0,
+                    None => self.tys[self.file_name][callee],
+                };
+                let (param_tys, return_ty) = callee_ty
                     .as_function()
+                    .or_else(|| callee_ty.as_closure())
                     .unwrap();
-                let fn_abi = Into::<Abi>::into(self.module.target_config())
-                    .fn_to_target((&param_tys, return_ty));
+                let abi = Into::<Abi>::into(self.module.target_config());
 
-                let arg_values = args
+                // the arguments after the fixed parameters of a variadic function
+                // get passed as their promoted types
+                let var_arg_tys = args
                     .iter()
-                    .zip(param_tys.iter())
-                    .filter_map(|(arg_expr, expected_ty)| {
-                        self.compile_and_cast(*arg_expr, *expected_ty)
+                    .skip(param_tys.len() - method.is_some() as usize)
+                    .map(|arg| {
+                        let CallArg::Given(arg) = arg else {
+                            unreachable!("only parameters have default values");
+                        };
+                        Intern::new(
+                            self.tys[self.file_name][*arg]
+                                .var_arg_promotion()
+                                .expect("invalid variadic arguments are caught by hir_ty"),
+                        )
                     })
                     .collect::<Vec<_>>();
-                let mut arg_values = fn_abi.get_arg_list(arg_values, self);
 
-                let ret_mem =
-                    fn_abi.ret_addr(&mut arg_values, &mut self.builder, return_ty, self.ptr_ty);
+                let fn_abi = if callee_ty.is_variadic_function() {
+                    abi.var_args_call_to_target((&param_tys, return_ty), &var_arg_tys)
+                } else if callee_ty.is_closure() {
+                    let mut param_tys = param_tys.clone();
+                    param_tys.push(closure_env_ty());
+                    abi.fn_to_target((&param_tys, return_ty))
+                } else if interface_call.is_some() {
+                    // the receiver gets passed as a `^mut any`, just like a closure's environment
+                    let mut param_tys = param_tys.clone();
+                    param_tys.insert(0, closure_env_ty());
+                    abi.fn_to_target((&param_tys, return_ty))
+                } else {
+                    abi.fn_to_target((&param_tys, return_ty))
+                };
+
+                let mut expected_tys = param_tys.iter().chain(var_arg_tys.iter());
+
+                let mut arg_values = Vec::with_capacity(args.len() + 1);
+
+                let interface_func = interface_call.map(|(receiver, idx)| {
+                    let mut interface = self.compile_expr(receiver).unwrap();
+
+                    // an interface is already behind a pointer,
+                    // so only pointers to pointers need to be followed
+                    let mut receiver_ty = self.tys[self.file_name][receiver];
+                    while let Some((_, sub_ty)) = receiver_ty.as_pointer() {
+                        if sub_ty.is_pointer() {
+                            interface = self.builder.ins().load(
+                                self.ptr_ty,
+                                MemFlags::trusted(),
+                                interface,
+                                0,
+                            );
+                        }
+                        receiver_ty = sub_ty;
+                    }
+
+                    let data =
+                        self.builder
+                            .ins()
+                            .load(self.ptr_ty, MemFlags::trusted(), interface, 0);
+                    let vtable = self.builder.ins().load(
+                        self.ptr_ty,
+                        MemFlags::trusted(),
+                        interface,
+                        self.ptr_ty.bytes() as i32,
+                    );
+                    arg_values.push(data);
+
+                    self.builder.ins().load(
+                        self.ptr_ty,
+                        MemFlags::trusted(),
+                        vtable,
+                        (idx as u32 * self.ptr_ty.bytes()) as i32,
+                    )
+                });
+
+                if let Some((receiver, method)) = method {
+                    let receiver_ty = *expected_tys.next().unwrap();
+
+                    arg_values.extend(match method.auto_ref {
+                        Some(_) => self.compile_ref(receiver),
+                        None => self.compile_and_cast(receiver, receiver_ty),
+                    });
+                }
+
+                arg_values.extend(args.iter().zip(expected_tys).filter_map(
+                    |(arg, expected_ty)| match arg {
+                        CallArg::Given(arg_expr) => self.compile_and_cast(*arg_expr, *expected_ty),
+                        CallArg::Default(default) => {
+                            self.compile_param_default(*default, *expected_ty)
+                        }
+                    },
+                ));
+
+                let callee = match self.world_bodies[self.file_name][callee] {
+                    _ if interface_func.is_some() => Callee::Indirect(interface_func.unwrap()),
+                    _ if callee_ty.is_closure() => {
+                        let closure = self.compile_expr(callee).unwrap();
 
-                let call = match self.world_bodies[self.file_name][callee] {
-                    hir::Expr::LocalGlobal(name) => {
+                        let func_addr =
+                            self.builder
+                                .ins()
+                                .load(self.ptr_ty, MemFlags::trusted(), closure, 0);
+                        let env = self.builder.ins().load(
+                            self.ptr_ty,
+                            MemFlags::trusted(),
+                            closure,
+                            self.ptr_ty.bytes() as i32,
+                        );
+                        arg_values.push(env);
+
+                        Callee::Indirect(func_addr)
+                    }
+                    hir::Expr::Member { .. } if method.is_some() => {
+                        Callee::Direct(self.get_local_func(method.unwrap().1.fqn))
+                    }
+                    hir::Expr::LocalGlobal(name)
+                        if !self.tys.is_runtime_global(hir::Fqn {
+                            file: self.file_name,
+                            name: name.name,
+                        }) =>
+                    {
                         let fqn = hir::Fqn {
                             file: self.file_name,
                             name: name.name,
                         };
 
-                        let local_func = self.get_local_func(fqn);
-
-                        self.builder.ins().call(local_func, &arg_values)
+                        Callee::Direct(self.get_local_func(fqn))
                     }
                     hir::Expr::Local(local)
                         if !self.world_bodies[self.file_name][local].mutable =>
@@ -1247,55 +1935,67 @@ impl FunctionCompiler<'_> {
                         if let Some(hir::Expr::Lambda(lambda)) =
                             value.map(|value| &self.world_bodies[self.file_name][value])
                         {
-                            let local_func = self.unnamed_func_to_local(callee, *lambda);
-
-                            self.builder.ins().call(local_func, &arg_values)
+                            Callee::Direct(self.unnamed_func_to_local(callee, *lambda))
                         } else {
-                            let callee = self.compile_expr(callee).unwrap();
-
-                            let comp_sig = fn_abi.to_cl(self.ptr_ty, self.module.target_config().default_call_conv);
-
-                            let sig_ref = self.builder.import_signature(comp_sig);
-
-                            self.builder
-                                .ins()
-                                .call_indirect(sig_ref, callee, &arg_values)
+                            Callee::Indirect(self.compile_expr(callee).unwrap())
                         }
                     }
                     hir::Expr::Member {
                         previous, field, ..
                     } => match &self.tys[self.file_name][previous].as_ref() {
-                        Ty::File(file) => {
+                        Ty::File(file)
+                            if !self.tys.is_runtime_global(hir::Fqn {
+                                file: *file,
+                                name: field.name,
+                            }) =>
+                        {
                             let fqn = hir::Fqn {
                                 file: *file,
                                 name: field.name,
                             };
 
-                            let local_func = self.get_local_func(fqn);
-
-                            self.builder.ins().call(local_func, &arg_values)
-                        }
-                        _ => {
-                            let callee = self.compile_expr(callee).unwrap();
-
-                            let comp_sig = fn_abi.to_cl(self.ptr_ty, self.module.target_config().default_call_conv);
-
-                            let sig_ref = self.builder.import_signature(comp_sig);
-
-                            self.builder
-                                .ins()
-                                .call_indirect(sig_ref, callee, &arg_values)
+                            Callee::Direct(self.get_local_func(fqn))
                         }
+                        _ => Callee::Indirect(self.compile_expr(callee).unwrap()),
                     },
                     hir::Expr::Lambda(lambda) => {
-                        let local_func = self.unnamed_func_to_local(callee, lambda);
+                        Callee::Direct(self.unnamed_func_to_local(callee, lambda))
+                    }
+                    _ => Callee::Indirect(self.compile_expr(callee).unwrap()),
+                };
 
-                        self.builder.ins().call(local_func, &arg_values)
+                let callee = match callee {
+                    // variadic calls use a different signature than the callee was declared
+                    // with, so they always have to be indirect
+                    Callee::Direct(func_ref) if callee_ty.is_variadic_function() => {
+                        Callee::Indirect(self.builder.ins().func_addr(self.ptr_ty, func_ref))
                     }
-                    _ => {
-                        let callee = self.compile_expr(callee).unwrap();
+                    callee => callee,
+                };
+
+                let callee = match fn_abi.trampoline() {
+                    Some(trampoline) => {
+                        let Callee::Indirect(callee_addr) = callee else {
+                            unreachable!("variadic calls are always indirect")
+                        };
+                        arg_values.push(callee_addr);
+
+                        let trampoline = self.get_var_args_trampoline(trampoline);
+                        Callee::Indirect(self.builder.ins().func_addr(self.ptr_ty, trampoline))
+                    }
+                    None => callee,
+                };
+
+                let mut arg_values = fn_abi.get_arg_list(arg_values, &mut self.builder);
 
-                        let comp_sig = fn_abi.to_cl(self.ptr_ty, self.module.target_config().default_call_conv);
+                let ret_mem =
+                    fn_abi.ret_addr(&mut arg_values, &mut self.builder, return_ty, self.ptr_ty);
+
+                let call = match callee {
+                    Callee::Direct(func_ref) => self.builder.ins().call(func_ref, &arg_values),
+                    Callee::Indirect(callee) => {
+                        let comp_sig = fn_abi
+                            .to_cl(self.ptr_ty, self.module.target_config().default_call_conv);
                         let sig_ref = self.builder.import_signature(comp_sig);
 
                         self.builder
@@ -1364,12 +2064,16 @@ impl FunctionCompiler<'_> {
                     .then(|| {
                         tail_expr.and_then(|tail_expr| {
                             let value = self.compile_expr_with_args(tail_expr, no_load);
-                            if scope_id.is_none()
-                                && *self.tys[self.file_name][tail_expr] == Ty::NoEval
-                            {
+                            let tail_ty = self.tys[self.file_name][tail_expr];
+                            if scope_id.is_none() && *tail_ty == Ty::NoEval {
                                 no_eval = true;
                             }
-                            value
+                            if no_load {
+                                value
+                            } else {
+                                // `{ 5 }` might have to be wrapped in a `?i32`
+                                self.cast(value, tail_ty, expr_ty)
+                            }
                         })
                     })
                     .flatten();
@@ -1433,8 +2137,20 @@ impl FunctionCompiler<'_> {
                 condition,
                 body,
                 else_branch,
+                binding,
             } => {
-                let condition = self.compile_expr(condition).unwrap();
+                let (condition, binding) = match binding {
+                    Some(binding) => {
+                        let sub_ty = self.tys[self.file_name][condition].as_optional().unwrap();
+                        let optional = self.compile_expr(condition).unwrap();
+
+                        (
+                            self.optional_has_value(sub_ty, optional),
+                            Some((binding, sub_ty, optional)),
+                        )
+                    }
+                    None => (self.compile_expr(condition).unwrap(), None),
+                };
 
                 let then_block = self.builder.create_block();
                 let else_block = self.builder.create_block();
@@ -1457,7 +2173,32 @@ impl FunctionCompiler<'_> {
                 self.builder.switch_to_block(then_block);
                 self.builder.seal_block(then_block);
 
+                if let Some((binding, sub_ty, optional)) = binding {
+                    let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+                        kind: StackSlotKind::ExplicitSlot,
+                        size: sub_ty.size(),
+                        align_shift: sub_ty.align().trailing_zeros() as u8,
+                    });
+
+                    let memory = MemoryLoc::from_stack(stack_slot, 0);
+
+                    let payload = self.optional_payload(sub_ty, optional);
+                    memory.write(payload, sub_ty, self.module, &mut self.builder);
+
+                    self.locals
+                        .insert(binding, memory.into_value(&mut self.builder, self.ptr_ty));
+                }
+
                 let body_value = self.compile_expr_with_args(body, no_load);
+                let body_value = if no_load {
+                    body_value
+                } else {
+                    self.cast(
+                        body_value,
+                        self.tys[self.file_name][body],
+                        self.tys[self.file_name][expr],
+                    )
+                };
 
                 if *self.tys[self.file_name][body] == Ty::NoEval {
                     self.builder.ins().trap(TrapCode::UnreachableCodeReached);
@@ -1479,6 +2220,15 @@ impl FunctionCompiler<'_> {
 
                 if let Some(else_branch) = else_branch {
                     let else_value = self.compile_expr_with_args(else_branch, no_load);
+                    let else_value = if no_load {
+                        else_value
+                    } else {
+                        self.cast(
+                            else_value,
+                            self.tys[self.file_name][else_branch],
+                            self.tys[self.file_name][expr],
+                        )
+                    };
 
                     if *self.tys[self.file_name][else_branch] == Ty::NoEval {
                         self.builder.ins().trap(TrapCode::UnreachableCodeReached);
@@ -1577,6 +2327,34 @@ impl FunctionCompiler<'_> {
                 .params
                 .get(&(idx as u64))
                 .map(|param| self.builder.use_var(*param)),
+            hir::Expr::Capture { idx } => {
+                let closure = &self.world_bodies[self.file_name]
+                    [self.closure.expect("captures only exist inside closures")];
+                let env_param = self.params[&(closure.params.len() as u64)];
+                let captures = closure.captures.clone();
+
+                let (offsets, _, _) = self.env_layout(&captures);
+                let offset = offsets[idx as usize] as i32;
+
+                let env = self.builder.use_var(env_param);
+                let ptr = if captures[idx as usize].mutable {
+                    self.builder
+                        .ins()
+                        .load(self.ptr_ty, MemFlags::trusted(), env, offset)
+                } else {
+                    self.builder.ins().iadd_imm(env, offset as i64)
+                };
+
+                let ty = &self.tys[self.file_name][expr];
+
+                if no_load || ty.is_aggregate() {
+                    Some(ptr)
+                } else {
+                    ty.get_final_ty()
+                        .into_real_type()
+                        .map(|ty| self.builder.ins().load(ty, MemFlags::trusted(), ptr, 0))
+                }
+            }
             hir::Expr::LocalGlobal(name) => {
                 if self.tys[self.file_name][expr].is_zero_sized() {
                     return None;
@@ -1648,7 +2426,7 @@ impl FunctionCompiler<'_> {
                                     kind: StackSlotKind::ExplicitSlot,
                                     size: self.ptr_ty.bytes(),
                                     // todo: maybe do this better
-                                    align_shift: self.ptr_ty.bytes() as u8,
+                                    align_shift: self.ptr_ty.bytes().trailing_zeros() as u8,
                                 });
 
                                 let len = self.builder.ins().iconst(self.ptr_ty, len as i64);
@@ -1660,16 +2438,21 @@ impl FunctionCompiler<'_> {
                             }
                         }
 
-                        let struct_fields = source_ty.as_struct().unwrap();
+                        // every member of a union lives at the start of it
+                        let offset = if source_ty.is_union() {
+                            0
+                        } else {
+                            let struct_fields = source_ty.as_struct().unwrap();
 
-                        let field_idx = struct_fields
-                            .iter()
-                            .enumerate()
-                            .find(|(_, (name, _))| *name == field.name)
-                            .map(|(idx, _)| idx)
-                            .unwrap();
+                            let field_idx = struct_fields
+                                .iter()
+                                .enumerate()
+                                .find(|(_, (name, _))| *name == field.name)
+                                .map(|(idx, _)| idx)
+                                .unwrap();
 
-                        let offset = source_ty.struct_layout().unwrap().offsets()[field_idx];
+                            source_ty.struct_layout().unwrap().offsets()[field_idx]
+                        };
 
                         let mut struct_addr = self.compile_expr_with_args(previous, false)?;
 
@@ -1697,8 +2480,28 @@ impl FunctionCompiler<'_> {
             }
             hir::Expr::Lambda(lambda) => {
                 let local_func = self.unnamed_func_to_local(expr, lambda);
+                let func_addr = self.builder.ins().func_addr(self.ptr_ty, local_func);
+
+                if !self.world_bodies[self.file_name][lambda].is_closure {
+                    return Some(func_addr);
+                }
+
+                let env = self.build_env(&self.world_bodies[self.file_name][lambda].captures);
 
-                Some(self.builder.ins().func_addr(self.ptr_ty, local_func))
+                let ty = self.tys[self.file_name][expr];
+
+                let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+                    kind: StackSlotKind::ExplicitSlot,
+                    size: ty.size(),
+                    align_shift: ty.align().trailing_zeros() as u8,
+                });
+
+                self.builder.ins().stack_store(func_addr, stack_slot, 0);
+                self.builder
+                    .ins()
+                    .stack_store(env, stack_slot, self.ptr_ty.bytes() as i32);
+
+                Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0))
             }
             hir::Expr::StructLiteral {
                 members: field_values,
@@ -1709,7 +2512,7 @@ impl FunctionCompiler<'_> {
                 let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
                     kind: StackSlotKind::ExplicitSlot,
                     size: ty.size(),
-                    align_shift: ty.align() as u8,
+                    align_shift: ty.align().trailing_zeros() as u8,
                 });
 
                 let memory = MemoryLoc::from_stack(stack_slot, 0);
@@ -1718,9 +2521,57 @@ impl FunctionCompiler<'_> {
 
                 Some(memory.into_value(&mut self.builder, self.ptr_ty))
             }
+            hir::Expr::Tuple { items } => {
+                let ty = self.tys[self.file_name][expr];
+
+                if ty.is_zero_sized() {
+                    for item in items {
+                        self.compile_expr(item);
+                    }
+                    return None;
+                }
+
+                let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+                    kind: StackSlotKind::ExplicitSlot,
+                    size: ty.size(),
+                    align_shift: ty.align().trailing_zeros() as u8,
+                });
+
+                let memory = MemoryLoc::from_stack(stack_slot, 0);
+
+                self.store_tuple_items(ty, items.into_iter(), memory);
+
+                Some(memory.into_value(&mut self.builder, self.ptr_ty))
+            }
+            hir::Expr::TupleItem { tuple, index, .. } => {
+                let item_ty = self.tys[self.file_name][expr];
+
+                let tuple_addr = self.compile_expr(tuple)?;
+
+                if item_ty.is_zero_sized() {
+                    return None;
+                }
+
+                let tuple_ty = self.tys[self.file_name][tuple];
+                let offset = tuple_ty.struct_layout().unwrap().offsets()[index as usize];
+
+                if no_load || item_ty.is_aggregate() {
+                    Some(self.builder.ins().iadd_imm(tuple_addr, offset as i64))
+                } else {
+                    Some(self.builder.ins().load(
+                        item_ty.get_final_ty().into_real_type()?,
+                        MemFlags::trusted(),
+                        tuple_addr,
+                        offset as i32,
+                    ))
+                }
+            }
             hir::Expr::PrimitiveTy { .. } => None,
             hir::Expr::Distinct { .. } => None,
+            hir::Expr::OptionalDecl { .. } => None,
             hir::Expr::StructDecl { .. } => None,
+            hir::Expr::UnionDecl { .. } => None,
+            hir::Expr::InterfaceDecl { .. } => None,
             hir::Expr::Import(_) => None,
             hir::Expr::Comptime(comptime) => {
                 let ctc = FQComptime {
@@ -1883,15 +2734,264 @@ impl FunctionCompiler<'_> {
         }
     }
 
+    /// returns the address of `expr`, copying it onto the stack if it doesn't have one
+    /// calls the function an operator was overloaded with, e.g. `a + b` becomes `op_add(a, b)`
+    fn compile_operator_call(
+        &mut self,
+        method: MethodCall,
+        operands: &[Idx<hir::Expr>],
+    ) -> Option<Value> {
+        let (param_tys, return_ty) = self.tys[method.fqn].0.as_function().unwrap();
+        let abi = Into::<Abi>::into(self.module.target_config());
+        let fn_abi = abi.fn_to_target((&param_tys, return_ty));
+
+        let mut arg_values = Vec::with_capacity(operands.len());
+
+        if method.auto_ref.is_some() {
+            arg_values.extend(self.compile_ref(operands[0]));
+        } else {
+            arg_values.extend(self.compile_and_cast(operands[0], param_tys[0]));
+        }
+
+        arg_values.extend(
+            operands[1..]
+                .iter()
+                .zip(param_tys[1..].iter())
+                .filter_map(|(operand, param_ty)| self.compile_and_cast(*operand, *param_ty)),
+        );
+
+        let func_ref = self.get_local_func(method.fqn);
+
+        let mut arg_values = fn_abi.get_arg_list(arg_values, &mut self.builder);
+
+        let ret_mem = fn_abi.ret_addr(&mut arg_values, &mut self.builder, return_ty, self.ptr_ty);
+
+        let call = self.builder.ins().call(func_ref, &arg_values);
+
+        if return_ty.is_zero_sized() {
+            None
+        } else {
+            fn_abi.handle_ret(call, self, ret_mem)
+        }
+    }
+
+    /// compares the values at `lhs` and `rhs` member by member.
+    /// padding is skipped, and floats are compared with IEEE rules (so `NaN != NaN`)
+    fn compile_structural_eq(&mut self, ty: Intern<Ty>, lhs: Value, rhs: Value) -> Value {
+        match ty.as_ref() {
+            Ty::Distinct { sub_ty, .. } => self.compile_structural_eq(*sub_ty, lhs, rhs),
+            Ty::Struct { .. } | Ty::Tuple { .. } => {
+                let member_tys: Vec<_> = match ty.as_ref() {
+                    Ty::Struct { members, .. } => members.iter().map(|(_, ty)| *ty).collect(),
+                    Ty::Tuple { item_tys } => item_tys.clone(),
+                    _ => unreachable!(),
+                };
+                let layout = ty.struct_layout().unwrap();
+
+                let mut result = self.builder.ins().iconst(types::I8, 1);
+
+                for (member_ty, offset) in member_tys.into_iter().zip(layout.offsets()) {
+                    if member_ty.is_zero_sized() {
+                        continue;
+                    }
+
+                    let lhs = self.builder.ins().iadd_imm(lhs, *offset as i64);
+                    let rhs = self.builder.ins().iadd_imm(rhs, *offset as i64);
+
+                    let member_eq = self.compile_structural_eq(member_ty, lhs, rhs);
+                    result = self.builder.ins().band(result, member_eq);
+                }
+
+                result
+            }
+            Ty::Array { size, sub_ty, .. } => {
+                let len = self.builder.ins().iconst(self.ptr_ty, *size as i64);
+
+                self.compile_elementwise_eq(*sub_ty, lhs, rhs, len)
+            }
+            Ty::Slice { .. } | Ty::String => {
+                let sub_ty = ty.as_slice().unwrap();
+
+                let lhs_len = self
+                    .builder
+                    .ins()
+                    .load(self.ptr_ty, MemFlags::trusted(), lhs, 0);
+                let rhs_len = self
+                    .builder
+                    .ins()
+                    .load(self.ptr_ty, MemFlags::trusted(), rhs, 0);
+
+                let same_len_block = self.builder.create_block();
+                let exit_block = self.builder.create_block();
+                self.builder.append_block_param(exit_block, types::I8);
+
+                // slices of different lengths are never equal
+                let same_len = self.builder.ins().icmp(IntCC::Equal, lhs_len, rhs_len);
+                let not_equal = self.builder.ins().iconst(types::I8, 0);
+                self.builder
+                    .ins()
+                    .brif(same_len, same_len_block, &[], exit_block, &[not_equal]);
+
+                self.builder.switch_to_block(same_len_block);
+                self.builder.seal_block(same_len_block);
+
+                let lhs = self.builder.ins().load(
+                    self.ptr_ty,
+                    MemFlags::trusted(),
+                    lhs,
+                    self.ptr_ty.bytes() as i32,
+                );
+                let rhs = self.builder.ins().load(
+                    self.ptr_ty,
+                    MemFlags::trusted(),
+                    rhs,
+                    self.ptr_ty.bytes() as i32,
+                );
+
+                let result = self.compile_elementwise_eq(sub_ty, lhs, rhs, lhs_len);
+                self.builder.ins().jump(exit_block, &[result]);
+
+                self.builder.switch_to_block(exit_block);
+                self.builder.seal_block(exit_block);
+
+                self.builder.block_params(exit_block)[0]
+            }
+            _ => {
+                let number_ty = ty.get_final_ty().into_number_type().unwrap();
+
+                let lhs = self
+                    .builder
+                    .ins()
+                    .load(number_ty.ty, MemFlags::trusted(), lhs, 0);
+                let rhs = self
+                    .builder
+                    .ins()
+                    .load(number_ty.ty, MemFlags::trusted(), rhs, 0);
+
+                if number_ty.float {
+                    self.builder.ins().fcmp(FloatCC::Equal, lhs, rhs)
+                } else {
+                    self.builder.ins().icmp(IntCC::Equal, lhs, rhs)
+                }
+            }
+        }
+    }
+
+    /// compares `len` elements starting at `lhs` and `rhs`, stopping at the first difference
+    fn compile_elementwise_eq(
+        &mut self,
+        element_ty: Intern<Ty>,
+        lhs: Value,
+        rhs: Value,
+        len: Value,
+    ) -> Value {
+        let header_block = self.builder.create_block();
+        let body_block = self.builder.create_block();
+        let exit_block = self.builder.create_block();
+
+        let idx = self.builder.append_block_param(header_block, self.ptr_ty);
+        let result = self.builder.append_block_param(exit_block, types::I8);
+
+        let zero = self.builder.ins().iconst(self.ptr_ty, 0);
+        self.builder.ins().jump(header_block, &[zero]);
+
+        self.builder.switch_to_block(header_block);
+
+        let in_bounds = self.builder.ins().icmp(IntCC::UnsignedLessThan, idx, len);
+        let equal = self.builder.ins().iconst(types::I8, 1);
+        self.builder
+            .ins()
+            .brif(in_bounds, body_block, &[], exit_block, &[equal]);
+
+        self.builder.switch_to_block(body_block);
+        self.builder.seal_block(body_block);
+
+        let byte_offset = self.builder.ins().imul_imm(idx, element_ty.stride() as i64);
+        let lhs = self.builder.ins().iadd(lhs, byte_offset);
+        let rhs = self.builder.ins().iadd(rhs, byte_offset);
+
+        let element_eq = self.compile_structural_eq(element_ty, lhs, rhs);
+
+        let next_idx = self.builder.ins().iadd_imm(idx, 1);
+        let not_equal = self.builder.ins().iconst(types::I8, 0);
+        self.builder.ins().brif(
+            element_eq,
+            header_block,
+            &[next_idx],
+            exit_block,
+            &[not_equal],
+        );
+
+        self.builder.seal_block(header_block);
+
+        self.builder.switch_to_block(exit_block);
+        self.builder.seal_block(exit_block);
+
+        result
+    }
+
+    fn compile_ref(&mut self, expr: Idx<hir::Expr>) -> Option<Value> {
+        if self.tys[self.file_name][expr].is_aggregate()
+            || matches!(
+                self.world_bodies[self.file_name][expr],
+                hir::Expr::Local(_)
+                    | hir::Expr::LocalGlobal(_)
+                    | hir::Expr::Capture { .. }
+                    | hir::Expr::Index { .. }
+                    | hir::Expr::Member { .. }
+            ) && self.tys[self.file_name].get_method_call(expr).is_none()
+        {
+            // references to locals or globals should return the actual memory address of the local or global
+            let res = self.compile_expr_with_args(expr, true);
+
+            if res.is_some() {
+                res
+            } else {
+                // even though the expression is void, we still need to get some
+                // result
+
+                let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+                    kind: StackSlotKind::ExplicitSlot,
+                    size: 0,
+                    align_shift: 1,
+                });
+
+                Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0))
+            }
+        } else {
+            let inner_ty = self.tys[self.file_name][expr];
+
+            // println!("{:?} = {inner_size}", self.tys[self.fqn.module][expr]);
+
+            let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+                kind: StackSlotKind::ExplicitSlot,
+                size: inner_ty.size(),
+                align_shift: inner_ty.align().trailing_zeros() as u8,
+            });
+
+            let expr = self.compile_expr(expr).unwrap();
+
+            self.builder.ins().stack_store(expr, stack_slot, 0);
+
+            Some(self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0))
+        }
+    }
+
     fn unnamed_func_to_local(&mut self, expr: Idx<hir::Expr>, lambda: Idx<hir::Lambda>) -> FuncRef {
         if let Some(func_ref) = self.local_lambdas.get(&lambda) {
             return *func_ref;
         }
 
-        let (param_tys, return_ty) = self.tys[self.file_name][expr].as_function().unwrap();
+        let ty = self.tys[self.file_name][expr];
+        let (param_tys, return_ty) = ty.as_function().or_else(|| ty.as_closure()).unwrap();
+
+        let mut sig_param_tys = param_tys.clone();
+        if ty.is_closure() {
+            sig_param_tys.push(closure_env_ty());
+        }
 
         let sig = Into::<Abi>::into(self.module.target_config())
-            .fn_to_target((&param_tys, return_ty))
+            .fn_to_target((&sig_param_tys, return_ty))
             .to_cl(self.ptr_ty, self.module.target_config().default_call_conv);
 
         let ftc = FunctionToCompile {
@@ -1900,6 +3000,8 @@ impl FunctionCompiler<'_> {
             lambda,
             param_tys,
             return_ty,
+            is_export: false,
+            is_closure: ty.is_closure(),
         };
 
         let mangled = ftc.to_mangled_name(self.mod_dir, self.interner);
@@ -1918,6 +3020,133 @@ impl FunctionCompiler<'_> {
         local_func
     }
 
+    /// returns the offset of every capture within a closure's environment,
+    /// along with the size and alignment of the whole environment.
+    ///
+    /// captures which can be changed are stored as pointers to the original value.
+    /// everything else gets copied, since nobody could tell the difference
+    fn env_layout(&self, captures: &[hir::Capture]) -> (Vec<u32>, u32, u32) {
+        let mut offsets = Vec::with_capacity(captures.len());
+        let mut max_align = 1;
+        let mut current_offset = 0;
+
+        for capture in captures {
+            let (size, align) = if capture.mutable {
+                (self.ptr_ty.bytes(), self.ptr_ty.bytes())
+            } else {
+                let ty = self.tys[self.file_name][capture.value];
+                (ty.size(), ty.align())
+            };
+            max_align = max_align.max(align);
+
+            current_offset += padding_needed_for(current_offset, align);
+            offsets.push(current_offset);
+            current_offset += size;
+        }
+
+        (offsets, current_offset, max_align)
+    }
+
+    /// stores the captures of a closure on the stack, and returns a pointer to them
+    fn build_env(&mut self, captures: &[hir::Capture]) -> Value {
+        let (offsets, size, align) = self.env_layout(captures);
+
+        if size == 0 {
+            return self.builder.ins().iconst(self.ptr_ty, 0);
+        }
+
+        let stack_slot = self.builder.create_sized_stack_slot(StackSlotData {
+            kind: StackSlotKind::ExplicitSlot,
+            size,
+            align_shift: align.trailing_zeros() as u8,
+        });
+
+        for (capture, offset) in captures.iter().zip(offsets) {
+            let memory = MemoryLoc::from_stack(stack_slot, offset);
+
+            if capture.mutable {
+                // only locals and other captures can be mutable, and both of these have addresses
+                let addr = self.compile_expr_with_args(capture.value, true).unwrap();
+                self.builder
+                    .ins()
+                    .stack_store(addr, stack_slot, offset as i32);
+            } else {
+                let ty = self.tys[self.file_name][capture.value];
+                self.store_expr_in_memory(capture.value, ty, memory);
+            }
+        }
+
+        self.builder.ins().stack_addr(self.ptr_ty, stack_slot, 0)
+    }
+
+    /// returns a non-zero value if the optional isn't null
+    fn optional_has_value(&mut self, sub_ty: Intern<Ty>, optional: Value) -> Value {
+        if sub_ty.has_null_niche() {
+            self.builder.ins().icmp_imm(IntCC::NotEqual, optional, 0)
+        } else {
+            self.builder.ins().load(
+                types::I8,
+                MemFlags::trusted(),
+                optional,
+                sub_ty.size() as i32,
+            )
+        }
+    }
+
+    /// the payload is always at the start of the optional,
+    /// so an aggregate payload has the same address as the optional itself
+    fn optional_payload(&mut self, sub_ty: Intern<Ty>, optional: Value) -> Option<Value> {
+        if sub_ty.has_null_niche() || sub_ty.is_aggregate() {
+            return Some(optional);
+        }
+
+        let payload_ty = sub_ty.get_final_ty().into_real_type()?;
+
+        Some(
+            self.builder
+                .ins()
+                .load(payload_ty, MemFlags::trusted(), optional, 0),
+        )
+    }
+
+    /// prints where the null optional was unwrapped and exits with a status of 1
+    fn compile_null_unwrap_panic(&mut self, line: u32, col: u32) {
+        let file = Path::new(self.interner.lookup(self.file_name.0));
+        let file = std::env::current_dir()
+            .ok()
+            .and_then(|curr_dir| file.strip_prefix(curr_dir).ok())
+            .unwrap_or(file);
+
+        let message = format!(
+            "attempted to unwrap a null optional at {}:{line}:{col}",
+            file.display()
+        );
+        let message = self.create_global_str(message);
+        let message = self.module.declare_data_in_func(message, self.builder.func);
+        let message = self.builder.ins().symbol_value(self.ptr_ty, message);
+
+        super::call_libc_func(
+            self.module,
+            &mut self.builder,
+            self.ptr_ty,
+            "puts",
+            &[message],
+            &[],
+        );
+
+        let status = self.builder.ins().iconst(types::I32, 1);
+        super::call_libc_func(
+            self.module,
+            &mut self.builder,
+            self.ptr_ty,
+            "exit",
+            &[status],
+            &[],
+        );
+
+        self.builder.ins().trap(TrapCode::UnreachableCodeReached);
+    }
+
     pub fn compile_and_cast(&mut self, expr: Idx<hir::Expr>, cast_to: Intern<Ty>) -> Option<Value> {
         let value = self.compile_expr(expr);
 
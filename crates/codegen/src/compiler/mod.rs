@@ -5,10 +5,12 @@ pub mod program;
 use cranelift::codegen::ir::StackSlot;
 use cranelift::codegen::{self, CodegenError};
 use cranelift::prelude::{
-    types, FunctionBuilder, FunctionBuilderContext, InstBuilder, MemFlags, StackSlotData,
-    StackSlotKind, Value,
+    types, AbiParam, FunctionBuilder, FunctionBuilderContext, InstBuilder, MemFlags, Signature,
+    StackSlotData, StackSlotKind, Value,
+};
+use cranelift_module::{
+    DataDescription, DataId, FuncId, FuncOrDataId, Linkage, Module, ModuleError,
 };
-use cranelift_module::{DataDescription, DataId, FuncId, Linkage, Module, ModuleError};
 use hir::FQComptime;
 use hir_ty::{ComptimeResult, InternTyExt, Ty};
 use interner::Interner;
@@ -27,7 +29,7 @@ use crate::{
     FinalSignature, Verbosity,
 };
 
-use self::abi::Abi;
+use self::abi::{x86_64::Trampoline, Abi};
 use self::functions::FunctionCompiler;
 
 #[cfg(not(debug_assertions))]
@@ -54,6 +56,9 @@ pub(crate) struct MetaTyData {
     pub(crate) distinct_uid_gen: UIDGenerator,
     pub(crate) function_uid_gen: UIDGenerator,
     pub(crate) struct_uid_gen: UIDGenerator,
+    pub(crate) union_uid_gen: UIDGenerator,
+    pub(crate) optional_uid_gen: UIDGenerator,
+    pub(crate) tuple_uid_gen: UIDGenerator,
 
     pub(crate) layout_arrays: Option<MetaTyLayoutArrays>,
     pub(crate) info_arrays: Option<MetaTyInfoArrays>,
@@ -63,10 +68,16 @@ pub(crate) struct MetaTyLayoutArrays {
     pub(crate) array_layout_array: DataId,
     pub(crate) distinct_layout_array: DataId,
     pub(crate) struct_layout_array: DataId,
+    pub(crate) union_layout_array: DataId,
+    pub(crate) optional_layout_array: DataId,
+    pub(crate) tuple_layout_array: DataId,
 
     pub(crate) array_layout_slice: DataId,
     pub(crate) distinct_layout_slice: DataId,
     pub(crate) struct_layout_slice: DataId,
+    pub(crate) union_layout_slice: DataId,
+    pub(crate) optional_layout_slice: DataId,
+    pub(crate) tuple_layout_slice: DataId,
     pub(crate) pointer_layout: DataId,
 }
 
@@ -90,6 +101,12 @@ impl MetaTyLayoutArrays {
             distinct_layout_slice: declare("distinct_layout_slice"),
             struct_layout_array: declare("struct_layout_array"),
             struct_layout_slice: declare("struct_layout_slice"),
+            union_layout_array: declare("union_layout_array"),
+            union_layout_slice: declare("union_layout_slice"),
+            optional_layout_array: declare("optional_layout_array"),
+            optional_layout_slice: declare("optional_layout_slice"),
+            tuple_layout_array: declare("tuple_layout_array"),
+            tuple_layout_slice: declare("tuple_layout_slice"),
             pointer_layout: declare("pointer_layout"),
         }
     }
@@ -102,6 +119,10 @@ pub(crate) struct MetaTyInfoArrays {
     pub(crate) pointer_info_array: DataId,
     pub(crate) distinct_info_array: DataId,
     pub(crate) struct_info_array: DataId,
+    pub(crate) union_info_array: DataId,
+    pub(crate) optional_info_array: DataId,
+    pub(crate) tuple_info_array: DataId,
+    pub(crate) function_info_array: DataId,
 
     // the global slices available in "meta.capy"
     pub(crate) array_info_slice: DataId,
@@ -109,6 +130,10 @@ pub(crate) struct MetaTyInfoArrays {
     pub(crate) pointer_info_slice: DataId,
     pub(crate) distinct_info_slice: DataId,
     pub(crate) struct_info_slice: DataId,
+    pub(crate) union_info_slice: DataId,
+    pub(crate) optional_info_slice: DataId,
+    pub(crate) tuple_info_slice: DataId,
+    pub(crate) function_info_slice: DataId,
 }
 
 impl MetaTyInfoArrays {
@@ -135,6 +160,14 @@ impl MetaTyInfoArrays {
             distinct_info_slice: declare("distinct_info_slice"),
             struct_info_array: declare("struct_info_array"),
             struct_info_slice: declare("struct_info_slice"),
+            union_info_array: declare("union_info_array"),
+            union_info_slice: declare("union_info_slice"),
+            optional_info_array: declare("optional_info_array"),
+            optional_info_slice: declare("optional_info_slice"),
+            tuple_info_array: declare("tuple_info_array"),
+            tuple_info_slice: declare("tuple_info_slice"),
+            function_info_array: declare("function_info_array"),
+            function_info_slice: declare("function_info_slice"),
         }
     }
 }
@@ -175,6 +208,18 @@ pub(crate) struct FunctionToCompile {
     pub(crate) lambda: Idx<hir::Lambda>,
     pub(crate) param_tys: Vec<Intern<Ty>>,
     pub(crate) return_ty: Intern<Ty>,
+    /// exported functions don't get their names mangled
+    pub(crate) is_export: bool,
+    /// closures get a pointer to their environment after all of their other parameters
+    pub(crate) is_closure: bool,
+}
+
+/// the type of the hidden parameter which closures get their environment through
+pub(crate) fn closure_env_ty() -> Intern<Ty> {
+    Intern::new(Ty::Pointer {
+        mutable: true,
+        sub_ty: Intern::new(Ty::Any),
+    })
 }
 
 pub(crate) struct Compiler<'a> {
@@ -199,10 +244,14 @@ pub(crate) struct Compiler<'a> {
     // globals
     pub(crate) functions: FxHashMap<hir::Fqn, FuncId>,
     pub(crate) compiler_defined_functions: FxHashMap<BuiltinFunction, FuncId>,
+    pub(crate) var_args_trampolines: FxHashMap<Trampoline, FuncId>,
+    pub(crate) vtables: FxHashMap<Vec<hir::Fqn>, DataId>,
     pub(crate) data: FxHashMap<hir::Fqn, DataId>,
+    pub(crate) global_inits: FxHashMap<hir::Fqn, FuncId>,
     pub(crate) meta_tys: MetaTyData,
     pub(crate) str_id_gen: UIDGenerator,
     pub(crate) i128_id_gen: UIDGenerator,
+    pub(crate) embeds: FxHashMap<(hir::FileName, Idx<hir::Expr>), DataId>,
     pub(crate) comptime_results: &'a FxHashMap<FQComptime, ComptimeResult>,
     pub(crate) comptime_data: FxHashMap<FQComptime, ComptimeData>,
 
@@ -211,13 +260,41 @@ pub(crate) struct Compiler<'a> {
 
 impl Compiler<'_> {
     fn finalize_tys(&mut self) {
-        layout::calc_layouts(self.tys.all_tys(), self.ptr_ty.bits());
-        convert::calc_finals(self.tys.all_tys(), self.ptr_ty);
+        // the environment parameter of closures might not appear anywhere else
+        let env_ty = std::iter::once(closure_env_ty());
+
+        layout::calc_layouts(self.tys.all_tys().chain(env_ty.clone()), self.ptr_ty.bits());
+        convert::calc_finals(self.tys.all_tys().chain(env_ty), self.ptr_ty);
+    }
+
+    /// the program might declare some of the libc functions which the compiler calls by itself
+    /// (see `call_libc_func`), possibly with a different signature than the compiler would use.
+    /// those have to be declared first, so the compiler can reuse the program's declarations
+    fn declare_libc_externs(&mut self) {
+        for name in LIBC_FUNCS {
+            let Some(name) = self.interner.get(name) else {
+                continue;
+            };
+            let Some(fqn) = self.world_bodies.find_extern(hir::Name(name)) else {
+                continue;
+            };
+
+            if self.tys[fqn].0.as_function().is_some() {
+                self.get_func_id(fqn);
+            }
+        }
     }
 
     fn compile_queued(&mut self) {
-        while let Some(ftc) = self.functions_to_compile.pop_front() {
-            self.compile_ftc(ftc);
+        // initializers can use functions and runtime globals that haven't been compiled yet
+        loop {
+            while let Some(ftc) = self.functions_to_compile.pop_front() {
+                self.compile_ftc(ftc);
+            }
+
+            if !self.compile_global_inits() {
+                break;
+            }
         }
 
         let mut array_count = 0;
@@ -225,17 +302,28 @@ impl Compiler<'_> {
         let mut pointer_count = 0;
         let mut distinct_count = 0;
         let mut struct_count = 0;
+        let mut union_count = 0;
+        let mut optional_count = 0;
+        let mut tuple_count = 0;
+        let mut function_count = 0;
 
         let mut array_mem_data = Vec::new();
         let mut distinct_mem_data = Vec::new();
         let mut struct_mem_data = Vec::new();
+        let mut union_mem_data = Vec::new();
+        let mut optional_mem_data = Vec::new();
+        let mut tuple_mem_data = Vec::new();
 
         let mut array_info_data = Vec::new();
         let mut slice_info_data = Vec::new();
         let mut pointer_info_data = Vec::new();
         let mut distinct_info_data = Vec::new();
+        let mut optional_info_data = Vec::new();
 
         let mut struct_infos_to_compile = Vec::new();
+        let mut union_infos_to_compile = Vec::new();
+        let mut tuple_infos_to_compile = Vec::new();
+        let mut function_infos_to_compile = Vec::new();
 
         for ty in &self.meta_tys.tys_to_compile {
             match ty.as_ref() {
@@ -254,6 +342,18 @@ impl Compiler<'_> {
                 Ty::Struct { .. } => {
                     struct_count += 1;
                 }
+                Ty::Union { .. } => {
+                    union_count += 1;
+                }
+                Ty::Optional { .. } => {
+                    optional_count += 1;
+                }
+                Ty::Tuple { .. } => {
+                    tuple_count += 1;
+                }
+                Ty::Function { .. } | Ty::Closure { .. } => {
+                    function_count += 1;
+                }
                 _ => {}
             }
 
@@ -263,6 +363,9 @@ impl Compiler<'_> {
                         Ty::Array { .. } => &mut array_mem_data,
                         Ty::Distinct { .. } => &mut distinct_mem_data,
                         Ty::Struct { .. } => &mut struct_mem_data,
+                        Ty::Union { .. } => &mut union_mem_data,
+                        Ty::Optional { .. } => &mut optional_mem_data,
+                        Ty::Tuple { .. } => &mut tuple_mem_data,
                         _ => break 'mem,
                     };
 
@@ -334,6 +437,22 @@ impl Compiler<'_> {
                     Ty::Struct { .. } => {
                         struct_infos_to_compile.push(ty);
                     }
+                    Ty::Union { .. } => {
+                        union_infos_to_compile.push(ty);
+                    }
+                    Ty::Tuple { .. } => {
+                        tuple_infos_to_compile.push(ty);
+                    }
+                    Ty::Optional { sub_ty } => {
+                        optional_info_data.extend_with_num_bytes(
+                            sub_ty.to_previous_type_id(&self.meta_tys, self.ptr_ty),
+                            32,
+                            self.module.isa().endianness(),
+                        );
+                    }
+                    Ty::Function { .. } | Ty::Closure { .. } => {
+                        function_infos_to_compile.push(ty);
+                    }
                     _ => continue,
                 }
             }
@@ -434,6 +553,27 @@ impl Compiler<'_> {
                 struct_mem_data,
                 ptr_align,
             );
+            define(
+                self.module,
+                &mut self.data_desc,
+                mem_arrays.union_layout_array,
+                union_mem_data,
+                ptr_align,
+            );
+            define(
+                self.module,
+                &mut self.data_desc,
+                mem_arrays.optional_layout_array,
+                optional_mem_data,
+                ptr_align,
+            );
+            define(
+                self.module,
+                &mut self.data_desc,
+                mem_arrays.tuple_layout_array,
+                tuple_mem_data,
+                ptr_align,
+            );
 
             define_slice(
                 self.module,
@@ -456,6 +596,27 @@ impl Compiler<'_> {
                 struct_count,
                 mem_arrays.struct_layout_array,
             );
+            define_slice(
+                self.module,
+                &mut self.data_desc,
+                mem_arrays.union_layout_slice,
+                union_count,
+                mem_arrays.union_layout_array,
+            );
+            define_slice(
+                self.module,
+                &mut self.data_desc,
+                mem_arrays.optional_layout_slice,
+                optional_count,
+                mem_arrays.optional_layout_array,
+            );
+            define_slice(
+                self.module,
+                &mut self.data_desc,
+                mem_arrays.tuple_layout_slice,
+                tuple_count,
+                mem_arrays.tuple_layout_array,
+            );
 
             // pointer layout
 
@@ -507,6 +668,13 @@ impl Compiler<'_> {
                 distinct_info_data,
                 meta_type_align,
             );
+            define(
+                self.module,
+                &mut self.data_desc,
+                info_arrays.optional_info_array,
+                optional_info_data,
+                meta_type_align,
+            );
 
             define_slice(
                 self.module,
@@ -543,8 +711,36 @@ impl Compiler<'_> {
                 struct_count,
                 info_arrays.struct_info_array,
             );
+            define_slice(
+                self.module,
+                &mut self.data_desc,
+                info_arrays.union_info_slice,
+                union_count,
+                info_arrays.union_info_array,
+            );
+            define_slice(
+                self.module,
+                &mut self.data_desc,
+                info_arrays.optional_info_slice,
+                optional_count,
+                info_arrays.optional_info_array,
+            );
+            define_slice(
+                self.module,
+                &mut self.data_desc,
+                info_arrays.tuple_info_slice,
+                tuple_count,
+                info_arrays.tuple_info_array,
+            );
+            define_slice(
+                self.module,
+                &mut self.data_desc,
+                info_arrays.function_info_slice,
+                function_count,
+                info_arrays.function_info_array,
+            );
 
-            // now building the arrays of every struct member in the program
+            // now building the arrays of every struct, union, and tuple member in the program
 
             fn declare(module: &mut dyn Module, name: &str) -> DataId {
                 module
@@ -558,13 +754,36 @@ impl Compiler<'_> {
             let mut member_array_data = Vec::new();
             let mut member_array_relocs = Vec::new();
 
-            for s in &struct_infos_to_compile {
-                let Ty::Struct { members, .. } = s.as_ref() else {
-                    unreachable_opt_on_release!();
+            for s in struct_infos_to_compile
+                .iter()
+                .chain(&union_infos_to_compile)
+                .chain(&tuple_infos_to_compile)
+            {
+                let named_members = |members: &[(hir::Name, Intern<Ty>)]| {
+                    members
+                        .iter()
+                        .map(|(name, ty)| (self.interner.lookup(name.0).to_string(), *ty))
+                        .collect::<Vec<_>>()
                 };
 
-                let member_offsets = s.struct_layout().unwrap();
-                let member_offsets = member_offsets.offsets();
+                let (members, member_offsets) = match s.as_ref() {
+                    Ty::Struct { members, .. } => (
+                        named_members(members),
+                        s.struct_layout().unwrap().offsets().to_vec(),
+                    ),
+                    // every member of a union starts at the same place
+                    Ty::Union { members, .. } => (named_members(members), vec![0; members.len()]),
+                    // the items of a tuple are named by their index
+                    Ty::Tuple { item_tys } => (
+                        item_tys
+                            .iter()
+                            .enumerate()
+                            .map(|(idx, ty)| (idx.to_string(), *ty))
+                            .collect(),
+                        s.struct_layout().unwrap().offsets().to_vec(),
+                    ),
+                    _ => unreachable_opt_on_release!(),
+                };
 
                 member_array_starting_offsets.push(member_array_data.len());
 
@@ -578,13 +797,21 @@ impl Compiler<'_> {
 
                     // name field
 
+                    let mut name_str_bytes = name.as_bytes().to_vec();
+
+                    // str len
+                    member_array_data.extend_with_num_bytes(
+                        name_str_bytes.len() as u32,
+                        self.ptr_ty.bits() as u8,
+                        self.module.isa().endianness(),
+                    );
+
                     let name_offset = member_array_data.len();
 
                     // zeroed-out str pointer, this will be written over later
                     member_array_data
                         .extend(std::iter::repeat(0).take(self.ptr_ty.bytes() as usize));
 
-                    let mut name_str_bytes = self.interner.lookup(name.0).as_bytes().to_vec();
                     name_str_bytes.push(0);
                     let name_str_id = declare(
                         self.module,
@@ -650,49 +877,175 @@ impl Compiler<'_> {
                 member_array_relocs,
             );
 
-            // now that all the members have been defined, we can assemble the actual struct info array
+            // now that all the members have been defined, we can assemble the actual struct and
+            // union info arrays. the union members come right after the struct members, and the
+            // tuple members come right after those
+
+            let aggregate_infos = [
+                (&struct_infos_to_compile, info_arrays.struct_info_array, 0),
+                (
+                    &union_infos_to_compile,
+                    info_arrays.union_info_array,
+                    struct_infos_to_compile.len(),
+                ),
+                (
+                    &tuple_infos_to_compile,
+                    info_arrays.tuple_info_array,
+                    struct_infos_to_compile.len() + union_infos_to_compile.len(),
+                ),
+            ];
+
+            for (infos_to_compile, info_array, first_idx) in aggregate_infos {
+                let member_array_local = self
+                    .module
+                    .declare_data_in_data(member_array_id, &mut self.data_desc);
+
+                let mut info_array_data = Vec::new();
+
+                for (idx, s) in infos_to_compile.iter().enumerate() {
+                    let members_len = match s.as_ref() {
+                        Ty::Struct { members, .. } | Ty::Union { members, .. } => members.len(),
+                        Ty::Tuple { item_tys } => item_tys.len(),
+                        _ => unreachable_opt_on_release!(),
+                    };
 
-            let member_array_local = self
-                .module
-                .declare_data_in_data(member_array_id, &mut self.data_desc);
+                    info_array_data.extend_with_num_bytes(
+                        members_len as u32,
+                        self.ptr_ty.bits() as u8,
+                        self.module.isa().endianness(),
+                    );
 
-            let mut struct_array_data = Vec::new();
+                    let ptr_member_offset = info_array_data.len();
 
-            for (idx, s) in struct_infos_to_compile.iter().enumerate() {
-                let Ty::Struct { members, .. } = s.as_ref() else {
+                    info_array_data.extend_with_num_bytes(
+                        0,
+                        self.ptr_ty.bits() as u8,
+                        self.module.isa().endianness(),
+                    );
+
+                    let member_array_starting_offset =
+                        member_array_starting_offsets[first_idx + idx];
+
+                    self.data_desc.write_data_addr(
+                        ptr_member_offset as u32,
+                        member_array_local,
+                        member_array_starting_offset as i64,
+                    );
+                }
+
+                define(
+                    self.module,
+                    &mut self.data_desc,
+                    info_array,
+                    info_array_data,
+                    ptr_align, // the alignment of a slice == the alignment of a single pointer
+                );
+            }
+
+            // now building the arrays of every function parameter in the program
+
+            let mut param_array_starting_offsets = Vec::new();
+            let mut param_array_data = Vec::new();
+
+            for f in &function_infos_to_compile {
+                let (Ty::Function { param_tys, .. } | Ty::Closure { param_tys, .. }) = f.as_ref()
+                else {
                     unreachable_opt_on_release!();
                 };
-                let members_len = members.len();
 
-                struct_array_data.extend_with_num_bytes(
-                    members_len as u32,
+                param_array_starting_offsets.push(param_array_data.len());
+
+                for param in param_tys {
+                    param_array_data.extend_with_num_bytes(
+                        param.to_previous_type_id(&self.meta_tys, self.ptr_ty),
+                        32,
+                        self.module.isa().endianness(),
+                    );
+                }
+            }
+
+            let param_array_id =
+                declare(self.module, &mangle::mangle_internal("function_param_info"));
+
+            define(
+                self.module,
+                &mut self.data_desc,
+                param_array_id,
+                param_array_data,
+                meta_type_align,
+            );
+
+            // now that all the parameters have been defined, we can assemble the function info array
+
+            let param_array_local = self
+                .module
+                .declare_data_in_data(param_array_id, &mut self.data_desc);
+
+            let mut function_array_data = Vec::new();
+
+            for (idx, f) in function_infos_to_compile.iter().enumerate() {
+                let (param_tys, return_ty, is_variadic) = match f.as_ref() {
+                    Ty::Function {
+                        param_tys,
+                        return_ty,
+                        is_variadic,
+                    } => (param_tys, return_ty, *is_variadic),
+                    Ty::Closure {
+                        param_tys,
+                        return_ty,
+                    } => (param_tys, return_ty, false),
+                    _ => unreachable_opt_on_release!(),
+                };
+
+                // params field
+
+                function_array_data.extend_with_num_bytes(
+                    param_tys.len() as u32,
                     self.ptr_ty.bits() as u8,
                     self.module.isa().endianness(),
                 );
 
-                let ptr_member_offset = struct_array_data.len();
+                let ptr_param_offset = function_array_data.len();
 
-                struct_array_data.extend_with_num_bytes(
+                function_array_data.extend_with_num_bytes(
                     0,
                     self.ptr_ty.bits() as u8,
                     self.module.isa().endianness(),
                 );
 
-                let member_array_starting_offset = member_array_starting_offsets[idx];
-
                 self.data_desc.write_data_addr(
-                    ptr_member_offset as u32,
-                    member_array_local,
-                    member_array_starting_offset as i64,
+                    ptr_param_offset as u32,
+                    param_array_local,
+                    param_array_starting_offsets[idx] as i64,
+                );
+
+                // return_ty field
+
+                function_array_data.extend_with_num_bytes(
+                    return_ty.to_previous_type_id(&self.meta_tys, self.ptr_ty),
+                    32,
+                    self.module.isa().endianness(),
                 );
+
+                // is_variadic field
+
+                function_array_data.push(is_variadic as u8);
+
+                // padding for the next function info
+
+                let padding = layout::padding_needed_for(
+                    function_array_data.len() as u32,
+                    self.ptr_ty.bytes().min(8),
+                );
+                function_array_data.extend(std::iter::repeat_n(0, padding as usize));
             }
 
             define(
                 self.module,
                 &mut self.data_desc,
-                info_arrays.struct_info_array,
-                struct_array_data,
-                ptr_align, // the alignment of a slice == the alignment of a single pointer
+                info_arrays.function_info_array,
+                function_array_data,
+                ptr_align,
             );
         }
     }
@@ -757,13 +1110,24 @@ impl Compiler<'_> {
             )
         };
 
+        let mangled_name = ftc.to_mangled_name(self.mod_dir, self.interner);
+
+        let (param_tys, closure) = if ftc.is_closure {
+            let mut param_tys = ftc.param_tys;
+            param_tys.push(closure_env_ty());
+            (param_tys, Some(ftc.lambda))
+        } else {
+            (ftc.param_tys, None)
+        };
+
         self.compile_real_function(
             &unmangled_name,
-            &ftc.to_mangled_name(self.mod_dir, self.interner),
+            &mangled_name,
             ftc.file_name,
             *body,
-            ftc.param_tys,
+            param_tys,
             ftc.return_ty,
+            closure,
         );
     }
 
@@ -821,31 +1185,14 @@ impl Compiler<'_> {
         self.module.clear_context(&mut self.ctx);
     }
 
-    fn compile_real_function(
-        &mut self,
-        unmangled_name: &str,
-        mangled_name: &str,
-        module_name: hir::FileName,
-        body: Idx<hir::Expr>,
-        param_tys: Vec<Intern<Ty>>,
-        return_ty: Intern<Ty>,
-    ) -> FuncId {
-        let fn_abi = self.default_abi.fn_to_target((&param_tys, return_ty));
-        let comp_sig = fn_abi.to_cl(self.ptr_ty, self.module.target_config().default_call_conv);
-        let func_id = self
-            .module
-            .declare_function(mangled_name, Linkage::Export, &comp_sig)
-            .unwrap();
-
-        self.ctx.func.signature = comp_sig.clone();
-
+    fn function_compiler(&mut self, file_name: hir::FileName) -> FunctionCompiler<'_> {
         // Create the builder to build a function.
         let builder = FunctionBuilder::new(&mut self.ctx.func, &mut self.builder_context);
 
-        let function_compiler = FunctionCompiler {
+        FunctionCompiler {
             final_binary: self.final_binary,
             builder,
-            file_name: module_name,
+            file_name,
             mod_dir: self.mod_dir,
             interner: self.interner,
             world_bodies: self.world_bodies,
@@ -859,18 +1206,43 @@ impl Compiler<'_> {
             local_lambdas: FxHashMap::default(),
             functions: &mut self.functions,
             compiler_defined_functions: &mut self.compiler_defined_functions,
+            var_args_trampolines: &mut self.var_args_trampolines,
+            vtables: &mut self.vtables,
             globals: &mut self.data,
             str_id_gen: &mut self.str_id_gen,
             i128_id_gen: &mut self.i128_id_gen,
+            embeds: &mut self.embeds,
             comptime_results: self.comptime_results,
             comptime_data: &mut self.comptime_data,
             var_id_gen: UIDGenerator::default(),
             locals: FxHashMap::default(),
             params: FxHashMap::default(),
+            closure: None,
             exits: FxHashMap::default(),
             continues: FxHashMap::default(),
             defer_stack: Vec::new(),
-        };
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn compile_real_function(
+        &mut self,
+        unmangled_name: &str,
+        mangled_name: &str,
+        module_name: hir::FileName,
+        body: Idx<hir::Expr>,
+        param_tys: Vec<Intern<Ty>>,
+        return_ty: Intern<Ty>,
+        closure: Option<Idx<hir::Lambda>>,
+    ) -> FuncId {
+        let fn_abi = self.default_abi.fn_to_target((&param_tys, return_ty));
+        let comp_sig = fn_abi.to_cl(self.ptr_ty, self.module.target_config().default_call_conv);
+        let func_id = self
+            .module
+            .declare_function(mangled_name, Linkage::Export, &comp_sig)
+            .unwrap();
+
+        self.ctx.func.signature = comp_sig.clone();
 
         let debug_print = self.verbosity == Verbosity::AllFunctions
             || (self.verbosity == Verbosity::LocalFunctions
@@ -880,6 +1252,8 @@ impl Compiler<'_> {
             println!("{} \x1B[90m{}\x1B[0m:", unmangled_name, mangled_name);
         }
 
+        let mut function_compiler = self.function_compiler(module_name);
+        function_compiler.closure = closure;
         function_compiler.finish(fn_abi, (&param_tys, return_ty), body, debug_print);
 
         self.module
@@ -898,6 +1272,69 @@ impl Compiler<'_> {
 
         func_id
     }
+
+    /// Compiles the initializers of any runtime globals which have been used but don't have
+    /// initializers yet. Returns false if there weren't any.
+    fn compile_global_inits(&mut self) -> bool {
+        let to_init = self
+            .data
+            .iter()
+            .filter(|(fqn, _)| {
+                self.tys.is_runtime_global(**fqn) && !self.global_inits.contains_key(*fqn)
+            })
+            .map(|(fqn, global)| (*fqn, *global))
+            .collect::<Vec<_>>();
+
+        for &(fqn, global) in &to_init {
+            let func_id = self.compile_global_init(fqn, global);
+            self.global_inits.insert(fqn, func_id);
+        }
+
+        !to_init.is_empty()
+    }
+
+    fn compile_global_init(&mut self, fqn: hir::Fqn, global: DataId) -> FuncId {
+        let unmangled_name = format!("{}.init", fqn.to_string(self.mod_dir, self.interner));
+        let mangled_name = (fqn, "init").to_mangled_name(self.mod_dir, self.interner);
+
+        let sig = Signature::new(self.module.target_config().default_call_conv);
+        let func_id = self
+            .module
+            .declare_function(&mangled_name, Linkage::Export, &sig)
+            .unwrap();
+
+        self.ctx.func.signature = sig;
+
+        let debug_print = self.verbosity == Verbosity::AllFunctions
+            || (self.verbosity == Verbosity::LocalFunctions
+                && !fqn.file.is_mod(self.mod_dir, self.interner));
+
+        if debug_print {
+            println!("{} \x1B[90m{}\x1B[0m:", unmangled_name, mangled_name);
+        }
+
+        let global_ty = self.tys[fqn].0;
+        let body = self.world_bodies.body(fqn);
+
+        self.function_compiler(fqn.file)
+            .finish_global_init(global, global_ty, body, debug_print);
+
+        self.module
+            .define_function(func_id, &mut self.ctx)
+            .unwrap_or_else(|err| {
+                println!("Error defining function:");
+                if let ModuleError::Compilation(CodegenError::Verifier(v)) = err {
+                    println!("{}", v.to_string().replace("):", "):\n "));
+                } else {
+                    println!("{:?}", err);
+                }
+                std::process::exit(1);
+            });
+
+        self.module.clear_context(&mut self.ctx);
+
+        func_id
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -995,6 +1432,8 @@ fn get_func_id(
         lambda,
         param_tys: param_tys.clone(),
         return_ty,
+        is_export: world_bodies[fqn.file][lambda].is_export,
+        is_closure: false,
     };
 
     if let Some(compiler_defined) = as_compiler_defined_func(is_extern, &ftc, mod_dir, interner) {
@@ -1029,19 +1468,19 @@ fn get_func_id(
         return func_id;
     }
 
-    functions_to_compile.push_back(ftc);
-
     let comp_sig = Into::<Abi>::into(module.target_config())
         .fn_to_target((&param_tys, return_ty))
         .to_cl(pointer_ty, module.target_config().default_call_conv);
 
     let func_id = module
         .declare_function(
-            &fqn.to_mangled_name(mod_dir, interner),
+            &ftc.to_mangled_name(mod_dir, interner),
             Linkage::Export,
             &comp_sig,
         )
-        .unwrap();
+        .expect("There are multiple exported functions with the same name");
+
+    functions_to_compile.push_back(ftc);
 
     functions.insert(fqn, func_id);
 
@@ -1197,7 +1636,7 @@ impl UnwrapOrAlloca for Option<MemoryLoc> {
                 let stack_slot = builder.create_sized_stack_slot(StackSlotData {
                     kind: StackSlotKind::ExplicitSlot,
                     size: ty.size(),
-                    align_shift: ty.align() as u8,
+                    align_shift: ty.align().trailing_zeros() as u8,
                 });
 
                 MemoryLoc::from_stack(stack_slot, 0)
@@ -1206,6 +1645,50 @@ impl UnwrapOrAlloca for Option<MemoryLoc> {
     }
 }
 
+/// the libc functions which the compiler calls by itself
+const LIBC_FUNCS: &[&str] = &["malloc", "puts", "exit"];
+
+/// calls one of the `LIBC_FUNCS`.
+///
+/// if the function has already been declared with a different signature (e.g. the program has
+/// `puts :: (s: cstr) -> i32 extern;`), it gets called through its address instead
+fn call_libc_func(
+    module: &mut dyn Module,
+    builder: &mut FunctionBuilder,
+    ptr_ty: types::Type,
+    name: &str,
+    args: &[Value],
+    returns: &[types::Type],
+) -> Option<Value> {
+    debug_assert!(LIBC_FUNCS.contains(&name));
+
+    let mut sig = module.make_signature();
+    sig.params.extend(
+        args.iter()
+            .map(|arg| AbiParam::new(builder.func.dfg.value_type(*arg))),
+    );
+    sig.returns
+        .extend(returns.iter().map(|ret| AbiParam::new(*ret)));
+
+    let func_id = match module.get_name(name) {
+        Some(FuncOrDataId::Func(func_id)) => func_id,
+        _ => module
+            .declare_function(name, Linkage::Import, &sig)
+            .expect("libc function has the same name as a global"),
+    };
+    let func_ref = module.declare_func_in_func(func_id, builder.func);
+
+    let call = if module.declarations().get_function_decl(func_id).signature == sig {
+        builder.ins().call(func_ref, args)
+    } else {
+        let addr = builder.ins().func_addr(ptr_ty, func_ref);
+        let sig = builder.import_signature(sig);
+        builder.ins().call_indirect(sig, addr, args)
+    };
+
+    builder.inst_results(call).first().copied()
+}
+
 #[allow(clippy::too_many_arguments)]
 fn cast_into_memory(
     meta_tys: &mut MetaTyData,
@@ -1234,7 +1717,7 @@ fn cast_into_memory(
     let cast_to = cast_to.remove_distinct();
 
     match (cast_from.as_ref(), cast_to.as_ref()) {
-        (Ty::Array { size, .. }, Ty::Slice { .. }) => {
+        (Ty::Array { size, .. }, Ty::Slice { .. } | Ty::String) => {
             let memory = memory.unwrap_or_alloca(builder, cast_to);
 
             let len = builder.ins().iconst(ptr_ty, *size as i64);
@@ -1243,7 +1726,7 @@ fn cast_into_memory(
 
             return Some(memory.into_value(builder, ptr_ty));
         }
-        (Ty::Slice { .. }, Ty::Array { .. }) => {
+        (Ty::Slice { .. } | Ty::String, Ty::Array { .. }) => {
             // todo: do a runtime check that the lengths match
 
             return Some(builder.ins().load(
@@ -1254,6 +1737,97 @@ fn cast_into_memory(
                 ptr_ty.bytes() as i32,
             ));
         }
+        (Ty::String, Ty::CString) => {
+            // only string literals can be casted to a `cstr`, and those are null-terminated
+            let ptr = builder
+                .ins()
+                .load(ptr_ty, MemFlags::trusted(), val?, ptr_ty.bytes() as i32);
+
+            if let Some(memory) = memory {
+                memory.write(Some(ptr), cast_to, module, builder);
+            }
+
+            return Some(ptr);
+        }
+        (Ty::CString, Ty::String) => {
+            let ptr = val?;
+
+            // count the bytes until the null
+            let loop_block = builder.create_block();
+            let exit_block = builder.create_block();
+
+            let idx = builder.append_block_param(loop_block, ptr_ty);
+            let len = builder.append_block_param(exit_block, ptr_ty);
+
+            let zero = builder.ins().iconst(ptr_ty, 0);
+            builder.ins().jump(loop_block, &[zero]);
+
+            builder.switch_to_block(loop_block);
+
+            let byte_addr = builder.ins().iadd(ptr, idx);
+            let byte = builder
+                .ins()
+                .load(types::I8, MemFlags::trusted(), byte_addr, 0);
+            let next_idx = builder.ins().iadd_imm(idx, 1);
+            builder
+                .ins()
+                .brif(byte, loop_block, &[next_idx], exit_block, &[idx]);
+
+            builder.seal_block(loop_block);
+
+            builder.switch_to_block(exit_block);
+            builder.seal_block(exit_block);
+
+            let memory = memory.unwrap_or_alloca(builder, cast_to);
+
+            memory.store(builder, len, 0);
+            memory.store(builder, ptr, ptr_ty.bytes() as i32);
+
+            return Some(memory.into_value(builder, ptr_ty));
+        }
+        (Ty::Null, Ty::Optional { sub_ty }) => {
+            if sub_ty.has_null_niche() {
+                let null = builder.ins().iconst(ptr_ty, 0);
+
+                if let Some(memory) = memory {
+                    memory.write(Some(null), cast_to, module, builder);
+                }
+
+                return Some(null);
+            }
+
+            let memory = memory.unwrap_or_alloca(builder, cast_to);
+
+            let tag = builder.ins().iconst(types::I8, 0);
+            memory.store(builder, tag, sub_ty.size() as i32);
+
+            return Some(memory.into_value(builder, ptr_ty));
+        }
+        (found, Ty::Optional { sub_ty }) if !found.is_optional() => {
+            if sub_ty.has_null_niche() {
+                return cast_into_memory(
+                    meta_tys, module, builder, ptr_ty, val, cast_from, *sub_ty, memory,
+                );
+            }
+
+            let memory = memory.unwrap_or_alloca(builder, cast_to);
+
+            cast_into_memory(
+                meta_tys,
+                module,
+                builder,
+                ptr_ty,
+                val,
+                cast_from,
+                *sub_ty,
+                Some(memory),
+            );
+
+            let tag = builder.ins().iconst(types::I8, 1);
+            memory.store(builder, tag, sub_ty.size() as i32);
+
+            return Some(memory.into_value(builder, ptr_ty));
+        }
         _ if cast_to.is_any_struct() => {
             let any_mem = memory.unwrap_or_alloca(builder, cast_to);
 
@@ -1272,7 +1846,7 @@ fn cast_into_memory(
                                     builder.create_sized_stack_slot(StackSlotData {
                                         kind: StackSlotKind::ExplicitSlot,
                                         size: cast_from.size(),
-                                        align_shift: cast_from.align() as u8,
+                                        align_shift: cast_from.align().trailing_zeros() as u8,
                                     });
 
                                 builder.ins().stack_store(val, tmp_stack_slot, 0);
@@ -1313,8 +1887,9 @@ fn cast_into_memory(
     // it's a simple cast
 
     let val = match (cast_from.get_final_ty(), cast_to.get_final_ty()) {
-        (FinalTy::Number(cast_from), FinalTy::Number(cast_to)) => {
-            Some(cast_num(builder, val?, cast_from, cast_to))
+        (FinalTy::Number(cast_from_num), FinalTy::Number(cast_to_num)) => {
+            let val = cast_num(builder, val?, cast_from_num, cast_to_num);
+            Some(wrap_int(builder, val, cast_to))
         }
         _ => val,
     };
@@ -1345,12 +1920,12 @@ fn cast_struct_to_struct(
 
     assert_eq!(from_members.len(), to_members.len());
 
-    if from_members
-        .iter()
-        .zip(to_members.iter())
-        .all(|((from_name, from_ty), (to_name, to_ty))| {
-            from_name == to_name && from_ty.is_functionally_equivalent_to(to_ty, true)
-        })
+    if cast_from.struct_repr() == cast_to.struct_repr()
+        && from_members.iter().zip(to_members.iter()).all(
+            |((from_name, from_ty), (to_name, to_ty))| {
+                from_name == to_name && from_ty.is_functionally_equivalent_to(to_ty, true)
+            },
+        )
     {
         val
     } else {
@@ -1492,6 +2067,35 @@ fn cast_ty_to_cranelift(
     }
 }
 
+/// ints like `u5` or `i24` are stored in a bigger cranelift int,
+/// so after anything that could overflow the unused bits have to be cleared
+/// (or filled with the sign bit for signed ints)
+fn wrap_int(builder: &mut FunctionBuilder, val: Value, ty: Intern<Ty>) -> Value {
+    let (bit_width, signed) = match ty.remove_distinct().as_ref() {
+        Ty::IInt(bit_width) => (*bit_width, true),
+        Ty::UInt(bit_width) => (*bit_width, false),
+        _ => return val,
+    };
+
+    let storage_bit_width = builder.func.dfg.value_type(val).bits();
+
+    if bit_width == 0 || bit_width == u8::MAX || bit_width as u32 >= storage_bit_width {
+        return val;
+    }
+
+    // `iconst` can't make an i128, so the bits are shifted out instead of masked
+    let unused_bits = builder
+        .ins()
+        .iconst(types::I32, (storage_bit_width - bit_width as u32) as i64);
+
+    let val = builder.ins().ishl(val, unused_bits);
+    if signed {
+        builder.ins().sshr(val, unused_bits)
+    } else {
+        builder.ins().ushr(val, unused_bits)
+    }
+}
+
 fn cast_num(
     builder: &mut FunctionBuilder,
     val: Value,
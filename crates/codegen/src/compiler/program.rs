@@ -1,24 +1,29 @@
 //! This module is for building the final executable of a capy program
 
 use cranelift::prelude::{
-    AbiParam, EntityRef, FunctionBuilder, FunctionBuilderContext, InstBuilder, Signature, Variable,
+    types, AbiParam, FunctionBuilder, FunctionBuilderContext, InstBuilder, IntCC, MemFlags,
+    Signature, StackSlotData, StackSlotKind,
 };
 use cranelift_module::{DataDescription, FuncId, Linkage, Module};
 use hir::FQComptime;
-use hir_ty::ComptimeResult;
+use hir_ty::{ComptimeResult, Ty};
 use interner::Interner;
+use internment::Intern;
 use rustc_hash::FxHashMap;
 use std::collections::VecDeque;
 use uid_gen::UIDGenerator;
 
-use crate::Verbosity;
+use crate::{layout::GetLayoutInfo, Verbosity};
 
-use super::{ cast_ty_to_cranelift, Compiler, FunctionToCompile, MetaTyData};
+use super::{
+    call_libc_func, cast_into_memory, cast_ty_to_cranelift, Compiler, FunctionToCompile, MemoryLoc,
+    MetaTyData,
+};
 
 #[allow(clippy::too_many_arguments)]
 pub(crate) fn compile_program<'a>(
     verbosity: Verbosity,
-    entry_point: hir::Fqn,
+    entry_point: Option<hir::Fqn>,
     mod_dir: &'a std::path::Path,
     interner: &'a Interner,
     world_bodies: &'a hir::WorldBodies,
@@ -26,7 +31,7 @@ pub(crate) fn compile_program<'a>(
     module: &'a mut dyn Module,
     comptime_results: &'a FxHashMap<FQComptime, ComptimeResult>,
 ) -> FuncId {
-    let entry_point_ftc = {
+    let entry_point_ftc = entry_point.map(|entry_point| {
         let (param_tys, return_ty) = tys[entry_point]
             .0
             .as_function()
@@ -45,8 +50,10 @@ pub(crate) fn compile_program<'a>(
             lambda,
             param_tys: param_tys.clone(),
             return_ty,
+            is_export: world_bodies[entry_point.file][lambda].is_export,
+            is_closure: false,
         }
-    };
+    });
 
     let default_abi = module.target_config().into();
 
@@ -62,22 +69,103 @@ pub(crate) fn compile_program<'a>(
         ptr_ty: module.target_config().pointer_type(),
         module,
         data_desc: DataDescription::new(),
-        functions_to_compile: VecDeque::from([entry_point_ftc]),
+        functions_to_compile: VecDeque::from_iter(entry_point_ftc),
         meta_tys: MetaTyData::default(),
         functions: FxHashMap::default(),
         compiler_defined_functions: FxHashMap::default(),
+        var_args_trampolines: FxHashMap::default(),
+        vtables: FxHashMap::default(),
         data: FxHashMap::default(),
+        global_inits: FxHashMap::default(),
         str_id_gen: UIDGenerator::default(),
         i128_id_gen: UIDGenerator::default(),
+        embeds: FxHashMap::default(),
         comptime_results,
         comptime_data: FxHashMap::default(),
         default_abi,
     };
 
     compiler.finalize_tys();
+    compiler.declare_libc_externs();
+
+    // exported functions have to be compiled even if nothing in the program uses them
+    for fqn in world_bodies.find_exports() {
+        compiler.get_func_id(fqn);
+    }
+
     compiler.compile_queued();
 
-    generate_main_function(compiler, entry_point)
+    match entry_point {
+        Some(entry_point) => generate_main_function(compiler, entry_point),
+        // libraries don't have a `main` to initialize the runtime globals
+        None => generate_init_function(compiler),
+    }
+}
+
+/// the runtime globals of a library get initialized by calling this function
+pub const LIBRARY_INIT_FUNCTION: &str = "capy_init";
+
+fn generate_init_function(mut compiler: Compiler) -> FuncId {
+    let init_sig = Signature::new(compiler.module.target_config().default_call_conv);
+    let init_id = compiler
+        .module
+        .declare_function(LIBRARY_INIT_FUNCTION, Linkage::Export, &init_sig)
+        .expect("There are multiple exported functions with the same name");
+
+    compiler.ctx.func.signature = init_sig;
+
+    let mut builder = FunctionBuilder::new(&mut compiler.ctx.func, &mut compiler.builder_context);
+
+    let entry_block = builder.create_block();
+
+    builder.switch_to_block(entry_block);
+    builder.seal_block(entry_block);
+
+    call_global_inits(
+        compiler.module,
+        &compiler.global_inits,
+        compiler.tys,
+        &mut builder,
+    );
+
+    builder.ins().return_(&[]);
+
+    builder.seal_all_blocks();
+    builder.finalize();
+
+    if compiler.verbosity == Verbosity::AllFunctions {
+        println!(
+            "{0} \x1B[90m{0}\x1B[0m:\n{1}",
+            LIBRARY_INIT_FUNCTION, compiler.ctx.func
+        );
+    }
+
+    compiler
+        .module
+        .define_function(init_id, &mut compiler.ctx)
+        .expect("error defining function");
+
+    compiler.module.clear_context(&mut compiler.ctx);
+
+    init_id
+}
+
+/// runtime globals have to be initialized before anything can use them
+fn call_global_inits(
+    module: &mut dyn Module,
+    global_inits: &FxHashMap<hir::Fqn, FuncId>,
+    tys: &hir_ty::ProjectInference,
+    builder: &mut FunctionBuilder,
+) {
+    for fqn in tys.global_init_order() {
+        let Some(init_func) = global_inits.get(fqn) else {
+            continue;
+        };
+
+        let local_init_func = module.declare_func_in_func(*init_func, builder.func);
+
+        builder.ins().call(local_init_func, &[]);
+    }
 }
 
 fn generate_main_function(mut compiler: Compiler, entry_point: hir::Fqn) -> FuncId {
@@ -85,7 +173,7 @@ fn generate_main_function(mut compiler: Compiler, entry_point: hir::Fqn) -> Func
 
     let cmain_sig = Signature {
         params: vec![
-            AbiParam::new(compiler.ptr_ty),
+            AbiParam::new(types::I32 /*int*/),
             AbiParam::new(compiler.ptr_ty),
         ],
         returns: vec![AbiParam::new(compiler.ptr_ty /*isize*/)],
@@ -108,24 +196,102 @@ fn generate_main_function(mut compiler: Compiler, entry_point: hir::Fqn) -> Func
     // tell the builder that the block will have no further predecessors
     builder.seal_block(entry_block);
 
-    let arg_argc = builder.append_block_param(entry_block, compiler.ptr_ty);
+    let arg_argc = builder.append_block_param(entry_block, types::I32);
     let arg_argv = builder.append_block_param(entry_block, compiler.ptr_ty);
 
-    let var_argc = Variable::new(0);
-    builder.declare_var(var_argc, compiler.ptr_ty);
-    builder.def_var(var_argc, arg_argc);
-
-    let var_argv = Variable::new(1);
-    builder.declare_var(var_argv, compiler.ptr_ty);
-    builder.def_var(var_argv, arg_argv);
+    call_global_inits(
+        compiler.module,
+        &compiler.global_inits,
+        compiler.tys,
+        &mut builder,
+    );
 
     let local_entry_point = compiler
         .module
         .declare_func_in_func(entry_point_func, builder.func);
 
-    let call = builder.ins().call(local_entry_point, &[]);
+    let (entry_param_tys, entry_return_ty) = compiler.tys[entry_point].0.as_function().unwrap();
+
+    let mut args = Vec::new();
+
+    // `hir_ty` makes sure that the only parameter the entry point could have is `[]str`.
+    // `argv` holds `cstr`s, so each of them gets turned into a `str` inside of a new buffer
+    if let Some(args_ty) = entry_param_tys.first() {
+        let ptr_ty = compiler.ptr_ty;
+        let str_ty = Intern::new(Ty::String);
+
+        let len = builder.ins().uextend(ptr_ty, arg_argc);
+        let buf_size = builder.ins().imul_imm(len, str_ty.stride() as i64);
+        let buf = call_libc_func(
+            compiler.module,
+            &mut builder,
+            ptr_ty,
+            "malloc",
+            &[buf_size],
+            &[ptr_ty],
+        )
+        .unwrap();
+
+        let header_block = builder.create_block();
+        let body_block = builder.create_block();
+        let exit_block = builder.create_block();
+
+        let idx = builder.append_block_param(header_block, ptr_ty);
+
+        let zero = builder.ins().iconst(ptr_ty, 0);
+        builder.ins().jump(header_block, &[zero]);
+
+        builder.switch_to_block(header_block);
+        let in_bounds = builder.ins().icmp(IntCC::UnsignedLessThan, idx, len);
+        builder
+            .ins()
+            .brif(in_bounds, body_block, &[], exit_block, &[]);
+
+        builder.switch_to_block(body_block);
+
+        let arg_offset = builder.ins().imul_imm(idx, ptr_ty.bytes() as i64);
+        let arg_addr = builder.ins().iadd(arg_argv, arg_offset);
+        let arg = builder.ins().load(ptr_ty, MemFlags::trusted(), arg_addr, 0);
+
+        let str_offset = builder.ins().imul_imm(idx, str_ty.stride() as i64);
+        let str_addr = builder.ins().iadd(buf, str_offset);
+
+        cast_into_memory(
+            &mut compiler.meta_tys,
+            compiler.module,
+            &mut builder,
+            ptr_ty,
+            Some(arg),
+            Intern::new(Ty::CString),
+            str_ty,
+            Some(MemoryLoc::from_addr(str_addr, 0)),
+        );
+
+        let next_idx = builder.ins().iadd_imm(idx, 1);
+        builder.ins().jump(header_block, &[next_idx]);
+
+        builder.switch_to_block(exit_block);
+
+        let args_slot = builder.create_sized_stack_slot(StackSlotData {
+            kind: StackSlotKind::ExplicitSlot,
+            size: args_ty.size(),
+            align_shift: args_ty.align().trailing_zeros() as u8,
+        });
+
+        builder.ins().stack_store(len, args_slot, 0);
+        builder
+            .ins()
+            .stack_store(buf, args_slot, ptr_ty.bytes() as i32);
+
+        args.push(builder.ins().stack_addr(ptr_ty, args_slot, 0));
+    }
+
+    let entry_abi = compiler
+        .default_abi
+        .fn_to_target((&entry_param_tys, entry_return_ty));
+    let args = entry_abi.get_arg_list(args, &mut builder);
 
-    let (_, entry_return_ty) = compiler.tys[entry_point].0.as_function().unwrap();
+    let call = builder.ins().call(local_entry_point, &args);
 
     let exit_code = if entry_return_ty.is_void() {
         builder.ins().iconst(compiler.ptr_ty, 0)
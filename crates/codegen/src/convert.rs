@@ -130,6 +130,15 @@ pub(crate) fn calc_finals(tys: impl Iterator<Item = Intern<Ty>>, ptr_ty: types::
     }
 }
 
+/// ints with a bit width cranelift doesn't have (e.g. `u5` or `i24`)
+/// are stored in the next biggest int which it does have
+pub(crate) fn int_storage_bit_width(bit_width: u8) -> u8 {
+    match bit_width {
+        0 | u8::MAX => bit_width,
+        _ => bit_width.next_power_of_two().max(8),
+    }
+}
+
 fn calc_single(ty: Intern<Ty>, ptr_ty: types::Type) {
     {
         let finals = unsafe { FINAL_TYS.lock() }.unwrap();
@@ -140,7 +149,7 @@ fn calc_single(ty: Intern<Ty>, ptr_ty: types::Type) {
     }
 
     let finalize_int = |bit_width: u8, signed: bool| -> FinalTy {
-        match bit_width {
+        match int_storage_bit_width(bit_width) {
             u8::MAX => FinalTy::Number(NumberType {
                 ty: ptr_ty,
                 float: false,
@@ -210,7 +219,7 @@ fn calc_single(ty: Intern<Ty>, ptr_ty: types::Type) {
             float: false,
             signed: false,
         }),
-        hir_ty::Ty::String => FinalTy::Pointer(ptr_ty),
+        hir_ty::Ty::String | hir_ty::Ty::CString => FinalTy::Pointer(ptr_ty),
         hir_ty::Ty::Array { sub_ty, .. } => {
             calc_single(*sub_ty, ptr_ty);
             FinalTy::Pointer(ptr_ty)
@@ -230,6 +239,11 @@ fn calc_single(ty: Intern<Ty>, ptr_ty: types::Type) {
         hir_ty::Ty::Function {
             param_tys,
             return_ty,
+            ..
+        }
+        | hir_ty::Ty::Closure {
+            param_tys,
+            return_ty,
         } => {
             for param in param_tys {
                 calc_single(*param, ptr_ty);
@@ -237,12 +251,32 @@ fn calc_single(ty: Intern<Ty>, ptr_ty: types::Type) {
             calc_single(*return_ty, ptr_ty);
             FinalTy::Pointer(ptr_ty)
         }
-        hir_ty::Ty::Struct { members, .. } => {
+        hir_ty::Ty::Struct { members, .. } | hir_ty::Ty::Union { members, .. } => {
             for (_, ty) in members {
                 calc_single(*ty, ptr_ty);
             }
             FinalTy::Pointer(ptr_ty)
         }
+        hir_ty::Ty::Tuple { item_tys } => {
+            for ty in item_tys {
+                calc_single(*ty, ptr_ty);
+            }
+            FinalTy::Pointer(ptr_ty)
+        }
+        hir_ty::Ty::Interface { methods, .. } => {
+            for (_, ty) in methods {
+                calc_single(*ty, ptr_ty);
+            }
+            FinalTy::Pointer(ptr_ty)
+        }
+        hir_ty::Ty::Optional { sub_ty } => {
+            calc_single(*sub_ty, ptr_ty);
+            if sub_ty.has_null_niche() {
+                sub_ty.get_final_ty()
+            } else {
+                FinalTy::Pointer(ptr_ty)
+            }
+        }
         hir_ty::Ty::Type => FinalTy::Number(NumberType {
             ty: types::I32,
             float: false,
@@ -251,6 +285,7 @@ fn calc_single(ty: Intern<Ty>, ptr_ty: types::Type) {
         // you should never be able to get an any value
         hir_ty::Ty::Any => FinalTy::Void,
         hir_ty::Ty::Void => FinalTy::Void,
+        hir_ty::Ty::Null => FinalTy::Void,
         hir_ty::Ty::NoEval => FinalTy::Void,
         hir_ty::Ty::File(_) => FinalTy::Void,
     };
@@ -271,6 +306,7 @@ pub(crate) const CHAR_DISCRIMINANT: u32 = 6;
 pub(crate) const META_TYPE_DISCRIMINANT: u32 = 7;
 pub(crate) const ANY_DISCRIMINANT: u32 = 8;
 pub(crate) const FILE_DISCRIMINANT: u32 = 9;
+pub(crate) const CSTRING_DISCRIMINANT: u32 = 10;
 
 pub(crate) const STRUCT_DISCRIMINANT: u32 = 16;
 pub(crate) const DISTINCT_DISCRIMINANT: u32 = 17;
@@ -278,18 +314,34 @@ pub(crate) const ARRAY_DISCRIMINANT: u32 = 18;
 pub(crate) const SLICE_DISCRIMINANT: u32 = 19;
 pub(crate) const POINTER_DISCRIMINANT: u32 = 20;
 pub(crate) const FUNCTION_DISCRIMINANT: u32 = 21;
+pub(crate) const UNION_DISCRIMINANT: u32 = 22;
+pub(crate) const OPTIONAL_DISCRIMINANT: u32 = 23;
+pub(crate) const TUPLE_DISCRIMINANT: u32 = 24;
+pub(crate) const CLOSURE_DISCRIMINANT: u32 = 25;
+pub(crate) const INTERFACE_DISCRIMINANT: u32 = 26;
 
 fn simple_id(discriminant: u32, bit_width: u32, signed: bool) -> u32 {
     // the last 6 bits are reserved for the discriminant
     let id = discriminant << 26;
 
-    let byte_width = bit_width / 8;
+    let byte_width = match bit_width {
+        0 => 0,
+        _ => bit_width.next_power_of_two().max(8) / 8,
+    };
 
     let align = byte_width.clamp(1, 8) << 5;
 
     let sign = (signed as u32) << 9;
 
-    id | sign | align | byte_width
+    // ints like `u5` are stored in a whole byte, so their exact width is kept in the next 8 bits.
+    // this also keeps them from having the same id as the int they're stored in
+    let exact_width = if bit_width != byte_width * 8 {
+        bit_width << 10
+    } else {
+        0
+    };
+
+    id | exact_width | sign | align | byte_width
 }
 
 pub(crate) trait ToTyId {
@@ -321,12 +373,15 @@ impl ToTyId for Intern<Ty> {
             Ty::Float(0) => simple_id(FLOAT_DISCRIMINANT, 32, false),
             Ty::Float(bit_width) => simple_id(FLOAT_DISCRIMINANT, *bit_width as u32, false),
             Ty::Bool => simple_id(BOOL_DISCRIMINANT, 8, false),
-            Ty::String => simple_id(STRING_DISCRIMINANT, pointer_ty.bits(), false),
+            Ty::String => simple_id(STRING_DISCRIMINANT, pointer_ty.bits() * 2, false),
+            Ty::CString => simple_id(CSTRING_DISCRIMINANT, pointer_ty.bits(), false),
             Ty::Char => simple_id(CHAR_DISCRIMINANT, 8, false),
             Ty::Type => simple_id(META_TYPE_DISCRIMINANT, 32, false),
             Ty::Any => simple_id(ANY_DISCRIMINANT, 0, false),
             Ty::File(_) => simple_id(FILE_DISCRIMINANT, 0, false),
-            Ty::Void | Ty::NoEval => simple_id(VOID_DISCRIMINANT, 0, false),
+            Ty::Void | Ty::Null | Ty::NoEval => simple_id(VOID_DISCRIMINANT, 0, false),
+            // interfaces don't have any infos, so the uid is enough to tell them apart
+            Ty::Interface { uid, .. } => return (INTERFACE_DISCRIMINANT << 26) | uid,
             Ty::Array { sub_ty, .. } => {
                 let id = ARRAY_DISCRIMINANT << 26;
 
@@ -410,17 +465,36 @@ impl ToTyId for Intern<Ty> {
 
                 return id | list_id;
             }
-            Ty::Function { .. } => {
-                let id = FUNCTION_DISCRIMINANT << 26;
-
+            Ty::Function {
+                param_tys,
+                return_ty,
+                ..
+            }
+            | Ty::Closure {
+                param_tys,
+                return_ty,
+            } => {
+                let id = if self.is_closure() {
+                    CLOSURE_DISCRIMINANT << 26
+                } else {
+                    FUNCTION_DISCRIMINANT << 26
+                };
+
+                // closures share the function infos
                 let list_id = meta_tys
                     .tys_to_compile
                     .iter()
-                    .filter(|ty| matches!(ty.as_ref(), Ty::Function { .. }))
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Function { .. } | Ty::Closure { .. }))
                     .enumerate()
                     .find(|(_, ty)| **ty == self)
                     .map(|(idx, _)| idx as u32)
                     .unwrap_or_else(|| {
+                        // make sure to compile the parameter and return types too
+                        for param in param_tys {
+                            param.to_type_id(meta_tys, pointer_ty);
+                        }
+                        return_ty.to_type_id(meta_tys, pointer_ty);
+
                         meta_tys.tys_to_compile.push(self);
                         meta_tys.function_uid_gen.generate_unique_id()
                     });
@@ -451,6 +525,70 @@ impl ToTyId for Intern<Ty> {
 
                 return id | list_id;
             }
+            Ty::Union { members, .. } => {
+                let id = UNION_DISCRIMINANT << 26;
+
+                let list_id = meta_tys
+                    .tys_to_compile
+                    .iter()
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Union { .. }))
+                    .enumerate()
+                    .find(|(_, other_ty)| self.is_equal_to(other_ty))
+                    .map(|(idx, _)| idx as u32)
+                    .unwrap_or_else(|| {
+                        for (_, member) in members {
+                            // make sure to compile the sub type too
+                            member.to_type_id(meta_tys, pointer_ty);
+                        }
+
+                        meta_tys.tys_to_compile.push(self);
+                        meta_tys.union_uid_gen.generate_unique_id()
+                    });
+
+                return id | list_id;
+            }
+            Ty::Optional { sub_ty } => {
+                let id = OPTIONAL_DISCRIMINANT << 26;
+
+                let list_id = meta_tys
+                    .tys_to_compile
+                    .iter()
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Optional { .. }))
+                    .enumerate()
+                    .find(|(_, other_ty)| self.is_equal_to(other_ty))
+                    .map(|(idx, _)| idx as u32)
+                    .unwrap_or_else(|| {
+                        // make sure to compile the sub type too
+                        sub_ty.to_type_id(meta_tys, pointer_ty);
+
+                        meta_tys.tys_to_compile.push(self);
+                        meta_tys.optional_uid_gen.generate_unique_id()
+                    });
+
+                return id | list_id;
+            }
+            Ty::Tuple { item_tys } => {
+                let id = TUPLE_DISCRIMINANT << 26;
+
+                let list_id = meta_tys
+                    .tys_to_compile
+                    .iter()
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Tuple { .. }))
+                    .enumerate()
+                    .find(|(_, other_ty)| self.is_equal_to(other_ty))
+                    .map(|(idx, _)| idx as u32)
+                    .unwrap_or_else(|| {
+                        for item in item_tys {
+                            // make sure to compile the sub type too
+                            item.to_type_id(meta_tys, pointer_ty);
+                        }
+
+                        meta_tys.tys_to_compile.push(self);
+                        meta_tys.tuple_uid_gen.generate_unique_id()
+                    });
+
+                return id | list_id;
+            }
         };
 
         if !meta_tys.tys_to_compile.iter().any(|ty| *ty == self) {
@@ -483,12 +621,14 @@ impl ToTyId for Intern<Ty> {
             Ty::Float(0) => simple_id(FLOAT_DISCRIMINANT, 32, false),
             Ty::Float(bit_width) => simple_id(FLOAT_DISCRIMINANT, *bit_width as u32, false),
             Ty::Bool => simple_id(BOOL_DISCRIMINANT, 8, false),
-            Ty::String => simple_id(STRING_DISCRIMINANT, pointer_ty.bits(), false),
+            Ty::String => simple_id(STRING_DISCRIMINANT, pointer_ty.bits() * 2, false),
+            Ty::CString => simple_id(CSTRING_DISCRIMINANT, pointer_ty.bits(), false),
             Ty::Char => simple_id(CHAR_DISCRIMINANT, 8, false),
             Ty::Type => simple_id(META_TYPE_DISCRIMINANT, 32, false),
             Ty::Any => simple_id(ANY_DISCRIMINANT, 0, false),
             Ty::File(_) => simple_id(FILE_DISCRIMINANT, 0, false),
-            Ty::Void | Ty::NoEval => simple_id(VOID_DISCRIMINANT, 0, false),
+            Ty::Void | Ty::Null | Ty::NoEval => simple_id(VOID_DISCRIMINANT, 0, false),
+            Ty::Interface { uid, .. } => (INTERFACE_DISCRIMINANT << 26) | uid,
             Ty::Array { .. } => {
                 let id = ARRAY_DISCRIMINANT << 26;
 
@@ -548,13 +688,17 @@ impl ToTyId for Intern<Ty> {
 
                 id | list_id
             }
-            Ty::Function { .. } => {
-                let id = FUNCTION_DISCRIMINANT << 26;
+            Ty::Function { .. } | Ty::Closure { .. } => {
+                let id = if self.is_closure() {
+                    CLOSURE_DISCRIMINANT << 26
+                } else {
+                    FUNCTION_DISCRIMINANT << 26
+                };
 
                 let list_id = meta_tys
                     .tys_to_compile
                     .iter()
-                    .filter(|ty| matches!(ty.as_ref(), Ty::Function { .. }))
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Function { .. } | Ty::Closure { .. }))
                     .enumerate()
                     .find(|(_, ty)| **ty == self)
                     .map(|(idx, _)| idx as u32)
@@ -576,6 +720,48 @@ impl ToTyId for Intern<Ty> {
 
                 id | list_id
             }
+            Ty::Union { .. } => {
+                let id = UNION_DISCRIMINANT << 26;
+
+                let list_id = meta_tys
+                    .tys_to_compile
+                    .iter()
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Union { .. }))
+                    .enumerate()
+                    .find(|(_, other_ty)| self.is_equal_to(other_ty))
+                    .map(|(idx, _)| idx as u32)
+                    .unwrap();
+
+                id | list_id
+            }
+            Ty::Optional { .. } => {
+                let id = OPTIONAL_DISCRIMINANT << 26;
+
+                let list_id = meta_tys
+                    .tys_to_compile
+                    .iter()
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Optional { .. }))
+                    .enumerate()
+                    .find(|(_, other_ty)| self.is_equal_to(other_ty))
+                    .map(|(idx, _)| idx as u32)
+                    .unwrap();
+
+                id | list_id
+            }
+            Ty::Tuple { .. } => {
+                let id = TUPLE_DISCRIMINANT << 26;
+
+                let list_id = meta_tys
+                    .tys_to_compile
+                    .iter()
+                    .filter(|ty| matches!(ty.as_ref(), Ty::Tuple { .. }))
+                    .enumerate()
+                    .find(|(_, other_ty)| self.is_equal_to(other_ty))
+                    .map(|(idx, _)| idx as u32)
+                    .unwrap();
+
+                id | list_id
+            }
         }
     }
 }
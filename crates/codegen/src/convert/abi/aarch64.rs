@@ -1,6 +1,6 @@
 // TODO: other kinds of aarch64 abis then apple
 
-use cranelift::codegen::ir::Type;
+use cranelift::codegen::ir::{types, Type};
 use hir_ty::Ty;
 use internment::Intern;
 use tinyvec::ArrayVec;
@@ -11,13 +11,28 @@ use super::{FnAbi, PassMode};
 
 /// "A Homogeneous Floating-point Aggregate (HFA) is a Homogeneous Aggregate with a Fundamental Data Type that is a Floating-Point type and at most four uniquely addressable members."
 pub fn is_hfa(ty: Intern<Ty>) -> Option<PassMode> {
-    if let Some(fields) = ty.as_struct() {
+    // tuples are laid out just like structs
+    let fields = ty
+        .as_struct()
+        .map(|fields| fields.into_iter().map(|(_, field_ty)| field_ty).collect())
+        .or_else(|| ty.as_tuple());
+
+    if let Some(fields) = fields {
         let mut tys = ArrayVec::new();
-        let ty = fields[0].1;
+        let layout = ty.struct_layout().unwrap();
+        let ty = fields[0];
         if fields.len() > 4 {
             None
+        } else if layout
+            .offsets()
+            .iter()
+            .enumerate()
+            .any(|(idx, offset)| *offset != idx as u32 * ty.size())
+        {
+            // `#packed` and `#align` can move the floats away from each other
+            None
         } else {
-            for (_, field_ty) in fields {
+            for field_ty in fields {
                 if field_ty != ty || !field_ty.is_float() {
                     return None;
                 }
@@ -114,3 +129,49 @@ pub fn fn_ty_to_abi((args, ret): (&Vec<Intern<Ty>>, Intern<Ty>)) -> FnAbi {
 
     sig
 }
+
+/// "Unlike in the generic procedure call standard, all variadic arguments are passed on the
+/// stack, each taking up a full 8 bytes."
+///
+/// to make cranelift put them on the stack, all the argument registers which haven't been
+/// used by the fixed parameters get filled with padding.
+pub fn var_args_call_to_abi(
+    func_ty: (&Vec<Intern<Ty>>, Intern<Ty>),
+    var_arg_tys: &[Intern<Ty>],
+) -> FnAbi {
+    let mut sig = fn_ty_to_abi(func_ty);
+
+    let mut int_regs: usize = 0;
+    let mut float_regs: usize = 0;
+
+    // the pointer type doesn't matter for counting registers
+    for (arg, _) in &sig.args {
+        for param in arg.to_abiparam(types::I64) {
+            if param.value_type.is_float() {
+                float_regs += 1;
+            } else {
+                int_regs += param.value_type.bytes().div_ceil(8) as usize;
+            }
+        }
+    }
+
+    for _ in int_regs..8 {
+        sig.args.push((PassMode::Padding(types::I64), 0));
+    }
+    for _ in float_regs..8 {
+        sig.args.push((PassMode::Padding(types::F64), 0));
+    }
+
+    let first_idx = func_ty.0.len();
+    for (idx, arg) in var_arg_tys.iter().enumerate() {
+        let ty = if arg.is_float() {
+            types::F64
+        } else {
+            types::I64
+        };
+        sig.args
+            .push((PassMode::direct(ty), (first_idx + idx).try_into().unwrap()));
+    }
+
+    sig
+}
@@ -41,6 +41,22 @@ impl Abi {
             _ => todo!(),
         }
     }
+
+    /// `var_arg_tys` are the already promoted types of the arguments passed after the fixed
+    /// parameters of a C variadic function
+    pub fn var_args_call_to_target(
+        &self,
+        func_ty: (&Vec<Intern<Ty>>, Intern<Ty>),
+        var_arg_tys: &[Intern<Ty>],
+    ) -> FnAbi {
+        #[allow(unreachable_patterns)]
+        match self {
+            Abi::X64SysV => x86_64::var_args_call_to_abi(func_ty, var_arg_tys),
+            Abi::X64Windows => x86_64_windows::var_args_call_to_abi(func_ty, var_arg_tys),
+            Abi::AppleAarch64 => aarch64::var_args_call_to_abi(func_ty, var_arg_tys),
+            _ => todo!(),
+        }
+    }
 }
 
 impl From<TargetFrontendConfig> for Abi {
@@ -62,6 +78,9 @@ pub enum PassMode {
     },
     Direct(Type),
     Indirect(Option<usize>),
+    /// a dummy argument used to fill up registers so that the next arguments
+    /// get passed on the stack
+    Padding(Type),
 }
 
 impl PassMode {
@@ -96,6 +115,7 @@ impl PassMode {
                 ArgumentPurpose::StructArgument(*sz as u32),
             )],
             PassMode::Indirect(None) => vec![AbiParam::new(ptr_ty)],
+            PassMode::Padding(ty) => vec![AbiParam::new(*ty)],
         }
     }
 }
@@ -104,6 +124,9 @@ impl PassMode {
 pub struct FnAbi {
     args: Vec<(PassMode, u16)>,
     ret: Option<PassMode>,
+    /// when set, the call has to go through this trampoline instead of directly to the
+    /// callee. the address of the callee is passed as the last argument
+    trampoline: Option<x86_64::Trampoline>,
 }
 
 impl FnAbi {
@@ -111,8 +134,13 @@ impl FnAbi {
         Self {
             args: vec![],
             ret: None,
+            trampoline: None,
         }
     }
+
+    pub fn trampoline(&self) -> Option<x86_64::Trampoline> {
+        self.trampoline
+    }
     pub fn to_cl(&self, ptr_ty: Type, conv: CallConv) -> Signature {
         // TODO: actually use the correct calling convention here
         let mut sig = Signature::new(conv);
@@ -132,25 +160,43 @@ impl FnAbi {
         sig
     }
 
-    pub fn get_arg_list(&self, args: Vec<Value>, func_cmplr: &mut FunctionCompiler) -> Vec<Value> {
+    pub fn get_arg_list(&self, args: Vec<Value>, builder: &mut FunctionBuilder) -> Vec<Value> {
         let mut arg_list = vec![];
 
         for (pass, idx) in &self.args {
-            let arg = args[*idx as usize];
             match pass {
                 PassMode::Cast { tys, .. } => {
+                    let arg = args[*idx as usize];
                     let mut off = 0;
                     for &ty in tys {
-                        let lo =
-                            func_cmplr
-                                .builder
-                                .ins()
-                                .load(ty, MemFlags::trusted(), arg, off as i32);
+                        let lo = builder.ins().load(ty, MemFlags::trusted(), arg, off as i32);
                         arg_list.push(lo);
                         off += ty.bytes();
                     }
                 }
-                _ => arg_list.push(arg),
+                PassMode::Direct(ty) => {
+                    let arg = args[*idx as usize];
+                    let arg_ty = builder.func.dfg.value_type(arg);
+                    // variadic arguments might have to be widened or moved into
+                    // a different kind of register
+                    let arg = if arg_ty == *ty {
+                        arg
+                    } else if arg_ty.is_float() {
+                        builder.ins().bitcast(*ty, MemFlags::new(), arg)
+                    } else {
+                        builder.ins().sextend(*ty, arg)
+                    };
+                    arg_list.push(arg)
+                }
+                PassMode::Padding(ty) => {
+                    let zero = if ty.is_float() {
+                        builder.ins().f64const(0.0)
+                    } else {
+                        builder.ins().iconst(*ty, 0)
+                    };
+                    arg_list.push(zero)
+                }
+                PassMode::Indirect(_) => arg_list.push(args[*idx as usize]),
             }
         }
         arg_list
@@ -167,7 +213,7 @@ impl FnAbi {
             let stack_slot = builder.create_sized_stack_slot(StackSlotData {
                 kind: StackSlotKind::ExplicitSlot,
                 size: return_ty.size(),
-                align_shift: return_ty.align() as u8,
+                align_shift: return_ty.align().trailing_zeros() as u8,
             });
             let stack_slot_addr = builder.ins().stack_addr(ptr_ty, stack_slot, 0);
 
@@ -213,6 +259,7 @@ impl FnAbi {
                 Some(rets[0])
             }
             PassMode::Indirect(_) => unreachable!("indirect return without stack address"),
+            PassMode::Padding(_) => unreachable!("padding is never returned"),
         }
     }
 
@@ -320,6 +367,7 @@ impl FnAbi {
                         func_cmplr.ptr_ty,
                     )
                 }
+                PassMode::Padding(_) => unreachable!("functions with bodies can't be variadic"),
             };
             func_cmplr.builder.declare_var(var, val_ty);
 
@@ -358,6 +406,7 @@ impl FnAbi {
                     func_cmplr.compile_and_cast_into_memory(function_body, return_ty, tmp_mem);
                     func_cmplr.builder.ins().return_(&[]);
                 }
+                PassMode::Padding(_) => unreachable!("padding is never returned"),
             }
         } else {
             func_cmplr.compile_and_cast(function_body, return_ty);
@@ -53,7 +53,7 @@ fn classify_arg(ty: Intern<Ty>) -> Option<[Class; 8]> {
             // "Arguments of types (signed and unsigned) _Bool, char, short, int,
             // long, long long, and pointers are in the INTEGER class."
             Ty::Type
-            | Ty::String
+            | Ty::CString
             | Ty::Char
             | Ty::IInt(_)
             | Ty::UInt(_)
@@ -81,7 +81,7 @@ fn classify_arg(ty: Intern<Ty>) -> Option<[Class; 8]> {
                     }
                 }
             }
-            Ty::Slice { .. } => {
+            Ty::Slice { .. } | Ty::String | Ty::Closure { .. } | Ty::Interface { .. } => {
                 classes[offset / 8] = classes[offset / 8].merge_eigthbyte(Int);
                 classes[offset / 8 + 1] = classes[offset / 8 + 1].merge_eigthbyte(Int)
             }
@@ -97,6 +97,25 @@ fn classify_arg(ty: Intern<Ty>) -> Option<[Class; 8]> {
                     classify_eight_byte(members[field].1, classes, offset + field_off as usize)
                 }
             }
+            Ty::Tuple { item_tys } => {
+                for (item, &item_off) in ty.struct_layout().unwrap().offsets().iter().enumerate() {
+                    classify_eight_byte(item_tys[item], classes, offset + item_off as usize)
+                }
+            }
+            // every member overlaps, so their classes just get merged together
+            Ty::Union { members, .. } => {
+                for (_, member_ty) in members {
+                    classify_eight_byte(member_ty, classes, offset)
+                }
+            }
+            Ty::Optional { sub_ty } => {
+                classify_eight_byte(sub_ty, classes, offset);
+                if !sub_ty.has_null_niche() {
+                    // the tag comes right after the payload
+                    let tag_offset = offset + sub_ty.size() as usize;
+                    classes[tag_offset / 8] = classes[tag_offset / 8].merge_eigthbyte(Int);
+                }
+            }
             _ => {}
         };
     }
@@ -105,6 +124,11 @@ fn classify_arg(ty: Intern<Ty>) -> Option<[Class; 8]> {
     if n > 8 {
         return None;
     }
+    // "If the size of an object is larger than eight eightbytes, or it contains unaligned
+    // fields, it has class MEMORY"
+    if has_unaligned_fields(ty) {
+        return None;
+    }
 
     let mut classes = [Class::NoClass; 8];
     classify_eight_byte(ty, &mut classes, 0);
@@ -134,6 +158,30 @@ fn classify_arg(ty: Intern<Ty>) -> Option<[Class; 8]> {
     }
 }
 
+/// only `#packed` structs can have fields which aren't at a multiple of their alignment
+fn has_unaligned_fields(ty: Intern<Ty>) -> bool {
+    match ty.as_ref() {
+        Ty::Array { sub_ty, .. } | Ty::Distinct { sub_ty, .. } | Ty::Optional { sub_ty } => {
+            has_unaligned_fields(*sub_ty)
+        }
+        Ty::Struct { members, .. } => ty
+            .struct_layout()
+            .unwrap()
+            .offsets()
+            .iter()
+            .zip(members.iter())
+            .any(|(offset, (_, field_ty))| {
+                offset % field_ty.align() != 0 || has_unaligned_fields(*field_ty)
+            }),
+        Ty::Union { members, .. } => members
+            .iter()
+            .any(|(_, member_ty)| has_unaligned_fields(*member_ty)),
+        // tuples are never packed, but their items might be
+        Ty::Tuple { item_tys } => item_tys.iter().copied().any(has_unaligned_fields),
+        _ => false,
+    }
+}
+
 fn reg_component(cls: &[Class], i: &mut usize, size: usize) -> Option<ir::Type> {
     if *i >= cls.len() {
         return None;
@@ -269,3 +317,121 @@ pub fn fn_ty_to_abi((args, ret): (&Vec<Intern<Ty>>, Intern<Ty>)) -> FnAbi {
 
     sig
 }
+
+/// where the trampoline of a variadic call can find the address of the actual callee
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CalleeLoc {
+    /// the index of the integer argument register
+    Reg(u8),
+    /// the offset into the stack arguments
+    Stack(u32),
+}
+
+/// "For calls that may call functions that use varargs or stdargs (prototype-less
+/// calls or calls to functions containing ellipsis (. . . ) in the declaration) %al
+/// is used as hidden argument to specify the number of vector registers used."
+///
+/// cranelift has no way of setting `%al` itself, so variadic calls go through a tiny
+/// trampoline which sets it and then jumps to the callee.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Trampoline {
+    callee: CalleeLoc,
+    vector_regs: u8,
+}
+
+impl Trampoline {
+    pub fn to_bytes(self) -> Vec<u8> {
+        // rdi, rsi, rdx, rcx, r8, r9
+        const INT_REGS: [(u8, u8); 6] = [(0, 7), (0, 6), (0, 2), (0, 1), (1, 0), (1, 1)];
+
+        let mut bytes = Vec::with_capacity(20);
+
+        match self.callee {
+            CalleeLoc::Reg(idx) => {
+                // mov r11, <reg>
+                let (rex_r, reg) = INT_REGS[idx as usize];
+                bytes.extend([0x49 | (rex_r << 2), 0x89, 0xC3 | (reg << 3)]);
+            }
+            CalleeLoc::Stack(offset) => {
+                // mov r11, [rsp + offset]
+                // (+ 8 to skip over the return address)
+                bytes.extend([0x4C, 0x8B, 0x9C, 0x24]);
+                bytes.extend((offset + 8).to_le_bytes());
+            }
+        }
+
+        // mov eax, <vector_regs>
+        bytes.push(0xB8);
+        bytes.extend((self.vector_regs as u32).to_le_bytes());
+
+        // jmp r11
+        bytes.extend([0x41, 0xFF, 0xE3]);
+
+        bytes
+    }
+}
+
+pub fn var_args_call_to_abi(
+    (args, ret): (&Vec<Intern<Ty>>, Intern<Ty>),
+    var_arg_tys: &[Intern<Ty>],
+) -> FnAbi {
+    let all_args = args
+        .iter()
+        .chain(var_arg_tys.iter())
+        .copied()
+        .collect::<Vec<_>>();
+
+    let mut sig = fn_ty_to_abi((&all_args, ret));
+
+    // figure out which registers cranelift is going to use for the arguments,
+    // so we know how many vector registers are used and where the callee's
+    // address can go
+    let mut int_regs = 0;
+    let mut vector_regs = 0;
+    let mut stack_bytes = 0;
+
+    if let Some(PassMode::Indirect(_)) = sig.ret {
+        int_regs += 1;
+    }
+
+    // the pointer type doesn't matter for counting registers
+    for (arg, _) in &sig.args {
+        for param in arg.to_abiparam(ir::types::I64) {
+            match param.purpose {
+                ir::ArgumentPurpose::StructArgument(size) => stack_bytes += size,
+                _ if param.value_type.is_float() => {
+                    if vector_regs < 8 {
+                        vector_regs += 1;
+                    } else {
+                        stack_bytes += 8;
+                    }
+                }
+                _ => {
+                    let needed = param.value_type.bytes().div_ceil(8) as u8;
+                    if int_regs + needed <= 6 {
+                        int_regs += needed;
+                    } else {
+                        stack_bytes += 8 * needed as u32;
+                    }
+                }
+            }
+        }
+    }
+
+    let callee = if int_regs < 6 {
+        CalleeLoc::Reg(int_regs)
+    } else {
+        CalleeLoc::Stack(stack_bytes)
+    };
+
+    sig.args.push((
+        PassMode::direct(ir::types::I64),
+        all_args.len().try_into().unwrap(),
+    ));
+    sig.trampoline = Some(Trampoline {
+        callee,
+        vector_regs,
+    });
+
+    sig
+}
@@ -1,4 +1,4 @@
-use cranelift::codegen::ir::Type;
+use cranelift::codegen::ir::{types, Type};
 use hir_ty::Ty;
 use internment::Intern;
 use tinyvec::array_vec;
@@ -46,3 +46,27 @@ pub fn fn_ty_to_abi((args, ret): (&Vec<Intern<Ty>>, Intern<Ty>)) -> FnAbi {
 
     sig
 }
+
+pub fn var_args_call_to_abi(
+    func_ty: (&Vec<Intern<Ty>>, Intern<Ty>),
+    var_arg_tys: &[Intern<Ty>],
+) -> FnAbi {
+    let mut sig = fn_ty_to_abi(func_ty);
+
+    let first_idx = func_ty.0.len();
+    for (idx, arg) in var_arg_tys.iter().enumerate() {
+        // "For floating-point values only, both the integer register and the floating-point
+        // register must contain the value, in case the callee expects the value in the integer
+        // registers."
+        // variadic callees always read them from the integer registers, so that's where they go
+        let ty = if arg.is_float() {
+            types::I64
+        } else {
+            arg.get_final_ty().into_real_type().unwrap()
+        };
+        sig.args
+            .push((PassMode::direct(ty), (first_idx + idx).try_into().unwrap()));
+    }
+
+    sig
+}
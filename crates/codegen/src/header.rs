@@ -0,0 +1,488 @@
+//! This module is for generating C headers for the functions marked with `export`
+
+use std::fmt;
+
+use hir_ty::Ty;
+use interner::Interner;
+use internment::Intern;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    compiler::program::LIBRARY_INIT_FUNCTION,
+    layout::{self, GetLayoutInfo},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HeaderError {
+    /// the type has no C equivalent
+    UnsupportedTy { function: String, ty: String },
+    /// the fields of the struct can't be placed at the same offsets in C
+    UnsupportedLayout { function: String, ty: String },
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::UnsupportedTy { function, ty } => write!(
+                f,
+                "`{function}` can't be exported: `{ty}` has no equivalent in C"
+            ),
+            HeaderError::UnsupportedLayout { function, ty } => write!(
+                f,
+                "`{function}` can't be exported: the layout of `{ty}` can't be represented in C"
+            ),
+        }
+    }
+}
+
+/// Generates the prototypes of all exported functions, along with all the
+/// `struct`s and `typedef`s they need.
+///
+/// If `include_init` is true, the header will also declare the function which
+/// initializes the runtime globals of a library
+pub fn generate_header(
+    header_name: &str,
+    include_init: bool,
+    mod_dir: &std::path::Path,
+    interner: &Interner,
+    world_bodies: &hir::WorldBodies,
+    tys: &hir_ty::ProjectInference,
+    pointer_bit_width: u32,
+) -> Result<String, HeaderError> {
+    layout::calc_layouts(tys.all_tys(), pointer_bit_width);
+
+    let mut generator = HeaderGenerator {
+        mod_dir,
+        interner,
+        function: String::new(),
+        names: FxHashMap::default(),
+        used_names: FxHashSet::default(),
+        forward_decls: Vec::new(),
+        defs: Vec::new(),
+    };
+
+    let mut exports = world_bodies.find_exports();
+    exports.sort_by_key(|fqn| fqn.to_string(mod_dir, interner));
+
+    let mut prototypes = Vec::new();
+    for fqn in exports {
+        let hir::Expr::Lambda(lambda) = world_bodies[fqn.file][world_bodies.body(fqn)] else {
+            unreachable!("only lambdas can be exported");
+        };
+        let params = &world_bodies[fqn.file][lambda].params;
+        let (param_tys, return_ty) = tys[fqn].0.as_function().unwrap();
+
+        generator.function = fqn.to_string(mod_dir, interner);
+
+        let mut c_params = Vec::new();
+        for (param, ty) in params.iter().zip(param_tys.iter()) {
+            // zero-sized arguments aren't actually passed
+            if ty.is_zero_sized() {
+                continue;
+            }
+            generator.check_by_value(*ty)?;
+
+            let name = param
+                .name
+                .map(|name| interner.lookup(name.0).to_string())
+                .unwrap_or_default();
+            c_params.push(generator.decl(*ty, name)?);
+        }
+
+        let name = interner.lookup(fqn.name.0);
+        let params = if c_params.is_empty() {
+            "void".to_string()
+        } else {
+            c_params.join(", ")
+        };
+
+        let prototype = if return_ty.is_zero_sized() {
+            format!("void {name}({params});")
+        } else {
+            generator.check_by_value(return_ty)?;
+            generator.decl(return_ty, format!("{name}({params})"))? + ";"
+        };
+        prototypes.push(prototype);
+    }
+
+    let guard = header_name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() {
+                ch.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        + "_H";
+
+    let mut header = format!(
+        "// generated by capy, do not edit\n\n#ifndef {guard}\n#define {guard}\n\n#include <stdbool.h>\n#include <stdint.h>\n"
+    );
+
+    for (section, separator) in [(&generator.forward_decls, "\n"), (&generator.defs, "\n\n")] {
+        if !section.is_empty() {
+            header.push('\n');
+            header.push_str(&section.join(separator));
+            header.push('\n');
+        }
+    }
+
+    if include_init {
+        header.push_str(&format!(
+            "\n// initializes the globals of the library, call this before anything else\nvoid {LIBRARY_INIT_FUNCTION}(void);\n"
+        ));
+    }
+
+    if !prototypes.is_empty() {
+        header.push('\n');
+        header.push_str(&prototypes.join("\n"));
+        header.push('\n');
+    }
+
+    header.push_str(&format!("\n#endif // {guard}\n"));
+
+    Ok(header)
+}
+
+struct HeaderGenerator<'a> {
+    mod_dir: &'a std::path::Path,
+    interner: &'a Interner,
+    /// the exported function currently being declared, used for errors
+    function: String,
+    /// the names given to structs, slices, and distinct types
+    names: FxHashMap<Intern<Ty>, String>,
+    used_names: FxHashSet<String>,
+    forward_decls: Vec<String>,
+    /// every definition comes after the definitions it depends on
+    defs: Vec<String>,
+}
+
+impl HeaderGenerator<'_> {
+    fn unsupported(&self, ty: Intern<Ty>) -> HeaderError {
+        HeaderError::UnsupportedTy {
+            function: self.function.clone(),
+            ty: ty.display(self.mod_dir, self.interner),
+        }
+    }
+
+    /// C can't pass arrays by value, they always decay to pointers
+    fn check_by_value(&self, ty: Intern<Ty>) -> Result<(), HeaderError> {
+        match ty.as_ref() {
+            Ty::Array { .. } => Err(self.unsupported(ty)),
+            Ty::Distinct { sub_ty, .. } => self.check_by_value(*sub_ty),
+            _ => Ok(()),
+        }
+    }
+
+    fn unique_name(&mut self, name: String) -> String {
+        let mut unique = name.clone();
+        let mut n = 1;
+        while self.used_names.contains(&unique) {
+            unique = format!("{name}_{n}");
+            n += 1;
+        }
+        self.used_names.insert(unique.clone());
+        unique
+    }
+
+    /// the C declaration of `declarator` with the given type, e.g. `int32_t (*foo)[3]`
+    fn decl(&mut self, ty: Intern<Ty>, declarator: String) -> Result<String, HeaderError> {
+        let base = match ty.as_ref() {
+            Ty::IInt(u8::MAX) => "intptr_t".to_string(),
+            Ty::UInt(u8::MAX) => "uintptr_t".to_string(),
+            Ty::IInt(128) => "__int128".to_string(),
+            Ty::UInt(128) => "unsigned __int128".to_string(),
+            Ty::IInt(0) => "int32_t".to_string(),
+            Ty::UInt(0) => "uint32_t".to_string(),
+            // C can't keep the unused bits of something like a `u5` zeroed
+            Ty::IInt(bit_width) | Ty::UInt(bit_width) if !matches!(bit_width, 8 | 16 | 32 | 64) => {
+                return Err(self.unsupported(ty))
+            }
+            Ty::IInt(bit_width) => format!("int{bit_width}_t"),
+            Ty::UInt(bit_width) => format!("uint{bit_width}_t"),
+            Ty::Float(64) => "double".to_string(),
+            Ty::Float(_) => "float".to_string(),
+            Ty::Bool => "bool".to_string(),
+            Ty::Char => "char".to_string(),
+            Ty::CString => return Ok(format!("char const *{declarator}")),
+            Ty::Void => "void".to_string(),
+            Ty::Pointer { mutable, sub_ty } => {
+                let declarator = match sub_ty.as_ref() {
+                    Ty::Array { .. } | Ty::Function { .. } => format!("(*{declarator})"),
+                    _ => pointer(&declarator, !*mutable),
+                };
+                return self.decl(*sub_ty, declarator);
+            }
+            Ty::Array { size, sub_ty, .. } => {
+                return self.decl(*sub_ty, format!("{declarator}[{size}]"));
+            }
+            Ty::Function {
+                param_tys,
+                return_ty,
+                is_variadic,
+                ..
+            } => {
+                let mut params = Vec::new();
+                for param_ty in param_tys.iter().filter(|ty| !ty.is_zero_sized()) {
+                    self.check_by_value(*param_ty)?;
+                    params.push(self.decl(*param_ty, String::new())?);
+                }
+                if *is_variadic {
+                    params.push("...".to_string());
+                } else if params.is_empty() {
+                    params.push("void".to_string());
+                }
+
+                let declarator = format!("(*{declarator})({})", params.join(", "));
+                if return_ty.is_zero_sized() {
+                    return Ok(format!("void {declarator}"));
+                }
+                self.check_by_value(*return_ty)?;
+                return self.decl(*return_ty, declarator);
+            }
+            Ty::Distinct {
+                fqn: None, sub_ty, ..
+            } => return self.decl(*sub_ty, declarator),
+            // a null optional pointer is just a null pointer
+            Ty::Optional { sub_ty } if sub_ty.has_null_niche() => {
+                return self.decl(*sub_ty, declarator)
+            }
+            Ty::String
+            | Ty::Slice { .. }
+            | Ty::Struct { .. }
+            | Ty::Union { .. }
+            | Ty::Optional { .. }
+            | Ty::Tuple { .. }
+            | Ty::Distinct { .. } => self.named(ty)?,
+            Ty::NotYetResolved
+            | Ty::Unknown
+            | Ty::Type
+            | Ty::Any
+            | Ty::File(_)
+            | Ty::Null
+            | Ty::NoEval
+            | Ty::Closure { .. }
+            | Ty::Interface { .. } => return Err(self.unsupported(ty)),
+        };
+
+        if declarator.is_empty() {
+            Ok(base)
+        } else {
+            Ok(format!("{base} {declarator}"))
+        }
+    }
+
+    /// returns the name of a type that needs its own definition,
+    /// defining it if it hasn't been already
+    fn named(&mut self, ty: Intern<Ty>) -> Result<String, HeaderError> {
+        if let Some(name) = self.names.get(&ty) {
+            return Ok(name.clone());
+        }
+
+        match ty.as_ref() {
+            Ty::Distinct {
+                fqn: Some(fqn),
+                sub_ty,
+                ..
+            } => {
+                let name = self.unique_name(self.interner.lookup(fqn.name.0).to_string());
+                let def = format!("typedef {};", self.decl(*sub_ty, name.clone())?);
+
+                self.names.insert(ty, name.clone());
+                self.defs.push(def);
+
+                Ok(name)
+            }
+            Ty::String | Ty::Slice { .. } => {
+                let (name, ptr) = match ty.as_ref() {
+                    Ty::Slice { sub_ty } => {
+                        let name = format!("capy_slice_{}", self.ident(*sub_ty));
+                        let name = self.unique_name(name);
+                        (name, self.decl(*sub_ty, "*ptr".to_string())?)
+                    }
+                    _ => (
+                        self.unique_name("capy_str".to_string()),
+                        "char const *ptr".to_string(),
+                    ),
+                };
+
+                self.names.insert(ty, name.clone());
+                self.defs.push(format!(
+                    "typedef struct {name} {{\n    uintptr_t len;\n    {ptr};\n}} {name};"
+                ));
+
+                Ok(name)
+            }
+            Ty::Struct {
+                fqn, members, repr, ..
+            } => {
+                let name = match fqn {
+                    Some(fqn) => self.interner.lookup(fqn.name.0).to_string(),
+                    None => "capy_struct".to_string(),
+                };
+                let name = self.unique_name(name);
+
+                // structs might point to themselves
+                self.names.insert(ty, name.clone());
+                self.forward_decls
+                    .push(format!("typedef struct {name} {name};"));
+
+                let struct_layout = ty.struct_layout().unwrap();
+                // C can't express `#packed` or `#align` portably, so the struct gets packed
+                // and every field is placed with explicit padding
+                let explicit_layout = !repr.is_default();
+
+                let mut fields = Vec::new();
+                let mut c_offset = 0;
+                let mut padding_count = 0;
+                for ((field_name, field_ty), &offset) in members.iter().zip(struct_layout.offsets())
+                {
+                    if field_ty.is_zero_sized() {
+                        continue;
+                    }
+
+                    // C always puts fields at the next aligned offset
+                    if !explicit_layout {
+                        c_offset += layout::padding_needed_for(c_offset, field_ty.align());
+                    }
+
+                    if offset < c_offset {
+                        return Err(HeaderError::UnsupportedLayout {
+                            function: self.function.clone(),
+                            ty: ty.display(self.mod_dir, self.interner),
+                        });
+                    } else if offset > c_offset {
+                        fields.push(format!(
+                            "    uint8_t _padding{padding_count}[{}];",
+                            offset - c_offset
+                        ));
+                        padding_count += 1;
+                    }
+
+                    let field_name = self.interner.lookup(field_name.0).to_string();
+                    fields.push(format!("    {};", self.decl(*field_ty, field_name)?));
+
+                    c_offset = offset + field_ty.stride();
+                }
+
+                let attributes = if explicit_layout {
+                    format!(" __attribute__((packed, aligned({})))", ty.align())
+                } else {
+                    String::new()
+                };
+
+                self.defs.push(format!(
+                    "struct {name} {{\n{}\n}}{attributes};\n_Static_assert(sizeof({name}) == {}, \"capy and C disagree on the size of `{name}`\");",
+                    fields.join("\n"),
+                    ty.stride(),
+                ));
+
+                Ok(name)
+            }
+            Ty::Union { fqn, members, .. } => {
+                let name = match fqn {
+                    Some(fqn) => self.interner.lookup(fqn.name.0).to_string(),
+                    None => "capy_union".to_string(),
+                };
+                let name = self.unique_name(name);
+
+                self.names.insert(ty, name.clone());
+                self.forward_decls
+                    .push(format!("typedef union {name} {name};"));
+
+                let mut fields = Vec::new();
+                for (field_name, field_ty) in members {
+                    if field_ty.is_zero_sized() {
+                        continue;
+                    }
+                    let field_name = self.interner.lookup(field_name.0).to_string();
+                    fields.push(format!("    {};", self.decl(*field_ty, field_name)?));
+                }
+
+                self.defs.push(format!(
+                    "union {name} {{\n{}\n}};\n_Static_assert(sizeof({name}) == {}, \"capy and C disagree on the size of `{name}`\");",
+                    fields.join("\n"),
+                    ty.stride(),
+                ));
+
+                Ok(name)
+            }
+            Ty::Optional { sub_ty } => {
+                let name = format!("capy_optional_{}", self.ident(*sub_ty));
+                let name = self.unique_name(name);
+
+                let value = self.decl(*sub_ty, "value".to_string())?;
+
+                self.names.insert(ty, name.clone());
+                self.defs.push(format!(
+                    "typedef struct {name} {{\n    {value};\n    bool has_value;\n}} {name};\n_Static_assert(sizeof({name}) == {}, \"capy and C disagree on the size of `{name}`\");",
+                    ty.stride(),
+                ));
+
+                Ok(name)
+            }
+            Ty::Tuple { item_tys } => {
+                let name = item_tys
+                    .iter()
+                    .fold("capy_tuple".to_string(), |name, item_ty| {
+                        format!("{name}_{}", self.ident(*item_ty))
+                    });
+                let name = self.unique_name(name);
+
+                // tuples are laid out like structs without any `#packed` or `#align`,
+                // which is exactly how C lays them out too
+                let mut fields = Vec::new();
+                for (idx, item_ty) in item_tys.iter().enumerate() {
+                    if item_ty.is_zero_sized() {
+                        continue;
+                    }
+                    fields.push(format!("    {};", self.decl(*item_ty, format!("_{idx}"))?));
+                }
+
+                self.names.insert(ty, name.clone());
+                self.defs.push(format!(
+                    "typedef struct {name} {{\n{}\n}} {name};\n_Static_assert(sizeof({name}) == {}, \"capy and C disagree on the size of `{name}`\");",
+                    fields.join("\n"),
+                    ty.stride(),
+                ));
+
+                Ok(name)
+            }
+            _ => unreachable!("{ty:?} doesn't need a name"),
+        }
+    }
+
+    /// a name for the type that's also a valid C identifier, used for naming slices
+    fn ident(&self, ty: Intern<Ty>) -> String {
+        match ty.as_ref() {
+            Ty::Pointer {
+                mutable: true,
+                sub_ty,
+            } => format!("mut_ptr_{}", self.ident(*sub_ty)),
+            Ty::Pointer { sub_ty, .. } => format!("ptr_{}", self.ident(*sub_ty)),
+            Ty::Array { size, sub_ty, .. } => format!("array{size}_{}", self.ident(*sub_ty)),
+            Ty::Slice { sub_ty } => format!("slice_{}", self.ident(*sub_ty)),
+            Ty::Optional { sub_ty } => format!("optional_{}", self.ident(*sub_ty)),
+            Ty::Tuple { .. } => "tuple".to_string(),
+            Ty::Distinct { fqn: Some(fqn), .. }
+            | Ty::Struct { fqn: Some(fqn), .. }
+            | Ty::Union { fqn: Some(fqn), .. } => self.interner.lookup(fqn.name.0).to_string(),
+            Ty::Distinct { sub_ty, .. } => self.ident(*sub_ty),
+            Ty::Struct { .. } => "struct".to_string(),
+            Ty::Union { .. } => "union".to_string(),
+            Ty::Function { .. } => "fn".to_string(),
+            _ => ty.display(self.mod_dir, self.interner),
+        }
+    }
+}
+
+/// `*declarator`, or `const *declarator` if the pointer is immutable
+fn pointer(declarator: &str, immutable: bool) -> String {
+    if immutable {
+        format!("const *{declarator}")
+    } else {
+        format!("*{declarator}")
+    }
+}
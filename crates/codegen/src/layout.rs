@@ -94,16 +94,18 @@ fn calc_single(ty: Intern<Ty>, pointer_bit_width: u32) {
         Ty::NotYetResolved | Ty::Unknown => 0,
         Ty::IInt(u8::MAX) | Ty::UInt(u8::MAX) => pointer_bit_width / 8,
         Ty::IInt(0) | Ty::UInt(0) => 32 / 8,
-        Ty::IInt(bit_width) | Ty::UInt(bit_width) => *bit_width as u32 / 8,
+        Ty::IInt(bit_width) | Ty::UInt(bit_width) => {
+            crate::convert::int_storage_bit_width(*bit_width) as u32 / 8
+        }
         Ty::Float(0) => 32 / 8,
         Ty::Float(bit_width) => *bit_width as u32 / 8,
         Ty::Bool | Ty::Char => 1, // bools and chars are u8's
-        Ty::String => pointer_bit_width / 8,
+        Ty::CString => pointer_bit_width / 8,
         Ty::Array { size, sub_ty, .. } => {
             calc_single(*sub_ty, pointer_bit_width);
             sub_ty.stride() * *size as u32
         }
-        Ty::Slice { .. } => {
+        Ty::Slice { .. } | Ty::String => {
             // a slice is len (usize) + ptr (usize)
             pointer_bit_width / 8 * 2
         }
@@ -113,16 +115,28 @@ fn calc_single(ty: Intern<Ty>, pointer_bit_width: u32) {
             ty.size()
         }
         Ty::Function { .. } => pointer_bit_width / 8,
-        Ty::Struct { members, .. } => {
-            let fields = members
-                .iter()
-                .map(|(_, ty)| ty)
-                .copied()
-                .collect::<Vec<_>>();
+        // a closure is a function pointer followed by an environment pointer
+        Ty::Closure { .. } => pointer_bit_width / 8 * 2,
+        // an interface is a data pointer followed by a vtable pointer
+        Ty::Interface { .. } => pointer_bit_width / 8 * 2,
+        Ty::Struct { .. } | Ty::Tuple { .. } => {
+            let (fields, repr) = match ty.as_ref() {
+                Ty::Struct { members, repr, .. } => (
+                    members
+                        .iter()
+                        .map(|(_, ty)| ty)
+                        .copied()
+                        .collect::<Vec<_>>(),
+                    repr.clone(),
+                ),
+                // tuples are laid out exactly like an anonymous struct
+                Ty::Tuple { item_tys } => (item_tys.clone(), hir::StructRepr::default()),
+                _ => unreachable!(),
+            };
             for field in &fields {
                 calc_single(*field, pointer_bit_width);
             }
-            let struct_layout = StructLayout::new(fields);
+            let struct_layout = StructLayout::new(fields, &repr);
             let size = struct_layout.size;
 
             {
@@ -136,6 +150,24 @@ fn calc_single(ty: Intern<Ty>, pointer_bit_width: u32) {
 
             size
         }
+        Ty::Union { members, .. } => members
+            .iter()
+            .map(|(_, member)| {
+                calc_single(*member, pointer_bit_width);
+                member.size()
+            })
+            .max()
+            .unwrap_or(0),
+        Ty::Optional { sub_ty } => {
+            calc_single(*sub_ty, pointer_bit_width);
+            if sub_ty.has_null_niche() {
+                sub_ty.size()
+            } else {
+                // the payload is followed by a u8 which is 1 when the payload is there
+                sub_ty.size() + 1
+            }
+        }
+        Ty::Null => 0,
         Ty::Type => 32 / 8,
         Ty::Any => 0,
         Ty::Void => 0,
@@ -147,12 +179,20 @@ fn calc_single(ty: Intern<Ty>, pointer_bit_width: u32) {
         Ty::NotYetResolved | Ty::Unknown => 1,
         Ty::IInt(_) | Ty::UInt(_) | Ty::Float(_) => size.min(8),
         Ty::Bool | Ty::Char => 1, // bools and chars are u8's
-        Ty::String | Ty::Pointer { .. } | Ty::Function { .. } => size,
+        Ty::CString | Ty::Pointer { .. } | Ty::Function { .. } => size,
         // the sub_ty was already `calc()`ed just before
         Ty::Array { sub_ty, .. } => sub_ty.align(),
-        Ty::Slice { .. } => size / 2,
+        Ty::Slice { .. } | Ty::String | Ty::Closure { .. } | Ty::Interface { .. } => size / 2,
         Ty::Distinct { sub_ty: ty, .. } => ty.align(),
-        Ty::Struct { .. } => ty.struct_layout().unwrap().align,
+        Ty::Struct { .. } | Ty::Tuple { .. } => ty.struct_layout().unwrap().align,
+        // the members were already `calc()`ed just before
+        Ty::Union { members, .. } => members
+            .iter()
+            .map(|(_, member)| member.align())
+            .max()
+            .unwrap_or(1),
+        Ty::Optional { sub_ty } => sub_ty.align(),
+        Ty::Null => 1,
         Ty::Type => size,
         Ty::Any => 1,
         Ty::Void => 1,
@@ -190,13 +230,20 @@ pub(crate) fn padding_needed_for(offset: u32, align: u32) -> u32 {
 }
 
 impl StructLayout {
-    pub(crate) fn new(fields: Vec<Intern<Ty>>) -> Self {
+    /// `#packed` fields are only aligned if they have their own `#align`,
+    /// and `#align` can only raise the alignment of a field or struct, never lower it
+    pub(crate) fn new(fields: Vec<Intern<Ty>>, repr: &hir::StructRepr) -> Self {
         let mut offsets = Vec::with_capacity(fields.len());
-        let mut max_align = 1;
+        let mut max_align = repr.align.unwrap_or(1);
         let mut current_offset = 0;
 
-        for field in fields {
-            let field_align = field.align();
+        for (idx, field) in fields.into_iter().enumerate() {
+            let explicit_align = repr.field_aligns.get(idx).copied().flatten();
+            let field_align = match (repr.packed, explicit_align) {
+                (true, explicit) => explicit.unwrap_or(1),
+                (false, Some(explicit)) => explicit.max(field.align()),
+                (false, None) => field.align(),
+            };
             if field_align > max_align {
                 max_align = field_align;
             }
@@ -2,6 +2,7 @@ mod builtin;
 mod compiler;
 pub(crate) mod convert;
 mod extend;
+mod header;
 mod layout;
 mod mangle;
 
@@ -32,6 +33,7 @@ pub enum Verbosity {
 pub(crate) type FinalSignature = cranelift::prelude::Signature;
 
 pub use compiler::comptime::eval_comptime_blocks;
+pub use header::{generate_header, HeaderError};
 
 pub fn compile_jit(
     verbosity: Verbosity,
@@ -41,7 +43,7 @@ pub fn compile_jit(
     world_bodies: &hir::WorldBodies,
     tys: &hir_ty::ProjectInference,
     comptime_results: &FxHashMap<FQComptime, ComptimeResult>,
-) -> fn(usize, usize) -> usize {
+) -> fn(i32, usize) -> usize {
     let mut flag_builder = settings::builder();
     flag_builder.set("use_colocated_libcalls", "false").unwrap();
     flag_builder.set("is_pic", "false").unwrap();
@@ -57,7 +59,7 @@ pub fn compile_jit(
 
     let cmain = compile_program(
         verbosity,
-        entry_point,
+        Some(entry_point),
         mod_dir,
         interner,
         world_bodies,
@@ -74,13 +76,18 @@ pub fn compile_jit(
 
     let code_ptr = module.get_finalized_function(cmain);
 
-    unsafe { mem::transmute::<_, fn(usize, usize) -> usize>(code_ptr) }
+    // dropping the module would free the writable data of the program (e.g. its globals),
+    // even though the program hasn't even started yet
+    mem::forget(module);
+
+    unsafe { mem::transmute::<_, fn(i32, usize) -> usize>(code_ptr) }
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn compile_obj(
     verbosity: Verbosity,
-    entry_point: hir::Fqn,
+    main_file: hir::FileName,
+    entry_point: Option<hir::Fqn>,
     mod_dir: &std::path::Path,
     interner: &Interner,
     world_bodies: &hir::WorldBodies,
@@ -102,7 +109,7 @@ pub fn compile_obj(
 
     let builder = ObjectBuilder::new(
         isa,
-        entry_point.file.to_string(mod_dir, interner),
+        main_file.to_string(mod_dir, interner),
         cranelift_module::default_libcall_names(),
     )
     .unwrap();
@@ -193,6 +200,70 @@ mod tests {
         entry_point: &str,
         stdout_expect: Expect,
         expected_status: i32,
+    ) {
+        check_files_impl(
+            main_file,
+            other_files,
+            Some(entry_point),
+            None,
+            &[],
+            stdout_expect,
+            expected_status,
+            core::panic::Location::caller(),
+        )
+    }
+
+    /// runs the compiled `main_file` with the given command line arguments
+    #[track_caller]
+    fn check_files_with_args(
+        main_file: &str,
+        args: &[&str],
+        stdout_expect: Expect,
+        expected_status: i32,
+    ) {
+        check_files_impl(
+            main_file,
+            &[],
+            Some("main"),
+            None,
+            args,
+            stdout_expect,
+            expected_status,
+            core::panic::Location::caller(),
+        )
+    }
+
+    /// compiles `main_file` as a library and links it to the C program in `c_file`
+    #[track_caller]
+    fn check_library(
+        main_file: &str,
+        c_file: &str,
+        header_expect: Expect,
+        stdout_expect: Expect,
+        expected_status: i32,
+    ) {
+        check_files_impl(
+            main_file,
+            &[],
+            None,
+            Some((c_file, header_expect)),
+            &[],
+            stdout_expect,
+            expected_status,
+            core::panic::Location::caller(),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_files_impl(
+        main_file: &str,
+        other_files: &[&str],
+        entry_point: Option<&str>,
+        c_driver: Option<(&str, Expect)>,
+        args: &[&str],
+        stdout_expect: Expect,
+        expected_status: i32,
+        caller: &'static std::panic::Location<'static>,
     ) {
         println!("testing {main_file}");
 
@@ -230,10 +301,12 @@ mod tests {
                 .collect(),
             &main_file.to_string_lossy(),
             entry_point,
+            c_driver,
             false,
+            args,
             stdout_expect,
             expected_status,
-            core::panic::Location::caller(),
+            caller,
         )
     }
 
@@ -281,8 +354,10 @@ mod tests {
                     .map(|(k, v)| (k.as_str(), v.as_str()))
                     .collect(),
                 &format!("{current_dir}{}main.capy", std::path::MAIN_SEPARATOR),
-                entry_point,
+                Some(entry_point),
+                None,
                 false,
+                &[],
                 stdout_expect,
                 expected_status,
                 core::panic::Location::caller(),
@@ -291,8 +366,10 @@ mod tests {
             check_impl(
                 modules,
                 "main.capy",
-                entry_point,
+                Some(entry_point),
+                None,
                 true,
+                &[],
                 stdout_expect,
                 expected_status,
                 core::panic::Location::caller(),
@@ -300,11 +377,14 @@ mod tests {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn check_impl(
         modules: FxHashMap<&str, &str>,
         main_file: &str,
-        entry_point: &str,
+        entry_point: Option<&str>,
+        c_driver: Option<(&str, Expect)>,
         fake_file_system: bool,
+        args: &[&str],
         stdout_expect: Expect,
         expected_status: i32,
         caller: &'static std::panic::Location<'static>,
@@ -332,7 +412,8 @@ mod tests {
 
             let tree = parse.into_syntax_tree();
             let root = ast::Root::cast(tree.root(), &tree).unwrap();
-            let (index, diagnostics) = hir::index(root, &tree, &mut interner);
+            let (index, diagnostics) =
+                hir::index(root, &tree, &mut interner, &hir::BuildConfig::default());
 
             assert_eq!(diagnostics, vec![]);
 
@@ -346,6 +427,7 @@ mod tests {
                 &mut uid_gen,
                 &mut interner,
                 &mod_dir,
+                &hir::BuildConfig::default(),
                 fake_file_system,
             );
 
@@ -363,7 +445,8 @@ mod tests {
 
         let tree = parse.into_syntax_tree();
         let root = ast::Root::cast(tree.root(), &tree).unwrap();
-        let (index, diagnostics) = hir::index(root, &tree, &mut interner);
+        let (index, diagnostics) =
+            hir::index(root, &tree, &mut interner, &hir::BuildConfig::default());
 
         assert_eq!(diagnostics, vec![]);
 
@@ -375,16 +458,17 @@ mod tests {
             &mut uid_gen,
             &mut interner,
             &mod_dir,
+            &hir::BuildConfig::default(),
             fake_file_system,
         );
         assert_eq!(diagnostics, vec![]);
         world_index.add_file(file, index);
         world_bodies.add_file(file, bodies);
 
-        let entry_point = hir::Fqn {
+        let entry_point = entry_point.map(|entry_point| hir::Fqn {
             file,
             name: hir::Name(interner.intern(entry_point)),
-        };
+        });
 
         let mut comptime_results = FxHashMap::default();
 
@@ -403,7 +487,7 @@ mod tests {
 
                 comptime_results[&comptime].clone()
             })
-            .finish(Some(entry_point), false);
+            .finish(entry_point, false);
         assert_eq!(diagnostics, vec![]);
 
         println!("comptime:");
@@ -424,6 +508,7 @@ mod tests {
 
         let bytes = compile_obj(
             Verbosity::AllFunctions,
+            file,
             entry_point,
             if fake_file_system {
                 Path::new("")
@@ -449,9 +534,32 @@ mod tests {
             panic!("{}: {why}", file.display());
         });
 
-        let exec = link_to_exec(&file, HOST, &[]);
+        let exec = match c_driver {
+            Some((c_file, header_expect)) => {
+                // the C program includes the header by its own name
+                let header_name = Path::new(c_file).file_stem().unwrap().to_string_lossy();
+
+                let header = generate_header(
+                    &header_name,
+                    true,
+                    &mod_dir,
+                    &interner,
+                    &world_bodies,
+                    &tys,
+                    HOST.pointer_width().unwrap().bits() as u32,
+                )
+                .unwrap();
+                header_expect.assert_eq(&header);
+
+                fs::write(output_folder.join(format!("{}.h", header_name)), header).unwrap();
+
+                link_to_c(&file, &output_folder, &out_name, c_file)
+            }
+            None => link_to_exec(&file, HOST, &[]),
+        };
 
         let output = std::process::Command::new(exec.clone())
+            .args(args)
             .output()
             .unwrap_or_else(|_| panic!("{} did not run successfully", exec.display()));
 
@@ -471,6 +579,34 @@ mod tests {
         stdout_expect.assert_eq(&stdout);
     }
 
+    /// builds the C program in `c_file` with the generated header and links in `object_file`
+    fn link_to_c(
+        object_file: &Path,
+        output_folder: &Path,
+        out_name: &str,
+        c_file: &str,
+    ) -> PathBuf {
+        let exe_path = output_folder.join(format!("{}_c", out_name));
+
+        let gcc = Command::new("gcc")
+            .arg("-o")
+            .arg(&exe_path)
+            .arg("-I")
+            .arg(output_folder)
+            .arg(c_file)
+            .arg(object_file)
+            .output()
+            .unwrap();
+
+        assert!(
+            gcc.status.success(),
+            "gcc failed!\n\nstderr:\n{}",
+            String::from_utf8_lossy(&gcc.stderr)
+        );
+
+        exe_path
+    }
+
     fn trim_indent(mut text: &str) -> String {
         if text.starts_with('\n') {
             text = &text[1..];
@@ -794,6 +930,305 @@ mod tests {
         )
     }
 
+    #[test]
+    fn packed() {
+        check_files(
+            "../../examples/packed.capy",
+            &[],
+            "main",
+            expect![[r#"
+            Header: size 7, align 1, offsets 0 1 5
+            Loose_Header: size 10, align 4, offsets 0 4 8
+            Vec4: size 16, align 16, offsets 0 4 8 12
+            Slot: size 12, align 8, offsets 0 8
+            kind 2, len 2000, checksum 100
+            loose: kind 2, len 2000, checksum 100
+            bytes: 2 208 7
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn unions() {
+        check_files(
+            "../../examples/unions.capy",
+            &[],
+            "main",
+            expect![[r#"
+            Bits: size 4, align 4
+            Value: size 8, align 8
+            Token: size 16, align 8
+            1.0 as bits: 3f800000
+            0x40490FDB as float: 3.141593
+            first byte: db
+            zeroed: 0
+            small token 42
+            big token 5000000000
+            int at 0
+            float at 0
+            bytes at 0
+            union { int: u32, float: f32, bytes: [4] u8 }
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn optionals() {
+        check_files(
+            "../../examples/optionals.capy",
+            &[],
+            "main",
+            expect![[r#"
+            5
+            null
+            null
+            6
+            no b
+            43
+            2
+            2
+            null
+            ?^i32
+            8
+            5
+            9
+            i64
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn tuples() {
+        check_files(
+            "../../examples/tuples.capy",
+            &[],
+            "main",
+            expect![[r#"
+            1234 1
+            12 0
+            13
+            25.0 1.333
+            -2 9 16 4
+            (7, true)
+            (true, 7)
+            (hello, (1.500, c), [ 1, 2 ])
+            (42,)
+            (0, 0)
+            (i32, bool)
+            16
+            8
+            0 at 0
+            1 at 8
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn closures() {
+        check_files(
+            "../../examples/closures.capy",
+            &[],
+            "main",
+            expect![[r#"
+            7
+            12
+            11
+            42
+            10
+            2
+            3
+            {closure}
+            (i32) [] -> i32
+            true
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn methods() {
+        check_files(
+            "../../examples/methods.capy",
+            &[],
+            "main",
+            expect![[r#"
+            5
+            3
+            9
+            18
+            42
+            [ 5, 6 ]
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn operators() {
+        check_files(
+            "../../examples/operators.capy",
+            &[],
+            "main",
+            expect![[r#"
+            [ 21, 42, 63 ]
+            [ -21, -42, -63 ]
+            false
+            true
+            true
+            3
+            5
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn interfaces() {
+        check_files(
+            "../../examples/interfaces.capy",
+            &["../../examples/circle.capy"],
+            "main",
+            expect![[r#"
+                rect with an area of 6.000
+                rect with an area of 24.000
+                4.000
+                rect with an area of 24.000
+                circle with an area of 3.000
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn struct_defaults() {
+        check_files(
+            "../../examples/defaults.capy",
+            &[],
+            "main",
+            expect![[r#"
+                { name = quiet, retries = 3, verbose = false, timeout = 2.500 }
+                { name = loud, retries = 10, verbose = true, timeout = 2.500 }
+                hello
+                hello
+                48
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn named_args() {
+        check_files(
+            "../../examples/named_args.capy",
+            &[],
+            "main",
+            expect![[r#"
+                hello, bob
+                hello, alice
+                hello, alice
+                hey, eve
+                15
+                3
+                42
+                255
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn slicing() {
+        check_files(
+            "../../examples/slicing.capy",
+            &[],
+            "main",
+            expect![[r#"
+                [ 2, 3 ]
+                [ 1, 2 ]
+                [ 4, 5 ]
+                15
+                [ 3, 4 ]
+                [ 1, 20, 3, 4, 5 ]
+                world
+                5
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn equality() {
+        check_files(
+            "../../examples/equality.capy",
+            &[],
+            "main",
+            expect![[r#"
+                true
+                true
+                true
+                false
+                true
+                false
+                true
+                false
+                true
+                false
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn uninit() {
+        check_files(
+            "../../examples/uninit.capy",
+            &[],
+            "main",
+            expect![[r#"
+                [ 97, 98, 99, 100, 101 ]
+                1
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn bit_widths() {
+        check_files(
+            "../../examples/bit_widths.capy",
+            &[],
+            "main",
+            expect![[r#"
+                { version = 4, header_len = 5 }
+                7
+                0
+                -8
+                44
+                24
+                4
+
+            "#]],
+            0,
+        )
+    }
+
     #[test]
     fn comptime() {
         check_files(
@@ -817,6 +1252,155 @@ mod tests {
 
     // comptime_types.capy cannot be tested as it gets user input
 
+    #[test]
+    fn embed() {
+        check_files(
+            "../../examples/embed.capy",
+            &[],
+            "main",
+            expect![[r#"
+                53
+                Hello from a file that was embedded at compile time!
+                true
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn args() {
+        check_files(
+            "../../examples/args.capy",
+            &[],
+            "main",
+            expect![[r#"
+                arguments given: 0
+
+            "#]],
+            1,
+        )
+    }
+
+    #[test]
+    fn args_given() {
+        check_files_with_args(
+            "../../examples/args.capy",
+            &["foo", "bar baz"],
+            expect![[r#"
+                arguments given: 2
+                foo
+                bar baz
+
+            "#]],
+            3,
+        )
+    }
+
+    #[test]
+    fn runtime_globals() {
+        check_files(
+            "../../examples/globals.capy",
+            &[],
+            "main",
+            expect![[r#"
+                initializing squares
+                initializing total
+                main started
+                16
+                30
+                15
+                80
+                4
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn var_args() {
+        check_files(
+            "../../examples/var_args.capy",
+            &[],
+            "main",
+            expect![[r#"
+                Hello, World!
+                42 c 1 0.50
+                7 -5000000000 18446744073709551615
+                1 2 3 4 5 6 7 8 1.0 2.0 3.0 4.0 5.0 6.0 7.0 8.0 9.0
+                1 + 2
+                6
+                formatted, ff
+
+            "#]],
+            0,
+        )
+    }
+
+    #[test]
+    fn export() {
+        check_library(
+            "../../examples/export.capy",
+            "../../examples/export.c",
+            expect![[r#"
+                // generated by capy, do not edit
+
+                #ifndef EXPORT_H
+                #define EXPORT_H
+
+                #include <stdbool.h>
+                #include <stdint.h>
+
+                typedef struct Particle Particle;
+                typedef struct Vec2 Vec2;
+
+                struct Vec2 {
+                    float x;
+                    float y;
+                };
+                _Static_assert(sizeof(Vec2) == 8, "capy and C disagree on the size of `Vec2`");
+
+                typedef struct capy_str {
+                    uintptr_t len;
+                    char const *ptr;
+                } capy_str;
+
+                struct Particle {
+                    Vec2 pos;
+                    Vec2 vel;
+                    uint8_t mass;
+                    bool alive;
+                    capy_str name;
+                };
+                _Static_assert(sizeof(Particle) == 40, "capy and C disagree on the size of `Particle`");
+
+                typedef struct capy_slice_i32 {
+                    uintptr_t len;
+                    int32_t *ptr;
+                } capy_slice_i32;
+
+                // initializes the globals of the library, call this before anything else
+                void capy_init(void);
+
+                void greet(char const *name);
+                Particle make_particle(uint8_t mass);
+                void step(Particle *p, float dt);
+                int64_t sum(capy_slice_i32 nums);
+
+                #endif // EXPORT_H
+            "#]],
+            expect![[r#"
+                Hello, C
+                dust weighs 7
+                pos = (2.00, 75.50), vel = (1.00, -19.60)
+                sum = 15
+
+            "#]],
+            0,
+        )
+    }
+
     #[test]
     fn strings() {
         check_files(
@@ -824,7 +1408,12 @@ mod tests {
             &[],
             "main",
             expect![[r#"
-            Hello World!
+                Hello World!
+                13
+                World
+                Goodbye!
+                Hello
+                6
 
             "#]],
             0,
@@ -909,16 +1498,16 @@ mod tests {
                 f32              (201326724) : size = 4, align = 4, stride = 4
                 void             (67108896) : size = 0, align = 1, stride = 0
                 any              (536870944) : size = 0, align = 1, stride = 0
-                str              (335544584) : size = 8, align = 8, stride = 8
+                str              (335544592) : size = 16, align = 8, stride = 16
                 char             (402653217) : size = 1, align = 1, stride = 1
                 type             (469762180) : size = 4, align = 4, stride = 4
-                Person           (1073741824) : size = 12, align = 8, stride = 16
+                Person           (1073741824) : size = 20, align = 8, stride = 24
                 Foo              (1073741825) : size = 1, align = 1, stride = 1
-                [6] Person       (1207959552) : size = 96, align = 8, stride = 96
+                [6] Person       (1207959552) : size = 144, align = 8, stride = 144
                 [ ] Person       (1275068416) : size = 16, align = 8, stride = 16
                  ^  Person       (1342177280) : size = 8, align = 8, stride = 8
-                distinct Person  (1140850688) : size = 12, align = 8, stride = 16
-                distinct Person  (1140850689) : size = 12, align = 8, stride = 16
+                distinct Person  (1140850688) : size = 20, align = 8, stride = 24
+                distinct Person  (1140850689) : size = 20, align = 8, stride = 24
                 ()       -> void (1409286144) : size = 8, align = 8, stride = 8
                 (x: i32) -> f32  (1409286145) : size = 8, align = 8, stride = 8
                 
@@ -1029,11 +1618,11 @@ mod tests {
                  ty =
                   STRING
                  name = flag
-                 offset = 8
+                 offset = 16
                  ty =
                   BOOL
                  name = array
-                 offset = 10
+                 offset = 18
                  ty =
                   ARRAY
                   len = 3
@@ -1049,7 +1638,7 @@ mod tests {
                  ty =
                   STRING
                  name = age
-                 offset = 8
+                 offset = 16
                  ty =
                   INT
                   bit_width = 32
@@ -1077,6 +1666,25 @@ mod tests {
                   ty =
                    BOOL
                 
+                FUNCTION
+                params =
+                 INT
+                 bit_width = 32
+                 signed    = true
+                 STRING
+                return_ty =
+                 BOOL
+                is_variadic = false
+                
+                FUNCTION
+                params =
+                 CSTRING
+                return_ty =
+                 INT
+                 bit_width = 32
+                 signed    = true
+                is_variadic = true
+                
                 123
                 [ 4, 8, 15, 16, 23, 42 ]
                 [ 1, 2, 3 ]
@@ -1089,6 +1697,7 @@ mod tests {
                 i32
                 ^struct { text: str, flag: bool, array: [3] i16 }
                 struct { ty: type, data: ^any }
+                (str, u64) -> ^i32
                 [ 3, -1, 4, 1, 5, 9 ]
                 [ 4, 8, 15, 16, 23, 42 ]
                 [ 1, hello, true, 5.300 ]
@@ -1178,31 +1787,31 @@ mod tests {
             r#"
                 a :: (x: bool) -> bool {
                     if x {
-                        puts("a: true");
+                        puts("a: true" as cstr);
                     } else {
-                        puts("a: false");
+                        puts("a: false" as cstr);
                     }
                     x
                 }
 
                 b :: (x: bool) -> bool {
                     if x {
-                        puts("b: true");
+                        puts("b: true" as cstr);
                     } else {
-                        puts("b: false");
+                        puts("b: false" as cstr);
                     }
                     x
                 }
 
                 main :: () {
-                    puts("logical AND:\n");
+                    puts("logical AND:\n" as cstr);
 
                     print_bool(a(true) && b(true));
                     print_bool(a(true) && b(false));
                     print_bool(a(false) && b(true));
                     print_bool(a(false) && b(false));
 
-                    puts("logical OR:\n");
+                    puts("logical OR:\n" as cstr);
 
                     print_bool(a(true) || b(true));
                     print_bool(a(true) || b(false));
@@ -1212,13 +1821,13 @@ mod tests {
 
                 print_bool :: (b: bool) {
                     if b {
-                        puts("true\n");
+                        puts("true\n" as cstr);
                     } else {
-                        puts("false\n");
+                        puts("false\n" as cstr);
                     }
                 }
 
-                puts :: (s: str) extern;
+                puts :: (s: cstr) extern;
             "#,
             "main",
             false,
@@ -1267,31 +1876,31 @@ mod tests {
             r#"
                 a :: (x: bool) -> bool {
                     if x {
-                        puts("a: true");
+                        puts("a: true" as cstr);
                     } else {
-                        puts("a: false");
+                        puts("a: false" as cstr);
                     }
                     x
                 }
 
                 b :: (x: bool) -> bool {
                     if x {
-                        puts("b: true");
+                        puts("b: true" as cstr);
                     } else {
-                        puts("b: false");
+                        puts("b: false" as cstr);
                     }
                     x
                 }
 
                 main :: () {
-                    puts("bitwise AND:\n");
+                    puts("bitwise AND:\n" as cstr);
 
                     print_bool(a(true) & b(true));
                     print_bool(a(true) & b(false));
                     print_bool(a(false) & b(true));
                     print_bool(a(false) & b(false));
 
-                    puts("bitwise OR:\n");
+                    puts("bitwise OR:\n" as cstr);
 
                     print_bool(a(true) | b(true));
                     print_bool(a(true) | b(false));
@@ -1301,13 +1910,13 @@ mod tests {
 
                 print_bool :: (b: bool) {
                     if b {
-                        puts("true\n");
+                        puts("true\n" as cstr);
                     } else {
-                        puts("false\n");
+                        puts("false\n" as cstr);
                     }
                 }
 
-                puts :: (s: str) extern;
+                puts :: (s: cstr) extern;
             "#,
             "main",
             false,
@@ -1370,9 +1979,9 @@ mod tests {
                 
                 main :: () -> i32 {
                     {
-                        puts("before return");
+                        puts("before return" as cstr);
                         return {
-                            puts("before break");
+                            puts("before break" as cstr);
                             x := 5;
                             break loop {
                                 res := fib(x);
@@ -1385,19 +1994,19 @@ mod tests {
                                 }
                                 x = x + 1;
                             };
-                            puts("after break");
+                            puts("after break" as cstr);
                             42
                         };
-                        puts("after return");
+                        puts("after return" as cstr);
                         1 + 1
                     }
                 
-                    puts("hello!");
+                    puts("hello!" as cstr);
                 
                     0
                 }
                 
-                puts :: (s: str) extern;
+                puts :: (s: cstr) extern;
             "#,
             "main",
             true,
@@ -1452,14 +2061,9 @@ mod tests {
                 }
 
                 print :: (s: str, n: i64) {
-                    s := s as ^any as ^[1000] char;
-                    idx := 0;
-                    while idx < 100 {
-                        ch := s[idx];
-                        if ch == '\0' {
-                            break;
-                        }
-                        putchar(ch);
+                    idx : usize = 0;
+                    while idx < s.len {
+                        putchar(s[idx] as char);
                         idx = idx + 1;
                     }
 
@@ -1591,11 +2195,11 @@ mod tests {
                             continue;
                         }
                 
-                        printf("%i\n", i);
+                        printf("%i\n" as cstr, i);
                     }
                 }
                 
-                printf :: (fmt: str, n: i32) extern;
+                printf :: (fmt: cstr, n: i32) extern;
             "#,
             "main",
             false,
@@ -1616,19 +2220,19 @@ mod tests {
         check_raw(
             r#"
                 main :: () -> i32 {
-                    defer printf(" How ye be?");
+                    defer printf(" How ye be?" as cstr);
                     {
-                        defer printf(" Sailor!");
-                        defer printf("ly");
+                        defer printf(" Sailor!" as cstr);
+                        defer printf("ly" as cstr);
                         {
-                            defer printf(" World");
-                            printf("Hello");
+                            defer printf(" World" as cstr);
+                            printf("Hello" as cstr);
                             return 5;
                         }
                     }
                 }
                 
-                printf :: (text: str) extern;
+                printf :: (text: cstr) extern;
             "#,
             "main",
             false,
@@ -1644,14 +2248,14 @@ mod tests {
         check_raw(
             r#"
                 main :: () {
-                    defer printf("ly Sailor!");
+                    defer printf("ly Sailor!" as cstr);
                     defer {
-                        defer printf("World");
-                        printf("Hello ");
+                        defer printf("World" as cstr);
+                        printf("Hello " as cstr);
                     };
                 }
                 
-                printf :: (text: str) extern;
+                printf :: (text: cstr) extern;
             "#,
             "main",
             false,
@@ -1667,10 +2271,10 @@ mod tests {
         check_raw(
             r#"
                 main :: () {
-                    printf("Hello World!");
+                    printf("Hello World!" as cstr);
                 }
                 
-                printf : (text: str) -> void : extern;
+                printf : (text: cstr) -> void : extern;
             "#,
             "main",
             false,
@@ -1686,10 +2290,10 @@ mod tests {
         check_raw(
             r#"
                 main :: () {
-                    printf("Hello World!");
+                    printf("Hello World!" as cstr);
                 }
                 
-                printf :: (text: str) extern;
+                printf :: (text: cstr) extern;
             "#,
             "main",
             false,
@@ -1705,7 +2309,7 @@ mod tests {
         check_raw(
             r#"
                 foo :: comptime {
-                    puts("comptime global in comptime global");
+                    puts("comptime global in comptime global" as cstr);
                     42
                 };
 
@@ -1724,7 +2328,7 @@ mod tests {
                     baz
                 }
                 
-                puts :: (text: str) extern;
+                puts :: (text: cstr) extern;
             "#,
             "main",
             false,
@@ -1854,7 +2458,7 @@ mod tests {
                     putchar({'0' as u8 + n} as char);
                 }
 
-                puts :: (text: str) extern;
+                puts :: (text: cstr) extern;
                 putchar :: (ch: char) extern;
             "#,
             "main",
@@ -2224,5 +2828,111 @@ mod tests {
         )
     }
 
+    #[test]
+    fn unwrap_null() {
+        check_raw(
+            r#"
+                main :: () -> i32 {
+                    x : ?i32 = null;
+
+                    x.?
+                }
+            "#,
+            "main",
+            false,
+            expect![[r#"
+                attempted to unwrap a null optional at main.capy:5:23
+
+            "#]],
+            1,
+        )
+    }
+
+    #[test]
+    fn unwrap_null_with_libc_declared() {
+        // the panic has to work even if the program declares the libc functions it uses
+        // with different signatures
+        check_raw(
+            r#"
+                puts :: (s: cstr) -> i32 extern;
+                exit :: (status: i64) extern;
+
+                main :: () -> i32 {
+                    puts("unwrapping" as cstr);
+
+                    x : ?i32 = null;
+
+                    x.?
+                }
+            "#,
+            "main",
+            false,
+            expect![[r#"
+                unwrapping
+                attempted to unwrap a null optional at main.capy:10:23
+
+            "#]],
+            1,
+        )
+    }
+
+    #[test]
+    fn optional_null_from_c() {
+        // C returns `NULL` for a missing value, so these have to be laid out as a single pointer
+        check_raw(
+            r#"
+                core :: mod "core";
+
+                getenv :: (name: cstr) -> ?cstr extern;
+                bsearch :: (
+                    key: ^any,
+                    base: ^any,
+                    len: usize,
+                    size: usize,
+                    compare: (a: ^any, b: ^any) -> i32,
+                ) -> ?Handle extern;
+
+                Handle :: distinct ^any;
+
+                compare :: (a: ^any, b: ^any) -> i32 {
+                    (a as ^i32)^ - (b as ^i32)^
+                }
+
+                main :: () {
+                    if value := getenv("NOPE_NOT_SET_XYZ" as cstr) {
+                        core.println("has value");
+                    } else {
+                        core.println("no value");
+                    }
+
+                    if path := getenv("PATH" as cstr) {
+                        core.println("has PATH");
+                    }
+
+                    nums := i32.[1, 2, 3];
+                    key := 5;
+                    found := bsearch(^key, ^nums, 3, 4, compare);
+                    core.println(found);
+
+                    callback : ?(a: ^any, b: ^any) -> i32 = null;
+                    core.println(callback);
+                    callback = compare;
+                    core.println(callback.?(^key, ^key));
+                }
+            "#,
+            "main",
+            true,
+            expect![[r#"
+                no value
+                has PATH
+                null
+                null
+                0
+
+            "#]],
+            0,
+        )
+    }
+
     // the "ptrs_to_ptrs.capy" and "comptime_types.capy" tests are not reproducible
 }
@@ -25,9 +25,32 @@ impl Mangle for Fqn {
     }
 }
 
+impl Mangle for (Fqn, &str) {
+    fn to_mangled_name(&self, mod_dir: &std::path::Path, interner: &Interner) -> String {
+        let mut mangled = "_C".to_string();
+
+        push_file_name(&mut mangled, self.0.file, mod_dir, interner, 'I');
+
+        let name_str = interner.lookup(self.0.name.0);
+        mangled.push_str(&name_str.len().to_string());
+        mangled.push_str(name_str);
+
+        mangled.push_str(&self.1.len().to_string());
+        mangled.push_str(self.1);
+
+        mangled.push('E');
+
+        mangled
+    }
+}
+
 impl Mangle for FunctionToCompile {
     fn to_mangled_name(&self, mod_dir: &std::path::Path, interner: &Interner) -> String {
         if let Some(name) = self.function_name {
+            if self.is_export {
+                return interner.lookup(name.0).to_string();
+            }
+
             return hir::Fqn {
                 file: self.file_name,
                 name,
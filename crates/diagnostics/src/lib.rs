@@ -421,6 +421,18 @@ fn lowering_diagnostic_message(d: &LoweringDiagnostic, interner: &Interner) -> S
         LoweringDiagnosticKind::NonGlobalExternFunc => {
             "non-global functions cannot be extern".to_string()
         }
+        LoweringDiagnosticKind::NonGlobalExportFunc => {
+            "non-global functions cannot be exported".to_string()
+        }
+        LoweringDiagnosticKind::ExportWithoutBody => {
+            "only functions with bodies can be exported".to_string()
+        }
+        LoweringDiagnosticKind::VarArgsNotLast => {
+            "`..` must come after all the other parameters".to_string()
+        }
+        LoweringDiagnosticKind::VarArgsWithBody => {
+            "only `extern` functions can take variadic arguments".to_string()
+        }
         LoweringDiagnosticKind::InvalidEscape => "invalid escape".to_string(),
         LoweringDiagnosticKind::ModMustBeAlphanumeric => "modules must be alphanumeric".to_string(),
         LoweringDiagnosticKind::ModDoesNotExist { module, mod_dir } => {
@@ -441,6 +453,15 @@ fn lowering_diagnostic_message(d: &LoweringDiagnostic, interner: &Interner) -> S
         LoweringDiagnosticKind::ImportOutsideCWD { file } => {
             format!("`{}` is outside the current working module", file)
         }
+        LoweringDiagnosticKind::ExpectedEmbedFile => {
+            "`#embed` expects a file name, like `#embed \"file.txt\"`".to_string()
+        }
+        LoweringDiagnosticKind::EmbedDoesNotExist { file } => {
+            format!("`{}` couldn't be found, so it can't be embedded", file)
+        }
+        LoweringDiagnosticKind::CouldNotEmbed { file, why } => {
+            format!("couldn't read `{}`: {}", file, why)
+        }
         LoweringDiagnosticKind::TooManyCharsInCharLiteral => {
             "character literals can only contain one character".to_string()
         }
@@ -466,6 +487,71 @@ fn lowering_diagnostic_message(d: &LoweringDiagnostic, interner: &Interner) -> S
         LoweringDiagnosticKind::ContinueFromDefer => {
             "cannot `continue` an outer loop from within a `defer`".to_string()
         }
+        LoweringDiagnosticKind::UnknownDirective { name } => {
+            format!("there is no directive named `#{}`", interner.lookup(*name))
+        }
+        LoweringDiagnosticKind::DirectiveTakesNoArgs { name } => {
+            format!("`#{}` doesn't take any arguments", interner.lookup(*name))
+        }
+        LoweringDiagnosticKind::UnknownLayoutDirective { name, on_field } => {
+            format!(
+                "`#{}` isn't a {} layout directive, expected {}",
+                interner.lookup(*name),
+                if *on_field { "field" } else { "struct" },
+                if *on_field {
+                    "`#align`"
+                } else {
+                    "`#packed` or `#align`"
+                }
+            )
+        }
+        LoweringDiagnosticKind::InvalidAlign => {
+            "`#align` takes a single power of two, e.g. `#align(16)`".to_string()
+        }
+        LoweringDiagnosticKind::ExpectedDefineName => {
+            "expected the name of a define, e.g. `#def(DEBUG)`".to_string()
+        }
+        LoweringDiagnosticKind::UndefinedDefine { name } => {
+            format!(
+                "`{}` was not defined, try passing `-D {}=<value>` to the compiler",
+                interner.lookup(*name),
+                interner.lookup(*name)
+            )
+        }
+        LoweringDiagnosticKind::NonStaticCondition => {
+            "`#if` conditions can only contain literals, directives, comparisons, `&&`, `||`, and `!`"
+                .to_string()
+        }
+        LoweringDiagnosticKind::StaticConditionMismatch => {
+            "mismatched types in `#if` condition".to_string()
+        }
+        LoweringDiagnosticKind::StaticConditionNotBool => {
+            "`#if` conditions must be booleans".to_string()
+        }
+        LoweringDiagnosticKind::TooManyAssertArgs => {
+            "`#assert` only takes a condition and a message".to_string()
+        }
+        LoweringDiagnosticKind::AssertMessageNotString => {
+            "the message of an `#assert` must be a string literal".to_string()
+        }
+        LoweringDiagnosticKind::CaptureNotLocal { name } => {
+            format!(
+                "`{}` is not a local or parameter, so it can't be captured",
+                interner.lookup(*name)
+            )
+        }
+        LoweringDiagnosticKind::GlobalClosure => {
+            "global functions cannot capture anything".to_string()
+        }
+        LoweringDiagnosticKind::PositionalArgAfterNamed => {
+            "unnamed arguments can't come after named ones".to_string()
+        }
+        LoweringDiagnosticKind::UninitOutsideLocal => {
+            "`---` can only be used as the value of a local variable".to_string()
+        }
+        LoweringDiagnosticKind::UninitWithoutTy => {
+            "an uninitialized variable needs a type annotation".to_string()
+        }
     }
 }
 
@@ -489,6 +575,11 @@ fn ty_diagnostic_message(
                 to.display(mod_dir, interner)
             )
         }
+        hir_ty::TyDiagnosticKind::NonLiteralToCStr => {
+            "only string literals can be casted to `cstr`, other strings have to be copied \
+             with `core.str_to_cstr`"
+                .to_string()
+        }
         hir_ty::TyDiagnosticKind::BinaryOpMismatch { op, first, second } => {
             format!(
                 "`{}` cannot be {} `{}`",
@@ -545,6 +636,18 @@ fn ty_diagnostic_message(
             index,
             actual_size,
             array_ty,
+            is_slice_bound: true,
+        } => format!(
+            "slice bound `{}` is too big, `{}` can only be sliced up to `[..{}]`",
+            index,
+            array_ty.display(mod_dir, interner),
+            actual_size,
+        ),
+        hir_ty::TyDiagnosticKind::IndexOutOfBounds {
+            index,
+            actual_size,
+            array_ty,
+            is_slice_bound: false,
         } => {
             match actual_size.checked_sub(1) {
                 Some(up_to) => format!(
@@ -560,9 +663,21 @@ fn ty_diagnostic_message(
                 )
             }
         }
+        hir_ty::TyDiagnosticKind::SliceStartAfterEnd { start, end } => {
+            format!("the slice `[{}..{}]` starts after it ends", start, end)
+        }
         hir_ty::TyDiagnosticKind::MismatchedArgCount { found, expected } => {
             format!("expected {} arguments but found {}", expected, found)
         }
+        hir_ty::TyDiagnosticKind::NotEnoughVarArgs { found, expected } => {
+            format!("expected at least {} arguments but found {}", expected, found)
+        }
+        hir_ty::TyDiagnosticKind::InvalidVarArg { found } => {
+            format!(
+                "`{}` can't be passed as a variadic argument",
+                found.display(mod_dir, interner)
+            )
+        }
         hir_ty::TyDiagnosticKind::CalledNonFunction { found } => {
             format!(
                 "expected a function, but found {}",
@@ -575,6 +690,19 @@ fn ty_diagnostic_message(
                 found.display(mod_dir, interner)
             )
         }
+        hir_ty::TyDiagnosticKind::UnwrapNonOptional { found } => {
+            format!(
+                "tried unwrapping `.?` a non-optional, `{}`",
+                found.display(mod_dir, interner)
+            )
+        }
+        hir_ty::TyDiagnosticKind::DestructureMismatch { expected, found } => {
+            format!(
+                "expected a tuple of {} items but found `{}`",
+                expected,
+                found.display(mod_dir, interner)
+            )
+        }
         hir_ty::TyDiagnosticKind::DerefAny => {
             "tried dereferencing `^` a pointer to `any`. try casting it to a different pointer type first".to_string()
         }
@@ -608,6 +736,14 @@ fn ty_diagnostic_message(
                 max
             )
         }
+        hir_ty::TyDiagnosticKind::IntTooSmallForType { found, min, ty } => {
+            format!(
+                "integer literal `{}` is too small for `{}`, which can only hold down to {}",
+                found,
+                ty.display(mod_dir, interner),
+                min
+            )
+        }
         hir_ty::TyDiagnosticKind::UnknownFile { file } => {
             format!(
                 "could not find a file named `{}`",
@@ -624,22 +760,88 @@ fn ty_diagnostic_message(
             interner.lookup(*field),
             found_ty.display(mod_dir, interner)
         ),
+        hir_ty::TyDiagnosticKind::AmbiguousMethod { first, second } => format!(
+            "`{}` is ambiguous, it could be either `{}` or `{}`",
+            interner.lookup(first.name.0),
+            first.to_string(mod_dir, interner),
+            second.to_string(mod_dir, interner),
+        ),
+        hir_ty::TyDiagnosticKind::InterfaceMethodNotFunction { found } => format!(
+            "interface methods must be function types, but found `{}`",
+            found.display(mod_dir, interner),
+        ),
+        hir_ty::TyDiagnosticKind::InterfaceMethodNotImplemented {
+            ty,
+            interface,
+            method,
+        } => format!(
+            "`{}` can't be used as `{}` because it has no `{}` method",
+            ty.display(mod_dir, interner),
+            interface.display(mod_dir, interner),
+            interner.lookup(*method),
+        ),
+        hir_ty::TyDiagnosticKind::InterfaceFromNonPointer { found, interface } => format!(
+            "only pointers can be casted to `{}`, but found `{}`",
+            interface.display(mod_dir, interner),
+            found.display(mod_dir, interner),
+        ),
+        hir_ty::TyDiagnosticKind::InvalidOperatorFunction { function, found } => format!(
+            "`{}` of type `{}` can't be used as this operator",
+            function.to_string(mod_dir, interner),
+            found.display(mod_dir, interner),
+        ),
         hir_ty::TyDiagnosticKind::StructLiteralMissingMember { member: field, expected_ty } => format!(
             "`{}` struct literal is missing the member `{}`",
             expected_ty.display(mod_dir, interner),
             interner.lookup(*field)
         ),
+        hir_ty::TyDiagnosticKind::UnknownParam { name } => {
+            format!("there is no parameter named `{}`", interner.lookup(*name))
+        }
+        hir_ty::TyDiagnosticKind::DuplicateArg { param } => {
+            format!("the parameter `{}` was given more than once", interner.lookup(*param))
+        }
+        hir_ty::TyDiagnosticKind::MissingArg { param } => {
+            format!("missing an argument for the parameter `{}`", interner.lookup(*param))
+        }
+        hir_ty::TyDiagnosticKind::NamedArgsOnIndirectCall => {
+            "named arguments can only be used when calling a function by name".to_string()
+        }
+        hir_ty::TyDiagnosticKind::UnionLiteralMemberCount { found, expected_ty } => format!(
+            "`{}` union literal must set exactly one member, but it sets {}",
+            expected_ty.display(mod_dir, interner),
+            found
+        ),
         hir_ty::TyDiagnosticKind::ComptimePointer => {
             "comptime blocks cannot return pointers. the data won't exist at runtime".to_string()
         }
-        hir_ty::TyDiagnosticKind::GlobalNotConst => {
-            "globals must be constant values. try wrapping this in `comptime { ... }`".to_string()
+        hir_ty::TyDiagnosticKind::GlobalInitCycle { path } => format!(
+            "initialization cycle: {}",
+            path.iter()
+                .map(|fqn| format!("`{}`", fqn.to_string(mod_dir, interner)))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        ),
+        hir_ty::TyDiagnosticKind::RuntimeGlobalInComptime { path } => {
+            let global = format!("`{}`", path.last().unwrap().to_string(mod_dir, interner));
+
+            if path.len() == 1 {
+                format!("runtime global {global} used at compile time")
+            } else {
+                format!(
+                    "runtime global {global} used at compile time through {}",
+                    path.iter()
+                        .map(|fqn| format!("`{}`", fqn.to_string(mod_dir, interner)))
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )
+            }
         }
         hir_ty::TyDiagnosticKind::EntryNotFunction => {
             "the entry point must be a function".to_string()
         }
-        hir_ty::TyDiagnosticKind::EntryHasParams => {
-            "the entry point cannot have any parameters".to_string()
+        hir_ty::TyDiagnosticKind::EntryBadParams => {
+            "the entry point can only take a single `[]str` parameter".to_string()
         }
         hir_ty::TyDiagnosticKind::EntryBadReturn => {
             "the entry point must either return `{int}` or `void`".to_string()
@@ -650,6 +852,9 @@ fn ty_diagnostic_message(
         hir_ty::TyDiagnosticKind::ArraySizeNotConst => {
             "array size must be known at compile-time".to_string()
         }
+        hir_ty::TyDiagnosticKind::DefaultNotConst => {
+            "default values must be known at compile-time".to_string()
+        }
         hir_ty::TyDiagnosticKind::ArraySizeMismatch { found, expected } => {
             format!("expected `{}` elements, found {}", expected, found)
         }
@@ -659,6 +864,50 @@ fn ty_diagnostic_message(
         hir_ty::TyDiagnosticKind::DeclTypeHasNoDefault { ty } => {
             format!("`{}` does not have a default value. one must be supplied", ty.display(mod_dir, interner))
         }
+        hir_ty::TyDiagnosticKind::StaticAssertFailed { message: Some(message) } => {
+            format!("static assertion failed: {}", interner.lookup(*message))
+        }
+        hir_ty::TyDiagnosticKind::StaticAssertFailed { message: None } => {
+            "static assertion failed".to_string()
+        }
+        hir_ty::TyDiagnosticKind::ReadBeforeWrite => {
+            "this variable is read before anything is written to it".to_string()
+        }
+        hir_ty::TyDiagnosticKind::LocalRefEscapes { path, into }
+        | hir_ty::TyDiagnosticKind::ClosureEscapes { path, into } => {
+            let into = match into {
+                hir_ty::RefEscape::Return => "returned".to_string(),
+                hir_ty::RefEscape::Global(fqn) => {
+                    format!("stored in `{}`", fqn.to_string(mod_dir, interner))
+                }
+                hir_ty::RefEscape::Param(param) => {
+                    format!("stored through `{}`", interner.lookup(*param))
+                }
+            };
+
+            let through = if path.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    " by way of {}",
+                    path.iter()
+                        .map(|name| format!("`{}`", interner.lookup(*name)))
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )
+            };
+
+            let what = if matches!(d.kind, hir_ty::TyDiagnosticKind::ClosureEscapes { .. }) {
+                "this closure uses the locals of its function"
+            } else {
+                "this points to a local"
+            };
+
+            format!(
+                "{}, which won't exist after the function returns, but it gets {}{}",
+                what, into, through
+            )
+        }
     }
 }
 
@@ -683,6 +932,12 @@ fn ty_diagnostic_help_message(
         hir_ty::TyDiagnosticHelpKind::ImmutableParam { assignment: false } => {
             "parameters are immutable".to_string()
         }
+        hir_ty::TyDiagnosticHelpKind::ImmutableCapture { by_value: true } => {
+            "this was captured by value, so the closure only has a copy of it".to_string()
+        }
+        hir_ty::TyDiagnosticHelpKind::ImmutableCapture { by_value: false } => {
+            "only `:=` bindings can be changed from within a closure".to_string()
+        }
         hir_ty::TyDiagnosticHelpKind::ImmutableGlobal => "globals are immutable".to_string(),
         hir_ty::TyDiagnosticHelpKind::NotMutatingRefThroughDeref => {
             "this is a reference, to mutate it's inner value add a `^` at the end to dereference it first"
@@ -700,6 +955,10 @@ fn ty_diagnostic_help_message(
         hir_ty::TyDiagnosticHelpKind::BreakHere { break_ty } => {
             format!("expected because this break returns a `{}`", break_ty.display(mod_dir, interner))
         }
+        hir_ty::TyDiagnosticHelpKind::LeftUninitHere => {
+            "it was left uninitialized here".to_string()
+        }
+        hir_ty::TyDiagnosticHelpKind::EscapesHere => "it escapes here".to_string(),
     }
 }
 
@@ -714,7 +973,10 @@ fn format_kind(kind: TokenKind) -> &'static str {
         TokenKind::Mut => "`mut`",
         TokenKind::Distinct => "`distinct`",
         TokenKind::Extern => "`extern`",
+        TokenKind::Export => "`export`",
         TokenKind::Struct => "`struct`",
+        TokenKind::Union => "`union`",
+        TokenKind::Interface => "`interface`",
         TokenKind::Import => "`import`",
         TokenKind::Mod => "`mod`",
         TokenKind::Comptime => "`comptime`",
@@ -722,6 +984,7 @@ fn format_kind(kind: TokenKind) -> &'static str {
         TokenKind::Break => "`break`",
         TokenKind::Continue => "`continue`",
         TokenKind::Defer => "`defer`",
+        TokenKind::Null => "`null`",
         TokenKind::Bool => "boolean",
         TokenKind::Int => "integer",
         TokenKind::Hex => "hex literal",
@@ -733,6 +996,7 @@ fn format_kind(kind: TokenKind) -> &'static str {
         TokenKind::StringContents => "string",
         TokenKind::Plus => "`+`",
         TokenKind::Hyphen => "`-`",
+        TokenKind::TripleHyphen => "`---`",
         TokenKind::Asterisk => "`*`",
         TokenKind::Slash => "`/`",
         TokenKind::Percent => "`%`",
@@ -752,11 +1016,14 @@ fn format_kind(kind: TokenKind) -> &'static str {
         TokenKind::Tilde => "`~`",
         TokenKind::Equals => "`=`",
         TokenKind::Dot => "`.`",
+        TokenKind::DoubleDot => "`..`",
         TokenKind::Colon => "`:`",
         TokenKind::Comma => "`,`",
         TokenKind::Semicolon => "`;`",
         TokenKind::Arrow => "`->`",
         TokenKind::Caret => "`^`",
+        TokenKind::Question => "`?`",
+        TokenKind::Hash => "`#`",
         TokenKind::Backtick => "'`'", // this one is a little weird lol
         TokenKind::LParen => "`(`",
         TokenKind::RParen => "`)`",
@@ -3,7 +3,8 @@ use std::{
     env,
     fmt::{Debug, Display},
     mem,
-    path::Path,
+    path::{Path, PathBuf},
+    sync::Arc,
     vec,
 };
 
@@ -15,7 +16,10 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use syntax::SyntaxTree;
 use text_size::TextRange;
 
-use crate::{subdir::SubDir, FileName, Fqn, Index, Name, NameWithRange, PrimitiveTy, UIDGenerator};
+use crate::{
+    config, subdir::SubDir, BuildConfig, ConfigValue, FileName, Fqn, Index, Name, NameWithRange,
+    PrimitiveTy, UIDGenerator,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct WorldBodies {
@@ -52,6 +56,14 @@ impl WorldBodies {
         self[fqn.file].global_is_extern(fqn.name)
     }
 
+    /// the extern global with the given name, if any file declares one
+    pub fn find_extern(&self, name: Name) -> Option<Fqn> {
+        self.bodies
+            .iter()
+            .find(|(_, bodies)| bodies.global_is_extern(name))
+            .map(|(file, _)| Fqn { file: *file, name })
+    }
+
     pub fn add_file(&mut self, file: FileName, bodies: Bodies) {
         self.bodies.insert(file, bodies);
     }
@@ -62,6 +74,26 @@ impl WorldBodies {
         }
     }
 
+    /// all the global functions marked with `export`
+    pub fn find_exports(&self) -> Vec<Fqn> {
+        self.bodies
+            .iter()
+            .flat_map(|(file, bodies)| {
+                bodies
+                    .global_bodies
+                    .iter()
+                    .filter(|(_, body)| match &bodies[**body] {
+                        Expr::Lambda(lambda) => bodies[*lambda].is_export,
+                        _ => false,
+                    })
+                    .map(|(name, _)| Fqn {
+                        file: *file,
+                        name: *name,
+                    })
+            })
+            .collect()
+    }
+
     pub fn find_comptimes(&self) -> Vec<FQComptime> {
         self.bodies
             .iter()
@@ -105,6 +137,7 @@ pub struct Bodies {
     scope_usages: FxHashMap<ScopeId, Vec<Idx<Stmt>>>,
     lambdas: Arena<Lambda>,
     comptimes: Arena<Comptime>,
+    static_asserts: Vec<StaticAssert>,
     imports: FxHashSet<FileName>,
 }
 
@@ -114,7 +147,11 @@ pub enum Expr {
     IntLiteral(u64),
     FloatLiteral(f64),
     BoolLiteral(bool),
+    NullLiteral,
     StringLiteral(String),
+    /// the contents of a file, from `#embed "file.txt"`.
+    /// this is reference counted because expressions get cloned a lot
+    Embed(Arc<[u8]>),
     CharLiteral(u8),
     Cast {
         expr: Idx<Expr>,
@@ -127,6 +164,12 @@ pub enum Expr {
     Deref {
         pointer: Idx<Expr>,
     },
+    /// `foo.?`, the line and column are printed if the optional turns out to be null
+    Unwrap {
+        optional: Idx<Expr>,
+        line: u32,
+        col: u32,
+    },
     Binary {
         lhs: Idx<Expr>,
         rhs: Idx<Expr>,
@@ -148,7 +191,24 @@ pub enum Expr {
         source: Idx<Expr>,
         index: Idx<Expr>,
     },
+    /// `foo[lo..hi]`, either bound can be left out
+    Slice {
+        source: Idx<Expr>,
+        start: Option<Idx<Expr>>,
+        end: Option<Idx<Expr>>,
+    },
     Paren(Option<Idx<Expr>>),
+    /// either a tuple literal `(5, true)`, or a tuple type `(i32, bool)`
+    Tuple {
+        items: Vec<Idx<Expr>>,
+    },
+    /// `x` in `x, y := foo`, where `tuple` is a hidden local holding `foo`.
+    /// `len` is the amount of names, which has to match the length of the tuple
+    TupleItem {
+        tuple: Idx<Expr>,
+        index: u32,
+        len: u32,
+    },
     Block {
         stmts: Vec<Idx<Stmt>>,
         tail_expr: Option<Idx<Expr>>,
@@ -157,6 +217,9 @@ pub enum Expr {
         condition: Idx<Expr>,
         body: Idx<Expr>,
         else_branch: Option<Idx<Expr>>,
+        /// `x` in `if x := foo { ... }`, where `foo` is the condition.
+        /// the local has no value, it gets the contents of the optional
+        binding: Option<Idx<LocalDef>>,
     },
     While {
         condition: Option<Idx<Expr>>,
@@ -168,6 +231,10 @@ pub enum Expr {
         idx: u32,
         range: TextRange,
     },
+    /// a local or parameter of an enclosing function, read through the closure's environment
+    Capture {
+        idx: u32,
+    },
     Member {
         previous: Idx<Expr>,
         field: NameWithRange,
@@ -175,6 +242,8 @@ pub enum Expr {
     Call {
         callee: Idx<Expr>,
         args: Vec<Idx<Expr>>,
+        /// lines up with `args`, only named arguments like `mode = "w"` have a name
+        arg_names: Vec<Option<NameWithRange>>,
     },
     Lambda(Idx<Lambda>),
     Comptime(Idx<Comptime>),
@@ -185,9 +254,25 @@ pub enum Expr {
         uid: u32,
         ty: Idx<Expr>,
     },
+    OptionalDecl {
+        ty: Idx<Expr>,
+    },
     StructDecl {
         uid: u32,
         members: Vec<(Option<NameWithRange>, Idx<Expr>)>,
+        /// lines up with `members`
+        defaults: Vec<Option<Idx<Expr>>>,
+        repr: StructRepr,
+    },
+    /// all the members of a union start at the same address
+    UnionDecl {
+        uid: u32,
+        members: Vec<(Option<NameWithRange>, Idx<Expr>)>,
+    },
+    /// a set of functions which can be called on any type that has them
+    InterfaceDecl {
+        uid: u32,
+        members: Vec<(Option<NameWithRange>, Idx<Expr>)>,
     },
     StructLiteral {
         ty: Option<Idx<Expr>>,
@@ -196,6 +281,23 @@ pub enum Expr {
     Import(FileName),
 }
 
+/// the layout directives given to a struct, e.g. `struct #packed #align(16) { ... }`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct StructRepr {
+    /// fields aren't padded to their natural alignment
+    pub packed: bool,
+    /// the minimum alignment of the whole struct
+    pub align: Option<u32>,
+    /// the minimum alignment of each field, empty if no field has an `#align`
+    pub field_aligns: Vec<Option<u32>>,
+}
+
+impl StructRepr {
+    pub fn is_default(&self) -> bool {
+        !self.packed && self.align.is_none() && self.field_aligns.is_empty()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Lambda {
     pub params: Vec<Param>,
@@ -203,12 +305,35 @@ pub struct Lambda {
     pub return_ty: Option<Idx<Expr>>,
     pub body: Idx<Expr>,
     pub is_extern: bool,
+    /// whether the parameter list ends in `..` (C variadic arguments)
+    pub is_variadic: bool,
+    /// exported functions keep their name in the final binary so they can be called from C
+    pub is_export: bool,
+    /// whether this lambda has a capture list (`[x, y]`) or refers to the locals of an
+    /// enclosing function.
+    /// closures get passed their environment alongside their parameters
+    pub is_closure: bool,
+    pub captures: Vec<Capture>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Capture {
+    /// the `Local`, `Param` or `Capture` of the enclosing function which is being captured
+    pub value: Idx<Expr>,
+    /// locals in the capture list get copied into the closure,
+    /// everything else is referenced by the closure
+    pub by_value: bool,
+    /// only `:=` locals captured by reference can be changed from inside the closure
+    pub mutable: bool,
+    pub range: TextRange,
 }
 
 #[derive(Debug, Clone)]
 pub struct Param {
     pub name: Option<Name>,
+    /// this is `Missing` for `mode := "r"`, which takes the type of its default value
     pub ty: Idx<Expr>,
+    pub default: Option<Idx<Expr>>,
 }
 
 /// Fully qualified lambda
@@ -224,8 +349,17 @@ pub struct Comptime {
     pub body: Idx<Expr>,
 }
 
+/// `#assert condition, "message";`
+#[derive(Debug, Clone, Copy)]
+pub struct StaticAssert {
+    /// always an `Expr::Comptime` so that it can be evaluated by the comptime machinery
+    pub condition: Idx<Expr>,
+    pub message: Option<Key>,
+    pub range: TextRange,
+}
+
 /// Fully qualified comptime
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, PartialOrd, Ord, Eq)]
 pub struct FQComptime {
     pub file: FileName,
     pub expr: Idx<Expr>,
@@ -256,10 +390,16 @@ pub enum Stmt {
 
 #[derive(Clone)]
 pub struct LocalDef {
+    /// `None` for the hidden local holding the value of `x, y := foo`
+    pub name: Option<Name>,
     pub mutable: bool,
     pub ty: Option<Idx<Expr>>,
     pub value: Option<Idx<Expr>>,
-    pub ast: ast::Define,
+    /// `x : T = ---`, the local starts out with whatever was already in memory
+    /// instead of being zeroed. `value` is always `None` when this is set
+    pub uninit: bool,
+    /// `None` for the hidden local holding the value of `x, y := foo`
+    pub ast: Option<ast::Define>,
     pub range: TextRange,
 }
 
@@ -275,6 +415,7 @@ impl std::fmt::Debug for LocalDef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("LocalDef")
             .field("value", &self.value)
+            .field("uninit", &self.uninit)
             .finish()
     }
 }
@@ -333,6 +474,10 @@ pub enum LoweringDiagnosticKind {
     UndefinedRef { name: Key },
     UndefinedLabel { name: Key },
     NonGlobalExternFunc,
+    NonGlobalExportFunc,
+    ExportWithoutBody,
+    VarArgsNotLast,
+    VarArgsWithBody,
     InvalidEscape,
     TooManyCharsInCharLiteral,
     EmptyCharLiteral,
@@ -343,10 +488,29 @@ pub enum LoweringDiagnosticKind {
     ImportMustEndInDotCapy,
     ImportDoesNotExist { file: String },
     ImportOutsideCWD { file: String },
+    ExpectedEmbedFile,
+    EmbedDoesNotExist { file: String },
+    CouldNotEmbed { file: String, why: String },
     ContinueNonLoop { name: Option<Key> },
     ReturnFromDefer,
     BreakFromDefer,
     ContinueFromDefer,
+    UnknownDirective { name: Key },
+    DirectiveTakesNoArgs { name: Key },
+    UnknownLayoutDirective { name: Key, on_field: bool },
+    InvalidAlign,
+    ExpectedDefineName,
+    UndefinedDefine { name: Key },
+    NonStaticCondition,
+    StaticConditionMismatch,
+    StaticConditionNotBool,
+    TooManyAssertArgs,
+    AssertMessageNotString,
+    CaptureNotLocal { name: Key },
+    GlobalClosure,
+    PositionalArgAfterNamed,
+    UninitOutsideLocal,
+    UninitWithoutTy,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -358,6 +522,7 @@ pub fn lower(
     uid_gen: &mut UIDGenerator,
     interner: &mut Interner,
     mod_dir: &Path,
+    config: &BuildConfig,
     fake_file_system: bool,
 ) -> (Bodies, Vec<LoweringDiagnostic>) {
     let mut ctx = Ctx::new(
@@ -367,16 +532,23 @@ pub fn lower(
         interner,
         tree,
         mod_dir,
+        config,
         fake_file_system,
     );
 
-    for def in root.defs(tree) {
-        ctx.lower_global(
-            def.name(tree),
-            def.ty(tree),
-            def.r#extern(tree).is_some(),
-            def.value(tree),
-        )
+    let decls = config::active_decls(root, tree, ctx.interner, config, &mut ctx.diagnostics);
+
+    for decl in decls {
+        match decl {
+            ast::Decl::Define(def) => ctx.lower_global(
+                def.name(tree),
+                def.ty(tree),
+                def.r#extern(tree).is_some(),
+                def.value(tree),
+            ),
+            ast::Decl::DirectiveDecl(directive) => ctx.lower_directive_decl(directive),
+            ast::Decl::StaticIf(_) => unreachable!("`active_decls` never returns an `#if`"),
+        }
     }
 
     ctx.bodies.shrink_to_fit();
@@ -394,6 +566,15 @@ enum ScopeKind {
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub struct ScopeId(u32);
 
+/// the names visible inside of a function,
+/// put aside while a lambda inside of that function is being lowered
+#[derive(Default)]
+struct Frame {
+    scopes: Vec<FxHashMap<Key, Idx<LocalDef>>>,
+    params: FxHashMap<Key, (u32, ast::Param)>,
+    captures: Vec<(Key, Capture)>,
+}
+
 impl Display for ScopeId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.0.to_string())
@@ -412,11 +593,16 @@ struct Ctx<'a> {
     label_kinds: Vec<ScopeKind>,
     label_gen: UIDGenerator,
     params: FxHashMap<Key, (u32, ast::Param)>,
+    captures: Vec<(Key, Capture)>,
+    /// the functions enclosing the current one, innermost last
+    enclosing: Vec<Frame>,
     mod_dir: &'a Path,
+    config: &'a BuildConfig,
     fake_file_system: bool, // used for importing files in tests
 }
 
 impl<'a> Ctx<'a> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         file_name: &'a std::path::Path,
         index: &'a Index,
@@ -424,6 +610,7 @@ impl<'a> Ctx<'a> {
         interner: &'a mut Interner,
         tree: &'a SyntaxTree,
         mod_dir: &'a Path,
+        config: &'a BuildConfig,
         fake_file_system: bool,
     ) -> Self {
         Self {
@@ -440,6 +627,7 @@ impl<'a> Ctx<'a> {
                 scope_usages: FxHashMap::default(),
                 lambdas: Arena::new(),
                 comptimes: Arena::new(),
+                static_asserts: Vec::new(),
                 imports: FxHashSet::default(),
             },
             file_name,
@@ -452,7 +640,10 @@ impl<'a> Ctx<'a> {
             label_kinds: Vec::new(),
             label_gen: UIDGenerator::default(),
             params: FxHashMap::default(),
+            captures: Vec::new(),
+            enclosing: Vec::new(),
             mod_dir,
+            config,
             fake_file_system,
         }
     }
@@ -505,14 +696,101 @@ impl<'a> Ctx<'a> {
         self.bodies.global_bodies.insert(name, body);
     }
 
+    fn lower_directive_decl(&mut self, directive: ast::DirectiveDecl) {
+        let Some(name) = directive.name(self.tree) else {
+            return;
+        };
+
+        if name.text(self.tree) != "assert" {
+            self.diagnostics.push(LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::UnknownDirective {
+                    name: self.interner.intern(name.text(self.tree)),
+                },
+                range: directive.range(self.tree),
+            });
+            return;
+        }
+
+        let mut args = directive.args(self.tree);
+
+        let condition = args.next();
+        let message = args.next();
+
+        if let Some(extra) = args.next() {
+            self.diagnostics.push(LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::TooManyAssertArgs,
+                range: extra.range(self.tree),
+            });
+        }
+
+        let message = match message {
+            Some(ast::Expr::StringLiteral(string_literal)) => {
+                match self.lower_string_literal(string_literal) {
+                    Expr::StringLiteral(text) => Some(self.interner.intern(&text)),
+                    _ => unreachable!(),
+                }
+            }
+            Some(other) => {
+                self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::AssertMessageNotString,
+                    range: other.range(self.tree),
+                });
+                None
+            }
+            None => None,
+        };
+
+        // the condition is wrapped in a comptime block so that it gets evaluated
+        // by the same machinery as every other comptime block
+        let body = self.lower_expr(condition);
+        let comptime = self.bodies.comptimes.alloc(Comptime { body });
+        let condition_expr = self.bodies.exprs.alloc(Expr::Comptime(comptime));
+        self.bodies.expr_ranges.insert(
+            condition_expr,
+            condition.map_or(directive.range(self.tree), |c| c.range(self.tree)),
+        );
+
+        self.bodies.static_asserts.push(StaticAssert {
+            condition: condition_expr,
+            message,
+            range: directive.range(self.tree),
+        });
+    }
+
     fn lower_lambda(&mut self, lambda: ast::Lambda, allow_extern: bool) -> Expr {
         let old_labels = mem::take(&mut self.label_kinds);
 
         let mut params = Vec::new();
         let mut param_keys = FxHashMap::default();
         let mut param_type_ranges = Vec::new();
+        let mut is_variadic = false;
 
         if let Some(param_list) = lambda.param_list(self.tree) {
+            for var_args in param_list.var_args(self.tree) {
+                let range = var_args.range(self.tree);
+
+                // `..` has to come after every other parameter, and only once
+                let is_last = param_list
+                    .params(self.tree)
+                    .all(|param| param.range(self.tree).start() < range.start())
+                    && !is_variadic;
+
+                if !is_last {
+                    self.diagnostics.push(LoweringDiagnostic {
+                        kind: LoweringDiagnosticKind::VarArgsNotLast,
+                        range,
+                    });
+                } else if lambda.body(self.tree).is_some() {
+                    // a function with a body would have no way to read the extra arguments
+                    self.diagnostics.push(LoweringDiagnostic {
+                        kind: LoweringDiagnosticKind::VarArgsWithBody,
+                        range,
+                    });
+                }
+
+                is_variadic = true;
+            }
+
             for (idx, param) in param_list.params(self.tree).enumerate() {
                 let key = param
                     .name(self.tree)
@@ -523,9 +801,14 @@ impl<'a> Ctx<'a> {
 
                 let ty = self.lower_expr(ty.and_then(|ty| ty.expr(self.tree)));
 
+                let default = param
+                    .default_value(self.tree)
+                    .map(|default| self.lower_expr(Some(default)));
+
                 params.push(Param {
                     name: key.map(Name),
                     ty,
+                    default,
                 });
 
                 if let Some(key) = key {
@@ -548,15 +831,92 @@ impl<'a> Ctx<'a> {
             }
         }
 
+        let mut is_export = false;
+        if let Some(export) = lambda.export(self.tree) {
+            if !allow_extern {
+                self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::NonGlobalExportFunc,
+                    range: export.range(self.tree),
+                });
+            } else if lambda.body(self.tree).is_none() {
+                self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::ExportWithoutBody,
+                    range: export.range(self.tree),
+                });
+            } else {
+                is_export = true;
+            }
+        }
+
+        let capture_list = lambda.capture_list(self.tree);
+
+        if let Some(capture_list) = capture_list {
+            if allow_extern && lambda.body(self.tree).is_some() {
+                self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::GlobalClosure,
+                    range: capture_list.range(self.tree),
+                });
+            }
+        }
+
+        // the capture list is resolved from outside of the lambda
+        let mut by_value = Vec::new();
+        for ident in capture_list
+            .into_iter()
+            .flat_map(|capture_list| capture_list.names(self.tree))
+        {
+            let name = self.interner.intern(ident.text(self.tree));
+            let range = ident.range(self.tree);
+
+            if by_value.iter().any(|(key, _)| *key == name) {
+                continue;
+            }
+
+            let value = if let Some(def) = self.look_up_in_current_scope(name) {
+                Expr::Local(def)
+            } else if let Some((idx, ast)) = self.look_up_param(name) {
+                Expr::Param {
+                    idx,
+                    range: ast.range(self.tree),
+                }
+            } else if let Some(idx) = self.look_up_capture(name, range) {
+                Expr::Capture { idx }
+            } else {
+                self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::CaptureNotLocal { name },
+                    range,
+                });
+                continue;
+            };
+
+            let value = self.bodies.exprs.alloc(value);
+            self.bodies.expr_ranges.insert(value, range);
+
+            by_value.push((
+                name,
+                Capture {
+                    value,
+                    by_value: true,
+                    mutable: false,
+                    range,
+                },
+            ));
+        }
+
         // todo: when parameter types are added, self.params should be cloned, and then updated in
         // place
-        let old_params = mem::replace(&mut self.params, param_keys);
-        let old_scopes = mem::take(&mut self.scopes);
+        self.enclosing.push(Frame {
+            scopes: mem::take(&mut self.scopes),
+            params: mem::replace(&mut self.params, param_keys),
+            captures: mem::replace(&mut self.captures, by_value),
+        });
 
         let body = self.lower_expr(lambda.body(self.tree));
 
-        self.params = old_params;
-        self.scopes = old_scopes;
+        let frame = self.enclosing.pop().unwrap();
+        self.params = frame.params;
+        self.scopes = frame.scopes;
+        let captures = mem::replace(&mut self.captures, frame.captures);
         self.label_kinds = old_labels;
 
         Expr::Lambda(self.bodies.lambdas.alloc(Lambda {
@@ -564,18 +924,27 @@ impl<'a> Ctx<'a> {
             params_range: lambda.param_list(self.tree).unwrap().range(self.tree),
             return_ty,
             is_extern: lambda.r#extern(self.tree).is_some(),
+            is_variadic,
+            is_export,
+            is_closure: capture_list.is_some() || !captures.is_empty(),
+            captures: captures.into_iter().map(|(_, capture)| capture).collect(),
             body,
         }))
     }
 
     fn lower_comptime(&mut self, comptime_expr: ast::ComptimeExpr) -> Expr {
+        // comptime blocks run while compiling, so they can't see any runtime locals
         let old_params = mem::take(&mut self.params);
         let old_scopes = mem::take(&mut self.scopes);
+        let old_captures = mem::take(&mut self.captures);
+        let old_enclosing = mem::take(&mut self.enclosing);
 
         let body = self.lower_expr(comptime_expr.body(self.tree));
 
         self.params = old_params;
         self.scopes = old_scopes;
+        self.captures = old_captures;
+        self.enclosing = old_enclosing;
 
         Expr::Comptime(self.bodies.comptimes.alloc(Comptime { body }))
     }
@@ -592,7 +961,74 @@ impl<'a> Ctx<'a> {
             ast::Stmt::Break(break_stmt) => self.lower_break(break_stmt),
             ast::Stmt::Continue(continue_stmt) => self.lower_continue(continue_stmt),
             ast::Stmt::Defer(defer_stmt) => self.lower_defer(defer_stmt),
+            ast::Stmt::Destructure(_) => unreachable!("destructures are lowered by blocks"),
+        }
+    }
+
+    /// `x, y := foo` becomes
+    /// ```text
+    /// l0 :: foo;
+    /// x := l0.0;
+    /// y := l0.1;
+    /// ```
+    /// where `l0` can't be named
+    fn lower_destructure(&mut self, destructure: ast::Destructure) -> Vec<Stmt> {
+        let value = destructure.value(self.tree);
+        let value_range = value.map(|value| value.range(self.tree));
+        let value = self.lower_expr(value);
+
+        let tuple = self.bodies.local_defs.alloc(LocalDef {
+            name: None,
+            mutable: false,
+            ty: None,
+            value: Some(value),
+            uninit: false,
+            ast: None,
+            range: destructure.range(self.tree),
+        });
+
+        let mut stmts = vec![Stmt::LocalDef(tuple)];
+
+        let names = destructure.names(self.tree).collect::<Vec<_>>();
+        let len = names.len() as u32;
+
+        for (index, name) in names.into_iter().enumerate() {
+            let range = name.range(self.tree);
+
+            let tuple = self.bodies.exprs.alloc(Expr::Local(tuple));
+            self.bodies
+                .expr_ranges
+                .insert(tuple, value_range.unwrap_or(range));
+
+            let item = self.bodies.exprs.alloc(Expr::TupleItem {
+                tuple,
+                index: index as u32,
+                len,
+            });
+            self.bodies.expr_ranges.insert(item, range);
+
+            let key = name
+                .name(self.tree)
+                .map(|ident| self.interner.intern(ident.text(self.tree)));
+
+            let id = self.bodies.local_defs.alloc(LocalDef {
+                name: key.map(Name),
+                mutable: true,
+                ty: None,
+                value: Some(item),
+                uninit: false,
+                ast: Some(ast::Define::Variable(name)),
+                range,
+            });
+
+            if let Some(key) = key {
+                self.insert_into_current_scope(key, id);
+            }
+
+            stmts.push(Stmt::LocalDef(id));
         }
+
+        stmts
     }
 
     fn lower_return(&mut self, return_stmt: ast::ReturnStmt) -> Stmt {
@@ -766,19 +1202,34 @@ impl<'a> Ctx<'a> {
             .and_then(|ty| ty.expr(self.tree))
             .map(|expr| self.lower_expr(Some(expr)));
 
-        let value = local_def
-            .value(self.tree)
+        let value = local_def.value(self.tree);
+
+        let uninit = matches!(value, Some(ast::Expr::UninitLiteral(_)));
+        if uninit && ty.is_none() {
+            self.diagnostics.push(LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::UninitWithoutTy,
+                range: value.unwrap().range(self.tree),
+            });
+        }
+
+        let value = value
+            .filter(|_| !uninit)
             .map(|expr| self.lower_expr(Some(expr)));
+        let name = local_def
+            .name(self.tree)
+            .map(|ident| self.interner.intern(ident.text(self.tree)));
+
         let id = self.bodies.local_defs.alloc(LocalDef {
+            name: name.map(Name),
             mutable: matches!(local_def, ast::Define::Variable(_)),
             ty,
             value,
-            ast: local_def,
+            uninit,
+            ast: Some(local_def),
             range: local_def.range(self.tree),
         });
 
-        if let Some(ident) = local_def.name(self.tree) {
-            let name = self.interner.intern(ident.text(self.tree));
+        if let Some(name) = name {
             self.insert_into_current_scope(name, id);
         }
 
@@ -825,11 +1276,18 @@ impl<'a> Ctx<'a> {
                 ast::Expr::Cast(cast_expr) => self.lower_cast_expr(cast_expr),
                 ast::Expr::Ref(ref_expr) => self.lower_ref_expr(ref_expr),
                 ast::Expr::Deref(deref_expr) => self.lower_deref_expr(deref_expr),
+                ast::Expr::Unwrap(unwrap_expr) => self.lower_unwrap_expr(unwrap_expr),
                 ast::Expr::Binary(binary_expr) => self.lower_binary_expr(binary_expr),
                 ast::Expr::Unary(unary_expr) => self.lower_unary_expr(unary_expr),
                 ast::Expr::ArrayDecl(array_decl) => self.lower_array_decl(array_decl),
                 ast::Expr::ArrayLiteral(array_lit) => self.lower_array_literal(array_lit),
                 ast::Expr::Paren(paren_expr) => self.lower_paren_expr(paren_expr),
+                ast::Expr::Tuple(tuple_expr) => Expr::Tuple {
+                    items: tuple_expr
+                        .items(self.tree)
+                        .map(|item| self.lower_expr(Some(item)))
+                        .collect(),
+                },
                 ast::Expr::Block(block) => return self.lower_block(block, true),
                 ast::Expr::If(if_expr) => self.lower_if(if_expr),
                 ast::Expr::While(while_expr) => {
@@ -838,21 +1296,45 @@ impl<'a> Ctx<'a> {
                 }
                 ast::Expr::Call(call) => self.lower_call(call),
                 ast::Expr::IndexExpr(index_expr) => self.lower_index_expr(index_expr),
+                ast::Expr::SliceExpr(slice_expr) => self.lower_slice_expr(slice_expr),
                 ast::Expr::VarRef(var_ref) => self.lower_var_ref(var_ref),
                 ast::Expr::Path(path) => self.lower_path(path),
                 ast::Expr::IntLiteral(int_literal) => self.lower_int_literal(int_literal),
                 ast::Expr::FloatLiteral(float_literal) => self.lower_float_literal(float_literal),
                 ast::Expr::BoolLiteral(bool_literal) => self.lower_bool_literal(bool_literal),
+                ast::Expr::NullLiteral(_) => Expr::NullLiteral,
+                ast::Expr::UninitLiteral(uninit) => {
+                    // locals are the only things which can be left uninitialized,
+                    // and they're handled in `lower_local_define`
+                    self.diagnostics.push(LoweringDiagnostic {
+                        kind: LoweringDiagnosticKind::UninitOutsideLocal,
+                        range: uninit.range(self.tree),
+                    });
+                    Expr::Missing
+                }
                 ast::Expr::CharLiteral(char_literal) => self.lower_char_literal(char_literal),
                 ast::Expr::StringLiteral(string_literal) => {
                     self.lower_string_literal(string_literal)
                 }
                 ast::Expr::Distinct(distinct) => self.lower_distinct(distinct),
+                ast::Expr::OptionalDecl(optional_decl) => {
+                    let ty = optional_decl
+                        .ty(self.tree)
+                        .and_then(|ty| ty.expr(self.tree));
+                    Expr::OptionalDecl {
+                        ty: self.lower_expr(ty),
+                    }
+                }
                 ast::Expr::Lambda(lambda) => self.lower_lambda(lambda, false),
                 ast::Expr::StructDecl(struct_decl) => self.lower_struct_declaration(struct_decl),
+                ast::Expr::UnionDecl(union_decl) => self.lower_union_declaration(union_decl),
+                ast::Expr::InterfaceDecl(interface_decl) => {
+                    self.lower_interface_declaration(interface_decl)
+                }
                 ast::Expr::StructLiteral(struct_lit) => self.lower_struct_literal(struct_lit),
                 ast::Expr::Import(import_expr) => self.lower_import(import_expr),
                 ast::Expr::Comptime(comptime_expr) => self.lower_comptime(comptime_expr),
+                ast::Expr::Directive(directive) => self.lower_directive(directive),
             },
             None,
         )
@@ -880,6 +1362,22 @@ impl<'a> Ctx<'a> {
         Expr::Deref { pointer }
     }
 
+    fn lower_unwrap_expr(&mut self, unwrap_expr: ast::UnwrapExpr) -> Expr {
+        let optional = self.lower_expr(unwrap_expr.optional(self.tree));
+
+        // codegen doesn't have the source text, so the location of the `?` is found here
+        let start = usize::from(unwrap_expr.range(self.tree).end()) - 1;
+        let before = &self.tree.root().text(self.tree)[..start];
+        let line = before.matches('\n').count() as u32 + 1;
+        let col = (start - before.rfind('\n').map_or(0, |idx| idx + 1)) as u32 + 1;
+
+        Expr::Unwrap {
+            optional,
+            line,
+            col,
+        }
+    }
+
     fn lower_distinct(&mut self, distinct: ast::Distinct) -> Expr {
         let ty = self.lower_expr(distinct.ty(self.tree).and_then(|ty| ty.expr(self.tree)));
 
@@ -890,9 +1388,62 @@ impl<'a> Ctx<'a> {
     }
 
     fn lower_struct_declaration(&mut self, struct_decl: ast::StructDecl) -> Expr {
+        let mut repr = StructRepr::default();
+
+        for directive in struct_decl.directives(self.tree) {
+            let Some(name) = directive.name(self.tree) else {
+                continue;
+            };
+
+            match name.text(self.tree) {
+                "packed" => {
+                    if directive.arg_list(self.tree).is_some() {
+                        self.diagnostics.push(LoweringDiagnostic {
+                            kind: LoweringDiagnosticKind::DirectiveTakesNoArgs {
+                                name: self.interner.intern("packed"),
+                            },
+                            range: directive.range(self.tree),
+                        });
+                    }
+                    repr.packed = true;
+                }
+                "align" => repr.align = self.lower_align_directive(directive),
+                other => self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::UnknownLayoutDirective {
+                        name: self.interner.intern(other),
+                        on_field: false,
+                    },
+                    range: directive.range(self.tree),
+                }),
+            }
+        }
+
+        let mut field_aligns = Vec::new();
+        let mut defaults = Vec::new();
+
         let members = struct_decl
             .members(self.tree)
             .map(|member| {
+                let mut field_align = None;
+                for directive in member.directives(self.tree) {
+                    let Some(name) = directive.name(self.tree) else {
+                        continue;
+                    };
+
+                    if name.text(self.tree) == "align" {
+                        field_align = self.lower_align_directive(directive);
+                    } else {
+                        self.diagnostics.push(LoweringDiagnostic {
+                            kind: LoweringDiagnosticKind::UnknownLayoutDirective {
+                                name: self.interner.intern(name.text(self.tree)),
+                                on_field: true,
+                            },
+                            range: directive.range(self.tree),
+                        });
+                    }
+                }
+                field_aligns.push(field_align);
+
                 let name = member.name(self.tree).map(|ident| NameWithRange {
                     name: Name(self.interner.intern(ident.text(self.tree))),
                     range: ident.range(self.tree),
@@ -900,14 +1451,97 @@ impl<'a> Ctx<'a> {
 
                 let ty = self.lower_expr(member.ty(self.tree).and_then(|ty| ty.expr(self.tree)));
 
+                defaults.push(
+                    member
+                        .default_value(self.tree)
+                        .map(|value| self.lower_expr(Some(value))),
+                );
+
                 (name, ty)
             })
             .collect();
 
+        if field_aligns.iter().any(Option::is_some) {
+            repr.field_aligns = field_aligns;
+        }
+
         Expr::StructDecl {
             uid: self.uid_gen.generate_unique_id(),
             members,
+            defaults,
+            repr,
+        }
+    }
+
+    fn lower_union_declaration(&mut self, union_decl: ast::UnionDecl) -> Expr {
+        let members = union_decl
+            .members(self.tree)
+            .map(|member| {
+                let name = member.name(self.tree).map(|ident| NameWithRange {
+                    name: Name(self.interner.intern(ident.text(self.tree))),
+                    range: ident.range(self.tree),
+                });
+
+                let ty = self.lower_expr(member.ty(self.tree).and_then(|ty| ty.expr(self.tree)));
+
+                (name, ty)
+            })
+            .collect();
+
+        Expr::UnionDecl {
+            uid: self.uid_gen.generate_unique_id(),
+            members,
+        }
+    }
+
+    fn lower_interface_declaration(&mut self, interface_decl: ast::InterfaceDecl) -> Expr {
+        let members = interface_decl
+            .members(self.tree)
+            .map(|member| {
+                let name = member.name(self.tree).map(|ident| NameWithRange {
+                    name: Name(self.interner.intern(ident.text(self.tree))),
+                    range: ident.range(self.tree),
+                });
+
+                let ty = self.lower_expr(member.ty(self.tree).and_then(|ty| ty.expr(self.tree)));
+
+                (name, ty)
+            })
+            .collect();
+
+        Expr::InterfaceDecl {
+            uid: self.uid_gen.generate_unique_id(),
+            members,
+        }
+    }
+
+    /// `#align(N)` takes a single integer literal which has to be a power of two
+    fn lower_align_directive(&mut self, directive: ast::Directive) -> Option<u32> {
+        let mut args = directive
+            .arg_list(self.tree)
+            .into_iter()
+            .flat_map(|arg_list| arg_list.args(self.tree));
+
+        let align = match (
+            args.next().and_then(|arg| arg.value(self.tree)),
+            args.next(),
+        ) {
+            (Some(ast::Expr::IntLiteral(int_literal)), None) => int_literal
+                .value(self.tree)
+                .and_then(|value| int_value(value, self.tree))
+                .and_then(|value| u32::try_from(value).ok())
+                .filter(|value| value.is_power_of_two()),
+            _ => None,
+        };
+
+        if align.is_none() {
+            self.diagnostics.push(LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::InvalidAlign,
+                range: directive.range(self.tree),
+            });
         }
+
+        align
     }
 
     fn lower_struct_literal(&mut self, struct_lit: ast::StructLiteral) -> Expr {
@@ -998,38 +1632,12 @@ impl<'a> Ctx<'a> {
         }
 
         let file = if !self.fake_file_system {
-            let file = std::path::Path::new(&file);
-
-            let file = env::current_dir()
-                .unwrap()
-                .join(self.file_name)
-                .join("..")
-                .join(file)
-                .clean();
-
-            if !file.is_file() {
-                self.diagnostics.push(LoweringDiagnostic {
-                    kind: LoweringDiagnosticKind::ImportDoesNotExist {
-                        file: file.to_string_lossy().to_string(),
-                    },
-                    range: file_name.range(self.tree),
-                });
-                return Expr::Missing;
-            }
-
-            if !file.is_sub_dir_of(self.mod_dir)
-                && !file.is_sub_dir_of(&env::current_dir().unwrap())
-            {
-                self.diagnostics.push(LoweringDiagnostic {
-                    kind: LoweringDiagnosticKind::ImportOutsideCWD {
-                        file: file.to_string_lossy().to_string(),
-                    },
-                    range: file_name.range(self.tree),
-                });
-                return Expr::Missing;
+            match self.resolve_relative_file(&file, file_name.range(self.tree), |file| {
+                LoweringDiagnosticKind::ImportDoesNotExist { file }
+            }) {
+                Some(file) => file,
+                None => return Expr::Missing,
             }
-
-            file
         } else {
             file.into()
         };
@@ -1043,9 +1651,106 @@ impl<'a> Ctx<'a> {
         Expr::Import(file_name)
     }
 
-    fn lower_binary_expr(&mut self, binary_expr: ast::BinaryExpr) -> Expr {
-        let lhs = self.lower_expr(binary_expr.lhs(self.tree));
-        let rhs = self.lower_expr(binary_expr.rhs(self.tree));
+    /// finds a file relative to the file currently being lowered.
+    /// this makes sure that the file exists, and that it's in either the
+    /// current working directory or the mod directory.
+    /// `does_not_exist` creates the diagnostic for when the file can't be found
+    fn resolve_relative_file(
+        &mut self,
+        file: &str,
+        range: TextRange,
+        does_not_exist: impl FnOnce(String) -> LoweringDiagnosticKind,
+    ) -> Option<PathBuf> {
+        let file = env::current_dir()
+            .unwrap()
+            .join(self.file_name)
+            .join("..")
+            .join(file)
+            .clean();
+
+        if !file.is_file() {
+            self.diagnostics.push(LoweringDiagnostic {
+                kind: does_not_exist(file.to_string_lossy().to_string()),
+                range,
+            });
+            return None;
+        }
+
+        if !file.is_sub_dir_of(self.mod_dir) && !file.is_sub_dir_of(&env::current_dir().unwrap()) {
+            self.diagnostics.push(LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::ImportOutsideCWD {
+                    file: file.to_string_lossy().to_string(),
+                },
+                range,
+            });
+            return None;
+        }
+
+        Some(file)
+    }
+
+    fn lower_embed(&mut self, directive: ast::Directive) -> Expr {
+        let Some(file_name) = directive.file(self.tree) else {
+            self.diagnostics.push(LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::ExpectedEmbedFile,
+                range: directive.range(self.tree),
+            });
+            return Expr::Missing;
+        };
+
+        let old_diags_len = self.diagnostics.len();
+        let file = match self.lower_string_literal(file_name) {
+            Expr::StringLiteral(text) => text.replace(['/', '\\'], std::path::MAIN_SEPARATOR_STR),
+            _ => unreachable!(),
+        };
+        if self.diagnostics.len() != old_diags_len {
+            return Expr::Missing;
+        }
+
+        // unlike imports, embedded files are always read from the real file system
+        let Some(file) = self.resolve_relative_file(&file, file_name.range(self.tree), |file| {
+            LoweringDiagnosticKind::EmbedDoesNotExist { file }
+        }) else {
+            return Expr::Missing;
+        };
+
+        match std::fs::read(&file) {
+            Ok(contents) => Expr::Embed(contents.into()),
+            Err(why) => {
+                self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::CouldNotEmbed {
+                        file: file.to_string_lossy().to_string(),
+                        why: why.to_string(),
+                    },
+                    range: file_name.range(self.tree),
+                });
+                Expr::Missing
+            }
+        }
+    }
+
+    fn lower_directive(&mut self, directive: ast::Directive) -> Expr {
+        if directive
+            .name(self.tree)
+            .is_some_and(|name| name.text(self.tree) == "embed")
+        {
+            return self.lower_embed(directive);
+        }
+
+        match config::eval_directive(directive, self.tree, self.interner, self.config) {
+            Ok(ConfigValue::Bool(value)) => Expr::BoolLiteral(value),
+            Ok(ConfigValue::Int(value)) => Expr::IntLiteral(value),
+            Ok(ConfigValue::String(value)) => Expr::StringLiteral(value),
+            Err(diagnostic) => {
+                self.diagnostics.push(diagnostic);
+                Expr::Missing
+            }
+        }
+    }
+
+    fn lower_binary_expr(&mut self, binary_expr: ast::BinaryExpr) -> Expr {
+        let lhs = self.lower_expr(binary_expr.lhs(self.tree));
+        let rhs = self.lower_expr(binary_expr.rhs(self.tree));
 
         let op = match binary_expr.op(self.tree) {
             Some(ast::BinaryOp::Add(_)) => BinaryOp::Add,
@@ -1139,6 +1844,13 @@ impl<'a> Ctx<'a> {
         let mut stmts = Vec::new();
 
         for stmt in block.stmts(self.tree) {
+            if let ast::Stmt::Destructure(destructure) = stmt {
+                for statement in self.lower_destructure(destructure) {
+                    stmts.push(self.bodies.stmts.alloc(statement));
+                }
+                continue;
+            }
+
             let statement = self.lower_stmt(stmt);
 
             let label_id = match statement {
@@ -1173,7 +1885,37 @@ impl<'a> Ctx<'a> {
     }
 
     fn lower_if(&mut self, if_expr: ast::IfExpr) -> Expr {
-        let condition = self.lower_expr(if_expr.condition(self.tree));
+        let binding = if_expr.binding(self.tree);
+
+        let condition = match binding {
+            Some(binding) => self.lower_expr(binding.value(self.tree)),
+            None => self.lower_expr(if_expr.condition(self.tree)),
+        };
+
+        // the binding can only be seen by the body
+        self.create_new_child_scope();
+
+        let binding = binding.map(|binding| {
+            let name = binding
+                .name(self.tree)
+                .map(|ident| self.interner.intern(ident.text(self.tree)));
+
+            let id = self.bodies.local_defs.alloc(LocalDef {
+                name: name.map(Name),
+                mutable: true,
+                ty: None,
+                value: None,
+                uninit: false,
+                ast: Some(ast::Define::Variable(binding)),
+                range: binding.range(self.tree),
+            });
+
+            if let Some(name) = name {
+                self.insert_into_current_scope(name, id);
+            }
+
+            id
+        });
 
         let body = if let Some(ast::Expr::Block(body)) = if_expr.body(self.tree) {
             let range = body.range(self.tree);
@@ -1188,6 +1930,8 @@ impl<'a> Ctx<'a> {
             self.bodies.exprs.alloc(Expr::Missing)
         };
 
+        self.destroy_current_scope();
+
         let else_branch = if let Some(else_branch) = if_expr.else_branch(self.tree) {
             Some(self.lower_expr(else_branch.body(self.tree)))
         } else {
@@ -1198,6 +1942,7 @@ impl<'a> Ctx<'a> {
             condition,
             body,
             else_branch,
+            binding,
         }
     }
 
@@ -1237,15 +1982,38 @@ impl<'a> Ctx<'a> {
         let callee = self.lower_expr(call.callee(self.tree));
 
         let mut args = Vec::new();
+        let mut arg_names = Vec::new();
 
         if let Some(arg_list) = call.arg_list(self.tree) {
+            let mut had_named = false;
+
             for arg in arg_list.args(self.tree) {
+                let name = arg.name(self.tree).map(|ident| NameWithRange {
+                    name: Name(self.interner.intern(ident.text(self.tree))),
+                    range: ident.range(self.tree),
+                });
+
+                // `f(a = 1, 2)` has no way of knowing where `2` should go
+                if name.is_some() {
+                    had_named = true;
+                } else if had_named {
+                    self.diagnostics.push(LoweringDiagnostic {
+                        kind: LoweringDiagnosticKind::PositionalArgAfterNamed,
+                        range: arg.range(self.tree),
+                    });
+                }
+
                 let expr = self.lower_expr(arg.value(self.tree));
                 args.push(expr);
+                arg_names.push(name);
             }
         }
 
-        Expr::Call { callee, args }
+        Expr::Call {
+            callee,
+            args,
+            arg_names,
+        }
     }
 
     fn lower_index_expr(&mut self, index_expr: ast::IndexExpr) -> Expr {
@@ -1264,6 +2032,25 @@ impl<'a> Ctx<'a> {
         }
     }
 
+    fn lower_slice_expr(&mut self, slice_expr: ast::SliceExpr) -> Expr {
+        let array = match slice_expr.array(self.tree) {
+            Some(array) => self.lower_expr(array.value(self.tree)),
+            None => unreachable!(),
+        };
+        let start = slice_expr
+            .start(self.tree)
+            .map(|start| self.lower_expr(start.value(self.tree)));
+        let end = slice_expr
+            .end(self.tree)
+            .map(|end| self.lower_expr(end.value(self.tree)));
+
+        Expr::Slice {
+            source: array,
+            start,
+            end,
+        }
+    }
+
     fn lower_path(&mut self, path: ast::Path) -> Expr {
         let field = match path.field_name(self.tree) {
             Some(field) => field,
@@ -1301,6 +2088,10 @@ impl<'a> Ctx<'a> {
             };
         }
 
+        if let Some(idx) = self.look_up_capture(ident_name, ident.range(self.tree)) {
+            return Expr::Capture { idx };
+        }
+
         let name = Name(ident_name);
         if self.index.has_definition(name) {
             return Expr::LocalGlobal(NameWithRange {
@@ -1328,70 +2119,15 @@ impl<'a> Ctx<'a> {
             return Expr::Missing;
         };
 
-        match value {
-            ast::IntValue::Dec(dec) => {
-                let value = dec.text(self.tree).replace('_', "");
-                let mut value = value.split(['e', 'E']);
-
-                // there will always be a first part
-                let Ok(base) = value.next().unwrap().parse::<u64>() else {
-                    self.diagnostics.push(LoweringDiagnostic {
-                        kind: LoweringDiagnosticKind::OutOfRangeIntLiteral,
-                        range: int_literal.range(self.tree),
-                    });
-                    return Expr::Missing;
-                };
-
-                let val = if let Some(e) = value.next() {
-                    let Some(result) = e
-                        .parse()
-                        .ok()
-                        .and_then(|e| 10_u64.checked_pow(e))
-                        .and_then(|e| base.checked_mul(e))
-                    else {
-                        self.diagnostics.push(LoweringDiagnostic {
-                            kind: LoweringDiagnosticKind::OutOfRangeIntLiteral,
-                            range: int_literal.range(self.tree),
-                        });
-                        return Expr::Missing;
-                    };
-
-                    result
-                } else {
-                    base
-                };
-
-                Expr::IntLiteral(val)
-            }
-            ast::IntValue::Hex(hex) => {
-                let value = hex.text(self.tree).strip_prefix("0x").unwrap();
-
-                match u64::from_str_radix(value, 16) {
-                    Ok(value) => Expr::IntLiteral(value),
-                    Err(_) => {
-                        self.diagnostics.push(LoweringDiagnostic {
-                            kind: LoweringDiagnosticKind::OutOfRangeIntLiteral,
-                            range: int_literal.range(self.tree),
-                        });
-
-                        Expr::Missing
-                    }
-                }
-            }
-            ast::IntValue::Bin(bin) => {
-                let value = bin.text(self.tree).strip_prefix("0b").unwrap();
-
-                match u64::from_str_radix(value, 2) {
-                    Ok(value) => Expr::IntLiteral(value),
-                    Err(_) => {
-                        self.diagnostics.push(LoweringDiagnostic {
-                            kind: LoweringDiagnosticKind::OutOfRangeIntLiteral,
-                            range: int_literal.range(self.tree),
-                        });
+        match int_value(value, self.tree) {
+            Some(value) => Expr::IntLiteral(value),
+            None => {
+                self.diagnostics.push(LoweringDiagnostic {
+                    kind: LoweringDiagnosticKind::OutOfRangeIntLiteral,
+                    range: int_literal.range(self.tree),
+                });
 
-                        Expr::Missing
-                    }
-                }
+                Expr::Missing
             }
         }
     }
@@ -1434,20 +2170,9 @@ impl<'a> Ctx<'a> {
                     let escape_char = chars.next().unwrap();
                     debug_assert!(chars.next().is_none());
 
-                    match escape_char {
-                        '0' => text.push('\0'),   // null
-                        'a' => text.push('\x07'), // bell (BEL)
-                        'b' => text.push('\x08'), // backspace
-                        'n' => text.push('\n'),   // line feed (new line)
-                        'f' => text.push('\x0C'), // form feed (new page)
-                        'r' => text.push('\r'),   // carraige return
-                        't' => text.push('\t'),   // horizontal tab
-                        'v' => text.push('\x0B'), // vertical tab
-                        'e' => text.push('\x1B'), // escape
-                        '"' => text.push('"'),
-                        '\'' => text.push('\''),
-                        '\\' => text.push('\\'),
-                        _ => self.diagnostics.push(LoweringDiagnostic {
+                    match unescape(escape_char) {
+                        Some(ch) => text.push(ch),
+                        None => self.diagnostics.push(LoweringDiagnostic {
                             kind: LoweringDiagnosticKind::InvalidEscape,
                             range: escape.range(self.tree),
                         }),
@@ -1484,20 +2209,9 @@ impl<'a> Ctx<'a> {
                     let escape_char = chars.next().unwrap();
                     debug_assert!(chars.next().is_none());
 
-                    match escape_char {
-                        '0' => text.push('\0'),   // null
-                        'a' => text.push('\x07'), // bell (BEL)
-                        'b' => text.push('\x08'), // backspace
-                        'n' => text.push('\n'),   // line feed (new line)
-                        'f' => text.push('\x0C'), // form feed (new page)
-                        'r' => text.push('\r'),   // carraige return
-                        't' => text.push('\t'),   // horizontal tab
-                        'v' => text.push('\x0B'), // vertical tab
-                        'e' => text.push('\x1B'), // escape
-                        '\'' => text.push('\''),
-                        '"' => text.push('"'),
-                        '\\' => text.push('\\'),
-                        _ => self.diagnostics.push(LoweringDiagnostic {
+                    match unescape(escape_char) {
+                        Some(ch) => text.push(ch),
+                        None => self.diagnostics.push(LoweringDiagnostic {
                             kind: LoweringDiagnosticKind::InvalidEscape,
                             range: escape.range(self.tree),
                         }),
@@ -1566,6 +2280,67 @@ impl<'a> Ctx<'a> {
         self.params.get(&name).copied()
     }
 
+    /// looks for a local or parameter of an enclosing function,
+    /// capturing it into every lambda in between
+    fn look_up_capture(&mut self, name: Key, range: TextRange) -> Option<u32> {
+        self.capture_into(self.enclosing.len(), name, range)
+    }
+
+    /// `depth` is the index of the function within `self.enclosing` which needs the capture.
+    /// a `depth` of `self.enclosing.len()` is the function currently being lowered
+    fn capture_into(&mut self, depth: usize, name: Key, range: TextRange) -> Option<u32> {
+        let captures = if depth == self.enclosing.len() {
+            &self.captures
+        } else {
+            &self.enclosing[depth].captures
+        };
+
+        if let Some(idx) = captures.iter().position(|(key, _)| *key == name) {
+            return Some(idx as u32);
+        }
+
+        // the outermost frame is the global scope, which isn't a function
+        if depth == 0 {
+            return None;
+        }
+
+        let outer = &self.enclosing[depth - 1];
+
+        let (value, mutable) =
+            if let Some(def) = outer.scopes.iter().rev().find_map(|scope| scope.get(&name)) {
+                (Expr::Local(*def), self.bodies[*def].mutable)
+            } else if let Some((idx, ast)) = outer.params.get(&name) {
+                let param = Expr::Param {
+                    idx: *idx,
+                    range: ast.range(self.tree),
+                };
+                (param, false)
+            } else {
+                let idx = self.capture_into(depth - 1, name, range)?;
+                let outer = &self.enclosing[depth - 1].captures[idx as usize].1;
+                (Expr::Capture { idx }, !outer.by_value && outer.mutable)
+            };
+
+        let value = self.bodies.exprs.alloc(value);
+        self.bodies.expr_ranges.insert(value, range);
+
+        let capture = Capture {
+            value,
+            by_value: false,
+            mutable,
+            range,
+        };
+
+        let captures = if depth == self.enclosing.len() {
+            &mut self.captures
+        } else {
+            &mut self.enclosing[depth].captures
+        };
+        captures.push((name, capture));
+
+        Some(captures.len() as u32 - 1)
+    }
+
     fn create_new_child_scope(&mut self) {
         self.scopes.push(FxHashMap::default());
     }
@@ -1575,6 +2350,57 @@ impl<'a> Ctx<'a> {
     }
 }
 
+/// parses the value of an integer literal, returning `None` if it's out of range
+pub(crate) fn int_value(value: ast::IntValue, tree: &SyntaxTree) -> Option<u64> {
+    match value {
+        ast::IntValue::Dec(dec) => {
+            let value = dec.text(tree).replace('_', "");
+            let mut value = value.split(['e', 'E']);
+
+            // there will always be a first part
+            let base = value.next().unwrap().parse::<u64>().ok()?;
+
+            if let Some(e) = value.next() {
+                e.parse()
+                    .ok()
+                    .and_then(|e| 10_u64.checked_pow(e))
+                    .and_then(|e| base.checked_mul(e))
+            } else {
+                Some(base)
+            }
+        }
+        ast::IntValue::Hex(hex) => {
+            let value = hex.text(tree).strip_prefix("0x").unwrap();
+
+            u64::from_str_radix(value, 16).ok()
+        }
+        ast::IntValue::Bin(bin) => {
+            let value = bin.text(tree).strip_prefix("0b").unwrap();
+
+            u64::from_str_radix(value, 2).ok()
+        }
+    }
+}
+
+/// takes the character after a `\` and returns what that escape sequence represents
+pub(crate) fn unescape(escape_char: char) -> Option<char> {
+    match escape_char {
+        '0' => Some('\0'),   // null
+        'a' => Some('\x07'), // bell (BEL)
+        'b' => Some('\x08'), // backspace
+        'n' => Some('\n'),   // line feed (new line)
+        'f' => Some('\x0C'), // form feed (new page)
+        'r' => Some('\r'),   // carraige return
+        't' => Some('\t'),   // horizontal tab
+        'v' => Some('\x0B'), // vertical tab
+        'e' => Some('\x1B'), // escape
+        '"' => Some('"'),
+        '\'' => Some('\''),
+        '\\' => Some('\\'),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Descendant {
     Expr(Idx<Expr>),
@@ -1655,7 +2481,9 @@ impl Iterator for Descendants<'_> {
                 Expr::IntLiteral(_) => {}
                 Expr::FloatLiteral(_) => {}
                 Expr::BoolLiteral(_) => {}
+                Expr::NullLiteral => {}
                 Expr::StringLiteral(_) => {}
+                Expr::Embed(_) => {}
                 Expr::CharLiteral(_) => {}
                 Expr::ArrayDecl { size, ty } => {
                     if is_all {
@@ -1684,11 +2512,17 @@ impl Iterator for Descendants<'_> {
                     self.todo.push(Descendant::Expr(source));
                     self.todo.push(Descendant::Expr(index));
                 }
+                Expr::Slice { source, start, end } => {
+                    self.todo.push(Descendant::Expr(source));
+                    self.todo.extend(start.map(Descendant::Expr));
+                    self.todo.extend(end.map(Descendant::Expr));
+                }
                 Expr::Ref { expr, .. } => {
                     self.todo.push(Descendant::Expr(expr));
                 }
                 Expr::Cast { expr, .. }
                 | Expr::Deref { pointer: expr }
+                | Expr::Unwrap { optional: expr, .. }
                 | Expr::Unary { expr, .. }
                 | Expr::Member { previous: expr, .. } => {
                     if include_eval {
@@ -1701,6 +2535,15 @@ impl Iterator for Descendants<'_> {
                 }
                 Expr::Paren(Some(expr)) => self.todo.push(Descendant::Expr(expr)),
                 Expr::Paren(None) => {}
+                Expr::Tuple { items } => {
+                    self.todo
+                        .extend(items.into_iter().rev().map(Descendant::Expr));
+                }
+                Expr::TupleItem { tuple, .. } => {
+                    if include_eval {
+                        self.todo.push(Descendant::Expr(tuple));
+                    }
+                }
                 Expr::Block { stmts, tail_expr } => match self.opts {
                     DescentOpts::Eval | DescentOpts::All { .. } => {
                         self.todo.extend(stmts.into_iter().map(Descendant::Stmt));
@@ -1730,6 +2573,7 @@ impl Iterator for Descendants<'_> {
                     condition,
                     body,
                     else_branch,
+                    ..
                 } => {
                     self.todo.push(Descendant::Expr(condition));
                     self.todo.push(Descendant::Expr(body));
@@ -1774,16 +2618,39 @@ impl Iterator for Descendants<'_> {
                     }
                 }
                 Expr::Param { .. } => {}
+                Expr::Capture { .. } => {}
                 Expr::LocalGlobal(_) => {}
-                Expr::Call { callee, args } => {
+                Expr::Call { callee, args, .. } => {
                     self.todo.push(Descendant::Expr(callee));
                     self.todo
                         .extend(args.into_iter().rev().map(Descendant::Expr));
                 }
                 Expr::Lambda(lambda) => {
-                    if include_types {
-                        let lambda = &self.bodies[lambda];
+                    let lambda = &self.bodies[lambda];
+
+                    // the captured values are read when the closure is created
+                    if include_eval {
+                        self.todo.extend(
+                            lambda
+                                .captures
+                                .iter()
+                                .rev()
+                                .map(|capture| Descendant::Expr(capture.value)),
+                        );
+                    }
+
+                    if is_all {
+                        self.todo.extend(
+                            lambda
+                                .params
+                                .iter()
+                                .rev()
+                                .filter_map(|param| param.default)
+                                .map(Descendant::Expr),
+                        );
+                    }
 
+                    if include_types {
                         self.todo.extend(
                             lambda
                                 .params
@@ -1832,13 +2699,25 @@ impl Iterator for Descendants<'_> {
                             .map(|(_, val)| Descendant::Expr(val)),
                     );
                 }
-                Expr::Distinct { ty, .. } => {
+                Expr::Distinct { ty, .. } | Expr::OptionalDecl { ty } => {
                     if include_types {
                         self.todo.push(Descendant::Expr(ty));
                     }
                 }
                 Expr::PrimitiveTy(_) => {}
-                Expr::StructDecl { members, .. } => {
+                Expr::StructDecl {
+                    members, defaults, ..
+                } => {
+                    if is_all {
+                        self.todo
+                            .extend(defaults.into_iter().flatten().map(Descendant::Expr));
+                    }
+                    if include_types {
+                        self.todo
+                            .extend(members.into_iter().map(|(_, ty)| Descendant::Expr(ty)));
+                    }
+                }
+                Expr::UnionDecl { members, .. } | Expr::InterfaceDecl { members, .. } => {
                     if include_types {
                         self.todo
                             .extend(members.into_iter().map(|(_, ty)| Descendant::Expr(ty)));
@@ -1937,6 +2816,10 @@ impl Bodies {
         self.comptimes.iter().map(|(idx, _)| idx)
     }
 
+    pub fn static_asserts(&self) -> impl Iterator<Item = &StaticAssert> + '_ {
+        self.static_asserts.iter()
+    }
+
     pub fn imports(&self) -> &FxHashSet<FileName> {
         &self.imports
     }
@@ -1969,6 +2852,7 @@ impl Bodies {
             scope_usages: label_usages,
             lambdas,
             comptimes,
+            static_asserts,
             imports,
         } = self;
 
@@ -1981,6 +2865,7 @@ impl Bodies {
         global_externs.shrink_to_fit();
         lambdas.shrink_to_fit();
         comptimes.shrink_to_fit();
+        static_asserts.shrink_to_fit();
         imports.shrink_to_fit();
         label_decls.shrink_to_fit();
         label_usages.shrink_to_fit()
@@ -2065,6 +2950,23 @@ impl Bodies {
             s.push_str(";\n");
         }
 
+        for static_assert in &self.static_asserts {
+            s.push_str("#assert ");
+            write_expr(
+                &mut s,
+                static_assert.condition,
+                show_expr_idx,
+                self,
+                mod_dir,
+                interner,
+                0,
+            );
+            if let Some(message) = static_assert.message {
+                s.push_str(&format!(", {:?}", interner.lookup(message)));
+            }
+            s.push_str(";\n");
+        }
+
         return s;
 
         #[allow(clippy::too_many_arguments)]
@@ -2090,8 +2992,12 @@ impl Bodies {
 
                 Expr::BoolLiteral(b) => s.push_str(&format!("{}", b)),
 
+                Expr::NullLiteral => s.push_str("null"),
+
                 Expr::StringLiteral(content) => s.push_str(&format!("{content:?}")),
 
+                Expr::Embed(contents) => s.push_str(&format!("#embed <{} bytes>", contents.len())),
+
                 Expr::CharLiteral(char) => s.push_str(&format!("{:?}", Into::<char>::into(*char))),
 
                 Expr::ArrayDecl { size, ty } => {
@@ -2129,6 +3035,19 @@ impl Bodies {
                     s.push(']');
                 }
 
+                Expr::Slice { source, start, end } => {
+                    write_expr(s, *source, show_idx, bodies, mod_dir, interner, indentation);
+                    s.push('[');
+                    if let Some(start) = start {
+                        write_expr(s, *start, show_idx, bodies, mod_dir, interner, indentation);
+                    }
+                    s.push_str("..");
+                    if let Some(end) = end {
+                        write_expr(s, *end, show_idx, bodies, mod_dir, interner, indentation);
+                    }
+                    s.push(']');
+                }
+
                 Expr::Cast { expr, ty } => {
                     write_expr(s, *expr, show_idx, bodies, mod_dir, interner, indentation);
 
@@ -2161,6 +3080,20 @@ impl Bodies {
                     s.push('^');
                 }
 
+                Expr::Unwrap { optional, .. } => {
+                    write_expr(
+                        s,
+                        *optional,
+                        show_idx,
+                        bodies,
+                        mod_dir,
+                        interner,
+                        indentation,
+                    );
+
+                    s.push_str(".?");
+                }
+
                 Expr::Binary { lhs, rhs, op } => {
                     write_expr(s, *lhs, show_idx, bodies, mod_dir, interner, indentation);
 
@@ -2213,6 +3146,29 @@ impl Bodies {
                     s.push_str("()");
                 }
 
+                Expr::Tuple { items } => {
+                    s.push('(');
+
+                    for (idx, item) in items.iter().enumerate() {
+                        write_expr(s, *item, show_idx, bodies, mod_dir, interner, indentation);
+                        if idx != items.len() - 1 {
+                            s.push_str(", ");
+                        }
+                    }
+
+                    if items.len() == 1 {
+                        s.push(',');
+                    }
+
+                    s.push(')');
+                }
+
+                Expr::TupleItem { tuple, index, .. } => {
+                    write_expr(s, *tuple, show_idx, bodies, mod_dir, interner, indentation);
+                    s.push('.');
+                    s.push_str(&index.to_string());
+                }
+
                 Expr::Block {
                     stmts,
                     tail_expr: None,
@@ -2302,8 +3258,12 @@ impl Bodies {
                     condition,
                     body,
                     else_branch,
+                    binding,
                 } => {
                     s.push_str("if ");
+                    if let Some(binding) = binding {
+                        s.push_str(&format!("l{} := ", binding.into_raw()));
+                    }
                     write_expr(
                         s,
                         *condition,
@@ -2358,7 +3318,13 @@ impl Bodies {
 
                 Expr::Param { idx, .. } => s.push_str(&format!("p{}", idx)),
 
-                Expr::Call { callee, args } => {
+                Expr::Capture { idx } => s.push_str(&format!("c{}", idx)),
+
+                Expr::Call {
+                    callee,
+                    args,
+                    arg_names,
+                } => {
                     write_expr(s, *callee, show_idx, bodies, mod_dir, interner, indentation);
 
                     s.push('(');
@@ -2367,6 +3333,11 @@ impl Bodies {
                             s.push_str(", ");
                         }
 
+                        if let Some(Some(name)) = arg_names.get(idx) {
+                            s.push_str(interner.lookup(name.name.0));
+                            s.push_str(" = ");
+                        }
+
                         write_expr(s, *arg, show_idx, bodies, mod_dir, interner, indentation);
                     }
                     s.push(')');
@@ -2398,6 +3369,10 @@ impl Bodies {
                         return_ty,
                         body,
                         is_extern,
+                        is_variadic,
+                        is_export,
+                        is_closure,
+                        captures,
                         ..
                     } = &bodies.lambdas[*lambda];
 
@@ -2405,24 +3380,72 @@ impl Bodies {
                     for (idx, param) in params.iter().enumerate() {
                         s.push('p');
                         s.push_str(idx.to_string().as_str());
-                        s.push_str(": ");
 
-                        write_expr(
-                            s,
-                            param.ty,
-                            show_idx,
-                            bodies,
-                            mod_dir,
-                            interner,
-                            indentation,
-                        );
+                        // `mode := "r"`
+                        if bodies[param.ty] == Expr::Missing && param.default.is_some() {
+                            s.push_str(" := ");
+                        } else {
+                            s.push_str(": ");
+                            write_expr(
+                                s,
+                                param.ty,
+                                show_idx,
+                                bodies,
+                                mod_dir,
+                                interner,
+                                indentation,
+                            );
+                            if param.default.is_some() {
+                                s.push_str(" = ");
+                            }
+                        }
+
+                        if let Some(default) = param.default {
+                            write_expr(
+                                s,
+                                default,
+                                show_idx,
+                                bodies,
+                                mod_dir,
+                                interner,
+                                indentation,
+                            );
+                        }
 
                         if idx != params.len() - 1 {
                             s.push_str(", ");
                         }
                     }
+                    if *is_variadic {
+                        if !params.is_empty() {
+                            s.push_str(", ");
+                        }
+                        s.push_str("..");
+                    }
                     s.push_str(") ");
 
+                    if *is_closure {
+                        s.push('[');
+                        for (idx, capture) in captures.iter().enumerate() {
+                            if idx != 0 {
+                                s.push_str(", ");
+                            }
+                            if !capture.by_value {
+                                s.push('^');
+                            }
+                            write_expr(
+                                s,
+                                capture.value,
+                                show_idx,
+                                bodies,
+                                mod_dir,
+                                interner,
+                                indentation,
+                            );
+                        }
+                        s.push_str("] ");
+                    }
+
                     if let Some(return_ty) = return_ty {
                         s.push_str("-> ");
 
@@ -2442,6 +3465,9 @@ impl Bodies {
                     if *is_extern {
                         s.push_str("extern");
                     } else {
+                        if *is_export {
+                            s.push_str("export ");
+                        }
                         write_expr(s, *body, show_idx, bodies, mod_dir, interner, indentation);
                     }
                 }
@@ -2479,6 +3505,11 @@ impl Bodies {
 
                 Expr::PrimitiveTy(ty) => s.push_str(&ty.display()),
 
+                Expr::OptionalDecl { ty } => {
+                    s.push('?');
+                    write_expr(s, *ty, show_idx, bodies, mod_dir, interner, indentation);
+                }
+
                 Expr::Distinct { uid, ty } => {
                     s.push_str("distinct'");
                     s.push_str(&uid.to_string());
@@ -2486,9 +3517,73 @@ impl Bodies {
                     write_expr(s, *ty, show_idx, bodies, mod_dir, interner, indentation);
                 }
 
-                Expr::StructDecl { uid, members } => {
+                Expr::StructDecl {
+                    uid,
+                    members,
+                    defaults,
+                    repr,
+                } => {
                     s.push_str("struct'");
                     s.push_str(&uid.to_string());
+                    if repr.packed {
+                        s.push_str(" #packed");
+                    }
+                    if let Some(align) = repr.align {
+                        s.push_str(&format!(" #align({})", align));
+                    }
+                    s.push_str(" {");
+                    for (idx, (name, ty)) in members.iter().enumerate() {
+                        if let Some(Some(align)) = repr.field_aligns.get(idx) {
+                            s.push_str(&format!("#align({}) ", align));
+                        }
+                        if let Some(name) = name {
+                            s.push_str(interner.lookup(name.name.0));
+                        } else {
+                            s.push('?');
+                        }
+                        s.push_str(": ");
+                        write_expr(s, *ty, show_idx, bodies, mod_dir, interner, indentation);
+                        if let Some(Some(default)) = defaults.get(idx) {
+                            s.push_str(" = ");
+                            write_expr(
+                                s,
+                                *default,
+                                show_idx,
+                                bodies,
+                                mod_dir,
+                                interner,
+                                indentation,
+                            );
+                        }
+                        if idx != members.len() - 1 {
+                            s.push_str(", ");
+                        }
+                    }
+                    s.push('}');
+                }
+
+                Expr::UnionDecl { uid, members } => {
+                    s.push_str("union'");
+                    s.push_str(&uid.to_string());
+                    s.push_str(" {");
+                    for (idx, (name, ty)) in members.iter().enumerate() {
+                        if let Some(name) = name {
+                            s.push_str(interner.lookup(name.name.0));
+                        } else {
+                            s.push('?');
+                        }
+                        s.push_str(": ");
+                        write_expr(s, *ty, show_idx, bodies, mod_dir, interner, indentation);
+                        if idx != members.len() - 1 {
+                            s.push_str(", ");
+                        }
+                    }
+                    s.push('}');
+                }
+
+                Expr::InterfaceDecl { uid, members } => {
+                    s.push_str("interface'");
+                    s.push_str(&uid.to_string());
                     s.push_str(" {");
                     for (idx, (name, ty)) in members.iter().enumerate() {
                         if let Some(name) = name {
@@ -2548,7 +3643,7 @@ impl Bodies {
                     if let Some(ty) = local_def.ty {
                         s.push(' ');
                         write_expr(s, ty, show_idx, bodies, mod_dir, interner, indentation);
-                        if local_def.value.is_some() {
+                        if local_def.value.is_some() || local_def.uninit {
                             s.push(' ');
                         }
                     }
@@ -2556,6 +3651,8 @@ impl Bodies {
                     if let Some(value) = local_def.value {
                         s.push_str("= ");
                         write_expr(s, value, show_idx, bodies, mod_dir, interner, indentation);
+                    } else if local_def.uninit {
+                        s.push_str("= ---");
                     }
                     s.push(';');
                 }
@@ -2626,6 +3723,17 @@ mod tests {
         expected_diagnostics: impl Fn(
             &mut Interner,
         ) -> [(LoweringDiagnosticKind, std::ops::Range<u32>); N],
+    ) {
+        check_with_config(input, &BuildConfig::default(), expect, expected_diagnostics)
+    }
+
+    fn check_with_config<const N: usize>(
+        input: &str,
+        config: &BuildConfig,
+        expect: Expect,
+        expected_diagnostics: impl Fn(
+            &mut Interner,
+        ) -> [(LoweringDiagnosticKind, std::ops::Range<u32>); N],
     ) {
         let mut interner = Interner::default();
         let mut uid_gen = UIDGenerator::default();
@@ -2633,7 +3741,7 @@ mod tests {
         let tokens = lexer::lex(input);
         let tree = parser::parse_source_file(&tokens, input).into_syntax_tree();
         let root = ast::Root::cast(tree.root(), &tree).unwrap();
-        let (index, _) = crate::index(root, &tree, &mut interner);
+        let (index, _) = crate::index(root, &tree, &mut interner, config);
 
         let (bodies, actual_diagnostics) = lower(
             root,
@@ -2643,6 +3751,7 @@ mod tests {
             &mut uid_gen,
             &mut interner,
             Path::new("/capy/modules"),
+            config,
             true,
         );
 
@@ -3116,7 +4225,7 @@ mod tests {
         check(
             r#"
                 foo :: () {
-                    null := '\0';
+                    nul := '\0';
                     bell := '\a';
                     backspace := '\b';
                     linefeed := '\n';
@@ -3266,6 +4375,51 @@ mod tests {
         )
     }
 
+    #[test]
+    fn named_args_and_param_defaults() {
+        check(
+            r#"
+                open :: (path: str, mode := "r", flags: i32 = 0) {}
+
+                foo :: () {
+                    open("a.txt", flags = 1, mode = "w");
+                    open(path = "b.txt", "rw");
+                }
+            "#,
+            expect![[r#"
+                main::open :: (p0: str, p1 := "r", p2: i32 = 0) {};
+                main::foo :: () {
+                    open("a.txt", flags = 1, mode = "w");
+                    open(path = "b.txt", "rw");
+                };
+            "#]],
+            |_| [(LoweringDiagnosticKind::PositionalArgAfterNamed, 197..201)],
+        )
+    }
+
+    #[test]
+    fn slices() {
+        check(
+            r#"
+                foo :: (nums: []i32) {
+                    nums[1..3];
+                    nums[2..];
+                    nums[..nums.len - 1];
+                    nums[..];
+                }
+            "#,
+            expect![[r#"
+                main::foo :: (p0: []i32) {
+                    p0[1..3];
+                    p0[2..];
+                    p0[..p0.len - 1];
+                    p0[..];
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
     #[test]
     fn recursion() {
         check(
@@ -3301,139 +4455,115 @@ mod tests {
     }
 
     #[test]
-    fn lambda_dont_capture_scope() {
+    fn lambda_capture_scope() {
         check(
             r#"
                 foo :: (x: i32) {
                     y := 5;
 
                     bar := () -> i32 {
-                        x + y
+                        x + y + x
                     };
                 }
             "#,
             expect![[r#"
                 main::foo :: (p0: i32) {
                     l0 := 5;
-                    l1 := () -> i32 { <missing> + <missing> };
+                    l1 := () [^p0, ^l0] -> i32 { c0 + c1 + c0 };
                 };
             "#]],
-            |i| {
-                [
-                    (
-                        LoweringDiagnosticKind::UndefinedRef {
-                            name: i.intern("x"),
-                        },
-                        127..128,
-                    ),
-                    (
-                        LoweringDiagnosticKind::UndefinedRef {
-                            name: i.intern("y"),
-                        },
-                        131..132,
-                    ),
-                ]
-            },
+            |_| [],
         )
     }
 
     #[test]
-    fn call_lambda() {
+    fn lambda_capture_by_value() {
         check(
             r#"
-                foo :: () -> i32 {
-                    {
-                        (x: i32, y: i32) -> i32 {
-                            x + y
-                        }
-                    } (1, 2)
+                foo :: (x: i32) {
+                    y := 5;
+
+                    bar := () [y] -> i32 {
+                        x + y
+                    };
                 }
             "#,
             expect![[r#"
-                main::foo :: () -> i32 { { (p0: i32, p1: i32) -> i32 { p0 + p1 } }(1, 2) };
+                main::foo :: (p0: i32) {
+                    l0 := 5;
+                    l1 := () [l0, ^p0] -> i32 { c1 + c0 };
+                };
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn extern_lambda() {
+    fn lambda_empty_capture_list() {
         check(
             r#"
-                main :: () -> i32 {
-                    puts := (s: str) extern;
+                foo :: () {
+                    bar := (x: i32) [] -> i32 { x };
                 }
             "#,
             expect![[r#"
-                main::main :: () -> i32 {
-                    l0 := (p0: str) extern;
+                main::foo :: () {
+                    l0 := (p0: i32) [] -> i32 { p0 };
                 };
             "#]],
-            |_| [(LoweringDiagnosticKind::NonGlobalExternFunc, 74..80)],
-        )
-    }
-
-    #[test]
-    fn extern_function() {
-        check(
-            r#"
-                puts :: (s: str) -> i32 extern;
-            "#,
-            expect![[r#"
-                main::puts :: (p0: str) -> i32 extern;
-            "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn scoped_local() {
+    fn lambda_capture_through_lambda() {
         check(
             r#"
-                foo :: () -> i32 {
-                    {
-                        a := 5;
-                    }
+                foo :: (x: i32) {
+                    y := 5;
 
-                    a
+                    bar := () {
+                        z := 2;
+
+                        baz := () -> i32 {
+                            x + y + z
+                        };
+                    };
                 }
             "#,
             expect![[r#"
-                main::foo :: () -> i32 {
-                    {
-                        l0 := 5;
+                main::foo :: (p0: i32) {
+                    l0 := 5;
+                    l3 := () [^p0, ^l0] {
+                        l1 := 2;
+                        l2 := () [^c0, ^c1, ^l1] -> i32 { c0 + c1 + c2 };
                     };
-                    <missing>
                 };
             "#]],
-            |i| {
-                [(
-                    LoweringDiagnosticKind::UndefinedRef {
-                        name: i.intern("a"),
-                    },
-                    133..134,
-                )]
-            },
+            |_| [],
         )
     }
 
     #[test]
-    fn locals_take_precedence_over_globals() {
+    fn lambda_capture_list_before_locals() {
         check(
             r#"
-                bar :: () -> i32 { 0 };
-
-                foo :: () -> i32 {
-                    bar := 25;
+                foo :: () {
+                    x := 5;
 
-                    bar
+                    bar := () [x] {
+                        x := 2;
+                        x
+                    };
                 }
             "#,
             expect![[r#"
-                main::bar :: () -> i32 { 0 };
-                main::foo :: () -> i32 {
-                    l0 := 25;
-                    l0
+                main::foo :: () {
+                    l0 := 5;
+                    l2 := () [l0] {
+                        l1 := 2;
+                        l1
+                    };
                 };
             "#]],
             |_| [],
@@ -3441,64 +4571,68 @@ mod tests {
     }
 
     #[test]
-    fn locals_take_precedence_over_params() {
+    fn capture_global() {
         check(
             r#"
-                main :: () -> i32 {
-                    foo := {
-                        bar := {
-                            baz := 9;
-                            baz * 10
-                        };
-                        bar - 1
-                    };
-                    foo + 3
+                foo :: () {
+                    bar := () [foo, baz] {};
                 }
             "#,
             expect![[r#"
-                main::main :: () -> i32 {
-                    l2 := {
-                        l1 := {
-                            l0 := 9;
-                            l0 * 10
-                        };
-                        l1 - 1
-                    };
-                    l2 + 3
+                main::foo :: () {
+                    l0 := () [] {};
                 };
             "#]],
-            |_| [],
+            |i| {
+                [
+                    (
+                        LoweringDiagnosticKind::CaptureNotLocal {
+                            name: i.intern("foo"),
+                        },
+                        60..63,
+                    ),
+                    (
+                        LoweringDiagnosticKind::CaptureNotLocal {
+                            name: i.intern("baz"),
+                        },
+                        65..68,
+                    ),
+                ]
+            },
         )
     }
 
     #[test]
-    fn slice() {
+    fn global_closure() {
         check(
             r#"
-                main :: () -> i32 {
-                    my_slice : []i32 = i32.[4, 8, 15, 16, 23, 42];
-                }
+                foo :: () [] {};
+
+                Callback :: (x: i32) [] -> i32;
             "#,
             expect![[r#"
-                main::main :: () -> i32 {
-                    l0 : []i32 = i32.[4, 8, 15, 16, 23, 42];
-                };
+                main::foo :: () [] {};
+                main::Callback :: (p0: i32) [] -> i32 <missing>;
             "#]],
-            |_| [],
+            |_| [(LoweringDiagnosticKind::GlobalClosure, 27..29)],
         )
     }
 
     #[test]
-    fn array() {
+    fn uninit_local() {
         check(
             r#"
-                main :: () -> i32 {
-                    my_array : [6]i32 = i32.[4, 8, 15, 16, 23, 42];
+                foo :: () {
+                    buf : [4096]char = ---;
+                    x : i32 = ---;
+                    x = 5;
                 }
             "#,
             expect![[r#"
-                main::main :: () -> i32 {
-                    l0 : [6]i32 = i32.[4, 8, 15, 16, 23, 42];
+                main::foo :: () {
+                    l0 : [4096]char = ---;
+                    l1 : i32 = ---;
+                    l1 = 5;
                 };
             "#]],
             |_| [],
@@ -3506,40 +4640,56 @@ mod tests {
     }
 
     #[test]
-    fn comptime() {
+    fn uninit_outside_local() {
         check(
             r#"
-                main :: () -> i32 {
-                    num :: comptime {
-                        1 + 1
-                    };
+                global : i32 = ---;
+
+                foo :: () {
+                    x := ---;
+                    bar(---);
                 }
+
+                bar :: (x: i32) {}
             "#,
             expect![[r#"
-                main::main :: () -> i32 {
-                    l0 := comptime { 1 + 1 };
+                main::global :: <missing>;
+                main::foo :: () {
+                    l0 := ---;
+                    bar(<missing>);
                 };
+                main::bar :: (p0: i32) {};
             "#]],
-            |_| [],
+            |_| {
+                [
+                    (LoweringDiagnosticKind::UninitOutsideLocal, 32..35),
+                    (LoweringDiagnosticKind::UninitWithoutTy, 91..94),
+                    (LoweringDiagnosticKind::UninitOutsideLocal, 120..123),
+                ]
+            },
         )
     }
 
     #[test]
-    fn comptime_dont_capture_scope() {
+    fn lambda_in_comptime_dont_capture_scope() {
         check(
             r#"
-                main :: (x: i32) -> i32 {
-                    y := 5;
+                foo :: () {
+                    x := 5;
 
-                    num :: comptime {
-                        x + y
+                    bar := comptime {
+                        baz := () -> i32 { x };
+                        x
                     };
                 }
             "#,
             expect![[r#"
-                main::main :: (p0: i32) -> i32 {
+                main::foo :: () {
                     l0 := 5;
-                    l1 := comptime { <missing> + <missing> };
+                    l2 := comptime {
+                        l1 := () -> i32 { <missing> };
+                        <missing>
+                    };
                 };
             "#]],
             |i| {
@@ -3548,13 +4698,13 @@ mod tests {
                         LoweringDiagnosticKind::UndefinedRef {
                             name: i.intern("x"),
                         },
-                        134..135,
+                        139..140,
                     ),
                     (
                         LoweringDiagnosticKind::UndefinedRef {
-                            name: i.intern("y"),
+                            name: i.intern("x"),
                         },
-                        138..139,
+                        168..169,
                     ),
                 ]
             },
@@ -3562,16 +4712,322 @@ mod tests {
     }
 
     #[test]
-    fn comptime_globals() {
+    fn call_lambda() {
         check(
             r#"
-                foo :: 5;
-
-                main :: () -> i32 {
-                    num :: comptime {
-                        foo * 2
-                    };
-                }
+                foo :: () -> i32 {
+                    {
+                        (x: i32, y: i32) -> i32 {
+                            x + y
+                        }
+                    } (1, 2)
+                }
+            "#,
+            expect![[r#"
+                main::foo :: () -> i32 { { (p0: i32, p1: i32) -> i32 { p0 + p1 } }(1, 2) };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn extern_lambda() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    puts := (s: str) extern;
+                }
+            "#,
+            expect![[r#"
+                main::main :: () -> i32 {
+                    l0 := (p0: str) extern;
+                };
+            "#]],
+            |_| [(LoweringDiagnosticKind::NonGlobalExternFunc, 74..80)],
+        )
+    }
+
+    #[test]
+    fn extern_function() {
+        check(
+            r#"
+                puts :: (s: str) -> i32 extern;
+            "#,
+            expect![[r#"
+                main::puts :: (p0: str) -> i32 extern;
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn variadic_extern_function() {
+        check(
+            r#"
+                printf :: (fmt: cstr, ..) -> i32 extern;
+            "#,
+            expect![[r#"
+                main::printf :: (p0: cstr, ..) -> i32 extern;
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn export_function() {
+        check(
+            r#"
+                add :: (x: i32, y: i32) -> i32 export { x + y };
+            "#,
+            expect![[r#"
+                main::add :: (p0: i32, p1: i32) -> i32 export { p0 + p1 };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn export_local_function() {
+        check(
+            r#"
+                main :: () {
+                    add :: (x: i32, y: i32) -> i32 export { x + y };
+                }
+            "#,
+            expect![[r#"
+                main::main :: () {
+                    l0 := (p0: i32, p1: i32) -> i32 { p0 + p1 };
+                };
+            "#]],
+            |_| [(LoweringDiagnosticKind::NonGlobalExportFunc, 81..87)],
+        )
+    }
+
+    #[test]
+    fn export_without_body() {
+        check(
+            r#"
+                puts :: (s: cstr) export extern;
+            "#,
+            expect![[r#"
+                main::puts :: (p0: cstr) extern;
+            "#]],
+            |_| [(LoweringDiagnosticKind::ExportWithoutBody, 35..41)],
+        )
+    }
+
+    #[test]
+    fn var_args_not_last() {
+        check(
+            r#"
+                foo :: (.., x: i32) extern;
+            "#,
+            expect![[r#"
+                main::foo :: (p0: i32, ..) extern;
+            "#]],
+            |_| [(LoweringDiagnosticKind::VarArgsNotLast, 25..27)],
+        )
+    }
+
+    #[test]
+    fn var_args_with_body() {
+        check(
+            r#"
+                foo :: (x: i32, ..) {};
+            "#,
+            expect![[r#"
+                main::foo :: (p0: i32, ..) {};
+            "#]],
+            |_| [(LoweringDiagnosticKind::VarArgsWithBody, 33..35)],
+        )
+    }
+
+    #[test]
+    fn scoped_local() {
+        check(
+            r#"
+                foo :: () -> i32 {
+                    {
+                        a := 5;
+                    }
+
+                    a
+                }
+            "#,
+            expect![[r#"
+                main::foo :: () -> i32 {
+                    {
+                        l0 := 5;
+                    };
+                    <missing>
+                };
+            "#]],
+            |i| {
+                [(
+                    LoweringDiagnosticKind::UndefinedRef {
+                        name: i.intern("a"),
+                    },
+                    133..134,
+                )]
+            },
+        )
+    }
+
+    #[test]
+    fn locals_take_precedence_over_globals() {
+        check(
+            r#"
+                bar :: () -> i32 { 0 };
+
+                foo :: () -> i32 {
+                    bar := 25;
+
+                    bar
+                }
+            "#,
+            expect![[r#"
+                main::bar :: () -> i32 { 0 };
+                main::foo :: () -> i32 {
+                    l0 := 25;
+                    l0
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn locals_take_precedence_over_params() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    foo := {
+                        bar := {
+                            baz := 9;
+                            baz * 10
+                        };
+                        bar - 1
+                    };
+                    foo + 3
+                }
+            "#,
+            expect![[r#"
+                main::main :: () -> i32 {
+                    l2 := {
+                        l1 := {
+                            l0 := 9;
+                            l0 * 10
+                        };
+                        l1 - 1
+                    };
+                    l2 + 3
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn slice() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    my_slice : []i32 = i32.[4, 8, 15, 16, 23, 42];
+                }
+            "#,
+            expect![[r#"
+                main::main :: () -> i32 {
+                    l0 : []i32 = i32.[4, 8, 15, 16, 23, 42];
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn array() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    my_array : [6]i32 = i32.[4, 8, 15, 16, 23, 42];
+                }
+            "#,
+            expect![[r#"
+                main::main :: () -> i32 {
+                    l0 : [6]i32 = i32.[4, 8, 15, 16, 23, 42];
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn comptime() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    num :: comptime {
+                        1 + 1
+                    };
+                }
+            "#,
+            expect![[r#"
+                main::main :: () -> i32 {
+                    l0 := comptime { 1 + 1 };
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn comptime_dont_capture_scope() {
+        check(
+            r#"
+                main :: (x: i32) -> i32 {
+                    y := 5;
+
+                    num :: comptime {
+                        x + y
+                    };
+                }
+            "#,
+            expect![[r#"
+                main::main :: (p0: i32) -> i32 {
+                    l0 := 5;
+                    l1 := comptime { <missing> + <missing> };
+                };
+            "#]],
+            |i| {
+                [
+                    (
+                        LoweringDiagnosticKind::UndefinedRef {
+                            name: i.intern("x"),
+                        },
+                        134..135,
+                    ),
+                    (
+                        LoweringDiagnosticKind::UndefinedRef {
+                            name: i.intern("y"),
+                        },
+                        138..139,
+                    ),
+                ]
+            },
+        )
+    }
+
+    #[test]
+    fn comptime_globals() {
+        check(
+            r#"
+                foo :: 5;
+
+                main :: () -> i32 {
+                    num :: comptime {
+                        foo * 2
+                    };
+                }
             "#,
             expect![[r#"
                 main::foo :: 5;
@@ -4168,4 +5624,404 @@ mod tests {
             |_| [],
         )
     }
+
+    #[test]
+    fn unions() {
+        check(
+            r#"
+                bar :: () {
+                    Bits :: union {
+                        int: u32,
+                        float: f32,
+                    };
+
+                    my_bits := Bits.{
+                        float = 1.0,
+                    };
+                }
+            "#,
+            expect![[r#"
+                main::bar :: () {
+                    l0 := union'0 {int: i32, float: f32};
+                    l1 := l0.{float = 1};
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn optionals() {
+        check(
+            r#"
+                foo :: (ptr: ?^i32) -> i32 {
+                    empty : ?i32 = null;
+
+                    if x := ptr {
+                        x^
+                    } else {
+                        ptr.?^
+                    }
+                }
+            "#,
+            expect![[r#"
+                main::foo :: (p0: ?^i32) -> i32 {
+                    l0 : ?i32 = null;
+                    if l1 := p0 { l1^ } else { p0.?^ }
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn tuples() {
+        check(
+            r#"
+                parse :: (s: str) -> (i32, bool) {
+                    (s.len as i32, true)
+                }
+
+                foo :: () {
+                    x, ok := parse("hello");
+                    single := (x,);
+                }
+            "#,
+            expect![[r#"
+                main::parse :: (p0: str) -> (i32, bool) { (p0.len as i32, true) };
+                main::foo :: () {
+                    l0 := parse("hello");
+                    l1 := l0.0;
+                    l2 := l0.1;
+                    l3 := (l1,);
+                };
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn interfaces() {
+        check(
+            r#"
+                Shape :: interface {
+                    area: () -> f32,
+                    scale: (by: f32) -> void,
+                };
+            "#,
+            expect![[r#"
+                main::Shape :: interface'0 {area: () -> f32 <missing>, scale: (p0: f32) -> void <missing>};
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn struct_layout_directives() {
+        check(
+            r#"
+                Header :: struct #packed {
+                    kind: u8,
+                    len: u32,
+                };
+                Vec4 :: struct #align(16) {
+                    x: f32,
+                    #align(8) y: f32,
+                };
+            "#,
+            expect![[r#"
+                main::Header :: struct'0 #packed {kind: i8, len: i32};
+                main::Vec4 :: struct'1 #align(16) {x: f32, #align(8) y: f32};
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn struct_member_defaults() {
+        check(
+            r#"
+                Config :: struct {
+                    name: str,
+                    retries: i32 = 3,
+                    verbose: bool = false,
+                };
+            "#,
+            expect![[r#"
+                main::Config :: struct'0 {name: str, retries: i32 = 3, verbose: bool = false};
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn invalid_struct_layout_directives() {
+        check(
+            r#"
+                Foo :: struct #packed(1) #align(3) #align #tight {
+                    #packed a: u8,
+                    #align(0x1_0000_0000) b: u32,
+                };
+            "#,
+            expect![[r#"
+                main::Foo :: struct'0 #packed {a: i8, b: i32};
+            "#]],
+            |i| {
+                [
+                    (
+                        LoweringDiagnosticKind::DirectiveTakesNoArgs {
+                            name: i.intern("packed"),
+                        },
+                        31..41,
+                    ),
+                    (LoweringDiagnosticKind::InvalidAlign, 42..51),
+                    (LoweringDiagnosticKind::InvalidAlign, 52..58),
+                    (
+                        LoweringDiagnosticKind::UnknownLayoutDirective {
+                            name: i.intern("tight"),
+                            on_field: false,
+                        },
+                        59..65,
+                    ),
+                    (
+                        LoweringDiagnosticKind::UnknownLayoutDirective {
+                            name: i.intern("packed"),
+                            on_field: true,
+                        },
+                        88..95,
+                    ),
+                    (LoweringDiagnosticKind::InvalidAlign, 123..144),
+                ]
+            },
+        )
+    }
+
+    fn linux_config() -> BuildConfig {
+        BuildConfig {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            pointer_bit_width: 64,
+            big_endian: false,
+            defines: [
+                ("DEBUG".to_string(), ConfigValue::Bool(true)),
+                ("LEVEL".to_string(), ConfigValue::Int(3)),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn static_if() {
+        check_with_config(
+            r#"
+                #if #os == "windows" {
+                    O_CREAT :: 0x100;
+                    win :: import "windows.capy";
+                } else #if #os == "linux" && #pointer_width == 64 {
+                    O_CREAT :: 0x40;
+                } else {
+                    O_CREAT :: 0x200;
+                }
+            "#,
+            &linux_config(),
+            expect![[r#"
+                main::O_CREAT :: 64;
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn static_if_nested() {
+        check_with_config(
+            r#"
+                #if #arch == "x86_64" {
+                    #if #defined(DEBUG) && #def(LEVEL) > 2 {
+                        log :: true;
+                    }
+                    #if !#defined(RELEASE) {
+                        debug :: true;
+                    } else {
+                        release :: true;
+                    }
+                }
+            "#,
+            &linux_config(),
+            expect![[r#"
+                main::log :: true;
+                main::debug :: true;
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn directives() {
+        check_with_config(
+            r#"
+                os :: #os;
+                arch :: #arch;
+                width :: #pointer_width;
+                endian :: #endian;
+                level :: #def(LEVEL);
+                has_debug :: #defined(DEBUG);
+            "#,
+            &linux_config(),
+            expect![[r#"
+                main::os :: "linux";
+                main::arch :: "x86_64";
+                main::width :: 64;
+                main::endian :: "little";
+                main::level :: 3;
+                main::has_debug :: true;
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn undefined_define() {
+        check_with_config(
+            r#"
+                foo :: #def(RELEASE);
+            "#,
+            &linux_config(),
+            expect![[r#"
+                main::foo :: <missing>;
+            "#]],
+            |i| {
+                [(
+                    LoweringDiagnosticKind::UndefinedDefine {
+                        name: i.intern("RELEASE"),
+                    },
+                    29..36,
+                )]
+            },
+        )
+    }
+
+    #[test]
+    fn unknown_directive() {
+        check_with_config(
+            r#"
+                foo :: #platform;
+                bar :: #os(linux);
+            "#,
+            &linux_config(),
+            expect![[r#"
+                main::foo :: <missing>;
+                main::bar :: <missing>;
+            "#]],
+            |i| {
+                [
+                    (
+                        LoweringDiagnosticKind::UnknownDirective {
+                            name: i.intern("platform"),
+                        },
+                        24..33,
+                    ),
+                    (
+                        LoweringDiagnosticKind::DirectiveTakesNoArgs {
+                            name: i.intern("os"),
+                        },
+                        58..68,
+                    ),
+                ]
+            },
+        )
+    }
+
+    #[test]
+    fn invalid_static_if_conditions() {
+        check_with_config(
+            r#"
+                #if foo { a :: 1; }
+                #if #os == 64 { b :: 2; }
+                #if #pointer_width { c :: 3; }
+            "#,
+            &linux_config(),
+            expect![""],
+            |_| {
+                [
+                    (LoweringDiagnosticKind::NonStaticCondition, 21..24),
+                    (LoweringDiagnosticKind::StaticConditionMismatch, 57..66),
+                    (LoweringDiagnosticKind::StaticConditionNotBool, 99..113),
+                ]
+            },
+        )
+    }
+
+    #[test]
+    fn static_assert() {
+        check(
+            r#"
+                foo :: 5;
+
+                #assert foo > 2, "foo is too small";
+                #assert foo < 10;
+            "#,
+            expect![[r#"
+                main::foo :: 5;
+                #assert comptime foo > 2, "foo is too small";
+                #assert comptime foo < 10;
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn invalid_static_asserts() {
+        check(
+            r#"
+                #assert true, 42;
+                #assert true, "message", "another message";
+                #unreachable false;
+            "#,
+            expect![[r##"
+                #assert comptime true;
+                #assert comptime true, "message";
+            "##]],
+            |i| {
+                [
+                    (LoweringDiagnosticKind::AssertMessageNotString, 31..33),
+                    (LoweringDiagnosticKind::TooManyAssertArgs, 76..93),
+                    (
+                        LoweringDiagnosticKind::UnknownDirective {
+                            name: i.intern("unreachable"),
+                        },
+                        111..130,
+                    ),
+                ]
+            },
+        )
+    }
+
+    #[test]
+    fn embed_missing_file() {
+        check(
+            r#"
+                foo :: #embed "missing.txt";
+                bar :: #embed;
+            "#,
+            expect![[r#"
+                main::foo :: <missing>;
+                main::bar :: <missing>;
+            "#]],
+            |_| {
+                [
+                    (
+                        LoweringDiagnosticKind::EmbedDoesNotExist {
+                            file: Path::new(env!("CARGO_MANIFEST_DIR"))
+                                .join("missing.txt")
+                                .to_string_lossy()
+                                .to_string(),
+                        },
+                        31..44,
+                    ),
+                    (LoweringDiagnosticKind::ExpectedEmbedFile, 69..75),
+                ]
+            },
+        )
+    }
 }
@@ -0,0 +1,339 @@
+use ast::{AstNode, AstToken};
+use interner::Interner;
+use rustc_hash::FxHashMap;
+use syntax::SyntaxTree;
+
+use crate::{body, LoweringDiagnostic, LoweringDiagnosticKind};
+
+/// Information about the target being compiled for, as well as any `-D name=value` defines
+/// given to the compiler.
+///
+/// Capy code can read these with directives like `#os` or `#def(NAME)`,
+/// and they are what `#if` conditions are evaluated against.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildConfig {
+    pub os: String,
+    pub arch: String,
+    pub pointer_bit_width: u32,
+    pub big_endian: bool,
+    pub defines: FxHashMap<String, ConfigValue>,
+}
+
+impl Default for BuildConfig {
+    /// the configuration of the host, with no defines
+    fn default() -> Self {
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            pointer_bit_width: usize::BITS,
+            big_endian: cfg!(target_endian = "big"),
+            defines: FxHashMap::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Bool(bool),
+    Int(u64),
+    String(String),
+}
+
+impl ConfigValue {
+    /// parses the `value` in `-D name=value`.
+    /// anything that isn't a bool or an integer is a string.
+    pub fn parse(text: &str) -> Self {
+        if let Ok(value) = text.parse() {
+            ConfigValue::Bool(value)
+        } else if let Ok(value) = text.replace('_', "").parse() {
+            ConfigValue::Int(value)
+        } else {
+            ConfigValue::String(text.to_string())
+        }
+    }
+}
+
+/// returns all the declarations which are reachable through the `#if` directives of the file.
+/// the `#if` directives themselves are never returned
+pub(crate) fn active_decls(
+    root: ast::Root,
+    tree: &SyntaxTree,
+    interner: &mut Interner,
+    config: &BuildConfig,
+    diagnostics: &mut Vec<LoweringDiagnostic>,
+) -> Vec<ast::Decl> {
+    let mut decls = Vec::new();
+
+    collect_decls(
+        root.decls(tree),
+        tree,
+        interner,
+        config,
+        diagnostics,
+        &mut decls,
+    );
+
+    decls
+}
+
+fn collect_decls(
+    decls: impl Iterator<Item = ast::Decl>,
+    tree: &SyntaxTree,
+    interner: &mut Interner,
+    config: &BuildConfig,
+    diagnostics: &mut Vec<LoweringDiagnostic>,
+    active: &mut Vec<ast::Decl>,
+) {
+    for decl in decls {
+        match decl {
+            ast::Decl::StaticIf(static_if) => {
+                let Some(block) = active_branch(static_if, tree, interner, config, diagnostics)
+                else {
+                    continue;
+                };
+
+                collect_decls(
+                    block.decls(tree),
+                    tree,
+                    interner,
+                    config,
+                    diagnostics,
+                    active,
+                );
+            }
+            _ => active.push(decl),
+        }
+    }
+}
+
+/// finds the block of an `#if` chain whose condition is true
+fn active_branch(
+    static_if: ast::StaticIf,
+    tree: &SyntaxTree,
+    interner: &mut Interner,
+    config: &BuildConfig,
+    diagnostics: &mut Vec<LoweringDiagnostic>,
+) -> Option<ast::DeclBlock> {
+    let condition = static_if.condition(tree)?;
+
+    let is_true = match eval_static(condition, tree, interner, config) {
+        Ok(ConfigValue::Bool(value)) => value,
+        Ok(_) => {
+            diagnostics.push(LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::StaticConditionNotBool,
+                range: condition.range(tree),
+            });
+            return None;
+        }
+        Err(diagnostic) => {
+            diagnostics.push(diagnostic);
+            return None;
+        }
+    };
+
+    if is_true {
+        return static_if.body(tree);
+    }
+
+    let else_branch = static_if.else_branch(tree)?;
+
+    match else_branch.static_if(tree) {
+        Some(else_if) => active_branch(else_if, tree, interner, config, diagnostics),
+        None => else_branch.body(tree),
+    }
+}
+
+/// evaluates an expression which must be known before type checking,
+/// like the condition of an `#if`
+pub(crate) fn eval_static(
+    expr: ast::Expr,
+    tree: &SyntaxTree,
+    interner: &mut Interner,
+    config: &BuildConfig,
+) -> Result<ConfigValue, LoweringDiagnostic> {
+    let error = |kind| LoweringDiagnostic {
+        kind,
+        range: expr.range(tree),
+    };
+
+    match expr {
+        ast::Expr::BoolLiteral(bool_literal) => {
+            Ok(ConfigValue::Bool(bool_literal.text(tree).parse().unwrap()))
+        }
+        ast::Expr::IntLiteral(int_literal) => int_literal
+            .value(tree)
+            .and_then(|value| body::int_value(value, tree))
+            .map(ConfigValue::Int)
+            .ok_or_else(|| error(LoweringDiagnosticKind::OutOfRangeIntLiteral)),
+        ast::Expr::StringLiteral(string_literal) => {
+            let mut text = String::new();
+
+            for component in string_literal.components(tree) {
+                match component {
+                    ast::StringComponent::Escape(escape) => {
+                        let escape_char = escape.text(tree).chars().nth(1).unwrap();
+
+                        match body::unescape(escape_char) {
+                            Some(ch) => text.push(ch),
+                            None => {
+                                return Err(LoweringDiagnostic {
+                                    kind: LoweringDiagnosticKind::InvalidEscape,
+                                    range: escape.range(tree),
+                                })
+                            }
+                        }
+                    }
+                    ast::StringComponent::Contents(contents) => {
+                        text.push_str(contents.text(tree));
+                    }
+                }
+            }
+
+            Ok(ConfigValue::String(text))
+        }
+        ast::Expr::Paren(paren_expr) => match paren_expr.expr(tree) {
+            Some(inner) => eval_static(inner, tree, interner, config),
+            None => Err(error(LoweringDiagnosticKind::NonStaticCondition)),
+        },
+        ast::Expr::Directive(directive) => eval_directive(directive, tree, interner, config),
+        ast::Expr::Unary(unary_expr) => {
+            let (Some(ast::UnaryOp::LNot(_)), Some(operand)) =
+                (unary_expr.op(tree), unary_expr.expr(tree))
+            else {
+                return Err(error(LoweringDiagnosticKind::NonStaticCondition));
+            };
+
+            match eval_static(operand, tree, interner, config)? {
+                ConfigValue::Bool(value) => Ok(ConfigValue::Bool(!value)),
+                _ => Err(error(LoweringDiagnosticKind::StaticConditionMismatch)),
+            }
+        }
+        ast::Expr::Binary(binary_expr) => {
+            let (Some(op), Some(lhs), Some(rhs)) = (
+                binary_expr.op(tree),
+                binary_expr.lhs(tree),
+                binary_expr.rhs(tree),
+            ) else {
+                return Err(error(LoweringDiagnosticKind::NonStaticCondition));
+            };
+
+            let lhs = eval_static(lhs, tree, interner, config)?;
+
+            // `&&` and `||` short circuit so that `#defined(FOO) && #def(FOO) > 1` works
+            match (op, &lhs) {
+                (ast::BinaryOp::LAnd(_), ConfigValue::Bool(false)) => {
+                    return Ok(ConfigValue::Bool(false))
+                }
+                (ast::BinaryOp::LOr(_), ConfigValue::Bool(true)) => {
+                    return Ok(ConfigValue::Bool(true))
+                }
+                _ => {}
+            }
+
+            let rhs = eval_static(rhs, tree, interner, config)?;
+
+            let result = match (op, lhs, rhs) {
+                (ast::BinaryOp::Eq(_), lhs, rhs) if same_kind(&lhs, &rhs) => lhs == rhs,
+                (ast::BinaryOp::Ne(_), lhs, rhs) if same_kind(&lhs, &rhs) => lhs != rhs,
+                (ast::BinaryOp::Lt(_), ConfigValue::Int(lhs), ConfigValue::Int(rhs)) => lhs < rhs,
+                (ast::BinaryOp::Gt(_), ConfigValue::Int(lhs), ConfigValue::Int(rhs)) => lhs > rhs,
+                (ast::BinaryOp::Le(_), ConfigValue::Int(lhs), ConfigValue::Int(rhs)) => lhs <= rhs,
+                (ast::BinaryOp::Ge(_), ConfigValue::Int(lhs), ConfigValue::Int(rhs)) => lhs >= rhs,
+                (
+                    ast::BinaryOp::LAnd(_) | ast::BinaryOp::LOr(_),
+                    ConfigValue::Bool(_),
+                    ConfigValue::Bool(rhs),
+                ) => rhs,
+                (
+                    ast::BinaryOp::Eq(_)
+                    | ast::BinaryOp::Ne(_)
+                    | ast::BinaryOp::Lt(_)
+                    | ast::BinaryOp::Gt(_)
+                    | ast::BinaryOp::Le(_)
+                    | ast::BinaryOp::Ge(_)
+                    | ast::BinaryOp::LAnd(_)
+                    | ast::BinaryOp::LOr(_),
+                    _,
+                    _,
+                ) => return Err(error(LoweringDiagnosticKind::StaticConditionMismatch)),
+                _ => return Err(error(LoweringDiagnosticKind::NonStaticCondition)),
+            };
+
+            Ok(ConfigValue::Bool(result))
+        }
+        _ => Err(error(LoweringDiagnosticKind::NonStaticCondition)),
+    }
+}
+
+fn same_kind(lhs: &ConfigValue, rhs: &ConfigValue) -> bool {
+    std::mem::discriminant(lhs) == std::mem::discriminant(rhs)
+}
+
+/// evaluates directives like `#os`, `#pointer_width`, or `#def(NAME)`
+pub(crate) fn eval_directive(
+    directive: ast::Directive,
+    tree: &SyntaxTree,
+    interner: &mut Interner,
+    config: &BuildConfig,
+) -> Result<ConfigValue, LoweringDiagnostic> {
+    let Some(name) = directive.name(tree) else {
+        return Err(LoweringDiagnostic {
+            kind: LoweringDiagnosticKind::NonStaticCondition,
+            range: directive.range(tree),
+        });
+    };
+
+    let error = |kind| LoweringDiagnostic {
+        kind,
+        range: directive.range(tree),
+    };
+
+    let takes_define = matches!(name.text(tree), "def" | "defined");
+
+    let args = directive
+        .arg_list(tree)
+        .map(|arg_list| arg_list.args(tree).collect::<Vec<_>>());
+
+    if !takes_define && (args.is_some() || directive.file(tree).is_some()) {
+        return Err(error(LoweringDiagnosticKind::DirectiveTakesNoArgs {
+            name: interner.intern(name.text(tree)),
+        }));
+    }
+
+    match name.text(tree) {
+        "os" => Ok(ConfigValue::String(config.os.clone())),
+        "arch" => Ok(ConfigValue::String(config.arch.clone())),
+        "pointer_width" => Ok(ConfigValue::Int(config.pointer_bit_width as u64)),
+        "endian" => Ok(ConfigValue::String(
+            if config.big_endian { "big" } else { "little" }.to_string(),
+        )),
+        "def" | "defined" => {
+            let define = match args.as_deref() {
+                Some([arg]) => match arg.value(tree) {
+                    Some(ast::Expr::VarRef(var_ref)) => var_ref.name(tree),
+                    _ => None,
+                },
+                _ => None,
+            };
+            let Some(define) = define else {
+                return Err(error(LoweringDiagnosticKind::ExpectedDefineName));
+            };
+
+            let value = config.defines.get(define.text(tree));
+
+            if name.text(tree) == "defined" {
+                return Ok(ConfigValue::Bool(value.is_some()));
+            }
+
+            value.cloned().ok_or_else(|| LoweringDiagnostic {
+                kind: LoweringDiagnosticKind::UndefinedDefine {
+                    name: interner.intern(define.text(tree)),
+                },
+                range: define.range(tree),
+            })
+        }
+        other => Err(error(LoweringDiagnosticKind::UnknownDirective {
+            name: interner.intern(other),
+        })),
+    }
+}
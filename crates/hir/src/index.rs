@@ -4,7 +4,7 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use syntax::SyntaxTree;
 use text_size::{TextRange, TextSize};
 
-use crate::Name;
+use crate::{config, BuildConfig, Name};
 
 #[derive(Clone, Debug)]
 pub struct Index {
@@ -53,7 +53,11 @@ pub fn index(
     root: ast::Root,
     tree: &SyntaxTree,
     interner: &mut Interner,
+    config: &BuildConfig,
 ) -> (Index, Vec<IndexingDiagnostic>) {
+    // any problems with `#if` conditions are reported during lowering
+    let decls = config::active_decls(root, tree, interner, config, &mut Vec::new());
+
     let mut ctx = IndexingCtx {
         index: Index {
             definitions: FxHashSet::default(),
@@ -64,8 +68,10 @@ pub fn index(
         interner,
     };
 
-    for def in root.defs(tree) {
-        ctx.index_def(def);
+    for decl in decls {
+        if let ast::Decl::Define(def) = decl {
+            ctx.index_def(def);
+        }
     }
 
     ctx.index.shrink_to_fit();
@@ -164,7 +170,8 @@ mod tests {
         let tokens = lexer::lex(input);
         let tree = parser::parse_source_file(&tokens, input).into_syntax_tree();
         let root = ast::Root::cast(tree.root(), &tree).unwrap();
-        let (index, actual_diagnostics) = index(root, &tree, &mut interner);
+        let (index, actual_diagnostics) =
+            index(root, &tree, &mut interner, &BuildConfig::default());
 
         expect.assert_eq(&index.debug(&interner));
 
@@ -269,4 +276,22 @@ mod tests {
             },
         )
     }
+
+    #[test]
+    fn definitions_in_static_if() {
+        check(
+            r#"
+                #if #pointer_width == 0 {
+                    foo :: 5;
+                    bar :: 6;
+                } else {
+                    foo :: "Hello";
+                }
+            "#,
+            expect![[r"
+                foo
+            "]],
+            |_| [],
+        )
+    }
 }
@@ -1,4 +1,5 @@
 mod body;
+mod config;
 mod index;
 mod subdir;
 mod world_index;
@@ -10,6 +11,7 @@ use std::{
 
 use ast::AstToken;
 pub use body::*;
+pub use config::{BuildConfig, ConfigValue};
 pub use index::*;
 use subdir::SubDir;
 use syntax::SyntaxTree;
@@ -153,6 +155,9 @@ pub enum PrimitiveTy {
     String {
         range: TextRange,
     },
+    CString {
+        range: TextRange,
+    },
     Char {
         range: TextRange,
     },
@@ -175,6 +180,7 @@ impl PrimitiveTy {
             | PrimitiveTy::Float { range, .. }
             | PrimitiveTy::Bool { range }
             | PrimitiveTy::String { range }
+            | PrimitiveTy::CString { range }
             | PrimitiveTy::Char { range }
             | PrimitiveTy::Type { range }
             | PrimitiveTy::Any { range }
@@ -269,6 +275,8 @@ impl PrimitiveTy {
                 Some(PrimitiveTy::Bool { range })
             } else if key == Key::str() {
                 Some(PrimitiveTy::String { range })
+            } else if key == Key::cstr() {
+                Some(PrimitiveTy::CString { range })
             } else if key == Key::char() {
                 Some(PrimitiveTy::Char { range })
             } else if key == Key::r#type() {
@@ -276,13 +284,31 @@ impl PrimitiveTy {
             } else if key == Key::any() {
                 Some(PrimitiveTy::Any { range })
             } else {
-                None
+                Self::parse_int_with_bit_width(ident.text(tree), range)
             }
         } else {
             None
         }
     }
 
+    /// any other int width, from `u1` and `i1` all the way up to `u128` and `i128`
+    fn parse_int_with_bit_width(text: &str, range: TextRange) -> Option<Self> {
+        let digits = text.strip_prefix(['i', 'u'])?;
+
+        // `i0` and `u08` aren't valid
+        if digits.starts_with('0') || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        let bit_width = digits.parse::<u8>().ok().filter(|width| *width <= 128)?;
+
+        if text.starts_with('i') {
+            Some(PrimitiveTy::IInt { bit_width, range })
+        } else {
+            Some(PrimitiveTy::UInt { bit_width, range })
+        }
+    }
+
     pub fn display(&self) -> String {
         match self {
             Self::IInt { bit_width, .. } => {
@@ -302,6 +328,7 @@ impl PrimitiveTy {
             Self::Float { bit_width, .. } => format!("f{}", bit_width),
             Self::Bool { .. } => "bool".to_string(),
             Self::String { .. } => "str".to_string(),
+            Self::CString { .. } => "cstr".to_string(),
             Self::Char { .. } => "char".to_string(),
             Self::Type { .. } => "type".to_string(),
             Self::Any { .. } => "any".to_string(),
@@ -0,0 +1,421 @@
+use hir::{Expr, LocalDef, ScopeId, Stmt};
+use la_arena::Idx;
+use rustc_hash::FxHashMap;
+use text_size::TextRange;
+
+use crate::FileInference;
+
+/// a local or a parameter, which can hold on to a pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Var {
+    Local(Idx<LocalDef>),
+    Param(u32),
+}
+
+/// where a pointer to the stack ends up once it leaves the function
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Sink {
+    Return,
+    Global(hir::Fqn),
+    Param(u32),
+}
+
+pub(crate) struct Escape {
+    /// the `^x` (or closure) which points to the stack
+    pub(crate) reference: Idx<Expr>,
+    /// the variables the pointer was passed through before it escaped, in order
+    pub(crate) path: Vec<Var>,
+    pub(crate) sink: Sink,
+    /// the return, or the assignment, where it escaped
+    pub(crate) range: TextRange,
+}
+
+/// finds the places in a function where a pointer to one of its locals or parameters
+/// (e.g. `^x`, or a closure capturing them) leaves the function, either by being returned,
+/// stored in a global, or stored through a pointer it was given as a parameter.
+///
+/// this only looks at the function itself, so a pointer given to another function won't be
+/// followed. each `^x` or closure is only reported once
+pub(crate) fn find_escaping_refs(
+    bodies: &hir::Bodies,
+    tys: &FileInference,
+    file: hir::FileName,
+    body: Idx<Expr>,
+) -> Vec<Escape> {
+    let mut checker = EscapeChecker {
+        bodies,
+        tys,
+        file,
+        body_scope: bodies.block_to_scope_id(body),
+        held: FxHashMap::default(),
+        changed: false,
+        escapes: None,
+    };
+
+    // pointers can be passed around in loops, so keep going until nothing new is learned
+    loop {
+        checker.changed = false;
+        checker.walk(body);
+
+        if !checker.changed {
+            break;
+        }
+    }
+
+    checker.escapes = Some(FxHashMap::default());
+    checker.walk(body);
+
+    if let Expr::Block {
+        tail_expr: Some(tail_expr),
+        ..
+    } = bodies[body]
+    {
+        checker.flow_into_sink(tail_expr, Sink::Return, bodies.range_for_expr(tail_expr));
+    }
+
+    let mut escapes = checker.escapes.unwrap().into_values().collect::<Vec<_>>();
+    escapes.sort_by_key(|escape| escape.reference);
+
+    escapes
+}
+
+struct EscapeChecker<'a> {
+    bodies: &'a hir::Bodies,
+    tys: &'a FileInference,
+    file: hir::FileName,
+    /// `return` breaks out of this scope
+    body_scope: Option<ScopeId>,
+    /// the pointers to the stack which each variable might be holding,
+    /// along with the path each pointer took to get there
+    held: FxHashMap<Var, FxHashMap<Idx<Expr>, Vec<Var>>>,
+    changed: bool,
+    /// this is only `Some` once everything that the variables could hold is known
+    escapes: Option<FxHashMap<Idx<Expr>, Escape>>,
+}
+
+/// what gets written to by an assignment
+enum Dest {
+    Var(Var),
+    Sink(Sink),
+    Unknown,
+}
+
+impl EscapeChecker<'_> {
+    fn is_pointer(&self, expr: Idx<Expr>) -> bool {
+        self.tys
+            .expr_tys
+            .get(expr)
+            .is_some_and(|ty| ty.is_pointer())
+    }
+
+    fn is_array(&self, expr: Idx<Expr>) -> bool {
+        self.tys.expr_tys.get(expr).is_some_and(|ty| ty.is_array())
+    }
+
+    /// whether `^expr` would point to memory owned by this function
+    fn is_stack_place(&self, expr: Idx<Expr>) -> bool {
+        match &self.bodies[expr] {
+            Expr::Local(_) | Expr::Param { .. } => true,
+            Expr::Paren(Some(inner)) | Expr::TupleItem { tuple: inner, .. } => {
+                self.is_stack_place(*inner)
+            }
+            Expr::Member { previous, .. } => {
+                !self.is_pointer(*previous) && self.is_stack_place(*previous)
+            }
+            Expr::Index { source, .. } => self.is_array(*source) && self.is_stack_place(*source),
+            _ => false,
+        }
+    }
+
+    /// the pointers to the stack that `expr` might evaluate to
+    fn pointers(&self, expr: Idx<Expr>) -> FxHashMap<Idx<Expr>, Vec<Var>> {
+        match &self.bodies[expr] {
+            Expr::Ref { expr: inner, .. } if self.is_stack_place(*inner) => {
+                FxHashMap::from_iter([(expr, Vec::new())])
+            }
+            // the environment of a closure, which holds its captures (or pointers to them),
+            // is stored on the stack
+            Expr::Lambda(lambda) if self.bodies[*lambda].is_closure => {
+                FxHashMap::from_iter([(expr, Vec::new())])
+            }
+            Expr::Local(local_def) => self
+                .held
+                .get(&Var::Local(*local_def))
+                .cloned()
+                .unwrap_or_default(),
+            Expr::Param { idx, .. } => self
+                .held
+                .get(&Var::Param(*idx))
+                .cloned()
+                .unwrap_or_default(),
+            Expr::Paren(Some(inner))
+            | Expr::Cast { expr: inner, .. }
+            | Expr::Unwrap {
+                optional: inner, ..
+            }
+            | Expr::TupleItem { tuple: inner, .. }
+            | Expr::Block {
+                tail_expr: Some(inner),
+                ..
+            } => self.pointers(*inner),
+            // a struct or array holding a pointer to the stack
+            Expr::Member { previous, .. } if !self.is_pointer(*previous) => {
+                self.pointers(*previous)
+            }
+            Expr::Index { source, .. } if self.is_array(*source) => self.pointers(*source),
+            Expr::If {
+                body, else_branch, ..
+            } => {
+                let mut pointers = self.pointers(*body);
+                if let Some(else_branch) = else_branch {
+                    for (reference, path) in self.pointers(*else_branch) {
+                        pointers.entry(reference).or_insert(path);
+                    }
+                }
+                pointers
+            }
+            Expr::ArrayLiteral { items, .. } | Expr::Tuple { items } => {
+                let mut pointers = FxHashMap::default();
+                for item in items {
+                    for (reference, path) in self.pointers(*item) {
+                        pointers.entry(reference).or_insert(path);
+                    }
+                }
+                pointers
+            }
+            Expr::StructLiteral { members, .. } => {
+                let mut pointers = FxHashMap::default();
+                for (_, value) in members {
+                    for (reference, path) in self.pointers(*value) {
+                        pointers.entry(reference).or_insert(path);
+                    }
+                }
+                pointers
+            }
+            _ => FxHashMap::default(),
+        }
+    }
+
+    /// the variable or global that `expr` belongs to when it's written to
+    fn dest(&self, expr: Idx<Expr>) -> Dest {
+        match &self.bodies[expr] {
+            Expr::Local(local_def) => Dest::Var(Var::Local(*local_def)),
+            Expr::Param { idx, .. } => Dest::Var(Var::Param(*idx)),
+            Expr::LocalGlobal(name) => Dest::Sink(Sink::Global(hir::Fqn {
+                file: self.file,
+                name: name.name,
+            })),
+            Expr::Paren(Some(inner)) | Expr::TupleItem { tuple: inner, .. } => self.dest(*inner),
+            Expr::Member { previous, field } => {
+                if let Some(crate::Ty::File(file)) =
+                    self.tys.expr_tys.get(*previous).map(|ty| ty.as_ref())
+                {
+                    Dest::Sink(Sink::Global(hir::Fqn {
+                        file: *file,
+                        name: field.name,
+                    }))
+                } else if self.is_pointer(*previous) {
+                    self.pointee_dest(*previous)
+                } else {
+                    self.dest(*previous)
+                }
+            }
+            Expr::Index { source, .. } => {
+                if self.is_array(*source) {
+                    self.dest(*source)
+                } else {
+                    self.pointee_dest(*source)
+                }
+            }
+            Expr::Deref { pointer } => self.pointee_dest(*pointer),
+            _ => Dest::Unknown,
+        }
+    }
+
+    /// where writing through the pointer `expr` ends up
+    fn pointee_dest(&self, expr: Idx<Expr>) -> Dest {
+        match self.dest(expr) {
+            // the caller owns whatever the parameter points to
+            Dest::Var(Var::Param(idx)) => Dest::Sink(Sink::Param(idx)),
+            Dest::Sink(sink) => Dest::Sink(sink),
+            Dest::Var(Var::Local(_)) | Dest::Unknown => Dest::Unknown,
+        }
+    }
+
+    fn flow_into_var(&mut self, value: Idx<Expr>, var: Var) {
+        let pointers = self.pointers(value);
+        let held = self.held.entry(var).or_default();
+
+        for (reference, mut path) in pointers {
+            if held.contains_key(&reference) {
+                continue;
+            }
+
+            path.push(var);
+            held.insert(reference, path);
+            self.changed = true;
+        }
+    }
+
+    fn flow_into_sink(&mut self, value: Idx<Expr>, sink: Sink, range: TextRange) {
+        let pointers = self.pointers(value);
+
+        let Some(escapes) = &mut self.escapes else {
+            return;
+        };
+
+        for (reference, path) in pointers {
+            escapes.entry(reference).or_insert(Escape {
+                reference,
+                path,
+                sink,
+                range,
+            });
+        }
+    }
+
+    fn stmt(&mut self, stmt: Idx<Stmt>) {
+        let bodies = self.bodies;
+
+        match bodies[stmt] {
+            Stmt::Expr(expr) => self.walk(expr),
+            Stmt::LocalDef(local_def) => {
+                if let Some(value) = bodies[local_def].value {
+                    self.walk(value);
+                    self.flow_into_var(value, Var::Local(local_def));
+                }
+            }
+            Stmt::Assign(assign) => {
+                let assign = &bodies[assign];
+
+                self.walk(assign.value);
+                self.walk(assign.dest);
+
+                match self.dest(assign.dest) {
+                    Dest::Var(var) => self.flow_into_var(assign.value, var),
+                    Dest::Sink(sink) => self.flow_into_sink(assign.value, sink, assign.range),
+                    Dest::Unknown => {}
+                }
+            }
+            Stmt::Break {
+                label,
+                value,
+                range,
+            } => {
+                if let Some(value) = value {
+                    self.walk(value);
+
+                    if label.is_some() && label == self.body_scope {
+                        self.flow_into_sink(value, Sink::Return, range);
+                    }
+                }
+            }
+            // deferred expressions are walked at the end of their block
+            Stmt::Continue { .. } | Stmt::Defer { .. } => {}
+        }
+    }
+
+    /// goes through every statement within `expr`
+    fn walk(&mut self, expr: Idx<Expr>) {
+        let bodies = self.bodies;
+
+        match &bodies[expr] {
+            Expr::Block { stmts, tail_expr } => {
+                for stmt in stmts {
+                    self.stmt(*stmt);
+                }
+                if let Some(tail_expr) = tail_expr {
+                    self.walk(*tail_expr);
+                }
+                for stmt in stmts.iter().rev() {
+                    if let Stmt::Defer { expr, .. } = bodies[*stmt] {
+                        self.walk(expr);
+                    }
+                }
+            }
+            Expr::If {
+                condition,
+                body,
+                else_branch,
+                ..
+            } => {
+                self.walk(*condition);
+                self.walk(*body);
+                if let Some(else_branch) = else_branch {
+                    self.walk(*else_branch);
+                }
+            }
+            Expr::While { condition, body } => {
+                if let Some(condition) = condition {
+                    self.walk(*condition);
+                }
+                self.walk(*body);
+            }
+            Expr::Cast { expr: inner, .. }
+            | Expr::Ref { expr: inner, .. }
+            | Expr::Deref { pointer: inner }
+            | Expr::Unwrap {
+                optional: inner, ..
+            }
+            | Expr::Paren(Some(inner))
+            | Expr::TupleItem { tuple: inner, .. }
+            | Expr::Unary { expr: inner, .. }
+            | Expr::Member {
+                previous: inner, ..
+            } => self.walk(*inner),
+            Expr::Binary { lhs, rhs, .. } => {
+                self.walk(*lhs);
+                self.walk(*rhs);
+            }
+            Expr::Index { source, index } => {
+                self.walk(*source);
+                self.walk(*index);
+            }
+            Expr::Slice { source, start, end } => {
+                self.walk(*source);
+                for bound in start.iter().chain(end) {
+                    self.walk(*bound);
+                }
+            }
+            Expr::ArrayLiteral { items, .. } | Expr::Tuple { items } => {
+                for item in items {
+                    self.walk(*item);
+                }
+            }
+            Expr::StructLiteral { members, .. } => {
+                for (_, value) in members {
+                    self.walk(*value);
+                }
+            }
+            Expr::Call { callee, args, .. } => {
+                self.walk(*callee);
+                for arg in args {
+                    self.walk(*arg);
+                }
+            }
+            // lambdas are checked on their own, and comptime blocks don't run with the function
+            Expr::Lambda(_) | Expr::Comptime(_) => {}
+            Expr::Missing
+            | Expr::IntLiteral(_)
+            | Expr::FloatLiteral(_)
+            | Expr::BoolLiteral(_)
+            | Expr::NullLiteral
+            | Expr::StringLiteral(_)
+            | Expr::Embed(_)
+            | Expr::CharLiteral(_)
+            | Expr::Paren(None)
+            | Expr::Local(_)
+            | Expr::LocalGlobal(_)
+            | Expr::Param { .. }
+            | Expr::Capture { .. }
+            | Expr::PrimitiveTy(_)
+            | Expr::ArrayDecl { .. }
+            | Expr::Distinct { .. }
+            | Expr::OptionalDecl { .. }
+            | Expr::StructDecl { .. }
+            | Expr::UnionDecl { .. }
+            | Expr::InterfaceDecl { .. }
+            | Expr::Import(_) => {}
+        }
+    }
+}
@@ -11,9 +11,11 @@ use text_size::TextRange;
 use topo::TopoSort;
 
 use crate::{
-    ty::BinaryOutput, ComptimeResult, EvalComptimeFn, InferResult, Inferrable, ProjectInference,
-    Ty, TyDiagnostic, TyDiagnosticHelp, TyDiagnosticHelpKind, TyDiagnosticKind, TypedOp,
-    UnaryOutput,
+    escape::{Sink, Var},
+    ty::BinaryOutput,
+    CallArg, ComptimeResult, DefaultValue, EvalComptimeFn, InferResult, Inferrable, InternTyExt,
+    MethodCall, ProjectInference, RefEscape, Ty, TyDiagnostic, TyDiagnosticHelp,
+    TyDiagnosticHelpKind, TyDiagnosticKind, TypedOp, UnaryOutput,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,6 +42,7 @@ enum ExprMutability {
     NotMutatingRefThroughDeref(TextRange),
     ImmutableRef(TextRange),
     ImmutableParam(TextRange, bool),
+    ImmutableCapture(TextRange, bool),
     ImmutableGlobal(TextRange),
     CannotMutate(TextRange),
 }
@@ -63,6 +66,10 @@ impl ExprMutability {
                 kind: TyDiagnosticHelpKind::ImmutableParam { assignment },
                 range,
             }),
+            ExprMutability::ImmutableCapture(range, by_value) => Some(TyDiagnosticHelp {
+                kind: TyDiagnosticHelpKind::ImmutableCapture { by_value },
+                range,
+            }),
             ExprMutability::ImmutableGlobal(range) => Some(TyDiagnosticHelp {
                 kind: TyDiagnosticHelpKind::ImmutableGlobal,
                 range,
@@ -128,21 +135,75 @@ impl GlobalInferenceCtx<'_> {
             actual_ty = f64;
         }
 
-        if global && self.get_const(body).should_report_not_const() {
+        for (read, local_def) in
+            crate::uninit::find_uninit_reads(self.bodies, &self.tys[self.file], body)
+        {
             self.diagnostics.push(TyDiagnostic {
-                kind: TyDiagnosticKind::GlobalNotConst,
+                kind: TyDiagnosticKind::ReadBeforeWrite,
                 file: self.file,
-                range: self.bodies.range_for_expr(body),
-                expr: Some(body),
-                help: None,
+                expr: Some(read),
+                range: self.bodies.range_for_expr(read),
+                help: Some(TyDiagnosticHelp {
+                    kind: TyDiagnosticHelpKind::LeftUninitHere,
+                    range: self.bodies[local_def].range,
+                }),
             });
+        }
 
-            // println!("not const: {:#?}", &self.bodies[body]);
+        if let Inferrable::Lambda(fql) = self.current_inferring {
+            self.report_escaping_refs(fql, body);
+        }
+
+        if global && self.get_const(body).should_report_not_const() {
+            let Inferrable::Global(fqn) = self.current_inferring else {
+                unreachable!("only globals have their bodies finished as globals")
+            };
+
+            // this global will be initialized before the entry point runs
+            self.tys.runtime_globals.insert(fqn);
         }
 
         Ok(actual_ty)
     }
 
+    fn report_escaping_refs(&mut self, fql: FQLambda, body: Idx<Expr>) {
+        let params = &self.bodies[fql.lambda].params;
+        let var_name = |var: Var| match var {
+            Var::Local(local_def) => self.bodies[local_def].name.map(|name| name.0),
+            Var::Param(idx) => params[idx as usize].name.map(|name| name.0),
+        };
+
+        for escape in
+            crate::escape::find_escaping_refs(self.bodies, &self.tys[self.file], self.file, body)
+        {
+            let into = match escape.sink {
+                Sink::Return => RefEscape::Return,
+                Sink::Global(fqn) => RefEscape::Global(fqn),
+                Sink::Param(idx) => match params[idx as usize].name {
+                    Some(name) => RefEscape::Param(name.0),
+                    None => continue,
+                },
+            };
+
+            let path = escape.path.into_iter().filter_map(var_name).collect();
+
+            self.diagnostics.push(TyDiagnostic {
+                kind: if matches!(self.bodies[escape.reference], Expr::Lambda(_)) {
+                    TyDiagnosticKind::ClosureEscapes { path, into }
+                } else {
+                    TyDiagnosticKind::LocalRefEscapes { path, into }
+                },
+                file: self.file,
+                expr: Some(escape.reference),
+                range: self.bodies.range_for_expr(escape.reference),
+                help: Some(TyDiagnosticHelp {
+                    kind: TyDiagnosticHelpKind::EscapesHere,
+                    range: escape.range,
+                }),
+            });
+        }
+    }
+
     fn reinfer_usages(&mut self, usages: FxHashSet<Idx<hir::Stmt>>) {
         for usage in usages {
             match self.bodies[usage] {
@@ -182,6 +243,74 @@ impl GlobalInferenceCtx<'_> {
         }
     }
 
+    /// Like C, an untyped integer literal passed as a variadic argument becomes the first of
+    /// `i32`, `i64` and `u64` which can hold it. Returns `None` for anything else
+    fn var_arg_literal_ty(&self, expr: Idx<hir::Expr>) -> Option<Ty> {
+        if !matches!(
+            self.tys[self.file][expr].as_ref(),
+            Ty::IInt(0) | Ty::UInt(0)
+        ) {
+            return None;
+        }
+
+        let (num, negated) = match &self.bodies[expr] {
+            Expr::IntLiteral(num) => (*num, false),
+            Expr::Unary {
+                expr: inner,
+                op: hir::UnaryOp::Neg,
+            } => match self.bodies[*inner] {
+                Expr::IntLiteral(num) => (num, true),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        Some(if negated {
+            if num <= 1 << 31 {
+                Ty::IInt(32)
+            } else {
+                Ty::IInt(64)
+            }
+        } else if num <= i32::MAX as u64 {
+            Ty::IInt(32)
+        } else if num <= i64::MAX as u64 {
+            Ty::IInt(64)
+        } else {
+            Ty::UInt(64)
+        })
+    }
+
+    /// makes sure the literal `num` (or `-num` when `expr` negates it) fits into `ty`
+    fn check_int_literal(&mut self, expr: Idx<hir::Expr>, num: u64, ty: Intern<Ty>, negated: bool) {
+        let kind = if negated {
+            match ty.get_min_int_size() {
+                Some(min_size) if num > min_size => TyDiagnosticKind::IntTooSmallForType {
+                    found: -(num as i128),
+                    min: -(min_size as i128),
+                    ty,
+                },
+                _ => return,
+            }
+        } else {
+            match ty.get_max_int_size() {
+                Some(max_size) if num > max_size => TyDiagnosticKind::IntTooBigForType {
+                    found: num,
+                    max: max_size,
+                    ty,
+                },
+                _ => return,
+            }
+        };
+
+        self.diagnostics.push(TyDiagnostic {
+            kind,
+            file: self.file,
+            expr: Some(expr),
+            range: self.bodies.range_for_expr(expr),
+            help: None,
+        });
+    }
+
     /// recursively replaces weakly-typed expressions with strong types.
     ///
     /// ```text
@@ -205,6 +334,16 @@ impl GlobalInferenceCtx<'_> {
         }
 
         let found_ty = self.tys[self.file].expr_tys[expr];
+
+        // `x : ?i32 = 5` should make the `5` an `i32`, it gets wrapped later.
+        // false is returned because `expr` still isn't of type `new_ty`
+        if let Some(sub_ty) = new_ty.as_optional() {
+            if !found_ty.is_optional() && *found_ty != Ty::Null {
+                self.replace_weak_tys(expr, sub_ty);
+                return false;
+            }
+        }
+
         if !found_ty.is_weak_replaceable_by(&new_ty) {
             return false;
         }
@@ -214,23 +353,7 @@ impl GlobalInferenceCtx<'_> {
         self.tys[self.file].expr_tys.insert(expr, new_ty);
 
         match expr_body {
-            Expr::IntLiteral(num) => {
-                if let Some(max_size) = new_ty.get_max_int_size() {
-                    if num > max_size {
-                        self.diagnostics.push(TyDiagnostic {
-                            kind: TyDiagnosticKind::IntTooBigForType {
-                                found: num,
-                                max: max_size,
-                                ty: new_ty,
-                            },
-                            file: self.file,
-                            expr: Some(expr),
-                            range: self.bodies.range_for_expr(expr),
-                            help: None,
-                        });
-                    }
-                }
-            }
+            Expr::IntLiteral(num) => self.check_int_literal(expr, num, new_ty, false),
             Expr::ArrayLiteral { ty: None, items } => {
                 let (_, sub_ty) = new_ty.as_array().unwrap();
 
@@ -241,6 +364,20 @@ impl GlobalInferenceCtx<'_> {
             Expr::Paren(Some(expr)) => {
                 self.replace_weak_tys(expr, new_ty);
             }
+            Expr::Tuple { items } => {
+                let item_tys = new_ty.as_tuple().unwrap();
+
+                for (item, item_ty) in items.into_iter().zip(item_tys) {
+                    self.replace_weak_tys(item, item_ty);
+                }
+            }
+            Expr::TupleItem { tuple, index, .. } => {
+                // `x, y := (1, 2); z : u8 = x;` should make the whole tuple `(u8, {uint})`
+                let mut item_tys = self.tys[self.file][tuple].as_tuple().unwrap();
+                item_tys[index as usize] = new_ty;
+
+                self.replace_weak_tys(tuple, Ty::Tuple { item_tys }.into());
+            }
             Expr::Block { tail_expr, .. } => {
                 if let Some(scope_id) = self.bodies.block_to_scope_id(expr) {
                     for usage in self.bodies.scope_id_usages(scope_id) {
@@ -317,10 +454,26 @@ impl GlobalInferenceCtx<'_> {
                     .into(),
                 );
             }
+            // the operands of overloaded operators don't share the type of the result
+            Expr::Binary { .. } | Expr::Unary { .. }
+                if self.tys[self.file].get_method_call(expr).is_some() => {}
             Expr::Binary { lhs, rhs, .. } => {
                 self.replace_weak_tys(lhs, new_ty);
                 self.replace_weak_tys(rhs, new_ty);
             }
+            // `-16` fits into an `i5` even though `16` doesn't
+            Expr::Unary {
+                expr: inner,
+                op: hir::UnaryOp::Neg,
+            } if new_ty.get_min_int_size().is_some() => match self.bodies[inner] {
+                Expr::IntLiteral(num) => {
+                    self.tys[self.file].expr_tys.insert(inner, new_ty);
+                    self.check_int_literal(expr, num, new_ty, true);
+                }
+                _ => {
+                    self.replace_weak_tys(inner, new_ty);
+                }
+            },
             Expr::Unary { expr, .. } => {
                 self.replace_weak_tys(expr, new_ty);
             }
@@ -350,8 +503,13 @@ impl GlobalInferenceCtx<'_> {
                 // self.reinfer_expr(self.bodies[local_def].value);
             }
             Expr::StructLiteral { members, .. } => {
-                let member_tys: FxHashMap<_, _> =
-                    new_ty.as_struct().unwrap().iter().copied().collect();
+                let member_tys: FxHashMap<_, _> = new_ty
+                    .as_struct()
+                    .or_else(|| new_ty.as_union())
+                    .unwrap()
+                    .iter()
+                    .copied()
+                    .collect();
 
                 for (name, value) in members.into_iter() {
                     let Some(name) = name else { continue };
@@ -377,12 +535,16 @@ impl GlobalInferenceCtx<'_> {
                 | Expr::Import(_)
                 | Expr::PrimitiveTy { .. }
                 | Expr::StructDecl { .. }
+                | Expr::UnionDecl { .. }
+                | Expr::InterfaceDecl { .. }
                 | Expr::Distinct { .. }
                 | Expr::Comptime(_)
                 | Expr::StringLiteral(_)
+                | Expr::Embed(_)
                 | Expr::IntLiteral(_)
                 | Expr::FloatLiteral(_)
-                | Expr::BoolLiteral(_) => ExprIsConst::Const,
+                | Expr::BoolLiteral(_)
+                | Expr::NullLiteral => ExprIsConst::Const,
                 Expr::ArrayLiteral { items, .. } if self.tys[file][expr].is_array() => {
                     to_check.extend(items.iter().map(|e| (file, *e)));
                     ExprIsConst::Const
@@ -472,6 +634,53 @@ impl GlobalInferenceCtx<'_> {
         ExprIsConst::Const
     }
 
+    /// default values get compiled wherever they're used, so they have to be constant
+    fn check_default_is_const(&mut self, default: Idx<Expr>) -> bool {
+        let expr_const = self.get_const(default);
+        if expr_const.should_report_not_const() {
+            self.diagnostics.push(TyDiagnostic {
+                kind: TyDiagnosticKind::DefaultNotConst,
+                file: self.file,
+                range: self.bodies.range_for_expr(default),
+                expr: Some(default),
+                help: None,
+            });
+        }
+        expr_const.is_const()
+    }
+
+    /// `mode := "r"` takes the type of its default value
+    fn param_ty(&mut self, param: &hir::Param) -> InferResult<Intern<Ty>> {
+        let Some(default) = param.default else {
+            return self.const_ty(param.ty);
+        };
+
+        // the default might not have been inferred yet
+        self.infer_expr(default)?;
+
+        let ty = if self.bodies[param.ty] == Expr::Missing {
+            // weak defaults are made strong the same way as globals
+            if !self.replace_weak_tys(default, Ty::IInt(32).into()) {
+                self.replace_weak_tys(default, Ty::Float(64).into());
+            }
+            let ty = self.tys[self.file][default];
+            self.tys[self.file].meta_tys.insert(param.ty, ty);
+            ty
+        } else {
+            let ty = self.const_ty(param.ty)?;
+            if self.expect_match(self.tys[self.file][default], ty, default)
+                && self.replace_weak_tys(default, ty)
+            {
+                self.tys[self.file].expr_tys.insert(default, ty);
+            }
+            ty
+        };
+
+        self.check_default_is_const(default);
+
+        Ok(ty)
+    }
+
     /// `deref` allows certain expressions to be mutable
     /// only if they are being mutated through a deref
     fn get_mutability(&self, expr: Idx<Expr>, assignment: bool, deref: bool) -> ExprMutability {
@@ -487,20 +696,57 @@ impl GlobalInferenceCtx<'_> {
                 _ => ExprMutability::ImmutableRef(self.bodies.range_for_expr(expr)),
             },
             Expr::Deref { pointer } => self.get_mutability(*pointer, assignment, true),
-            Expr::Index { source: array, .. } => self.get_mutability(
-                *array,
-                assignment,
-                deref || self.tys[self.file][*array].is_pointer(),
-            ),
+            // `op_index` returns a value, not a place
+            Expr::Index { .. } if self.tys[self.file].get_method_call(expr).is_some() => {
+                ExprMutability::CannotMutate(self.bodies.range_for_expr(expr))
+            }
+            Expr::Index { source: array, .. } => {
+                let mut array_ty = self.tys[self.file][*array];
+                while let Some((_, sub_ty)) = array_ty.as_pointer() {
+                    array_ty = sub_ty;
+                }
+
+                // the bytes of a string might be in read-only memory
+                if *array_ty == Ty::String {
+                    return ExprMutability::CannotMutate(self.bodies.range_for_expr(*array));
+                }
+
+                self.get_mutability(
+                    *array,
+                    assignment,
+                    deref || self.tys[self.file][*array].is_pointer(),
+                )
+            }
+            // the items of a slice are as mutable as the items it was taken from
+            Expr::Slice { source, .. } => {
+                let mut source_ty = self.tys[self.file][*source];
+                while let Some((_, sub_ty)) = source_ty.as_pointer() {
+                    source_ty = sub_ty;
+                }
+
+                if *source_ty == Ty::String {
+                    return ExprMutability::CannotMutate(self.bodies.range_for_expr(*source));
+                }
+
+                self.get_mutability(
+                    *source,
+                    assignment,
+                    deref || self.tys[self.file][*source].is_pointer(),
+                )
+            }
             Expr::Block {
                 tail_expr: Some(tail_expr),
                 ..
             } => self.get_mutability(*tail_expr, assignment, deref),
             Expr::Local(local_def) if deref => {
+                let local_ty = self.tys[self.file].local_tys[*local_def];
                 let local_def = &self.bodies[*local_def];
 
                 if let Some(value) = local_def.value {
                     self.get_mutability(value, false, deref)
+                } else if let Some((false, _)) = local_ty.as_pointer() {
+                    // the binding of `if x := foo { ... }` is a copy of the pointer inside `foo`
+                    ExprMutability::ImmutableRef(local_def.range)
                 } else {
                     // todo: does this make sense?
                     ExprMutability::Mutable
@@ -539,6 +785,31 @@ impl GlobalInferenceCtx<'_> {
                     _ => ExprMutability::ImmutableParam(*range, assignment),
                 }
             }
+            Expr::Capture { idx } => {
+                let capture = self.current_capture(*idx);
+
+                match self.tys[self.file][expr].as_pointer() {
+                    Some((mutable, _)) if deref => {
+                        if mutable {
+                            ExprMutability::Mutable
+                        } else {
+                            ExprMutability::ImmutableRef(self.bodies.range_for_expr(expr))
+                        }
+                    }
+                    _ if capture.mutable => ExprMutability::Mutable,
+                    _ if capture.by_value => ExprMutability::ImmutableCapture(capture.range, true),
+                    // point at whatever made the original immutable
+                    _ => match &self.bodies[capture.value] {
+                        Expr::Local(local_def) => {
+                            ExprMutability::ImmutableBinding(self.bodies[*local_def].range)
+                        }
+                        Expr::Param { range, .. } => {
+                            ExprMutability::ImmutableParam(*range, assignment)
+                        }
+                        _ => ExprMutability::ImmutableCapture(capture.range, false),
+                    },
+                }
+            }
             Expr::LocalGlobal(name) => {
                 let fqn = hir::Fqn {
                     file: self.file,
@@ -584,7 +855,7 @@ impl GlobalInferenceCtx<'_> {
                 }
             }
             Expr::Call { .. } if deref => ExprMutability::Mutable,
-            Expr::Cast { .. } if deref => {
+            Expr::Unwrap { .. } | Expr::Cast { .. } if deref => {
                 let ty = self.tys[self.file][expr];
 
                 match ty.as_pointer() {
@@ -613,6 +884,15 @@ impl GlobalInferenceCtx<'_> {
         }
     }
 
+    /// the captures can only be used inside of the closure which captured them
+    fn current_capture(&self, idx: u32) -> hir::Capture {
+        let Inferrable::Lambda(fql) = self.current_inferring else {
+            unreachable!("only lambdas can capture locals")
+        };
+
+        self.bodies[fql.lambda].captures[idx as usize]
+    }
+
     fn find_usages(&mut self, exprs: &[Idx<hir::Expr>], local_usage: Idx<hir::Stmt>) {
         let mut locals = HashSet::default();
         for expr in exprs {
@@ -719,6 +999,18 @@ impl GlobalInferenceCtx<'_> {
                                 .map(|(_, sub_ty)| sub_ty)
                                 .unwrap_or_else(|| Ty::Unknown.into())
                         }
+                        Expr::Unwrap { optional, .. } => self.tys[self.file][*optional]
+                            .as_optional()
+                            .unwrap_or_else(|| Ty::Unknown.into()),
+                        Expr::TupleItem { tuple, index, .. } => self.tys[self.file][*tuple]
+                            .as_tuple()
+                            .and_then(|item_tys| item_tys.get(*index as usize).copied())
+                            .unwrap_or_else(|| Ty::Unknown.into()),
+                        Expr::Binary { .. } | Expr::Unary { .. } | Expr::Index { .. }
+                            if self.tys[self.file].get_method_call(expr).is_some() =>
+                        {
+                            continue
+                        }
                         Expr::Binary { lhs, rhs, op } => {
                             let lhs_ty = self.tys[self.file][*lhs];
                             let rhs_ty = self.tys[self.file][*rhs];
@@ -754,6 +1046,24 @@ impl GlobalInferenceCtx<'_> {
                                 .or_else(|| source_ty.as_slice())
                                 .unwrap_or_else(|| Ty::Unknown.into())
                         }
+                        Expr::Slice { source, .. } => {
+                            let mut source_ty = self.tys[self.file][*source];
+
+                            while let Some(ptr) = source_ty.as_pointer() {
+                                source_ty = ptr.1;
+                            }
+
+                            if *source_ty == Ty::String {
+                                source_ty
+                            } else {
+                                source_ty
+                                    .as_array()
+                                    .map(|(_, sub_ty)| sub_ty)
+                                    .or_else(|| source_ty.as_slice())
+                                    .map(|sub_ty| Ty::Slice { sub_ty }.into())
+                                    .unwrap_or_else(|| Ty::Unknown.into())
+                            }
+                        }
                         Expr::Block { tail_expr, .. } => {
                             let tail_ty = tail_expr.map(|tail_expr| self.tys[self.file][tail_expr]);
 
@@ -873,10 +1183,42 @@ impl GlobalInferenceCtx<'_> {
             return Ok(*ty);
         }
 
-        let descendants = self.bodies.descendants(expr, hir::DescentOpts::Eval);
+        let descendants = self
+            .bodies
+            .descendants(expr, hir::DescentOpts::Eval)
+            .collect_vec();
+
+        // the condition of `if x := foo { ... }` comes before the body,
+        // so the type of `x` can be set as soon as the condition is known
+        let if_bindings: FxHashMap<_, _> = descendants
+            .iter()
+            .filter_map(|descendant| match descendant {
+                Descendant::Expr(expr) => match self.bodies[*expr] {
+                    Expr::If {
+                        condition,
+                        binding: Some(binding),
+                        ..
+                    } => Some((condition, binding)),
+                    _ => None,
+                },
+                Descendant::Stmt(_) => None,
+            })
+            .collect();
+
+        // `a.f` can only fall back to calling `f(a)` when it's being called
+        let method_callees: FxHashSet<_> = descendants
+            .iter()
+            .filter_map(|descendant| match descendant {
+                Descendant::Expr(expr) => match self.bodies[*expr] {
+                    Expr::Call { callee, .. } => Some(callee),
+                    _ => None,
+                },
+                Descendant::Stmt(_) => None,
+            })
+            .collect();
 
         // This all works because children will ALWAYS come before parents
-        for descendant in descendants.collect_vec().into_iter().rev() {
+        for descendant in descendants.into_iter().rev() {
             match descendant {
                 Descendant::Expr(expr) => {
                     if self.tys[self.file].expr_tys.contains_idx(expr)
@@ -890,7 +1232,14 @@ impl GlobalInferenceCtx<'_> {
                         Expr::IntLiteral(_) => Ty::UInt(0).into(),
                         Expr::FloatLiteral(_) => Ty::Float(0).into(),
                         Expr::BoolLiteral(_) => Ty::Bool.into(),
+                        Expr::NullLiteral => Ty::Null.into(),
                         Expr::StringLiteral(_) => Ty::String.into(),
+                        Expr::Embed(contents) => Ty::Array {
+                            anonymous: false,
+                            size: contents.len() as u64,
+                            sub_ty: Ty::UInt(8).into(),
+                        }
+                        .into(),
                         Expr::CharLiteral(_) => Ty::Char.into(),
                         Expr::ArrayDecl { .. } => {
                             self.const_ty(expr)?;
@@ -972,7 +1321,13 @@ impl GlobalInferenceCtx<'_> {
                             }
                             .into()
                         }
-                        Expr::Index { source, index } => {
+                        Expr::Index { source, index } => 'index: {
+                            if let Some(output_ty) =
+                                self.find_operator(expr, &[*source, *index], Some("op_index"))?
+                            {
+                                break 'index output_ty;
+                            }
+
                             let source_ty = self.tys[self.file][*source];
                             // because it's annoying to do `foo^[0]`, this code lets you do `foo[0]`
                             let mut deref_source_ty = source_ty;
@@ -998,6 +1353,7 @@ impl GlobalInferenceCtx<'_> {
                                                 index,
                                                 actual_size,
                                                 array_ty: source_ty,
+                                                is_slice_bound: false,
                                             },
                                             file: self.file,
                                             expr: Some(expr),
@@ -1048,6 +1404,114 @@ impl GlobalInferenceCtx<'_> {
                                 Ty::Unknown.into()
                             }
                         }
+                        Expr::Slice { source, start, end } => {
+                            let source_ty = self.tys[self.file][*source];
+                            let mut deref_source_ty = source_ty;
+                            while let Some((_, sub_ty)) = deref_source_ty.as_pointer() {
+                                deref_source_ty = sub_ty;
+                            }
+
+                            for bound in start.iter().chain(end) {
+                                let bound_ty = self.tys[self.file][*bound];
+
+                                if self.expect_match(bound_ty, Ty::UInt(u8::MAX).into(), *bound) {
+                                    self.replace_weak_tys(*bound, Ty::UInt(u8::MAX).into());
+                                }
+                            }
+
+                            let const_bound = |bound: Option<Idx<Expr>>| match bound
+                                .map(|bound| &self.bodies[bound])
+                            {
+                                Some(hir::Expr::IntLiteral(bound)) => Some(*bound),
+                                _ => None,
+                            };
+                            let const_start = const_bound(*start);
+                            let const_end = const_bound(*end);
+
+                            if let (Some(start), Some(end)) = (const_start, const_end) {
+                                if start > end {
+                                    self.diagnostics.push(TyDiagnostic {
+                                        kind: TyDiagnosticKind::SliceStartAfterEnd { start, end },
+                                        file: self.file,
+                                        expr: Some(expr),
+                                        range: self.bodies.range_for_expr(expr),
+                                        help: None,
+                                    });
+                                }
+                            }
+
+                            if *deref_source_ty == Ty::Unknown {
+                                Ty::Unknown.into()
+                            } else if *deref_source_ty == Ty::String {
+                                // a part of a string is still a string
+                                Ty::String.into()
+                            } else if let Some((actual_size, array_sub_ty)) =
+                                deref_source_ty.as_array()
+                            {
+                                for bound in const_start.into_iter().chain(const_end) {
+                                    if bound > actual_size {
+                                        self.diagnostics.push(TyDiagnostic {
+                                            kind: TyDiagnosticKind::IndexOutOfBounds {
+                                                index: bound,
+                                                actual_size,
+                                                array_ty: source_ty,
+                                                is_slice_bound: true,
+                                            },
+                                            file: self.file,
+                                            expr: Some(expr),
+                                            range: self.bodies.range_for_expr(expr),
+                                            help: None,
+                                        });
+                                    }
+                                }
+
+                                if array_sub_ty.is_any_type() {
+                                    self.diagnostics.push(TyDiagnostic {
+                                        kind: TyDiagnosticKind::IndexAny {
+                                            size: Some(actual_size),
+                                        },
+                                        file: self.file,
+                                        expr: Some(expr),
+                                        range: self.bodies.range_for_expr(expr),
+                                        help: None,
+                                    });
+
+                                    Ty::Unknown.into()
+                                } else {
+                                    Ty::Slice {
+                                        sub_ty: array_sub_ty,
+                                    }
+                                    .into()
+                                }
+                            } else if let Some(slice_sub_ty) = deref_source_ty.as_slice() {
+                                if slice_sub_ty.is_any_type() {
+                                    self.diagnostics.push(TyDiagnostic {
+                                        kind: TyDiagnosticKind::IndexAny { size: None },
+                                        file: self.file,
+                                        expr: Some(expr),
+                                        range: self.bodies.range_for_expr(expr),
+                                        help: None,
+                                    });
+
+                                    Ty::Unknown.into()
+                                } else {
+                                    Ty::Slice {
+                                        sub_ty: slice_sub_ty,
+                                    }
+                                    .into()
+                                }
+                            } else {
+                                self.diagnostics.push(TyDiagnostic {
+                                    kind: TyDiagnosticKind::IndexNonArray { found: source_ty },
+                                    file: self.file,
+                                    expr: Some(expr),
+                                    range: self.bodies.range_for_expr(expr),
+                                    help: None,
+                                });
+
+                                Ty::Unknown.into()
+                            }
+                        }
                         Expr::Cast { expr: sub_expr, ty } => {
                             let expr_ty = self.tys[self.file][*sub_expr];
 
@@ -1058,6 +1522,11 @@ impl GlobalInferenceCtx<'_> {
 
                                 if cast_ty.is_unknown() {
                                     Ty::Unknown.into()
+                                } else if cast_ty.is_interface() && !expr_ty.is_equal_to(&cast_ty) {
+                                    // the pointer stays a pointer, it just gets a vtable
+                                    self.find_interface_impls(expr, expr_ty, cast_ty)?;
+
+                                    cast_ty
                                 } else {
                                     if !expr_ty.can_cast_to(&cast_ty) {
                                         self.diagnostics.push(TyDiagnostic {
@@ -1070,6 +1539,18 @@ impl GlobalInferenceCtx<'_> {
                                             range: self.bodies.range_for_expr(expr),
                                             help: None,
                                         });
+                                    } else if *expr_ty.remove_distinct() == Ty::String
+                                        && *cast_ty.remove_distinct() == Ty::CString
+                                        && !matches!(self.bodies[*sub_expr], Expr::StringLiteral(_))
+                                    {
+                                        // only string literals are sure to end with a null
+                                        self.diagnostics.push(TyDiagnostic {
+                                            kind: TyDiagnosticKind::NonLiteralToCStr,
+                                            file: self.file,
+                                            expr: Some(expr),
+                                            range: self.bodies.range_for_expr(expr),
+                                            help: None,
+                                        });
                                     }
 
                                     // replacing the existing type with the casted type
@@ -1144,12 +1625,60 @@ impl GlobalInferenceCtx<'_> {
                                 }
                             }
                         }
+                        Expr::Unwrap { optional, .. } => {
+                            let optional_ty = self.tys[self.file][*optional];
+
+                            match optional_ty.as_optional() {
+                                Some(sub_ty) => sub_ty,
+                                None => {
+                                    if !optional_ty.is_unknown() {
+                                        self.diagnostics.push(TyDiagnostic {
+                                            kind: TyDiagnosticKind::UnwrapNonOptional {
+                                                found: optional_ty,
+                                            },
+                                            file: self.file,
+                                            expr: Some(expr),
+                                            range: self.bodies.range_for_expr(expr),
+                                            help: None,
+                                        });
+                                    }
+
+                                    Ty::Unknown.into()
+                                }
+                            }
+                        }
                         Expr::Binary { lhs, rhs, op } => {
                             let lhs_ty = self.tys[self.file][*lhs];
                             let rhs_ty = self.tys[self.file][*rhs];
 
-                            if let Some(output_ty) = op.get_possible_output_ty(&lhs_ty, &rhs_ty) {
-                                if *lhs_ty != Ty::Unknown
+                            if let Some(output_ty) =
+                                self.find_operator(expr, &[*lhs, *rhs], op.overload_name())?
+                            {
+                                // `a != b` negates the result of `op_eq`
+                                if *op == hir::BinaryOp::Ne
+                                    && !output_ty.is_unknown()
+                                    && *output_ty != Ty::Bool
+                                {
+                                    let function =
+                                        self.tys[self.file].get_method_call(expr).unwrap().fqn;
+
+                                    self.diagnostics.push(TyDiagnostic {
+                                        kind: TyDiagnosticKind::InvalidOperatorFunction {
+                                            function,
+                                            found: self.tys[function].0,
+                                        },
+                                        file: self.file,
+                                        expr: Some(expr),
+                                        range: self.bodies.range_for_expr(expr),
+                                        help: None,
+                                    });
+                                }
+
+                                output_ty
+                            } else if let Some(output_ty) =
+                                op.get_possible_output_ty(&lhs_ty, &rhs_ty)
+                            {
+                                if *lhs_ty != Ty::Unknown
                                     && *rhs_ty != Ty::Unknown
                                     && !op.can_perform(&output_ty.max_ty)
                                 {
@@ -1188,26 +1717,32 @@ impl GlobalInferenceCtx<'_> {
                                 op.default_ty().into()
                             }
                         }
-                        Expr::Unary { expr, op } => {
-                            let expr_ty = self.tys[self.file][*expr];
+                        Expr::Unary { expr: inner, op } => 'unary: {
+                            if let Some(output_ty) =
+                                self.find_operator(expr, &[*inner], op.overload_name())?
+                            {
+                                break 'unary output_ty;
+                            }
 
-                            if !op.can_perform(&expr_ty) {
+                            let inner_ty = self.tys[self.file][*inner];
+
+                            if !op.can_perform(&inner_ty) {
                                 self.diagnostics.push(TyDiagnostic {
                                     kind: TyDiagnosticKind::UnaryOpMismatch {
                                         op: *op,
-                                        ty: expr_ty,
+                                        ty: inner_ty,
                                     },
                                     file: self.file,
-                                    expr: Some(*expr),
-                                    range: self.bodies.range_for_expr(*expr),
+                                    expr: Some(*inner),
+                                    range: self.bodies.range_for_expr(*inner),
                                     help: None,
                                 });
 
                                 op.default_ty().into()
                             } else {
-                                let output = op.get_possible_output_ty(expr_ty);
+                                let output = op.get_possible_output_ty(inner_ty);
 
-                                self.replace_weak_tys(*expr, output);
+                                self.replace_weak_tys(*inner, output);
 
                                 output
                             }
@@ -1216,6 +1751,49 @@ impl GlobalInferenceCtx<'_> {
                             Some(expr) => self.tys[self.file][*expr],
                             None => Ty::Void.into(),
                         },
+                        Expr::Tuple { items } => {
+                            let item_tys: Vec<_> = items
+                                .iter()
+                                .map(|item| self.tys[self.file][*item])
+                                .collect();
+
+                            // `(i32, bool)` is a tuple type, not a tuple of types
+                            if !item_tys.is_empty() && item_tys.iter().all(|ty| **ty == Ty::Type) {
+                                self.const_ty(expr)?;
+
+                                Ty::Type.into()
+                            } else {
+                                Ty::Tuple { item_tys }.into()
+                            }
+                        }
+                        Expr::TupleItem { tuple, index, len } => {
+                            let tuple_ty = self.tys[self.file][*tuple];
+
+                            match tuple_ty.as_tuple() {
+                                Some(item_tys) if item_tys.len() == *len as usize => {
+                                    item_tys[*index as usize]
+                                }
+                                item_tys => {
+                                    // only report the mismatch once for the whole destructure
+                                    if *index == 0 && !tuple_ty.is_unknown() {
+                                        self.diagnostics.push(TyDiagnostic {
+                                            kind: TyDiagnosticKind::DestructureMismatch {
+                                                expected: *len,
+                                                found: tuple_ty,
+                                            },
+                                            file: self.file,
+                                            expr: Some(*tuple),
+                                            range: self.bodies.range_for_expr(*tuple),
+                                            help: None,
+                                        });
+                                    }
+
+                                    item_tys
+                                        .and_then(|item_tys| item_tys.get(*index as usize).copied())
+                                        .unwrap_or_else(|| Ty::Unknown.into())
+                                }
+                            }
+                        }
                         Expr::Block { stmts, tail_expr } => {
                             let label = self.bodies.block_to_scope_id(expr);
 
@@ -1312,9 +1890,20 @@ impl GlobalInferenceCtx<'_> {
                             condition,
                             body,
                             else_branch,
+                            binding,
                         } => {
                             let cond_ty = self.tys[self.file][*condition];
-                            self.expect_match(cond_ty, Ty::Bool.into(), *condition);
+                            if binding.is_none() {
+                                self.expect_match(cond_ty, Ty::Bool.into(), *condition);
+                            } else if !cond_ty.is_optional() && !cond_ty.is_unknown() {
+                                self.diagnostics.push(TyDiagnostic {
+                                    kind: TyDiagnosticKind::UnwrapNonOptional { found: cond_ty },
+                                    file: self.file,
+                                    expr: Some(*condition),
+                                    range: self.bodies.range_for_expr(*condition),
+                                    help: None,
+                                });
+                            }
 
                             let body_ty = self.tys[self.file][*body];
 
@@ -1397,6 +1986,10 @@ impl GlobalInferenceCtx<'_> {
                         }
                         Expr::Local(local) => self.tys[self.file].local_tys[*local],
                         Expr::Param { idx, .. } => self.param_tys[*idx as usize],
+                        Expr::Capture { idx } => {
+                            let capture = self.current_capture(*idx);
+                            self.tys[self.file][capture.value]
+                        }
                         Expr::LocalGlobal(name) => {
                             let fqn = hir::Fqn {
                                 file: self.file,
@@ -1477,9 +2070,13 @@ impl GlobalInferenceCtx<'_> {
                                         deref_ty = sub_ty;
                                     }
 
-                                    if let Some((_, ty)) = deref_ty.as_struct().and_then(|fields| {
-                                        fields.into_iter().find(|(name, _)| *name == field.name)
-                                    }) {
+                                    if let Some((_, ty)) = deref_ty
+                                        .as_struct()
+                                        .or_else(|| deref_ty.as_union())
+                                        .and_then(|fields| {
+                                            fields.into_iter().find(|(name, _)| *name == field.name)
+                                        })
+                                    {
                                         ty
                                     } else if let Some((sub_ty, field_name)) =
                                         deref_ty.as_slice().and_then(|sub_ty| {
@@ -1502,6 +2099,24 @@ impl GlobalInferenceCtx<'_> {
                                         && self.interner.lookup(field.name.0) == "len"
                                     {
                                         Ty::UInt(u8::MAX).into()
+                                    } else if let Some(method_ty) = deref_ty
+                                        .as_interface()
+                                        .filter(|_| method_callees.contains(&expr))
+                                        .and_then(|methods| {
+                                            methods
+                                                .into_iter()
+                                                .find(|(name, _)| *name == field.name)
+                                        })
+                                        .map(|(_, ty)| ty)
+                                    {
+                                        method_ty
+                                    } else if let Some(method_ty) = method_callees
+                                        .contains(&expr)
+                                        .then(|| self.find_method(expr, *previous, field.name))
+                                        .transpose()?
+                                        .flatten()
+                                    {
+                                        method_ty
                                     } else {
                                         if !previous_ty.is_unknown() {
                                             self.diagnostics.push(TyDiagnostic {
@@ -1521,11 +2136,39 @@ impl GlobalInferenceCtx<'_> {
                                 }
                             }
                         }
-                        Expr::Call { callee, args } => {
+                        Expr::Call {
+                            callee,
+                            args,
+                            arg_names,
+                        } => {
                             let callee_ty = self.tys[self.file][*callee];
 
-                            if let Some((params, return_ty)) = callee_ty.clone().as_function() {
-                                if params.len() != args.len() {
+                            if let Some((params, return_ty)) =
+                                callee_ty.as_function().or_else(|| callee_ty.as_closure())
+                            {
+                                let is_variadic = callee_ty.is_variadic_function();
+
+                                let ordered_args =
+                                    self.order_call_args(expr, *callee, args, arg_names);
+                                let call_args = match &ordered_args {
+                                    Some(ordered_args) => ordered_args.clone(),
+                                    None => {
+                                        args.iter().map(|arg| Some(CallArg::Given(*arg))).collect()
+                                    }
+                                };
+
+                                if is_variadic && call_args.len() < params.len() {
+                                    self.diagnostics.push(TyDiagnostic {
+                                        kind: TyDiagnosticKind::NotEnoughVarArgs {
+                                            found: args.len(),
+                                            expected: params.len(),
+                                        },
+                                        file: self.file,
+                                        expr: Some(expr),
+                                        range: self.bodies.range_for_expr(expr),
+                                        help: None,
+                                    });
+                                } else if !is_variadic && params.len() != call_args.len() {
                                     self.diagnostics.push(TyDiagnostic {
                                         kind: TyDiagnosticKind::MismatchedArgCount {
                                             found: args.len(),
@@ -1538,10 +2181,47 @@ impl GlobalInferenceCtx<'_> {
                                     });
                                 }
 
-                                for (idx, arg) in args.iter().enumerate() {
+                                if let Some(ordered_args) = ordered_args {
+                                    if let Some(ordered_args) =
+                                        ordered_args.into_iter().collect::<Option<Vec<_>>>()
+                                    {
+                                        self.tys[self.file].call_args.insert(expr, ordered_args);
+                                    }
+                                }
+
+                                for (idx, arg) in call_args.iter().enumerate() {
+                                    // default values have already been checked
+                                    let Some(CallArg::Given(arg)) = arg else {
+                                        continue;
+                                    };
                                     let arg_ty = self.tys[self.file][*arg];
 
                                     if idx >= params.len() {
+                                        if !is_variadic {
+                                            continue;
+                                        }
+
+                                        // the extra arguments of a variadic function get
+                                        // promoted the same way C would promote them
+                                        let promoted = self
+                                            .var_arg_literal_ty(*arg)
+                                            .or_else(|| arg_ty.var_arg_promotion());
+
+                                        match promoted {
+                                            Some(promoted) => {
+                                                self.replace_weak_tys(*arg, promoted.into());
+                                            }
+                                            None => self.diagnostics.push(TyDiagnostic {
+                                                kind: TyDiagnosticKind::InvalidVarArg {
+                                                    found: arg_ty,
+                                                },
+                                                file: self.file,
+                                                expr: Some(*arg),
+                                                range: self.bodies.range_for_expr(*arg),
+                                                help: None,
+                                            }),
+                                        }
+
                                         continue;
                                     }
                                     let param_ty = params[idx];
@@ -1574,6 +2254,9 @@ impl GlobalInferenceCtx<'_> {
                                 return_ty,
                                 body,
                                 is_extern,
+                                is_variadic,
+                                is_closure,
+                                captures,
                                 ..
                             } = &self.bodies[*lambda];
 
@@ -1581,6 +2264,14 @@ impl GlobalInferenceCtx<'_> {
                                 && return_ty.is_some()
                                 && self.bodies[*body] == hir::Expr::Missing;
 
+                            // the environment needs to know how big each capture is,
+                            // so weak captures are made strong the same way as globals
+                            for capture in captures {
+                                if !self.replace_weak_tys(capture.value, Ty::IInt(32).into()) {
+                                    self.replace_weak_tys(capture.value, Ty::Float(64).into());
+                                }
+                            }
+
                             let return_ty = if let Some(return_ty) = return_ty {
                                 self.const_ty(*return_ty)?
                             } else {
@@ -1589,12 +2280,20 @@ impl GlobalInferenceCtx<'_> {
 
                             let param_tys = params
                                 .iter()
-                                .map(|param| self.const_ty(param.ty))
+                                .map(|param| self.param_ty(param))
                                 .collect::<InferResult<Vec<_>>>()?;
 
-                            let ty = Ty::Function {
-                                param_tys: param_tys.clone(),
-                                return_ty,
+                            let ty = if *is_closure {
+                                Ty::Closure {
+                                    param_tys: param_tys.clone(),
+                                    return_ty,
+                                }
+                            } else {
+                                Ty::Function {
+                                    param_tys: param_tys.clone(),
+                                    return_ty,
+                                    is_variadic: *is_variadic,
+                                }
                             }
                             .into();
 
@@ -1617,7 +2316,7 @@ impl GlobalInferenceCtx<'_> {
 
                             let ty = self.tys[self.file][body];
 
-                            if ty.is_pointer() || ty.is_function() {
+                            if ty.is_pointer() || ty.is_function() || ty.is_closure() {
                                 self.diagnostics.push(TyDiagnostic {
                                     kind: TyDiagnosticKind::ComptimePointer,
                                     file: self.file,
@@ -1652,18 +2351,22 @@ impl GlobalInferenceCtx<'_> {
                                 })
                                 .collect::<IndexMap<_, _>>();
 
-                            let expected_tys = match expected_ty.as_struct() {
-                                Some(f) => f,
-                                None => {
-                                    self.tys[self.file]
-                                        .expr_tys
-                                        .insert(expr, Ty::Unknown.into());
+                            // unions use the same syntax, but only one member is set
+                            let is_union = expected_ty.is_union();
+
+                            let expected_tys =
+                                match expected_ty.as_struct().or_else(|| expected_ty.as_union()) {
+                                    Some(f) => f,
+                                    None => {
+                                        self.tys[self.file]
+                                            .expr_tys
+                                            .insert(expr, Ty::Unknown.into());
 
-                                    break 'struct_lit Ty::Unknown.into();
+                                        break 'struct_lit Ty::Unknown.into();
+                                    }
                                 }
-                            }
-                            .into_iter()
-                            .collect::<IndexMap<_, _>>();
+                                .into_iter()
+                                .collect::<IndexMap<_, _>>();
 
                             for (
                                 found_member_name,
@@ -1697,10 +2400,32 @@ impl GlobalInferenceCtx<'_> {
                                 }
                             }
 
+                            if is_union {
+                                if found_member_tys.len() != 1 {
+                                    self.diagnostics.push(TyDiagnostic {
+                                        kind: TyDiagnosticKind::UnionLiteralMemberCount {
+                                            found: found_member_tys.len(),
+                                            expected_ty,
+                                        },
+                                        file: self.file,
+                                        expr: Some(expr),
+                                        range: self.bodies.range_for_expr(expr),
+                                        help: None,
+                                    })
+                                }
+
+                                break 'struct_lit expected_ty;
+                            }
+
+                            let defaults = expected_ty.struct_defaults().unwrap_or_default();
+
                             for expected_member_name in expected_tys
                                 .iter()
-                                .filter(|(_, ty)| !ty.is_unknown())
-                                .map(|(name, _)| name)
+                                .enumerate()
+                                .filter(|(idx, (_, ty))| {
+                                    !ty.is_unknown() && !matches!(defaults.get(*idx), Some(Some(_)))
+                                })
+                                .map(|(_, (name, _))| name)
                             {
                                 if found_member_tys.get(expected_member_name).is_none() {
                                     self.diagnostics.push(TyDiagnostic {
@@ -1732,14 +2457,20 @@ impl GlobalInferenceCtx<'_> {
                                     name.map(|name| (name.name, self.tys[self.file][value]))
                                 })
                                 .collect(),
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
-                        Expr::Distinct { .. } | Expr::PrimitiveTy(_) => {
+                        Expr::Distinct { .. }
+                        | Expr::OptionalDecl { .. }
+                        | Expr::PrimitiveTy(_) => {
                             // resolving the type might reveal diagnostics such as recursive types
                             self.const_ty(expr)?;
                             Ty::Type.into()
                         }
-                        Expr::StructDecl { .. } => {
+                        Expr::StructDecl { .. }
+                        | Expr::UnionDecl { .. }
+                        | Expr::InterfaceDecl { .. } => {
                             self.const_ty(expr)?;
                             Ty::Type.into()
                         }
@@ -1747,6 +2478,11 @@ impl GlobalInferenceCtx<'_> {
                     };
 
                     self.tys[self.file].expr_tys.insert(expr, ty);
+
+                    if let Some(binding) = if_bindings.get(&expr) {
+                        let binding_ty = ty.as_optional().unwrap_or_else(|| Ty::Unknown.into());
+                        self.tys[self.file].local_tys.insert(*binding, binding_ty);
+                    }
                 }
                 Descendant::Stmt(stmt) => {
                     if self.inferred_stmts.contains(&(self.file, stmt)) {
@@ -1771,7 +2507,7 @@ impl GlobalInferenceCtx<'_> {
                                     {
                                         self.tys[self.file].expr_tys.insert(value, ty_annotation);
                                     }
-                                } else if !ty_annotation.has_default_value() {
+                                } else if !def_body.uninit && !ty_annotation.has_default_value() {
                                     self.diagnostics.push(TyDiagnostic {
                                         kind: TyDiagnosticKind::DeclTypeHasNoDefault {
                                             ty: ty_annotation,
@@ -1942,6 +2678,477 @@ impl GlobalInferenceCtx<'_> {
         }
     }
 
+    /// the lambda which `callee` definitely refers to, and how many of its parameters
+    /// were already given (`a` in `a.f(b)`).
+    ///
+    /// function pointers could point to anything, so they return `None`
+    fn callee_lambda(&self, callee: Idx<Expr>) -> Option<(hir::FileName, Idx<hir::Lambda>, usize)> {
+        let (fqn, skip) = match &self.bodies[callee] {
+            Expr::Member { previous, field } => {
+                if let Some(method) = self.tys[self.file].get_method_call(callee) {
+                    (method.fqn, 1)
+                } else if let Ty::File(file) = self.tys[self.file][*previous].as_ref() {
+                    (
+                        hir::Fqn {
+                            file: *file,
+                            name: field.name,
+                        },
+                        0,
+                    )
+                } else {
+                    return None;
+                }
+            }
+            Expr::LocalGlobal(name) => (
+                hir::Fqn {
+                    file: self.file,
+                    name: name.name,
+                },
+                0,
+            ),
+            Expr::Local(local) => {
+                let local_def = &self.bodies[*local];
+                if local_def.mutable {
+                    return None;
+                }
+
+                return match local_def.value.map(|value| &self.bodies[value]) {
+                    Some(Expr::Lambda(lambda)) => Some((self.file, *lambda, 0)),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+
+        if !self.world_bodies.exists(fqn) || self.world_bodies.is_extern(fqn) {
+            return None;
+        }
+
+        match &self.world_bodies[fqn.file][self.world_bodies.body(fqn)] {
+            Expr::Lambda(lambda) => Some((fqn.file, *lambda, skip)),
+            _ => None,
+        }
+    }
+
+    /// matches up the arguments of `open(p, mode = "w")` with the parameters of `open`,
+    /// filling in default values for the parameters which weren't given.
+    ///
+    /// the result has a slot for every parameter, followed by any extra arguments.
+    /// a slot is `None` if its parameter was missing.
+    ///
+    /// returns `None` if the arguments are purely positional
+    fn order_call_args(
+        &mut self,
+        call: Idx<Expr>,
+        callee: Idx<Expr>,
+        args: &[Idx<Expr>],
+        arg_names: &[Option<hir::NameWithRange>],
+    ) -> Option<Vec<Option<CallArg>>> {
+        let named = arg_names.iter().any(Option::is_some);
+
+        let Some((file, lambda, skip)) = self.callee_lambda(callee) else {
+            if named {
+                self.diagnostics.push(TyDiagnostic {
+                    kind: TyDiagnosticKind::NamedArgsOnIndirectCall,
+                    file: self.file,
+                    expr: Some(call),
+                    range: self.bodies.range_for_expr(call),
+                    help: None,
+                });
+            }
+            return None;
+        };
+
+        let world_bodies = self.world_bodies;
+        let params = world_bodies[file][lambda]
+            .params
+            .get(skip..)
+            .unwrap_or_default();
+
+        // without any defaults to fill in, missing arguments are caught by the arg count checks
+        let any_defaults_needed = params
+            .get(args.len()..)
+            .is_some_and(|rest| rest.iter().any(|param| param.default.is_some()));
+        if !named && !any_defaults_needed {
+            return None;
+        }
+
+        let mut slots = vec![None; params.len()];
+        let mut extra = Vec::new();
+        let mut next_positional = 0;
+
+        for (arg, name) in args.iter().zip(arg_names) {
+            let Some(name) = name else {
+                if next_positional < slots.len() {
+                    slots[next_positional] = Some(CallArg::Given(*arg));
+                    next_positional += 1;
+                } else {
+                    extra.push(Some(CallArg::Given(*arg)));
+                }
+                continue;
+            };
+
+            match params
+                .iter()
+                .position(|param| param.name == Some(name.name))
+            {
+                Some(idx) if slots[idx].is_some() => {
+                    self.diagnostics.push(TyDiagnostic {
+                        kind: TyDiagnosticKind::DuplicateArg { param: name.name.0 },
+                        file: self.file,
+                        expr: Some(*arg),
+                        range: name.range,
+                        help: None,
+                    });
+                }
+                Some(idx) => slots[idx] = Some(CallArg::Given(*arg)),
+                None => {
+                    self.diagnostics.push(TyDiagnostic {
+                        kind: TyDiagnosticKind::UnknownParam { name: name.name.0 },
+                        file: self.file,
+                        expr: Some(*arg),
+                        range: name.range,
+                        help: None,
+                    });
+                }
+            }
+        }
+
+        for (slot, param) in slots.iter_mut().zip(params) {
+            if slot.is_some() {
+                continue;
+            }
+
+            if let Some(default) = param.default {
+                *slot = Some(CallArg::Default(DefaultValue {
+                    file,
+                    expr: default,
+                }));
+            } else if let Some(name) = param.name {
+                self.diagnostics.push(TyDiagnostic {
+                    kind: TyDiagnosticKind::MissingArg { param: name.0 },
+                    file: self.file,
+                    expr: Some(call),
+                    range: self.bodies.range_for_expr(call),
+                    help: None,
+                });
+            }
+        }
+
+        slots.extend(extra);
+
+        Some(slots)
+    }
+
+    /// resolves `a.f(b)` into `f(a, b)`, returning the type of `a.f` with `a` already applied.
+    ///
+    /// `f` is searched for in the file which defined the type of `a`, and then in the current
+    /// file. `a` gets referenced automatically if `f` expects a pointer to it.
+    fn find_method(
+        &mut self,
+        member: Idx<Expr>,
+        receiver: Idx<Expr>,
+        name: hir::Name,
+    ) -> InferResult<Option<Intern<Ty>>> {
+        let receiver_ty = self.tys[self.file][receiver];
+        if receiver_ty.is_unknown() {
+            return Ok(None);
+        }
+
+        let mut candidates = Vec::new();
+
+        for (fqn, ty) in self.method_candidates(receiver_ty, name)? {
+            let Some(first_param) = ty
+                .as_function()
+                .and_then(|(params, _)| params.first().copied())
+            else {
+                continue;
+            };
+
+            let auto_ref = if receiver_ty.can_fit_into(&first_param) {
+                None
+            } else {
+                match first_param.as_pointer() {
+                    Some((mutable, sub_ty)) if receiver_ty.can_fit_into(&sub_ty) => Some(mutable),
+                    _ => continue,
+                }
+            };
+
+            candidates.push((fqn, ty, first_param, auto_ref));
+        }
+
+        match candidates.as_slice() {
+            [] => Ok(None),
+            [(fqn, ty, first_param, auto_ref)] => {
+                match auto_ref {
+                    None => {
+                        self.replace_weak_tys(receiver, *first_param);
+                    }
+                    Some(true) => {
+                        let help = self
+                            .get_mutability(receiver, false, false)
+                            .into_diagnostic();
+
+                        if help.is_some() {
+                            self.diagnostics.push(TyDiagnostic {
+                                kind: TyDiagnosticKind::MutableRefToImmutableData,
+                                file: self.file,
+                                expr: Some(receiver),
+                                range: self.bodies.range_for_expr(receiver),
+                                help,
+                            });
+                        }
+                    }
+                    Some(false) => {}
+                }
+
+                self.tys[self.file].method_calls.insert(
+                    member,
+                    MethodCall {
+                        fqn: *fqn,
+                        auto_ref: *auto_ref,
+                    },
+                );
+
+                let Ty::Function {
+                    param_tys,
+                    return_ty,
+                    is_variadic,
+                } = ty.as_ref()
+                else {
+                    unreachable!("only functions become candidates");
+                };
+
+                Ok(Some(
+                    Ty::Function {
+                        param_tys: param_tys[1..].to_vec(),
+                        return_ty: *return_ty,
+                        is_variadic: *is_variadic,
+                    }
+                    .into(),
+                ))
+            }
+            [(first, ..), (second, ..), ..] => {
+                self.diagnostics.push(TyDiagnostic {
+                    kind: TyDiagnosticKind::AmbiguousMethod {
+                        first: *first,
+                        second: *second,
+                    },
+                    file: self.file,
+                    expr: Some(member),
+                    range: self.bodies.range_for_expr(member),
+                    help: None,
+                });
+
+                Ok(Some(Ty::Unknown.into()))
+            }
+        }
+    }
+
+    /// all the functions called `name` which could be methods of `receiver_ty`.
+    ///
+    /// these are searched for in the file which defined the type of `a`, and then in the current
+    /// file.
+    fn method_candidates(
+        &self,
+        receiver_ty: Intern<Ty>,
+        name: hir::Name,
+    ) -> InferResult<Vec<(hir::Fqn, Intern<Ty>)>> {
+        let mut deref_ty = receiver_ty;
+        while let Some((_, sub_ty)) = deref_ty.as_pointer() {
+            deref_ty = sub_ty;
+        }
+
+        let defining_file = match deref_ty.as_ref() {
+            Ty::Struct { fqn: Some(fqn), .. }
+            | Ty::Union { fqn: Some(fqn), .. }
+            | Ty::Distinct { fqn: Some(fqn), .. } => Some(fqn.file),
+            _ => None,
+        };
+
+        let mut candidates = Vec::new();
+
+        for file in defining_file.into_iter().chain(Some(self.file)).dedup() {
+            let fqn = hir::Fqn { file, name };
+
+            if !matches!(
+                self.world_index.definition(fqn),
+                hir::DefinitionStatus::Defined
+            ) {
+                continue;
+            }
+
+            let ty = self
+                .tys
+                .signatures
+                .get(&fqn)
+                .ok_or_else(|| vec![Inferrable::Global(fqn)])?
+                .0;
+
+            candidates.push((fqn, ty));
+        }
+
+        Ok(candidates)
+    }
+
+    /// checks that `ptr as Interface` is valid, and finds the functions which will go in the
+    /// vtable.
+    ///
+    /// every method of the interface needs a function with the same name whose first parameter
+    /// is a pointer that `ptr` can be passed as, and whose other parameters and return type match
+    /// the method exactly.
+    fn find_interface_impls(
+        &mut self,
+        cast: Idx<Expr>,
+        source_ty: Intern<Ty>,
+        interface_ty: Intern<Ty>,
+    ) -> InferResult<()> {
+        let methods = interface_ty.as_interface().unwrap();
+
+        if !source_ty.is_pointer() {
+            self.diagnostics.push(TyDiagnostic {
+                kind: TyDiagnosticKind::InterfaceFromNonPointer {
+                    found: source_ty,
+                    interface: interface_ty,
+                },
+                file: self.file,
+                expr: Some(cast),
+                range: self.bodies.range_for_expr(cast),
+                help: None,
+            });
+
+            return Ok(());
+        }
+
+        let mut impls = Vec::with_capacity(methods.len());
+
+        for (name, method_ty) in methods {
+            let (method_params, method_return) = method_ty.as_function().unwrap();
+
+            let candidates: Vec<_> = self
+                .method_candidates(source_ty, name)?
+                .into_iter()
+                .filter(|(_, ty)| {
+                    let Some((params, return_ty)) = ty.as_function() else {
+                        return false;
+                    };
+
+                    !ty.is_variadic_function()
+                        && params.len() == method_params.len() + 1
+                        && source_ty.can_fit_into(&params[0])
+                        && params[0].is_pointer()
+                        && params[1..]
+                            .iter()
+                            .zip(method_params.iter())
+                            .all(|(param, method_param)| param.is_equal_to(method_param))
+                        && return_ty.is_equal_to(&method_return)
+                })
+                .map(|(fqn, _)| fqn)
+                .collect();
+
+            match candidates.as_slice() {
+                [] => {
+                    self.diagnostics.push(TyDiagnostic {
+                        kind: TyDiagnosticKind::InterfaceMethodNotImplemented {
+                            ty: source_ty,
+                            interface: interface_ty,
+                            method: name.0,
+                        },
+                        file: self.file,
+                        expr: Some(cast),
+                        range: self.bodies.range_for_expr(cast),
+                        help: None,
+                    });
+
+                    return Ok(());
+                }
+                [fqn] => impls.push(*fqn),
+                [first, second, ..] => {
+                    self.diagnostics.push(TyDiagnostic {
+                        kind: TyDiagnosticKind::AmbiguousMethod {
+                            first: *first,
+                            second: *second,
+                        },
+                        file: self.file,
+                        expr: Some(cast),
+                        range: self.bodies.range_for_expr(cast),
+                        help: None,
+                    });
+
+                    return Ok(());
+                }
+            }
+        }
+
+        self.tys[self.file].interface_impls.insert(cast, impls);
+
+        Ok(())
+    }
+
+    /// resolves `a + b`, `-a` and `a[b]` into `op_add(a, b)`, `op_neg(a)` and `op_index(a, b)`,
+    /// returning the output type of the operator.
+    ///
+    /// the function is searched for the same way `a.op_add(b)` would be, but only if `a` is a
+    /// struct, union or distinct type
+    fn find_operator(
+        &mut self,
+        expr: Idx<Expr>,
+        operands: &[Idx<Expr>],
+        name: Option<&str>,
+    ) -> InferResult<Option<Intern<Ty>>> {
+        let first = operands[0];
+
+        if !matches!(
+            self.tys[self.file][first].as_ref(),
+            Ty::Struct { fqn: Some(_), .. }
+                | Ty::Union { fqn: Some(_), .. }
+                | Ty::Distinct { fqn: Some(_), .. }
+        ) {
+            return Ok(None);
+        }
+
+        // if the name was never interned, no function could possibly have it
+        let Some(name) = name.and_then(|name| self.interner.get(name)) else {
+            return Ok(None);
+        };
+
+        let Some(bound_ty) = self.find_method(expr, first, hir::Name(name))? else {
+            return Ok(None);
+        };
+
+        // the call was ambiguous
+        let Some((param_tys, return_ty)) = bound_ty.as_function() else {
+            return Ok(Some(bound_ty));
+        };
+
+        if param_tys.len() != operands.len() - 1 {
+            let function = self.tys[self.file].method_calls.remove(expr).unwrap().fqn;
+
+            self.diagnostics.push(TyDiagnostic {
+                kind: TyDiagnosticKind::InvalidOperatorFunction {
+                    function,
+                    found: self.tys[function].0,
+                },
+                file: self.file,
+                expr: Some(expr),
+                range: self.bodies.range_for_expr(expr),
+                help: None,
+            });
+
+            return Ok(Some(Ty::Unknown.into()));
+        }
+
+        for (operand, param_ty) in operands[1..].iter().zip(param_tys) {
+            let operand_ty = self.tys[self.file][*operand];
+
+            if self.expect_match(operand_ty, param_ty, *operand) {
+                self.replace_weak_tys(*operand, param_ty);
+            }
+        }
+
+        Ok(Some(return_ty))
+    }
+
     /// If found does not match expected, an error is thrown at the expression
     pub(crate) fn expect_match(
         &mut self,
@@ -1959,21 +3166,7 @@ impl GlobalInferenceCtx<'_> {
                 self.tys[self.file].expr_tys[expr] = expected;
             }
 
-            if let Some(max_size) = expected.get_max_int_size() {
-                if *num > max_size {
-                    self.diagnostics.push(TyDiagnostic {
-                        kind: TyDiagnosticKind::IntTooBigForType {
-                            found: *num,
-                            max: max_size,
-                            ty: expected,
-                        },
-                        file: self.file,
-                        expr: Some(expr),
-                        range: self.bodies.range_for_expr(expr),
-                        help: None,
-                    });
-                }
-            }
+            self.check_int_literal(expr, *num, expected, false);
 
             return true;
         }
@@ -2099,11 +3292,35 @@ impl GlobalInferenceCtx<'_> {
                         fqn: None,
                         members,
                         uid,
+                        defaults,
+                        repr,
                     } => Ty::Struct {
                         anonymous: *anonymous,
                         fqn: Some(fqn),
                         members: members.clone(),
                         uid: *uid,
+                        defaults: defaults.clone(),
+                        repr: repr.clone(),
+                    }
+                    .into(),
+                    Ty::Union {
+                        fqn: None,
+                        members,
+                        uid,
+                    } => Ty::Union {
+                        fqn: Some(fqn),
+                        members: members.clone(),
+                        uid: *uid,
+                    }
+                    .into(),
+                    Ty::Interface {
+                        fqn: None,
+                        methods,
+                        uid,
+                    } => Ty::Interface {
+                        fqn: Some(fqn),
+                        methods: methods.clone(),
+                        uid: *uid,
                     }
                     .into(),
                     _ => actual_ty,
@@ -2330,8 +3547,85 @@ impl GlobalInferenceCtx<'_> {
                             sub_ty: self.tys[self.file].meta_tys[*ty],
                         }
                         .into(),
-                        Expr::StructDecl { uid, members } => Ty::Struct {
-                            anonymous: false,
+                        Expr::OptionalDecl { ty } => Ty::Optional {
+                            sub_ty: self.tys[self.file].meta_tys[*ty],
+                        }
+                        .into(),
+                        Expr::StructDecl {
+                            uid,
+                            members,
+                            defaults,
+                            repr,
+                        } => {
+                            let mut repr = repr.clone();
+                            if !repr.field_aligns.is_empty() {
+                                // unnamed members get dropped, so their alignments have to go too
+                                repr.field_aligns = members
+                                    .iter()
+                                    .zip(repr.field_aligns)
+                                    .filter(|((name, _), _)| name.is_some())
+                                    .map(|(_, align)| align)
+                                    .collect();
+                            }
+
+                            let members = members
+                                .iter()
+                                .cloned()
+                                .zip(defaults.iter().copied())
+                                .filter_map(|((name, ty), default)| {
+                                    name.map(|name| (name.name, ty, default))
+                                })
+                                .collect_vec();
+
+                            let mut member_defaults = Vec::with_capacity(members.len());
+                            for (_, ty, default) in &members {
+                                let Some(default) = default else {
+                                    member_defaults.push(None);
+                                    continue;
+                                };
+
+                                // the default might not have been inferred yet
+                                self.infer_expr(*default)?;
+
+                                let member_ty = self.tys[self.file].meta_tys[*ty];
+                                if self.expect_match(
+                                    self.tys[self.file][*default],
+                                    member_ty,
+                                    *default,
+                                ) && self.replace_weak_tys(*default, member_ty)
+                                {
+                                    self.tys[self.file].expr_tys.insert(*default, member_ty);
+                                }
+
+                                if !self.check_default_is_const(*default) {
+                                    member_defaults.push(None);
+                                    continue;
+                                }
+
+                                member_defaults.push(Some(DefaultValue {
+                                    file: self.file,
+                                    expr: *default,
+                                }));
+                            }
+
+                            if member_defaults.iter().all(Option::is_none) {
+                                member_defaults.clear();
+                            }
+
+                            Ty::Struct {
+                                anonymous: false,
+                                fqn: None,
+                                uid: *uid,
+                                members: members
+                                    .into_iter()
+                                    .map(|(name, ty, _)| (name, self.tys[self.file].meta_tys[ty]))
+                                    .collect(),
+                                defaults: member_defaults,
+                                repr,
+                            }
+                            .into()
+                        }
+                        Expr::UnionDecl { uid, members } => Ty::Union {
                             fqn: None,
                             uid: *uid,
                             members: members
@@ -2342,12 +3636,48 @@ impl GlobalInferenceCtx<'_> {
                                 .collect(),
                         }
                         .into(),
+                        Expr::InterfaceDecl { uid, members } => {
+                            let mut methods = Vec::with_capacity(members.len());
+
+                            for (name, ty) in members {
+                                let Some(name) = name else { continue };
+
+                                let method_ty = self.tys[self.file].meta_tys[*ty];
+
+                                // the methods get called through a table of function pointers
+                                if method_ty.is_unknown()
+                                    || (method_ty.is_function()
+                                        && !method_ty.is_variadic_function())
+                                {
+                                    methods.push((name.name, method_ty));
+                                } else {
+                                    self.diagnostics.push(TyDiagnostic {
+                                        kind: TyDiagnosticKind::InterfaceMethodNotFunction {
+                                            found: method_ty,
+                                        },
+                                        file: self.file,
+                                        expr: Some(*ty),
+                                        range: self.bodies.range_for_expr(*ty),
+                                        help: None,
+                                    });
+                                }
+                            }
+
+                            Ty::Interface {
+                                fqn: None,
+                                uid: *uid,
+                                methods,
+                            }
+                            .into()
+                        }
                         Expr::Lambda(lambda) => {
                             let hir::Lambda {
                                 params,
                                 return_ty,
                                 body,
                                 is_extern,
+                                is_variadic,
+                                is_closure,
                                 ..
                             } = &self.bodies[*lambda];
 
@@ -2362,9 +3692,17 @@ impl GlobalInferenceCtx<'_> {
                                 .map(|param| self.tys[self.file].meta_tys[param.ty])
                                 .collect::<Vec<_>>();
 
-                            let ty = Ty::Function {
-                                param_tys: param_tys.clone(),
-                                return_ty,
+                            let ty = if *is_closure {
+                                Ty::Closure {
+                                    param_tys: param_tys.clone(),
+                                    return_ty,
+                                }
+                            } else {
+                                Ty::Function {
+                                    param_tys: param_tys.clone(),
+                                    return_ty,
+                                    is_variadic: *is_variadic,
+                                }
                             }
                             .into();
 
@@ -2394,7 +3732,17 @@ impl GlobalInferenceCtx<'_> {
                             if *ty == Ty::Type {
                                 self.tys[self.file].expr_tys.insert(expr, ty);
 
-                                if self.is_safe_to_compile(body)? {
+                                // reading a runtime global gets reported once everything is inferred
+                                if self.is_safe_to_compile(body)?
+                                    && self
+                                        .tys
+                                        .runtime_global_in_comptime(
+                                            self.world_bodies,
+                                            self.file,
+                                            body,
+                                        )
+                                        .is_none()
+                                {
                                     match (self.eval_comptime)(
                                         FQComptime {
                                             file: self.file,
@@ -2418,6 +3766,17 @@ impl GlobalInferenceCtx<'_> {
                             // todo: remove recursion
                             self.const_ty(*paren_expr)?
                         }
+                        Expr::Tuple { items } => {
+                            let items = items.clone();
+
+                            let mut item_tys = Vec::with_capacity(items.len());
+                            for item in items {
+                                // todo: remove recursion
+                                item_tys.push(self.const_ty(item)?);
+                            }
+
+                            Ty::Tuple { item_tys }.into()
+                        }
                         // todo: should we remove the void type?
                         Expr::Paren(None) => Ty::Void.into(),
                         Expr::Block { .. } => todo!("blocks as types"),
@@ -2476,7 +3835,13 @@ impl GlobalInferenceCtx<'_> {
             Expr::Comptime(comptime) => {
                 let hir::Comptime { body } = self.world_bodies[file][*comptime];
 
-                if self.is_safe_to_compile(body)? {
+                // reading a runtime global gets reported once everything is inferred
+                if self.is_safe_to_compile(body)?
+                    && self
+                        .tys
+                        .runtime_global_in_comptime(self.world_bodies, file, body)
+                        .is_none()
+                {
                     Ok(Some((self.eval_comptime)(
                         FQComptime {
                             file,
@@ -2643,17 +4008,56 @@ impl GlobalInferenceCtx<'_> {
                         Expr::IntLiteral(_) => {}
                         Expr::FloatLiteral(_) => {}
                         Expr::BoolLiteral(_) => {}
+                        Expr::NullLiteral => {}
                         Expr::StringLiteral(_) => {}
+                        Expr::Embed(_) => {}
                         Expr::CharLiteral(_) => {}
-                        Expr::Cast { .. } => {}
+                        Expr::Cast { .. } => {
+                            // `ptr as Interface` depends on every function in the vtable
+                            let impls = self.tys[file]
+                                .get_interface_impls(expr)
+                                .unwrap_or_default()
+                                .to_vec();
+
+                            for fqn in impls {
+                                let new_inf = Inferrable::Global(fqn);
+
+                                if checked.contains(&new_inf) || self.world_bodies.is_extern(fqn) {
+                                    continue;
+                                }
+
+                                checked.insert(new_inf);
+
+                                if !self.all_inferred.contains(&new_inf) {
+                                    return Err(vec![new_inf]);
+                                }
+
+                                let body = self.world_bodies.body(fqn);
+
+                                checking_stack.push((
+                                    new_inf,
+                                    self.world_bodies[fqn.file]
+                                        .descendants(
+                                            body,
+                                            hir::DescentOpts::All {
+                                                include_lambdas: false,
+                                            },
+                                        )
+                                        .collect(),
+                                ));
+                            }
+                        }
                         Expr::Ref { .. } => {}
                         Expr::Deref { .. } => {}
-                        Expr::Binary { .. } => {}
-                        Expr::Unary { .. } => {}
+                        Expr::Unwrap { .. } => {}
+                        Expr::Binary { .. } | Expr::Unary { .. } | Expr::Index { .. }
+                            if self.tys[file].get_method_call(expr).is_none() => {}
+                        Expr::Slice { .. } => {}
                         Expr::ArrayDecl { .. } => {}
                         Expr::ArrayLiteral { .. } => {}
-                        Expr::Index { .. } => {}
                         Expr::Paren(_) => {}
+                        Expr::Tuple { .. } => {}
+                        Expr::TupleItem { .. } => {}
                         Expr::Block { .. } => {}
                         Expr::If { .. } => {}
                         Expr::While { .. } => {}
@@ -2695,14 +4099,32 @@ impl GlobalInferenceCtx<'_> {
                             ));
                         }
                         Expr::Param { .. } => {}
-                        Expr::Member { previous, field } => {
-                            let previous_ty = self.tys[file][*previous];
-                            if let Ty::File(file) = previous_ty.as_ref() {
-                                let fqn = hir::Fqn {
-                                    file: *file,
-                                    name: field.name,
-                                };
+                        Expr::Capture { .. } => {}
+                        Expr::Member { .. }
+                        | Expr::Binary { .. }
+                        | Expr::Unary { .. }
+                        | Expr::Index { .. } => {
+                            let fqn = match &self.world_bodies[file][expr] {
+                                Expr::Member { previous, field } => {
+                                    match self.tys[file][*previous].as_ref() {
+                                        Ty::File(file) => Some(hir::Fqn {
+                                            file: *file,
+                                            name: field.name,
+                                        }),
+                                        _ => None,
+                                    }
+                                }
+                                _ => None,
+                            }
+                            // `a.f()` and `a + b` depend on `f` and `op_add`
+                            // the same way `file.f()` would
+                            .or_else(|| {
+                                self.tys[file]
+                                    .get_method_call(expr)
+                                    .map(|method| method.fqn)
+                            });
 
+                            if let Some(fqn) = fqn {
                                 let new_inf = Inferrable::Global(fqn);
 
                                 if checked.contains(&new_inf) {
@@ -2820,7 +4242,10 @@ impl GlobalInferenceCtx<'_> {
                         Expr::Comptime(_) => {}
                         Expr::PrimitiveTy(_) => {}
                         Expr::Distinct { .. } => {}
+                        Expr::OptionalDecl { .. } => {}
                         Expr::StructDecl { .. } => {}
+                        Expr::UnionDecl { .. } => {}
+                        Expr::InterfaceDecl { .. } => {}
                         Expr::StructLiteral { .. } => {}
                         Expr::Import(_) => {}
                     }
@@ -1,5 +1,7 @@
+mod escape;
 mod globals;
 mod ty;
+mod uninit;
 
 use globals::GlobalInferenceCtx;
 use hir::{FQComptime, FQLambda, FileName};
@@ -8,6 +10,7 @@ use internment::Intern;
 use itertools::Itertools;
 use la_arena::{ArenaMap, Idx};
 use rustc_hash::{FxHashMap, FxHashSet};
+use std::collections::VecDeque;
 use text_size::TextRange;
 
 use topo::TopoSort;
@@ -29,6 +32,10 @@ pub(crate) type InferResult<T> = Result<T, Vec<Inferrable>>;
 pub struct ProjectInference {
     signatures: FxHashMap<hir::Fqn, Signature>,
     files: FxHashMap<hir::FileName, FileInference>,
+    /// globals whose values can't be known at compile-time
+    runtime_globals: FxHashSet<hir::Fqn>,
+    /// the order in which the runtime globals should be initialized
+    global_init_order: Vec<hir::Fqn>,
 }
 
 impl std::ops::Index<hir::Fqn> for ProjectInference {
@@ -59,12 +66,49 @@ pub struct FileInference {
     /// the actual types of type expressions
     meta_tys: ArenaMap<Idx<hir::Expr>, Intern<Ty>>,
     local_tys: ArenaMap<Idx<hir::LocalDef>, Intern<Ty>>,
+    /// the member expressions of `a.f(b)` calls which resolved to `f(a, b)`,
+    /// and the operator expressions which resolved to `op_add(a, b)` and friends
+    method_calls: ArenaMap<Idx<hir::Expr>, MethodCall>,
+    /// the functions which make up the vtable of each `ptr as Interface` cast,
+    /// in the same order as the interface's methods
+    interface_impls: ArenaMap<Idx<hir::Expr>, Vec<hir::Fqn>>,
+    /// the arguments of calls which used named arguments or default values,
+    /// in the same order as the parameters of the function
+    call_args: ArenaMap<Idx<hir::Expr>, Vec<CallArg>>,
 }
 
 impl FileInference {
     pub fn get_meta_ty(&self, expr: Idx<hir::Expr>) -> Option<Intern<Ty>> {
         self.meta_tys.get(expr).copied()
     }
+
+    pub fn get_method_call(&self, expr: Idx<hir::Expr>) -> Option<MethodCall> {
+        self.method_calls.get(expr).copied()
+    }
+
+    pub fn get_interface_impls(&self, expr: Idx<hir::Expr>) -> Option<&[hir::Fqn]> {
+        self.interface_impls.get(expr).map(|impls| impls.as_slice())
+    }
+
+    pub fn get_call_args(&self, expr: Idx<hir::Expr>) -> Option<&[CallArg]> {
+        self.call_args.get(expr).map(|args| args.as_slice())
+    }
+}
+
+/// the function which `a.f(b)` or an overloaded operator actually calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodCall {
+    pub fqn: hir::Fqn,
+    /// `Some` if `a` gets passed as `^a`, with whether or not that's a `^mut a`
+    pub auto_ref: Option<bool>,
+}
+
+/// an argument of `open(p, mode = "w")` once it's been matched up with its parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallArg {
+    Given(Idx<hir::Expr>),
+    /// the parameter wasn't given, so its default value gets passed instead
+    Default(DefaultValue),
 }
 
 impl std::ops::Index<Idx<hir::Expr>> for FileInference {
@@ -122,7 +166,12 @@ impl TyDiagnostic {
 
     pub fn is_error(&self) -> bool {
         // !matches!(self.kind, TyDiagnosticKind::IntTooBigForType { .. })
-        true
+        !matches!(
+            self.kind,
+            TyDiagnosticKind::ReadBeforeWrite
+                | TyDiagnosticKind::LocalRefEscapes { .. }
+                | TyDiagnosticKind::ClosureEscapes { .. }
+        )
     }
 }
 
@@ -136,6 +185,7 @@ pub enum TyDiagnosticKind {
         from: Intern<Ty>,
         to: Intern<Ty>,
     },
+    NonLiteralToCStr,
     BinaryOpMismatch {
         op: hir::BinaryOp,
         first: Intern<Ty>,
@@ -156,11 +206,24 @@ pub enum TyDiagnosticKind {
         index: u64,
         actual_size: u64,
         array_ty: Intern<Ty>,
+        /// the bounds of `foo[lo..hi]` can go up to and including the length
+        is_slice_bound: bool,
+    },
+    SliceStartAfterEnd {
+        start: u64,
+        end: u64,
     },
     MismatchedArgCount {
         found: usize,
         expected: usize,
     },
+    NotEnoughVarArgs {
+        found: usize,
+        expected: usize,
+    },
+    InvalidVarArg {
+        found: Intern<Ty>,
+    },
     CalledNonFunction {
         found: Intern<Ty>,
     },
@@ -168,6 +231,13 @@ pub enum TyDiagnosticKind {
         found: Intern<Ty>,
     },
     DerefAny,
+    UnwrapNonOptional {
+        found: Intern<Ty>,
+    },
+    DestructureMismatch {
+        expected: u32,
+        found: Intern<Ty>,
+    },
     IndexAny {
         // set this if it is an array, leave `None` if slice
         size: Option<u64>,
@@ -187,6 +257,11 @@ pub enum TyDiagnosticKind {
         max: u64,
         ty: Intern<Ty>,
     },
+    IntTooSmallForType {
+        found: i128,
+        min: i128,
+        ty: Intern<Ty>,
+    },
     UnknownFile {
         file: FileName,
     },
@@ -197,17 +272,64 @@ pub enum TyDiagnosticKind {
         member: Key,
         found_ty: Intern<Ty>,
     },
+    /// `a.f()` could call either of these functions
+    AmbiguousMethod {
+        first: hir::Fqn,
+        second: hir::Fqn,
+    },
+    InterfaceMethodNotFunction {
+        found: Intern<Ty>,
+    },
+    /// `ty` was casted to `interface`, but there's no `method` for it
+    InterfaceMethodNotImplemented {
+        ty: Intern<Ty>,
+        interface: Intern<Ty>,
+        method: Key,
+    },
+    /// interfaces hold a pointer to their data, so only pointers can be casted to them
+    InterfaceFromNonPointer {
+        found: Intern<Ty>,
+        interface: Intern<Ty>,
+    },
+    /// `op_add` was found for `a + b`, but takes the wrong parameters
+    InvalidOperatorFunction {
+        function: hir::Fqn,
+        found: Intern<Ty>,
+    },
     StructLiteralMissingMember {
         member: Key,
         expected_ty: Intern<Ty>,
     },
+    UnknownParam {
+        name: Key,
+    },
+    DuplicateArg {
+        param: Key,
+    },
+    MissingArg {
+        param: Key,
+    },
+    /// function pointers don't know the names of their parameters
+    NamedArgsOnIndirectCall,
+    UnionLiteralMemberCount {
+        found: usize,
+        expected_ty: Intern<Ty>,
+    },
     ComptimePointer,
-    GlobalNotConst,
+    GlobalInitCycle {
+        /// starts and ends with the same global
+        path: Vec<hir::Fqn>,
+    },
+    RuntimeGlobalInComptime {
+        /// ends with the runtime global
+        path: Vec<hir::Fqn>,
+    },
     EntryNotFunction,
-    EntryHasParams,
+    EntryBadParams,
     EntryBadReturn,
     ArraySizeNotInt,
     ArraySizeNotConst,
+    DefaultNotConst,
     ArraySizeMismatch {
         found: u32,
         expected: u32,
@@ -216,6 +338,35 @@ pub enum TyDiagnosticKind {
     DeclTypeHasNoDefault {
         ty: Intern<Ty>,
     },
+    StaticAssertFailed {
+        message: Option<Key>,
+    },
+    /// a local declared with `---` was read before anything was written to it.
+    /// this is only a warning
+    ReadBeforeWrite,
+    /// a pointer to a local or parameter (e.g. `^x`) outlives the function it was made in.
+    /// this is only a warning
+    LocalRefEscapes {
+        /// the variables the pointer was passed through on its way out, in order
+        path: Vec<Key>,
+        into: RefEscape,
+    },
+    /// a closure, whose environment is on the stack, outlives the function it was made in.
+    /// this is only a warning
+    ClosureEscapes {
+        /// the variables the closure was passed through on its way out, in order
+        path: Vec<Key>,
+        into: RefEscape,
+    },
+}
+
+/// where a pointer to a local ends up after escaping its function
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefEscape {
+    Return,
+    Global(hir::Fqn),
+    /// stored through a pointer the function was given
+    Param(Key),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -230,12 +381,15 @@ pub enum TyDiagnosticHelpKind {
     ImmutableBinding,
     ImmutableRef,
     ImmutableParam { assignment: bool },
+    ImmutableCapture { by_value: bool },
     ImmutableGlobal,
     NotMutatingRefThroughDeref,
     IfReturnsTypeHere { found: Intern<Ty> },
     MutableVariable,
     TailExprReturnsHere,
     BreakHere { break_ty: Intern<Ty> },
+    LeftUninitHere,
+    EscapesHere,
 }
 
 // todo: I want to make this more expansive. `Data` should be removed and
@@ -254,6 +408,8 @@ pub enum ComptimeResult {
 enum Inferrable {
     Global(hir::Fqn),
     Lambda(FQLambda),
+    /// the comptime block holding the condition of an `#assert`
+    StaticAssert(FQComptime),
 }
 
 impl Inferrable {
@@ -265,6 +421,11 @@ impl Inferrable {
                 lambda.file.debug(interner),
                 lambda.expr.into_raw()
             ),
+            Inferrable::StaticAssert(ctc) => format!(
+                "#assert {} #{}",
+                ctc.file.debug(interner),
+                ctc.expr.into_raw()
+            ),
         }
     }
 
@@ -272,6 +433,7 @@ impl Inferrable {
         match self {
             Inferrable::Global(fqn) => fqn.file,
             Inferrable::Lambda(fql) => fql.file,
+            Inferrable::StaticAssert(ctc) => ctc.file,
         }
     }
 }
@@ -343,6 +505,27 @@ impl<'a, F: EvalComptimeFn> InferenceCtx<'a, F> {
                     }),
             );
 
+        self.to_infer.extend(
+            self.world_index
+                .get_all_files()
+                .into_iter()
+                .flat_map(|(file, _)| {
+                    let bodies = &self.world_bodies[file];
+
+                    bodies.static_asserts().map(move |static_assert| {
+                        let hir::Expr::Comptime(comptime) = bodies[static_assert.condition] else {
+                            unreachable!("`#assert` conditions are always comptime blocks")
+                        };
+
+                        Inferrable::StaticAssert(FQComptime {
+                            file,
+                            expr: static_assert.condition,
+                            comptime,
+                        })
+                    })
+                }),
+        );
+
         if self.to_infer.is_empty() {
             return InferenceResult {
                 tys: self.tys,
@@ -404,28 +587,33 @@ impl<'a, F: EvalComptimeFn> InferenceCtx<'a, F> {
             }
         }
 
+        self.order_runtime_globals();
+        self.report_runtime_globals_in_comptime();
+
         let mut any_were_unsafe_to_compile = false;
 
         if track_unsafe_to_compile {
-            for fqn in self
-                .all_inferred
-                .clone()
-                .into_iter()
-                .filter_map(|i| match i {
-                    Inferrable::Global(fqn) => Some(fqn),
-                    _ => None,
-                })
-            {
-                if self.world_bodies.is_extern(fqn) {
-                    continue;
-                }
+            for inferrable in self.all_inferred.clone() {
+                let (body, ty) = match inferrable {
+                    Inferrable::Global(fqn) => {
+                        if self.world_bodies.is_extern(fqn) {
+                            continue;
+                        }
+
+                        (self.world_bodies.body(fqn), self.world_bodies.ty(fqn))
+                    }
+                    Inferrable::StaticAssert(ctc) => (ctc.expr, None),
+                    Inferrable::Lambda(_) => continue,
+                };
+
+                let file = inferrable.file();
 
                 let mut global_ctx = GlobalInferenceCtx {
-                    file: fqn.file,
-                    current_inferring: Inferrable::Global(fqn),
+                    file,
+                    current_inferring: inferrable,
                     world_index: self.world_index,
                     world_bodies: self.world_bodies,
-                    bodies: &self.world_bodies[fqn.file],
+                    bodies: &self.world_bodies[file],
                     interner: self.interner,
                     local_usages: Default::default(),
                     tys: &mut self.tys,
@@ -437,15 +625,12 @@ impl<'a, F: EvalComptimeFn> InferenceCtx<'a, F> {
                     eval_comptime: &mut self.eval_comptime,
                 };
 
-                let body = self.world_bodies.body(fqn);
-                let ty = self.world_bodies.ty(fqn);
-
                 if !global_ctx.is_safe_to_compile(body).unwrap()
                     || ty.map_or(false, |ty| !global_ctx.is_safe_to_compile(ty).unwrap())
                 {
                     println!(
                         "{} is unsafe to compile",
-                        fqn.to_string(std::path::Path::new(""), self.interner)
+                        inferrable.to_string(self.interner)
                     );
                     any_were_unsafe_to_compile = true;
                 }
@@ -473,9 +658,19 @@ impl<'a, F: EvalComptimeFn> InferenceCtx<'a, F> {
                         _ => todo!("entry point doesn't have lambda body"),
                     };
 
-                    if !param_tys.is_empty() {
+                    // the entry point can optionally take the command line arguments
+                    let valid_params = match param_tys.as_slice() {
+                        [] => true,
+                        [args] => matches!(
+                            args.as_ref(),
+                            Ty::Slice { sub_ty } if **sub_ty == Ty::String
+                        ),
+                        _ => false,
+                    };
+
+                    if !valid_params {
                         self.diagnostics.push(TyDiagnostic {
-                            kind: TyDiagnosticKind::EntryHasParams,
+                            kind: TyDiagnosticKind::EntryBadParams,
                             file: entry_point.file,
                             // `None` because the correctness of the entry point
                             // will not affect the compilability of this global.
@@ -526,6 +721,7 @@ impl<'a, F: EvalComptimeFn> InferenceCtx<'a, F> {
         match inferrable {
             Inferrable::Global(fqn) => self.infer_fqn(fqn)?,
             Inferrable::Lambda(lambda) => self.infer_lambda(lambda)?,
+            Inferrable::StaticAssert(ctc) => self.infer_static_assert(ctc)?,
         }
 
         self.all_inferred.insert(inferrable);
@@ -622,7 +818,11 @@ impl<'a, F: EvalComptimeFn> InferenceCtx<'a, F> {
             return Ok(());
         }
 
-        let (param_tys, return_ty) = self.tys[fql.file][fql.expr].as_function().unwrap();
+        let lambda_ty = self.tys[fql.file][fql.expr];
+        let (param_tys, return_ty) = lambda_ty
+            .as_function()
+            .or_else(|| lambda_ty.as_closure())
+            .unwrap();
 
         let mut global_ctx = GlobalInferenceCtx {
             file: fql.file,
@@ -645,9 +845,354 @@ impl<'a, F: EvalComptimeFn> InferenceCtx<'a, F> {
 
         Ok(())
     }
+
+    fn infer_static_assert(&mut self, ctc: FQComptime) -> InferResult<()> {
+        let mut global_ctx = GlobalInferenceCtx {
+            file: ctc.file,
+            current_inferring: Inferrable::StaticAssert(ctc),
+            world_index: self.world_index,
+            world_bodies: self.world_bodies,
+            bodies: &self.world_bodies[ctc.file],
+            interner: self.interner,
+            local_usages: Default::default(),
+            inferred_stmts: &mut self.inferred_stmts,
+            tys: &mut self.tys,
+            param_tys: Vec::new(),
+            all_inferred: &self.all_inferred,
+            to_infer: &mut self.to_infer,
+            diagnostics: &mut self.diagnostics,
+            eval_comptime: &mut self.eval_comptime,
+        };
+
+        global_ctx.finish_body(ctc.expr, Some(Ty::Bool.into()), false)?;
+
+        // any errors within the condition have already been reported
+        if !global_ctx.is_safe_to_compile(ctc.expr)? {
+            return Ok(());
+        }
+
+        // reading a runtime global gets reported once everything is inferred
+        let hir::Comptime { body } = self.world_bodies[ctc.file][ctc.comptime];
+        if self
+            .tys
+            .runtime_global_in_comptime(self.world_bodies, ctc.file, body)
+            .is_some()
+        {
+            return Ok(());
+        }
+
+        if let ComptimeResult::Integer { num: 0, .. } = (self.eval_comptime)(ctc, &self.tys) {
+            let static_assert = self.world_bodies[ctc.file]
+                .static_asserts()
+                .find(|static_assert| static_assert.condition == ctc.expr)
+                .unwrap();
+
+            self.diagnostics.push(TyDiagnostic {
+                kind: TyDiagnosticKind::StaticAssertFailed {
+                    message: static_assert.message,
+                },
+                file: ctc.file,
+                // `None` because a failed assertion doesn't stop
+                // anything else from being compiled
+                expr: None,
+                range: static_assert.range,
+                help: None,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Sorts the runtime globals so that each one is initialized after the runtime globals it
+    /// depends on. A global depends on anything its body refers to, including whatever is
+    /// referred to by the bodies of the functions it uses (whether or not those get called).
+    fn order_runtime_globals(&mut self) {
+        let runtime_globals = self
+            .tys
+            .runtime_globals
+            .iter()
+            .copied()
+            .sorted()
+            .collect_vec();
+
+        let mut references = FxHashMap::default();
+        // for each runtime global, the runtime globals it depends on along with the
+        // path of references leading to them
+        let mut deps: FxHashMap<hir::Fqn, Vec<(hir::Fqn, Vec<hir::Fqn>)>> = FxHashMap::default();
+        let mut to_init = TopoSort::<hir::Fqn>::new();
+
+        for &global in &runtime_globals {
+            to_init.insert(global);
+
+            // breadth-first so that the paths we report are as short as possible
+            let mut parents = FxHashMap::default();
+            let mut queue = VecDeque::from([global]);
+            let mut global_deps = Vec::new();
+
+            while let Some(current) = queue.pop_front() {
+                for referenced in self.referenced_globals(current, &mut references) {
+                    if parents.contains_key(&referenced) {
+                        continue;
+                    }
+                    parents.insert(referenced, current);
+
+                    if referenced != global && !self.tys.runtime_globals.contains(&referenced) {
+                        queue.push_back(referenced);
+                        continue;
+                    }
+
+                    let mut path = vec![referenced];
+                    let mut node = current;
+                    while node != global {
+                        path.push(node);
+                        node = parents[&node];
+                    }
+                    path.reverse();
+
+                    to_init.insert_dep(global, referenced);
+                    global_deps.push((referenced, path));
+                }
+            }
+
+            deps.insert(global, global_deps);
+        }
+
+        let mut order = Vec::with_capacity(runtime_globals.len());
+
+        loop {
+            match to_init.pop_all() {
+                Ok(ready) if ready.is_empty() => break,
+                Ok(ready) => order.extend(ready.into_iter().sorted()),
+                Err(_) => {
+                    let remaining = to_init
+                        .peek_all_cyclic()
+                        .unwrap()
+                        .into_iter()
+                        .copied()
+                        .sorted()
+                        .collect_vec();
+
+                    // every remaining global still has a remaining dependency,
+                    // so following them will eventually loop back around
+                    let mut walk = vec![remaining[0]];
+                    let cycle = loop {
+                        let current = *walk.last().unwrap();
+                        let (next, _) = deps[&current]
+                            .iter()
+                            .find(|(dep, _)| remaining.contains(dep))
+                            .unwrap();
+
+                        if let Some(start) = walk.iter().position(|global| global == next) {
+                            break walk.split_off(start);
+                        }
+
+                        walk.push(*next);
+                    };
+
+                    let mut path = vec![cycle[0]];
+                    for (idx, global) in cycle.iter().enumerate() {
+                        let next = cycle.get(idx + 1).unwrap_or(&cycle[0]);
+
+                        let (_, sub_path) =
+                            deps[global].iter().find(|(dep, _)| dep == next).unwrap();
+
+                        path.extend(sub_path);
+                    }
+
+                    self.diagnostics.push(TyDiagnostic {
+                        kind: TyDiagnosticKind::GlobalInitCycle { path },
+                        file: cycle[0].file,
+                        // `None` because the cycle doesn't stop the
+                        // individual globals from being compiled
+                        expr: None,
+                        range: self.world_index.range_info(cycle[0]).whole,
+                        help: None,
+                    });
+
+                    for global in &cycle {
+                        to_init.remove(global);
+                    }
+                }
+            }
+        }
+
+        self.tys.global_init_order = order;
+    }
+
+    /// Runtime globals only get initialized once the program starts,
+    /// so they can't be read by anything that runs during compilation
+    fn report_runtime_globals_in_comptime(&mut self) {
+        let comptimes = self
+            .world_bodies
+            .find_comptimes()
+            .into_iter()
+            .chain(
+                self.all_inferred
+                    .iter()
+                    .filter_map(|inferrable| match inferrable {
+                        Inferrable::StaticAssert(ctc) => Some(*ctc),
+                        _ => None,
+                    }),
+            )
+            .sorted()
+            .collect_vec();
+
+        for ctc in comptimes {
+            let hir::Comptime { body } = self.world_bodies[ctc.file][ctc.comptime];
+
+            let Some(path) = self
+                .tys
+                .runtime_global_in_comptime(self.world_bodies, ctc.file, body)
+            else {
+                continue;
+            };
+
+            self.diagnostics.push(TyDiagnostic {
+                kind: TyDiagnosticKind::RuntimeGlobalInComptime { path },
+                file: ctc.file,
+                expr: Some(ctc.expr),
+                range: self.world_bodies[ctc.file].range_for_expr(ctc.expr),
+                help: None,
+            });
+        }
+    }
+
+    /// the globals which are referenced within the body of the given global
+    fn referenced_globals(
+        &self,
+        fqn: hir::Fqn,
+        cache: &mut FxHashMap<hir::Fqn, Vec<hir::Fqn>>,
+    ) -> Vec<hir::Fqn> {
+        if let Some(referenced) = cache.get(&fqn) {
+            return referenced.clone();
+        }
+
+        if !self.world_bodies.exists(fqn) || self.world_bodies.is_extern(fqn) {
+            return Vec::new();
+        }
+
+        let referenced = self.tys.globals_referenced_in(
+            self.world_bodies,
+            fqn.file,
+            self.world_bodies.body(fqn),
+        );
+
+        cache.insert(fqn, referenced.clone());
+
+        referenced
+    }
 }
 
 impl ProjectInference {
+    /// the globals which are referenced within `body`, including within the lambdas it contains
+    fn globals_referenced_in(
+        &self,
+        world_bodies: &hir::WorldBodies,
+        file: hir::FileName,
+        body: Idx<hir::Expr>,
+    ) -> Vec<hir::Fqn> {
+        let bodies = &world_bodies[file];
+        let file_tys = &self[file];
+
+        bodies
+            .descendants(
+                body,
+                hir::DescentOpts::All {
+                    include_lambdas: true,
+                },
+            )
+            .filter_map(|descendant| match descendant {
+                hir::Descendant::Expr(expr) => Some(expr),
+                hir::Descendant::Stmt(_) => None,
+            })
+            .flat_map(|expr| {
+                let referenced = match &bodies[expr] {
+                    hir::Expr::LocalGlobal(global) => Some(hir::Fqn {
+                        file,
+                        name: global.name,
+                    }),
+                    hir::Expr::Member { previous, field } => {
+                        match file_tys.expr_tys.get(*previous).map(AsRef::as_ref) {
+                            Some(Ty::File(file)) => Some(hir::Fqn {
+                                file: *file,
+                                name: field.name,
+                            }),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+
+                // `a.f()` and `a + b` refer to `f` and `op_add`, and `ptr as Interface`
+                // refers to every function in the vtable
+                referenced
+                    .into_iter()
+                    .chain(file_tys.get_method_call(expr).map(|method| method.fqn))
+                    .chain(
+                        file_tys
+                            .get_interface_impls(expr)
+                            .unwrap_or_default()
+                            .iter()
+                            .copied(),
+                    )
+            })
+            // globals that couldn't be resolved have already been reported as cyclic
+            .filter(|referenced| {
+                self.signatures
+                    .get(referenced)
+                    .is_some_and(|Signature(ty)| !ty.is_unknown())
+            })
+            .unique()
+            .collect_vec()
+    }
+
+    /// Finds a runtime global which would be read by evaluating `body` at compile-time,
+    /// either directly or through the globals and functions it refers to.
+    /// Returns the path of references leading to that runtime global.
+    pub(crate) fn runtime_global_in_comptime(
+        &self,
+        world_bodies: &hir::WorldBodies,
+        file: hir::FileName,
+        body: Idx<hir::Expr>,
+    ) -> Option<Vec<hir::Fqn>> {
+        let mut parents = FxHashMap::default();
+        let mut queue = VecDeque::new();
+
+        // breadth-first so that the path we report is as short as possible
+        for referenced in self.globals_referenced_in(world_bodies, file, body) {
+            parents.insert(referenced, None);
+            queue.push_back(referenced);
+        }
+
+        while let Some(current) = queue.pop_front() {
+            if self.runtime_globals.contains(&current) {
+                let mut path = vec![current];
+                while let Some(&Some(parent)) = parents.get(path.last().unwrap()) {
+                    path.push(parent);
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            if !world_bodies.exists(current) || world_bodies.is_extern(current) {
+                continue;
+            }
+
+            for referenced in
+                self.globals_referenced_in(world_bodies, current.file, world_bodies.body(current))
+            {
+                if parents.contains_key(&referenced) {
+                    continue;
+                }
+                parents.insert(referenced, Some(current));
+                queue.push_back(referenced);
+            }
+        }
+
+        None
+    }
+
     /// This might be slightly superficial in some scenarios, I'm not sure
     pub fn all_tys(&self) -> impl Iterator<Item = Intern<Ty>> + '_ {
         self.signatures
@@ -663,13 +1208,29 @@ impl ProjectInference {
             .unique()
     }
 
+    /// Returns true if the global's value has to be computed at runtime,
+    /// before the entry point is called
+    pub fn is_runtime_global(&self, fqn: hir::Fqn) -> bool {
+        self.runtime_globals.contains(&fqn)
+    }
+
+    /// All the runtime globals, ordered so that every global comes after
+    /// the runtime globals its initializer depends on
+    pub fn global_init_order(&self) -> &[hir::Fqn] {
+        &self.global_init_order
+    }
+
     fn shrink_to_fit(&mut self) {
         let Self {
             signatures,
             files: modules,
+            runtime_globals,
+            global_init_order,
         } = self;
         signatures.shrink_to_fit();
         modules.shrink_to_fit();
+        runtime_globals.shrink_to_fit();
+        global_init_order.shrink_to_fit();
     }
 
     pub fn debug(
@@ -756,6 +1317,7 @@ impl Ty {
             },
             Self::Bool => "bool".to_string(),
             Self::String => "str".to_string(),
+            Self::CString => "cstr".to_string(),
             Self::Char => "char".to_string(),
             Self::Array {
                 anonymous,
@@ -787,6 +1349,8 @@ impl Ty {
             Self::Function {
                 param_tys: params,
                 return_ty,
+                is_variadic,
+                ..
             } => {
                 let mut res = "(".to_string();
 
@@ -797,25 +1361,61 @@ impl Ty {
                         res.push_str(", ");
                     }
                 }
+                if *is_variadic {
+                    if !params.is_empty() {
+                        res.push_str(", ");
+                    }
+                    res.push_str("..");
+                }
                 res.push_str(") -> ");
                 res.push_str(&return_ty.display(mod_dir, interner));
 
                 res
             }
+            Self::Closure {
+                param_tys: params,
+                return_ty,
+            } => {
+                let mut res = "(".to_string();
+
+                for (idx, param) in params.iter().enumerate() {
+                    res.push_str(&param.display(mod_dir, interner));
+
+                    if idx != params.len() - 1 {
+                        res.push_str(", ");
+                    }
+                }
+                res.push_str(") [] -> ");
+                res.push_str(&return_ty.display(mod_dir, interner));
+
+                res
+            }
             Self::Struct { fqn: Some(fqn), .. } => fqn.to_string(mod_dir, interner),
             Self::Struct {
                 anonymous,
                 fqn: None,
                 uid,
                 members,
+                repr,
+                ..
             } => {
                 let mut res = if *anonymous {
-                    "struct ~{".to_string()
+                    "struct ~".to_string()
                 } else {
-                    format!("struct'{} {{", uid)
+                    format!("struct'{} ", uid)
                 };
+                if repr.packed {
+                    res.push_str("#packed ");
+                }
+                if let Some(align) = repr.align {
+                    res.push_str(&format!("#align({}) ", align));
+                }
+                res.push('{');
 
                 for (idx, (name, ty)) in members.iter().enumerate() {
+                    if let Some(Some(align)) = repr.field_aligns.get(idx) {
+                        res.push_str(&format!("#align({}) ", align));
+                    }
                     res.push_str(interner.lookup(name.0));
                     res.push_str(": ");
 
@@ -830,34 +1430,101 @@ impl Ty {
 
                 res
             }
-            Self::Type => "type".to_string(),
-            Self::Any => "any".to_string(),
-            Self::Void => "void".to_string(),
-            Self::File(file_name) => {
-                format!("file {}", file_name.to_string(mod_dir, interner))
-            }
-            Self::NoEval => "noeval".to_string(),
-        }
-    }
-}
+            Self::Tuple { item_tys } => {
+                let mut res = "(".to_string();
 
-#[cfg(test)]
-mod tests {
-    use std::{path::Path, vec};
+                for (idx, item_ty) in item_tys.iter().enumerate() {
+                    res.push_str(&item_ty.display(mod_dir, interner));
 
-    use super::*;
-    use ast::AstNode;
-    use codegen::Verbosity;
-    use expect_test::{expect, Expect};
-    use interner::Interner;
-    use target_lexicon::Triple;
-    use uid_gen::UIDGenerator;
+                    if idx != item_tys.len() - 1 {
+                        res.push_str(", ");
+                    }
+                }
 
-    #[track_caller]
-    fn check<const N: usize>(
-        input: &str,
-        expect: Expect,
-        expected_diagnostics: impl Fn(
+                if item_tys.len() == 1 {
+                    res.push(',');
+                }
+
+                res.push(')');
+
+                res
+            }
+            Self::Union { fqn: Some(fqn), .. } => fqn.to_string(mod_dir, interner),
+            Self::Union {
+                fqn: None,
+                uid,
+                members,
+            } => {
+                let mut res = format!("union'{} {{", uid);
+
+                for (idx, (name, ty)) in members.iter().enumerate() {
+                    res.push_str(interner.lookup(name.0));
+                    res.push_str(": ");
+
+                    res.push_str(&ty.display(mod_dir, interner));
+
+                    if idx != members.len() - 1 {
+                        res.push_str(", ");
+                    }
+                }
+
+                res.push('}');
+
+                res
+            }
+            Self::Interface { fqn: Some(fqn), .. } => fqn.to_string(mod_dir, interner),
+            Self::Interface {
+                fqn: None,
+                uid,
+                methods,
+            } => {
+                let mut res = format!("interface'{} {{", uid);
+
+                for (idx, (name, ty)) in methods.iter().enumerate() {
+                    res.push_str(interner.lookup(name.0));
+                    res.push_str(": ");
+
+                    res.push_str(&ty.display(mod_dir, interner));
+
+                    if idx != methods.len() - 1 {
+                        res.push_str(", ");
+                    }
+                }
+
+                res.push('}');
+
+                res
+            }
+            Self::Optional { sub_ty } => format!("?{}", sub_ty.display(mod_dir, interner)),
+            Self::Null => "null".to_string(),
+            Self::Type => "type".to_string(),
+            Self::Any => "any".to_string(),
+            Self::Void => "void".to_string(),
+            Self::File(file_name) => {
+                format!("file {}", file_name.to_string(mod_dir, interner))
+            }
+            Self::NoEval => "noeval".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, vec};
+
+    use super::*;
+    use ast::AstNode;
+    use codegen::Verbosity;
+    use expect_test::{expect, Expect};
+    use interner::Interner;
+    use target_lexicon::Triple;
+    use uid_gen::UIDGenerator;
+
+    #[track_caller]
+    fn check<const N: usize>(
+        input: &str,
+        expect: Expect,
+        expected_diagnostics: impl Fn(
             &mut Interner,
         ) -> [(
             TyDiagnosticKind,
@@ -902,7 +1569,7 @@ mod tests {
             let tree = parse.into_syntax_tree();
 
             let root = ast::Root::cast(tree.root(), &tree).unwrap();
-            let (index, _) = hir::index(root, &tree, &mut interner);
+            let (index, _) = hir::index(root, &tree, &mut interner, &hir::BuildConfig::default());
 
             let module = hir::FileName(interner.intern(name));
 
@@ -914,6 +1581,7 @@ mod tests {
                 &mut uid_gen,
                 &mut interner,
                 Path::new(""),
+                &hir::BuildConfig::default(),
                 true,
             );
             world_index.add_file(module, index);
@@ -929,7 +1597,7 @@ mod tests {
         let tree = parse.into_syntax_tree();
         let root = ast::Root::cast(tree.root(), &tree).unwrap();
 
-        let (index, d) = hir::index(root, &tree, &mut interner);
+        let (index, d) = hir::index(root, &tree, &mut interner, &hir::BuildConfig::default());
         index_diags.extend(d);
 
         let (bodies, d) = hir::lower(
@@ -940,6 +1608,7 @@ mod tests {
             &mut uid_gen,
             &mut interner,
             Path::new(""),
+            &hir::BuildConfig::default(),
             true,
         );
         lowering_diags.extend(d);
@@ -1822,12 +2491,7 @@ mod tests {
                 8 : () -> void
                 l0 : <unknown>
             "#]],
-            |_| {
-                [
-                    (TyDiagnosticKind::GlobalNotConst, 63..66, None),
-                    (TyDiagnosticKind::ArraySizeNotConst, 130..133, None),
-                ]
-            },
+            |_| [(TyDiagnosticKind::ArraySizeNotConst, 130..133, None)],
         );
     }
 
@@ -2062,6 +2726,7 @@ mod tests {
                             sub_ty: Ty::IInt(32).into(),
                         }
                         .into(),
+                        is_slice_bound: false,
                     },
                     118..132,
                     None,
@@ -2508,6 +3173,103 @@ mod tests {
         );
     }
 
+    #[test]
+    fn structural_eq() {
+        check(
+            r#"
+                Vec2 :: struct { x: f32, y: f32 };
+                Line :: struct { start: Vec2, end: Vec2, name: str };
+
+                foo :: (a: Line, b: Line, c: [3]i32, d: []bool) -> bool {
+                    a == b && c != i32.[1, 2, 3] && d == d && (1, 'a') == (2, 'b')
+                };
+            "#,
+            expect![[r#"
+                main::Line : type
+                main::Vec2 : type
+                main::foo : (main::Line, main::Line, [3]i32, []bool) -> bool
+                2 : type
+                6 : type
+                9 : usize
+                15 : main::Line
+                16 : main::Line
+                17 : bool
+                18 : [3]i32
+                20 : i32
+                21 : i32
+                22 : i32
+                23 : [3]i32
+                24 : bool
+                25 : bool
+                26 : []bool
+                27 : []bool
+                28 : bool
+                29 : bool
+                30 : {uint}
+                31 : char
+                32 : ({uint}, char)
+                33 : {uint}
+                34 : char
+                35 : ({uint}, char)
+                36 : bool
+                37 : bool
+                38 : bool
+                39 : (main::Line, main::Line, [3]i32, []bool) -> bool
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn structural_eq_uncomparable_member() {
+        check(
+            r#"
+                foo :: (a: [2]^i32, b: [2]^i32) -> bool {
+                    a == b
+                };
+            "#,
+            expect![[r#"
+                main::foo : ([2]^i32, [2]^i32) -> bool
+                0 : usize
+                4 : usize
+                9 : [2]^i32
+                10 : [2]^i32
+                11 : bool
+                12 : bool
+                13 : ([2]^i32, [2]^i32) -> bool
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::BinaryOpMismatch {
+                        op: hir::BinaryOp::Eq,
+                        first: Ty::Array {
+                            anonymous: false,
+                            size: 2,
+                            sub_ty: Ty::Pointer {
+                                mutable: false,
+                                sub_ty: Ty::IInt(32).into(),
+                            }
+                            .into(),
+                        }
+                        .into(),
+                        second: Ty::Array {
+                            anonymous: false,
+                            size: 2,
+                            sub_ty: Ty::Pointer {
+                                mutable: false,
+                                sub_ty: Ty::IInt(32).into(),
+                            }
+                            .into(),
+                        }
+                        .into(),
+                    },
+                    79..85,
+                    None,
+                )]
+            },
+        );
+    }
+
     #[test]
     fn binary_expr_with_missing_operand() {
         check(
@@ -3301,7 +4063,16 @@ mod tests {
                 6 : () -> ^i32
                 l0 : i32
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::LocalRefEscapes {
+                        path: vec![],
+                        into: RefEscape::Return,
+                    },
+                    88..90,
+                    Some((TyDiagnosticHelpKind::EscapesHere, 88..90)),
+                )]
+            },
         );
     }
 
@@ -4954,11 +5725,13 @@ mod tests {
                             expected: Ty::Function {
                                 param_tys: vec![float, Ty::IInt(8).into()],
                                 return_ty: Ty::String.into(),
+                                is_variadic: false,
                             }
                             .into(),
                             found: Ty::Function {
                                 param_tys: vec![int],
                                 return_ty: Ty::Void.into(),
+                                is_variadic: false,
                             }
                             .into(),
                         },
@@ -5011,6 +5784,7 @@ mod tests {
                             found: Ty::Function {
                                 param_tys: vec![int],
                                 return_ty: Ty::Void.into(),
+                                is_variadic: false,
                             }
                             .into(),
                         },
@@ -5210,6 +5984,128 @@ mod tests {
         );
     }
 
+    #[test]
+    fn int_too_large_for_odd_width() {
+        check(
+            r#"
+                foo :: () {
+                    flags : u5 = 31;
+                    more_flags : u5 = 32;
+                    small : i3 = 4;
+                    huge : u100 = 18_446_744_073_709_551_615;
+                };
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                1 : u5
+                3 : u5
+                5 : i3
+                7 : u100
+                8 : void
+                9 : () -> void
+                l0 : u5
+                l1 : u5
+                l2 : i3
+                l3 : u100
+            "#]],
+            |_| {
+                [
+                    (
+                        TyDiagnosticKind::IntTooBigForType {
+                            found: 32,
+                            max: 31,
+                            ty: Ty::UInt(5).into(),
+                        },
+                        104..106,
+                        None,
+                    ),
+                    (
+                        TyDiagnosticKind::IntTooBigForType {
+                            found: 4,
+                            max: 3,
+                            ty: Ty::IInt(3).into(),
+                        },
+                        141..142,
+                        None,
+                    ),
+                ]
+            },
+        );
+    }
+
+    #[test]
+    fn int_too_small_for_type() {
+        check(
+            r#"
+                foo :: () {
+                    lowest : i5 = -16;
+                    too_low : i5 = -17;
+                    byte : i8 = -128;
+                    wide : i64 = -9_223_372_036_854_775_808;
+                };
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                1 : i5
+                2 : i5
+                4 : i5
+                5 : i5
+                7 : i8
+                8 : i8
+                10 : i64
+                11 : i64
+                12 : void
+                13 : () -> void
+                l0 : i5
+                l1 : i5
+                l2 : i8
+                l3 : i64
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::IntTooSmallForType {
+                        found: -17,
+                        min: -16,
+                        ty: Ty::IInt(5).into(),
+                    },
+                    103..106,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn odd_width_ints() {
+        check(
+            r#"
+                foo :: (bit: u1, rgb: u24) {
+                    wide : u32 = rgb;
+                    narrow : u5 = rgb;
+                };
+            "#,
+            expect![[r#"
+                main::foo : (u1, u24) -> void
+                3 : u24
+                5 : u24
+                6 : void
+                7 : (u1, u24) -> void
+                l0 : u32
+                l1 : u5
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::UInt(5).into(),
+                        found: Ty::UInt(24).into(),
+                    },
+                    118..121,
+                    None,
+                )]
+            },
+        );
+    }
+
     #[test]
     fn inference_by_too_large_for_u32() {
         check(
@@ -5319,6 +6215,8 @@ mod tests {
                         (hir::Name(i.intern("name")), Ty::String.into()),
                         (hir::Name(i.intern("age")), Ty::IInt(32).into()),
                     ],
+                    defaults: Vec::new(),
+                    repr: hir::StructRepr::default(),
                 }
                 .into();
 
@@ -5353,127 +6251,103 @@ mod tests {
     }
 
     #[test]
-    fn get_struct_field() {
+    fn struct_member_defaults() {
         check(
             r#"
-                Person :: struct {
+                Config :: struct {
                     name: str,
-                    age: i32
+                    retries: i32 = 3,
+                    verbose: bool = false,
                 };
 
-                foo :: () -> i32 {
-                    some_guy := Person.{
-                        name = "Joe Schmoe",
-                        age = 31,
-                    };
+                foo :: () {
+                    a := Config.{ name = "a" };
+                    b := Config.{ name = "b", retries = 5 };
+                };
 
-                    some_guy.age
+                Point :: struct {
+                    label: str = "origin",
+                    x: f32 = 1.5,
+                    y: f32,
+                };
+
+                bar :: () {
+                    p : Point;
                 };
             "#,
             expect![[r#"
-                main::Person : type
-                main::foo : () -> i32
-                2 : type
-                5 : str
-                6 : i32
-                7 : main::Person
-                8 : main::Person
-                9 : i32
-                10 : i32
-                11 : () -> i32
-                l0 : main::Person
+                main::Config : type
+                main::Point : type
+                main::bar : () -> void
+                main::foo : () -> void
+                2 : i32
+                4 : bool
+                5 : type
+                7 : str
+                8 : main::Config
+                10 : str
+                11 : i32
+                12 : main::Config
+                13 : void
+                14 : () -> void
+                16 : str
+                18 : f32
+                20 : type
+                22 : void
+                23 : () -> void
+                l0 : main::Config
+                l1 : main::Config
+                l2 : main::Point
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn non_existent_field() {
+    fn struct_member_default_not_const() {
         check(
             r#"
-                Person :: struct {
-                    name: str,
-                    age: i32
-                };
-
-                foo :: () -> i32 {
-                    some_guy := Person.{
-                        name = "Joe Schmoe",
-                        age = 31,
+                foo :: () {
+                    x := 5;
+                    Counter :: struct {
+                        count: i32 = x,
                     };
-
-                    some_guy.height
                 };
             "#,
             expect![[r#"
-                main::Person : type
-                main::foo : () -> i32
-                2 : type
-                5 : str
-                6 : i32
-                7 : main::Person
-                8 : main::Person
-                9 : <unknown>
-                10 : <unknown>
-                11 : () -> i32
-                l0 : main::Person
+                main::foo : () -> void
+                0 : i32
+                2 : i32
+                3 : type
+                4 : void
+                5 : () -> void
+                l0 : i32
+                l1 : type
             "#]],
-            |i| {
-                [(
-                    TyDiagnosticKind::NonExistentMember {
-                        member: i.intern("height"),
-                        found_ty: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Person")),
-                            }),
-                            uid: 0,
-                            members: vec![
-                                (hir::Name(i.intern("name")), Ty::String.into()),
-                                (hir::Name(i.intern("age")), Ty::IInt(32).into()),
-                            ],
-                        }
-                        .into(),
-                    },
-                    315..330,
-                    None,
-                )]
-            },
+            |_| [(TyDiagnosticKind::DefaultNotConst, 134..135, None)],
         );
     }
 
     #[test]
-    fn if_mismatch() {
+    fn struct_member_default_mismatch() {
         check(
             r#"
-                foo :: (bar: bool) -> bool {
-                    // this evaluates to `<unknown>`, so the function's return type isn't checked
-                    if bar {
-                        "Hello!"
-                    } else {
-                        100
-                    }
-                }
+                Config :: struct {
+                    retries: i32 = "three",
+                };
             "#,
             expect![[r#"
-                main::foo : (bool) -> bool
-                2 : bool
-                3 : str
-                4 : str
-                5 : {uint}
-                6 : {uint}
-                7 : <unknown>
-                8 : <unknown>
-                9 : (bool) -> bool
+                main::Config : type
+                1 : str
+                2 : type
             "#]],
             |_| {
                 [(
-                    TyDiagnosticKind::IfMismatch {
-                        found: Ty::UInt(0).into(),
-                        expected: Ty::String.into(),
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::IInt(32).into(),
+                        found: Ty::String.into(),
                     },
-                    164..284,
+                    71..78,
                     None,
                 )]
             },
@@ -5481,31 +6355,56 @@ mod tests {
     }
 
     #[test]
-    fn index_non_array() {
+    fn union_literal_member_count() {
         check(
             r#"
-                foo :: () {
-                    bar := "Hello!";
+                Bits :: union {
+                    int: u32,
+                    float: f32,
+                };
 
-                    bar[0];
+                foo :: () -> u32 {
+                    one := Bits.{ float = 1.0 };
+                    two := Bits.{ int = 1, float = 1.0 };
+                    one.int
                 };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : str
-                1 : str
-                2 : usize
-                3 : <unknown>
-                4 : void
-                5 : () -> void
-                l0 : str
+                main::Bits : type
+                main::foo : () -> u32
+                2 : type
+                5 : f32
+                6 : main::Bits
+                8 : u32
+                9 : f32
+                10 : main::Bits
+                11 : main::Bits
+                12 : u32
+                13 : u32
+                14 : () -> u32
+                l0 : main::Bits
+                l1 : main::Bits
             "#]],
-            |_| {
+            |i| {
+                let bits_ty = Ty::Union {
+                    fqn: Some(hir::Fqn {
+                        file: hir::FileName(i.intern("main.capy")),
+                        name: hir::Name(i.intern("Bits")),
+                    }),
+                    uid: 0,
+                    members: vec![
+                        (hir::Name(i.intern("int")), Ty::UInt(32).into()),
+                        (hir::Name(i.intern("float")), Ty::Float(32).into()),
+                    ],
+                }
+                .into();
+
                 [(
-                    TyDiagnosticKind::IndexNonArray {
-                        found: Ty::String.into(),
+                    TyDiagnosticKind::UnionLiteralMemberCount {
+                        found: 2,
+                        expected_ty: bits_ty,
                     },
-                    87..93,
+                    226..255,
                     None,
                 )]
             },
@@ -5513,35 +6412,83 @@ mod tests {
     }
 
     #[test]
-    fn mismatch_arg_count() {
+    fn optionals() {
         check(
             r#"
-                bar :: (num: i32) {};
+                find :: (x: i32) -> ?i32 {
+                    if x > 0 {
+                        return x;
+                    }
+                    null
+                };
 
-                foo :: () {
-                    bar(1, 2, 3);
-                }
+                foo :: () -> i32 {
+                    a : ?i32 = 5;
+                    b := find(a.?);
+                    if c := b {
+                        c
+                    } else {
+                        0
+                    }
+                };
             "#,
             expect![[r#"
-                main::bar : (i32) -> void
-                main::foo : () -> void
-                1 : void
-                2 : (i32) -> void
-                3 : (i32) -> void
+                main::find : (i32) -> ?i32
+                main::foo : () -> i32
+                3 : i32
                 4 : i32
-                5 : {uint}
-                6 : {uint}
-                7 : void
+                5 : bool
+                6 : i32
+                7 : noeval
                 8 : void
-                9 : () -> void
+                9 : ?i32
+                10 : ?i32
+                11 : (i32) -> ?i32
+                15 : i32
+                16 : (i32) -> ?i32
+                17 : ?i32
+                18 : i32
+                19 : ?i32
+                20 : ?i32
+                21 : i32
+                22 : i32
+                23 : i32
+                24 : i32
+                25 : i32
+                26 : i32
+                27 : () -> i32
+                l0 : ?i32
+                l1 : ?i32
+                l2 : i32
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn unwrap_non_optional() {
+        check(
+            r#"
+                foo :: () -> i32 {
+                    a : i32 = 5;
+                    a.?
+                };
+            "#,
+            expect![[r#"
+                main::foo : () -> i32
+                2 : i32
+                3 : i32
+                4 : <unknown>
+                5 : <unknown>
+                6 : () -> i32
+                l0 : i32
             "#]],
             |_| {
                 [(
-                    TyDiagnosticKind::MismatchedArgCount {
-                        found: 3,
-                        expected: 1,
+                    TyDiagnosticKind::UnwrapNonOptional {
+                        found: Ty::IInt(32).into(),
                     },
-                    88..100,
+                    89..92,
                     None,
                 )]
             },
@@ -5549,183 +6496,325 @@ mod tests {
     }
 
     #[test]
-    fn call_non_function() {
+    fn tuples() {
         check(
             r#"
-                foo :: () {
-                    wow := "Wow!";
+                parse :: (s: str) -> (i32, bool) {
+                    (s.len as i32, true)
+                };
 
-                    wow(42);
-                }
+                foo :: () {
+                    x, ok := parse("42");
+                    a, b := (1, 2.5);
+                    c : u8 = a;
+                };
             "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : str
-                1 : str
-                2 : {uint}
-                3 : <unknown>
-                4 : void
-                5 : () -> void
-                l0 : str
+                main::parse : (str) -> (i32, bool)
+                4 : str
+                5 : usize
+                7 : i32
+                8 : bool
+                9 : (i32, bool)
+                10 : (i32, bool)
+                11 : (str) -> (i32, bool)
+                12 : (str) -> (i32, bool)
+                13 : str
+                14 : (i32, bool)
+                15 : (i32, bool)
+                16 : i32
+                17 : (i32, bool)
+                18 : bool
+                19 : u8
+                20 : {float}
+                21 : (u8, {float})
+                22 : (u8, {float})
+                23 : u8
+                24 : (u8, {float})
+                25 : {float}
+                27 : u8
+                28 : void
+                29 : () -> void
+                l0 : (i32, bool)
+                l1 : i32
+                l2 : bool
+                l3 : (u8, {float})
+                l4 : u8
+                l5 : {float}
+                l6 : u8
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::CalledNonFunction {
-                        found: Ty::String.into(),
-                    },
-                    85..92,
-                    None,
-                )]
-            },
+            |_| [],
         );
     }
 
     #[test]
-    fn deref_non_pointer() {
+    fn destructure_mismatch() {
         check(
             r#"
                 foo :: () {
-                    wow := "Wow!";
-
-                    wow^;
-                }
+                    x, y := (1, 2, 3);
+                    z, w := 5;
+                };
             "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : str
-                1 : str
-                2 : <unknown>
-                3 : void
-                4 : () -> void
-                l0 : str
+                0 : {uint}
+                1 : {uint}
+                2 : {uint}
+                3 : ({uint}, {uint}, {uint})
+                4 : ({uint}, {uint}, {uint})
+                5 : {uint}
+                6 : ({uint}, {uint}, {uint})
+                7 : {uint}
+                8 : {uint}
+                9 : {uint}
+                10 : <unknown>
+                11 : {uint}
+                12 : <unknown>
+                13 : void
+                14 : () -> void
+                l0 : ({uint}, {uint}, {uint})
+                l1 : {uint}
+                l2 : {uint}
+                l3 : {uint}
+                l4 : <unknown>
+                l5 : <unknown>
             "#]],
             |_| {
-                [(
-                    TyDiagnosticKind::DerefNonPointer {
-                        found: Ty::String.into(),
-                    },
-                    85..89,
-                    None,
-                )]
+                [
+                    (
+                        TyDiagnosticKind::DestructureMismatch {
+                            expected: 2,
+                            found: Ty::Tuple {
+                                item_tys: vec![Ty::UInt(0).into(); 3],
+                            }
+                            .into(),
+                        },
+                        57..66,
+                        None,
+                    ),
+                    (
+                        TyDiagnosticKind::DestructureMismatch {
+                            expected: 2,
+                            found: Ty::UInt(0).into(),
+                        },
+                        96..97,
+                        None,
+                    ),
+                ]
             },
         );
     }
 
     #[test]
-    fn param_as_ty() {
+    fn closures() {
         check(
             r#"
-                foo :: (T: type) {
-                    wow : T = "hello :)";
-                }
+                apply :: (f: (x: i32) [] -> i32, x: i32) -> i32 {
+                    f(x)
+                };
+
+                foo :: (y: i64) -> i32 {
+                    offset := 5;
+                    count : u8 = 0;
+
+                    add := (x: i32) [y] -> i32 {
+                        count = count + 1;
+                        x + offset + y as i32
+                    };
+
+                    apply(add, 10)
+                };
             "#,
             expect![[r#"
-                main::foo : (type) -> void
-                2 : str
-                3 : void
-                4 : (type) -> void
-                l0 : <unknown>
+                main::apply : ((i32) [] -> i32, i32) -> i32
+                main::foo : (i64) -> i32
+                6 : (i32) [] -> i32
+                7 : i32
+                8 : i32
+                9 : i32
+                10 : ((i32) [] -> i32, i32) -> i32
+                13 : i32
+                15 : u8
+                18 : i64
+                19 : u8
+                20 : u8
+                21 : u8
+                22 : u8
+                23 : u8
+                24 : i32
+                25 : i32
+                26 : i32
+                27 : i32
+                28 : i64
+                30 : i32
+                31 : i32
+                32 : i32
+                33 : (i32) [] -> i32
+                34 : ((i32) [] -> i32, i32) -> i32
+                35 : (i32) [] -> i32
+                36 : i32
+                37 : i32
+                38 : i32
+                39 : (i64) -> i32
+                l0 : i32
+                l1 : u8
+                l2 : (i32) [] -> i32
             "#]],
-            |_| [(TyDiagnosticKind::ParamNotATy, 62..63, None)],
+            |_| [],
         );
     }
 
     #[test]
-    fn cast_as_local_ty() {
+    fn call_closure_wrong_args() {
         check(
             r#"
                 foo :: () {
-                    imaginary :: distinct i32;
-
-                    real : i32 = 5;
-
-                    real as imaginary;
-                }
+                    x := 5;
+                    f := (a: bool) { x; };
+                    f(1);
+                };
             "#,
             expect![[r#"
                 main::foo : () -> void
-                1 : type
+                0 : i32
+                2 : i32
                 3 : i32
-                4 : i32
-                6 : distinct'0 i32
-                7 : void
-                8 : () -> void
-                l0 : type
-                l1 : i32
+                4 : void
+                5 : (bool) [] -> void
+                6 : (bool) [] -> void
+                7 : {uint}
+                8 : void
+                9 : void
+                10 : () -> void
+                l0 : i32
+                l1 : (bool) [] -> void
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Bool.into(),
+                        found: Ty::UInt(0).into(),
+                    },
+                    122..123,
+                    None,
+                )]
+            },
         );
     }
 
     #[test]
-    fn no_implicit_struct_cast() {
+    fn assign_to_capture_by_value() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32,
-                    b: i8,
-                };
-
-                Bar :: struct {
-                    a: i32,
-                    b: i8,
+                foo :: () {
+                    x := 5;
+                    f := () [x] {
+                        x = 6;
+                    };
                 };
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                0 : i32
+                1 : i32
+                2 : i32
+                3 : {uint}
+                4 : void
+                5 : () [] -> void
+                6 : void
+                7 : () -> void
+                l0 : i32
+                l1 : () [] -> void
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::CannotMutate,
+                    115..121,
+                    Some((
+                        TyDiagnosticHelpKind::ImmutableCapture { by_value: true },
+                        86..87,
+                    )),
+                )]
+            },
+        );
+    }
 
-                main :: () {
-                    my_foo : Foo = Foo.{
-                        a = 1,
-                        b = 2,
+    #[test]
+    fn assign_to_immutable_capture() {
+        check(
+            r#"
+                foo :: (y: i32) {
+                    x :: 5;
+                    f := () {
+                        x = 6;
+                        y = 7;
                     };
+                };
+            "#,
+            expect![[r#"
+                main::foo : (i32) -> void
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : {uint}
+                5 : i32
+                6 : i32
+                7 : {uint}
+                8 : void
+                9 : () [] -> void
+                10 : void
+                11 : (i32) -> void
+                l0 : i32
+                l1 : () [] -> void
+            "#]],
+            |_| {
+                [
+                    (
+                        TyDiagnosticKind::CannotMutate,
+                        117..123,
+                        Some((TyDiagnosticHelpKind::ImmutableBinding, 55..62)),
+                    ),
+                    (
+                        TyDiagnosticKind::CannotMutate,
+                        148..154,
+                        Some((
+                            TyDiagnosticHelpKind::ImmutableParam { assignment: true },
+                            25..31,
+                        )),
+                    ),
+                ]
+            },
+        );
+    }
 
-                    my_bar : Bar = my_foo;
+    #[test]
+    fn if_binding_non_optional() {
+        check(
+            r#"
+                foo :: () {
+                    a := true;
+                    if b := a {}
                 };
             "#,
             expect![[r#"
-                main::Bar : type
-                main::Foo : type
-                main::main : () -> void
-                2 : type
-                5 : type
-                8 : i32
-                9 : i8
-                10 : main::Foo
-                12 : main::Foo
-                13 : void
-                14 : () -> void
-                l0 : main::Foo
-                l1 : main::Bar
+                main::foo : () -> void
+                0 : bool
+                1 : bool
+                2 : void
+                3 : void
+                4 : void
+                5 : () -> void
+                l0 : bool
+                l1 : <unknown>
             "#]],
-            |i| {
+            |_| {
                 [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Bar")),
-                            }),
-                            uid: 1,
-                            members: vec![
-                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
-                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
-                            ],
-                        }
-                        .into(),
-                        found: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Foo")),
-                            }),
-                            uid: 0,
-                            members: vec![
-                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
-                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
-                            ],
-                        }
-                        .into(),
+                    TyDiagnosticKind::UnwrapNonOptional {
+                        found: Ty::Bool.into(),
                     },
-                    406..412,
+                    88..89,
                     None,
                 )]
             },
@@ -5733,224 +6822,179 @@ mod tests {
     }
 
     #[test]
-    fn cast_struct_same_fields() {
+    fn method_call() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32,
-                    b: i8,
+                Vec2 :: struct {
+                    x: i32,
+                    y: i32,
                 };
 
-                Bar :: struct {
-                    a: i32,
-                    b: i8,
-                };
+                length :: (v: Vec2) -> i32 { v.x + v.y };
+                scale :: (v: ^mut Vec2, by: i32) {};
+                sum :: (v: ^Vec2) -> i32 { 0 };
 
                 main :: () {
-                    my_foo : Foo = Foo.{
-                        a = 1,
-                        b = 2,
-                    };
-
-                    my_bar : Bar = my_foo as Bar;
+                    v := Vec2.{ x = 1, y = 2 };
+                    v.scale(3);
+                    a := v.length();
+                    b := v.sum();
                 };
             "#,
             expect![[r#"
-                main::Bar : type
-                main::Foo : type
+                main::Vec2 : type
+                main::length : (main::Vec2) -> i32
                 main::main : () -> void
+                main::scale : (^mut main::Vec2, i32) -> void
+                main::sum : (^main::Vec2) -> i32
                 2 : type
-                5 : type
+                5 : main::Vec2
+                6 : i32
+                7 : main::Vec2
                 8 : i32
-                9 : i8
-                10 : main::Foo
-                12 : main::Foo
-                14 : main::Bar
+                9 : i32
+                10 : i32
+                11 : (main::Vec2) -> i32
                 15 : void
-                16 : () -> void
-                l0 : main::Foo
-                l1 : main::Bar
+                16 : (^mut main::Vec2, i32) -> void
+                20 : i32
+                21 : i32
+                22 : (^main::Vec2) -> i32
+                24 : i32
+                25 : i32
+                26 : main::Vec2
+                27 : main::Vec2
+                28 : (i32) -> void
+                29 : i32
+                30 : void
+                31 : main::Vec2
+                32 : () -> i32
+                33 : i32
+                34 : main::Vec2
+                35 : () -> i32
+                36 : i32
+                37 : void
+                38 : () -> void
+                l0 : main::Vec2
+                l1 : i32
+                l2 : i32
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn cast_struct_diff_field_order() {
+    fn method_call_from_defining_file() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32,
-                    b: i8,
+                #- main.capy
+                list :: import "list.capy";
+
+                main :: () {
+                    l := list.make();
+                    l.push(5);
                 };
 
-                Bar :: struct {
-                    b: i8,
-                    a: i32,
+                #- list.capy
+                List :: struct {
+                    len: usize,
                 };
 
-                main :: () {
-                    my_foo : Foo = Foo.{
-                        a = 1,
-                        b = 2,
-                    };
+                make :: () -> List { List.{ len = 0 } };
 
-                    my_bar : Bar = my_foo as Bar;
-                };
+                push :: (l: ^mut List, item: i64) {};
             "#,
             expect![[r#"
-                main::Bar : type
-                main::Foo : type
+                list::List : type
+                list::make : () -> list::List
+                list::push : (^mut list::List, i64) -> void
+                main::list : file list
                 main::main : () -> void
-                2 : type
-                5 : type
-                8 : i32
-                9 : i8
-                10 : main::Foo
-                12 : main::Foo
-                14 : main::Bar
-                15 : void
-                16 : () -> void
-                l0 : main::Foo
-                l1 : main::Bar
+                list:
+                  1 : type
+                  4 : usize
+                  5 : list::List
+                  6 : list::List
+                  7 : () -> list::List
+                  11 : void
+                  12 : (^mut list::List, i64) -> void
+                main:
+                  0 : file list
+                  1 : file list
+                  2 : () -> list::List
+                  3 : list::List
+                  4 : list::List
+                  5 : (i64) -> void
+                  6 : i64
+                  7 : void
+                  8 : void
+                  9 : () -> void
+                  l0 : list::List
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn cast_struct_diff_field_ty_castable() {
+    fn method_call_ambiguous() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32,
-                    b: i8,
-                };
+                #- main.capy
+                list :: import "list.capy";
 
-                Bar :: struct {
-                    a: i32,
-                    b: i16,
-                };
+                push :: (l: list.List, item: i64) {};
 
                 main :: () {
-                    my_foo : Foo = Foo.{
-                        a = 1,
-                        b = 2,
-                    };
+                    l := list.List.{ len = 0 };
+                    l.push(5);
+                };
 
-                    my_bar : Bar = my_foo as Bar;
+                #- list.capy
+                List :: struct {
+                    len: usize,
                 };
+
+                push :: (l: ^mut List, item: i64) {};
             "#,
             expect![[r#"
-                main::Bar : type
-                main::Foo : type
+                list::List : type
+                list::push : (^mut list::List, i64) -> void
+                main::list : file list
                 main::main : () -> void
-                2 : type
-                5 : type
-                8 : i32
-                9 : i8
-                10 : main::Foo
-                12 : main::Foo
-                14 : main::Bar
-                15 : void
-                16 : () -> void
-                l0 : main::Foo
-                l1 : main::Bar
-            "#]],
-            |_| [],
-        );
-    }
-
-    #[test]
-    fn cast_struct_diff_field_ty_uncastable() {
-        check(
-            r#"
-                Foo :: struct {
-                    a: i32,
-                    b: [2]i32,
-                };
-
-                Bar :: struct {
-                    a: i32,
-                    b: [3]i32,
-                };
-
-                main :: () {
-                    my_foo : Foo = Foo.{
-                        a = 1,
-                        b = .[2, 3],
-                    };
-
-                    my_bar : Bar = my_foo as Bar;
-                };
-            "#,
-            expect![[r#"
-                main::Bar : type
-                main::Foo : type
-                main::main : () -> void
-                1 : usize
-                4 : type
-                6 : usize
-                9 : type
-                12 : i32
-                13 : i32
-                14 : i32
-                15 : [2]i32
-                16 : main::Foo
-                18 : main::Foo
-                20 : main::Bar
-                21 : void
-                22 : () -> void
-                l0 : main::Foo
-                l1 : main::Bar
+                main::push : (list::List, i64) -> void
+                list:
+                  1 : type
+                  5 : void
+                  6 : (^mut list::List, i64) -> void
+                main:
+                  0 : file list
+                  1 : file list
+                  4 : void
+                  5 : (list::List, i64) -> void
+                  6 : file list
+                  8 : usize
+                  9 : list::List
+                  10 : list::List
+                  11 : <unknown>
+                  12 : {uint}
+                  13 : <unknown>
+                  14 : void
+                  15 : () -> void
+                  l0 : list::List
             "#]],
             |i| {
                 [(
-                    TyDiagnosticKind::Uncastable {
-                        from: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Foo")),
-                            }),
-                            uid: 0,
-                            members: vec![
-                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
-                                (
-                                    hir::Name(i.intern("b")),
-                                    Ty::Array {
-                                        anonymous: false,
-                                        size: 2,
-                                        sub_ty: Ty::IInt(32).into(),
-                                    }
-                                    .into(),
-                                ),
-                            ],
-                        }
-                        .into(),
-                        to: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Bar")),
-                            }),
-                            uid: 1,
-                            members: vec![
-                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
-                                (
-                                    hir::Name(i.intern("b")),
-                                    Ty::Array {
-                                        anonymous: false,
-                                        size: 3,
-                                        sub_ty: Ty::IInt(32).into(),
-                                    }
-                                    .into(),
-                                ),
-                            ],
-                        }
-                        .into(),
+                    TyDiagnosticKind::AmbiguousMethod {
+                        first: hir::Fqn {
+                            file: hir::FileName(i.intern("list.capy")),
+                            name: hir::Name(i.intern("push")),
+                        },
+                        second: hir::Fqn {
+                            file: hir::FileName(i.intern("main.capy")),
+                            name: hir::Name(i.intern("push")),
+                        },
                     },
-                    420..433,
+                    197..203,
                     None,
                 )]
             },
@@ -5958,153 +7002,104 @@ mod tests {
     }
 
     #[test]
-    fn cast_struct_diff_field_name() {
+    fn method_call_mut_ref_to_immutable() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32,
-                    b: i8,
-                };
-
-                Bar :: struct {
+                Vec2 :: struct {
                     x: i32,
-                    y: i8,
+                    y: i32,
                 };
 
-                main :: () {
-                    my_foo : Foo = Foo.{
-                        a = 1,
-                        b = 2,
-                    };
+                scale :: (v: ^mut Vec2, by: i32) {};
 
-                    my_bar : Bar = my_foo as Bar;
+                main :: () {
+                    v :: Vec2.{ x = 1, y = 2 };
+                    v.scale(3);
                 };
             "#,
             expect![[r#"
-                main::Bar : type
-                main::Foo : type
+                main::Vec2 : type
                 main::main : () -> void
+                main::scale : (^mut main::Vec2, i32) -> void
                 2 : type
-                5 : type
-                8 : i32
-                9 : i8
-                10 : main::Foo
-                12 : main::Foo
-                14 : main::Bar
+                6 : void
+                7 : (^mut main::Vec2, i32) -> void
+                9 : i32
+                10 : i32
+                11 : main::Vec2
+                12 : main::Vec2
+                13 : (i32) -> void
+                14 : i32
                 15 : void
-                16 : () -> void
-                l0 : main::Foo
-                l1 : main::Bar
+                16 : void
+                17 : () -> void
+                l0 : main::Vec2
             "#]],
-            |i| {
+            |_| {
                 [(
-                    TyDiagnosticKind::Uncastable {
-                        from: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Foo")),
-                            }),
-                            uid: 0,
-                            members: vec![
-                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
-                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
-                            ],
-                        }
-                        .into(),
-                        to: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Bar")),
-                            }),
-                            uid: 1,
-                            members: vec![
-                                (hir::Name(i.intern("x")), Ty::IInt(32).into()),
-                                (hir::Name(i.intern("y")), Ty::IInt(8).into()),
-                            ],
-                        }
-                        .into(),
-                    },
-                    406..419,
-                    None,
+                    TyDiagnosticKind::MutableRefToImmutableData,
+                    261..262,
+                    Some((TyDiagnosticHelpKind::ImmutableBinding, 213..240)),
                 )]
             },
         );
     }
 
     #[test]
-    fn cast_struct_diff_field_len() {
+    fn method_call_wrong_receiver() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32,
-                    b: i8,
+                Vec2 :: struct {
+                    x: i32,
+                    y: i32,
                 };
 
-                Bar :: struct {
-                    a: i32,
-                    b: i8,
-                    c: str,
-                };
+                scale :: (by: i32, v: ^mut Vec2) {};
 
                 main :: () {
-                    my_foo : Foo = Foo.{
-                        a = 1,
-                        b = 2,
-                    };
-
-                    my_bar : Bar = my_foo as Bar;
+                    v := Vec2.{ x = 1, y = 2 };
+                    v.scale(3);
                 };
             "#,
             expect![[r#"
-                main::Bar : type
-                main::Foo : type
+                main::Vec2 : type
                 main::main : () -> void
+                main::scale : (i32, ^mut main::Vec2) -> void
                 2 : type
-                6 : type
+                6 : void
+                7 : (i32, ^mut main::Vec2) -> void
                 9 : i32
-                10 : i8
-                11 : main::Foo
-                13 : main::Foo
-                15 : main::Bar
+                10 : i32
+                11 : main::Vec2
+                12 : main::Vec2
+                13 : <unknown>
+                14 : {uint}
+                15 : <unknown>
                 16 : void
                 17 : () -> void
-                l0 : main::Foo
-                l1 : main::Bar
+                l0 : main::Vec2
             "#]],
             |i| {
                 [(
-                    TyDiagnosticKind::Uncastable {
-                        from: Ty::Struct {
+                    TyDiagnosticKind::NonExistentMember {
+                        member: i.intern("scale"),
+                        found_ty: Ty::Struct {
                             anonymous: false,
                             fqn: Some(hir::Fqn {
                                 file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Foo")),
+                                name: hir::Name(i.intern("Vec2")),
                             }),
                             uid: 0,
                             members: vec![
-                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
-                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
-                            ],
-                        }
-                        .into(),
-                        to: Ty::Struct {
-                            anonymous: false,
-                            fqn: Some(hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("Bar")),
-                            }),
-                            uid: 1,
-                            members: vec![
-                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
-                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
-                                (hir::Name(i.intern("c")), Ty::String.into()),
+                                (hir::Name(i.intern("x")), Ty::IInt(32).into()),
+                                (hir::Name(i.intern("y")), Ty::IInt(32).into()),
                             ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                     },
-                    434..447,
+                    261..268,
                     None,
                 )]
             },
@@ -6112,132 +7107,184 @@ mod tests {
     }
 
     #[test]
-    fn lambda_ty_annotation() {
+    fn operator_overload() {
         check(
             r#"
-                bar :: (s: str) {
-                    // do stuff
-                } 
-
-                foo :: () {
-                    a : (s: str) -> void = (s: str) {};
+                Vector3 :: distinct [3] i32;
 
-                    a = bar;
+                op_add :: (a: Vector3, b: Vector3) -> Vector3 { a };
+                op_mul :: (a: Vector3, by: i32) -> Vector3 { a };
+                op_neg :: (a: Vector3) -> Vector3 { a };
+                op_eq :: (a: ^Vector3, b: Vector3) -> bool { true };
 
-                    a("Hello!");
-                }
+                main :: () {
+                    a : Vector3 = i32.[1, 2, 3];
+                    b := a + a * 2;
+                    c := -b;
+                    d := a != b;
+                };
             "#,
             expect![[r#"
-                main::bar : (str) -> void
-                main::foo : () -> void
-                1 : void
-                2 : (str) -> void
-                8 : void
-                9 : (str) -> void
-                10 : (str) -> void
-                11 : (str) -> void
-                12 : (str) -> void
-                13 : str
-                14 : void
-                15 : void
-                16 : () -> void
-                l0 : (str) -> void
+                main::Vector3 : type
+                main::main : () -> void
+                main::op_add : (main::Vector3, main::Vector3) -> main::Vector3
+                main::op_eq : (^main::Vector3, main::Vector3) -> bool
+                main::op_mul : (main::Vector3, i32) -> main::Vector3
+                main::op_neg : (main::Vector3) -> main::Vector3
+                0 : usize
+                3 : type
+                7 : main::Vector3
+                8 : main::Vector3
+                9 : (main::Vector3, main::Vector3) -> main::Vector3
+                13 : main::Vector3
+                14 : main::Vector3
+                15 : (main::Vector3, i32) -> main::Vector3
+                18 : main::Vector3
+                19 : main::Vector3
+                20 : (main::Vector3) -> main::Vector3
+                25 : bool
+                26 : bool
+                27 : (^main::Vector3, main::Vector3) -> bool
+                30 : i32
+                31 : i32
+                32 : i32
+                33 : [3]i32
+                34 : main::Vector3
+                35 : main::Vector3
+                36 : i32
+                37 : main::Vector3
+                38 : main::Vector3
+                39 : main::Vector3
+                40 : main::Vector3
+                41 : main::Vector3
+                42 : main::Vector3
+                43 : bool
+                44 : void
+                45 : () -> void
+                l0 : main::Vector3
+                l1 : main::Vector3
+                l2 : main::Vector3
+                l3 : bool
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn lambda_with_body_ty_annotation() {
+    fn operator_overload_index() {
         check(
             r#"
-                foo :: () {
-                    a : (s: str) -> void {} = (s: str) {};
+                Grid :: struct {
+                    width: usize,
+                    cells: [4] u8,
+                };
 
-                    a("Hello!");
-                }
+                op_index :: (g: ^Grid, pos: [2] usize) -> u8 {
+                    g.cells[pos[1] * g.width + pos[0]]
+                };
+
+                main :: () {
+                    g := Grid.{ width = 2, cells = u8.[1, 2, 3, 4] };
+                    x := g[usize.[1, 1]];
+                    g[usize.[0, 0]] = 5;
+                };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                5 : void
-                6 : (str) -> void
-                7 : <unknown>
-                8 : str
-                9 : <unknown>
-                10 : void
-                11 : () -> void
-                l0 : <unknown>
+                main::Grid : type
+                main::main : () -> void
+                main::op_index : (^main::Grid, [2]usize) -> u8
+                1 : usize
+                4 : type
+                7 : usize
+                11 : ^main::Grid
+                12 : [4]u8
+                13 : [2]usize
+                14 : usize
+                15 : usize
+                16 : ^main::Grid
+                17 : usize
+                18 : usize
+                19 : [2]usize
+                20 : usize
+                21 : usize
+                22 : usize
+                23 : u8
+                24 : u8
+                25 : (^main::Grid, [2]usize) -> u8
+                27 : usize
+                29 : u8
+                30 : u8
+                31 : u8
+                32 : u8
+                33 : [4]u8
+                34 : main::Grid
+                35 : main::Grid
+                37 : {uint}
+                38 : {uint}
+                39 : [2]usize
+                40 : u8
+                41 : main::Grid
+                43 : {uint}
+                44 : {uint}
+                45 : [2]usize
+                46 : u8
+                47 : {uint}
+                48 : void
+                49 : () -> void
+                l0 : main::Grid
+                l1 : u8
             "#]],
             |_| {
                 [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Type.into(),
-                        found: Ty::Function {
-                            param_tys: vec![Ty::String.into()],
-                            return_ty: Ty::Void.into(),
-                        }
-                        .into(),
-                    },
-                    53..72,
-                    None,
+                    TyDiagnosticKind::CannotMutate,
+                    422..442,
+                    Some((TyDiagnosticHelpKind::FoundToBeImmutable, 422..437)),
                 )]
             },
         );
     }
 
     #[test]
-    fn lambda_ty_no_return() {
-        // this is only to make sure that `is_safe_to_compile` works correctly.
-        // this will panic if there's some error and `is_safe_to_compile`
-        // returns true. since I know for a fact that the parser is going to throw an
-        // error on the following input, and I know that the following did not panic,
-        // it's safe to say that `is_safe_to_compile` returned false and
-        // works correctly on this input.
+    fn operator_overload_operand_mismatch() {
         check(
             r#"
-                foo :: () {
-                    (a: i32, b: str);
-                }
-            "#,
-            expect![[r#"
-                main::foo : () -> void
-                2 : <unknown>
-                3 : (i32, str) -> void
-                4 : void
-                5 : () -> void
-            "#]],
-            |_| [],
-        );
-    }
+                Vector3 :: distinct [3] i32;
 
-    #[test]
-    fn comptime() {
-        check(
-            r#"
-                foo :: () {
-                    foo : str = comptime {
-                        2 * 5
-                    };
-                }
+                op_mul :: (a: Vector3, by: i32) -> Vector3 { a };
+
+                main :: () {
+                    a : Vector3 = i32.[1, 2, 3];
+                    b := a * true;
+                };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                1 : {uint}
-                2 : {uint}
-                3 : {uint}
-                4 : {uint}
-                5 : {uint}
-                6 : void
-                7 : () -> void
-                l0 : str
+                main::Vector3 : type
+                main::main : () -> void
+                main::op_mul : (main::Vector3, i32) -> main::Vector3
+                0 : usize
+                3 : type
+                7 : main::Vector3
+                8 : main::Vector3
+                9 : (main::Vector3, i32) -> main::Vector3
+                12 : i32
+                13 : i32
+                14 : i32
+                15 : [3]i32
+                16 : main::Vector3
+                17 : bool
+                18 : main::Vector3
+                19 : void
+                20 : () -> void
+                l0 : main::Vector3
+                l1 : main::Vector3
             "#]],
             |_| {
                 [(
                     TyDiagnosticKind::Mismatch {
-                        expected: Intern::new(Ty::String),
-                        found: Intern::new(Ty::UInt(0)),
+                        expected: Ty::IInt(32).into(),
+                        found: Ty::Bool.into(),
                     },
-                    61..123,
+                    221..225,
                     None,
                 )]
             },
@@ -6245,166 +7292,412 @@ mod tests {
     }
 
     #[test]
-    fn comptime_pointer() {
+    fn operator_overload_wrong_param_count() {
         check(
             r#"
-                foo :: () {
-                    comptime {
-                        x := 5;
+                Vector3 :: distinct [3] i32;
 
-                        ^x
-                    };
-                }
+                op_neg :: (a: Vector3, b: Vector3) -> Vector3 { a };
+
+                main :: () {
+                    a : Vector3 = i32.[1, 2, 3];
+                    b := -a;
+                };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : {uint}
-                1 : {uint}
-                2 : ^{uint}
-                3 : ^{uint}
-                4 : <unknown>
-                5 : void
-                6 : () -> void
-                l0 : {uint}
+                main::Vector3 : type
+                main::main : () -> void
+                main::op_neg : (main::Vector3, main::Vector3) -> main::Vector3
+                0 : usize
+                3 : type
+                7 : main::Vector3
+                8 : main::Vector3
+                9 : (main::Vector3, main::Vector3) -> main::Vector3
+                12 : i32
+                13 : i32
+                14 : i32
+                15 : [3]i32
+                16 : main::Vector3
+                17 : <unknown>
+                18 : void
+                19 : () -> void
+                l0 : main::Vector3
+                l1 : <unknown>
             "#]],
-            |_| [(TyDiagnosticKind::ComptimePointer, 49..141, None)],
+            |i| {
+                let vector3_ty: Intern<Ty> = Ty::Distinct {
+                    fqn: Some(hir::Fqn {
+                        file: hir::FileName(i.intern("main.capy")),
+                        name: hir::Name(i.intern("Vector3")),
+                    }),
+                    uid: 0,
+                    sub_ty: Ty::Array {
+                        anonymous: false,
+                        size: 3,
+                        sub_ty: Ty::IInt(32).into(),
+                    }
+                    .into(),
+                }
+                .into();
+
+                [(
+                    TyDiagnosticKind::InvalidOperatorFunction {
+                        function: hir::Fqn {
+                            file: hir::FileName(i.intern("main.capy")),
+                            name: hir::Name(i.intern("op_neg")),
+                        },
+                        found: Ty::Function {
+                            param_tys: vec![vector3_ty, vector3_ty],
+                            return_ty: vector3_ty,
+                            is_variadic: false,
+                        }
+                        .into(),
+                    },
+                    220..222,
+                    None,
+                )]
+            },
         );
     }
 
     #[test]
-    fn non_const_global() {
+    fn operator_overload_ne_non_bool() {
         check(
             r#"
-                foo :: 2 + 2;
+                Vector3 :: distinct [3] i32;
+
+                op_eq :: (a: Vector3, b: Vector3) -> i32 { 0 };
+
+                main :: () {
+                    a : Vector3 = i32.[1, 2, 3];
+                    b := a == a;
+                    c := a != a;
+                };
             "#,
             expect![[r#"
-                main::foo : i32
-                0 : i32
-                1 : i32
-                2 : i32
+                main::Vector3 : type
+                main::main : () -> void
+                main::op_eq : (main::Vector3, main::Vector3) -> i32
+                0 : usize
+                3 : type
+                7 : i32
+                8 : i32
+                9 : (main::Vector3, main::Vector3) -> i32
+                12 : i32
+                13 : i32
+                14 : i32
+                15 : [3]i32
+                16 : main::Vector3
+                17 : main::Vector3
+                18 : i32
+                19 : main::Vector3
+                20 : main::Vector3
+                21 : i32
+                22 : void
+                23 : () -> void
+                l0 : main::Vector3
+                l1 : i32
+                l2 : i32
             "#]],
-            |_| [(TyDiagnosticKind::GlobalNotConst, 24..29, None)],
+            |i| {
+                let vector3_ty: Intern<Ty> = Ty::Distinct {
+                    fqn: Some(hir::Fqn {
+                        file: hir::FileName(i.intern("main.capy")),
+                        name: hir::Name(i.intern("Vector3")),
+                    }),
+                    uid: 0,
+                    sub_ty: Ty::Array {
+                        anonymous: false,
+                        size: 3,
+                        sub_ty: Ty::IInt(32).into(),
+                    }
+                    .into(),
+                }
+                .into();
+
+                [(
+                    TyDiagnosticKind::InvalidOperatorFunction {
+                        function: hir::Fqn {
+                            file: hir::FileName(i.intern("main.capy")),
+                            name: hir::Name(i.intern("op_eq")),
+                        },
+                        found: Ty::Function {
+                            param_tys: vec![vector3_ty, vector3_ty],
+                            return_ty: Ty::IInt(32).into(),
+                            is_variadic: false,
+                        }
+                        .into(),
+                    },
+                    248..254,
+                    None,
+                )]
+            },
         );
     }
 
     #[test]
-    fn any_ptr() {
+    fn interface() {
         check(
             r#"
-                get_any :: () {
-                    foo : i32 = 5;
+                Shape :: interface {
+                    area: () -> i32,
+                    grow: (by: i32) -> void,
+                };
 
-                    ptr : ^i32 = ^foo;
-                    ptr : ^any = ptr as ^any;
-                    ptr : ^f32 = ptr as ^f32;
+                Square :: struct {
+                    size: i32,
+                };
 
-                    foo : f32 = ptr^;
-                }
+                area :: (s: ^Square) -> i32 {
+                    s.size * s.size
+                };
+
+                grow :: (s: ^mut Square, by: i32) {
+                    s.size = s.size + by;
+                };
+
+                main :: () -> i32 {
+                    sq := Square.{ size = 2 };
+                    shape := ^mut sq as Shape;
+                    shape.grow(3);
+                    shape.area()
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : i32
-                4 : i32
-                5 : ^i32
-                8 : ^i32
-                11 : ^any
-                14 : ^any
-                17 : ^f32
-                19 : ^f32
-                20 : f32
-                21 : void
-                22 : () -> void
-                l0 : i32
-                l1 : ^i32
-                l2 : ^any
-                l3 : ^f32
-                l4 : f32
+                main::Shape : type
+                main::Square : type
+                main::area : (^main::Square) -> i32
+                main::grow : (^mut main::Square, i32) -> void
+                main::main : () -> i32
+                7 : type
+                9 : type
+                13 : ^main::Square
+                14 : i32
+                15 : ^main::Square
+                16 : i32
+                17 : i32
+                18 : i32
+                19 : (^main::Square) -> i32
+                23 : ^mut main::Square
+                24 : i32
+                25 : ^mut main::Square
+                26 : i32
+                27 : i32
+                28 : i32
+                29 : void
+                30 : (^mut main::Square, i32) -> void
+                33 : i32
+                34 : main::Square
+                35 : main::Square
+                36 : ^mut main::Square
+                38 : main::Shape
+                39 : main::Shape
+                40 : (i32) -> void
+                41 : i32
+                42 : void
+                43 : main::Shape
+                44 : () -> i32
+                45 : i32
+                46 : i32
+                47 : () -> i32
+                l0 : main::Square
+                l1 : main::Shape
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn any_slice() {
+    fn interface_method_not_implemented() {
         check(
             r#"
-                get_any :: () {
-                    foo : [] i32 = i32.[100, 200];
+                Shape :: interface {
+                    area: () -> i32,
+                    grow: (by: i32) -> void,
+                };
 
-                    ptr : [] any = foo as [] any;
+                Square :: struct {
+                    size: i32,
+                };
 
-                    foo : [] f32 = ptr as [] f32;
+                area :: (s: ^Square) -> i32 {
+                    s.size * s.size
+                };
 
-                    first : f32 = foo[0];
-                }
+                main :: () {
+                    sq := Square.{ size = 2 };
+                    shape := ^sq as Shape;
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                3 : i32
-                4 : i32
-                5 : [2]i32
-                8 : []i32
-                11 : []any
-                14 : []any
-                17 : []f32
-                19 : []f32
-                20 : usize
-                21 : f32
-                22 : void
-                23 : () -> void
-                l0 : []i32
-                l1 : []any
-                l2 : []f32
-                l3 : f32
+                main::Shape : type
+                main::Square : type
+                main::area : (^main::Square) -> i32
+                main::main : () -> void
+                7 : type
+                9 : type
+                13 : ^main::Square
+                14 : i32
+                15 : ^main::Square
+                16 : i32
+                17 : i32
+                18 : i32
+                19 : (^main::Square) -> i32
+                21 : i32
+                22 : main::Square
+                23 : main::Square
+                24 : ^main::Square
+                26 : main::Shape
+                27 : void
+                28 : () -> void
+                l0 : main::Square
+                l1 : main::Shape
             "#]],
-            |_| [],
+            |i| {
+                let shape_ty: Intern<Ty> = Ty::Interface {
+                    fqn: Some(hir::Fqn {
+                        file: hir::FileName(i.intern("main.capy")),
+                        name: hir::Name(i.intern("Shape")),
+                    }),
+                    uid: 0,
+                    methods: vec![
+                        (
+                            hir::Name(i.intern("area")),
+                            Ty::Function {
+                                param_tys: vec![],
+                                return_ty: Ty::IInt(32).into(),
+                                is_variadic: false,
+                            }
+                            .into(),
+                        ),
+                        (
+                            hir::Name(i.intern("grow")),
+                            Ty::Function {
+                                param_tys: vec![Ty::IInt(32).into()],
+                                return_ty: Ty::Void.into(),
+                                is_variadic: false,
+                            }
+                            .into(),
+                        ),
+                    ],
+                }
+                .into();
+
+                [(
+                    TyDiagnosticKind::InterfaceMethodNotImplemented {
+                        ty: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Struct {
+                                anonymous: false,
+                                fqn: Some(hir::Fqn {
+                                    file: hir::FileName(i.intern("main.capy")),
+                                    name: hir::Name(i.intern("Square")),
+                                }),
+                                uid: 1,
+                                members: vec![(hir::Name(i.intern("size")), Ty::IInt(32).into())],
+                                defaults: Vec::new(),
+                                repr: hir::StructRepr::default(),
+                            }
+                            .into(),
+                        }
+                        .into(),
+                        interface: shape_ty,
+                        method: i.intern("grow"),
+                    },
+                    433..445,
+                    None,
+                )]
+            },
         );
     }
 
     #[test]
-    fn cast_ptr_without_any() {
+    fn interface_needs_mutable_pointer() {
         check(
             r#"
-                get_any :: () {
-                    foo : i32 = 5;
+                Shape :: interface {
+                    grow: (by: i32) -> void,
+                };
 
-                    ptr : ^i32 = ^foo;
-                    ptr : ^f32 = ptr as ^f32;
+                Square :: struct {
+                    size: i32,
+                };
 
-                    foo : f32 = ptr^;
-                }
+                grow :: (s: ^mut Square, by: i32) {
+                    s.size = s.size + by;
+                };
+
+                main :: () {
+                    sq := Square.{ size = 2 };
+                    shape := ^sq as Shape;
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : i32
-                4 : i32
-                5 : ^i32
-                8 : ^i32
-                11 : ^f32
-                13 : ^f32
-                14 : f32
-                15 : void
-                16 : () -> void
-                l0 : i32
-                l1 : ^i32
-                l2 : ^f32
-                l3 : f32
+                main::Shape : type
+                main::Square : type
+                main::grow : (^mut main::Square, i32) -> void
+                main::main : () -> void
+                4 : type
+                6 : type
+                10 : ^mut main::Square
+                11 : i32
+                12 : ^mut main::Square
+                13 : i32
+                14 : i32
+                15 : i32
+                16 : void
+                17 : (^mut main::Square, i32) -> void
+                19 : i32
+                20 : main::Square
+                21 : main::Square
+                22 : ^main::Square
+                24 : main::Shape
+                25 : void
+                26 : () -> void
+                l0 : main::Square
+                l1 : main::Shape
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Uncastable {
-                        from: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::IInt(32).into(),
+            |i| {
+                let shape_ty: Intern<Ty> = Ty::Interface {
+                    fqn: Some(hir::Fqn {
+                        file: hir::FileName(i.intern("main.capy")),
+                        name: hir::Name(i.intern("Shape")),
+                    }),
+                    uid: 0,
+                    methods: vec![(
+                        hir::Name(i.intern("grow")),
+                        Ty::Function {
+                            param_tys: vec![Ty::IInt(32).into()],
+                            return_ty: Ty::Void.into(),
+                            is_variadic: false,
                         }
                         .into(),
-                        to: Ty::Pointer {
+                    )],
+                }
+                .into();
+
+                [(
+                    TyDiagnosticKind::InterfaceMethodNotImplemented {
+                        ty: Ty::Pointer {
                             mutable: false,
-                            sub_ty: Ty::Float(32).into(),
+                            sub_ty: Ty::Struct {
+                                anonymous: false,
+                                fqn: Some(hir::Fqn {
+                                    file: hir::FileName(i.intern("main.capy")),
+                                    name: hir::Name(i.intern("Square")),
+                                }),
+                                uid: 1,
+                                members: vec![(hir::Name(i.intern("size")), Ty::IInt(32).into())],
+                                defaults: Vec::new(),
+                                repr: hir::StructRepr::default(),
+                            }
+                            .into(),
                         }
                         .into(),
+                        interface: shape_ty,
+                        method: i.intern("grow"),
                     },
-                    141..152,
+                    408..420,
                     None,
                 )]
             },
@@ -6412,40 +7705,64 @@ mod tests {
     }
 
     #[test]
-    fn cast_slice_without_any() {
+    fn interface_from_non_pointer() {
         check(
             r#"
-                get_any :: () {
-                    foo : [] i32 = i32.[100, 200];
+                Shape :: interface {
+                    area: () -> i32,
+                };
 
-                    foo : [] f32 = foo as [] f32;
-                }
+                area :: (s: ^i32) -> i32 {
+                    s^
+                };
+
+                main :: () {
+                    x : i32 = 5;
+                    shape := x as Shape;
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                3 : i32
-                4 : i32
-                5 : [2]i32
-                8 : []i32
-                11 : []f32
-                12 : void
-                13 : () -> void
-                l0 : []i32
-                l1 : []f32
+                main::Shape : type
+                main::area : (^i32) -> i32
+                main::main : () -> void
+                3 : type
+                7 : ^i32
+                8 : i32
+                9 : i32
+                10 : (^i32) -> i32
+                12 : i32
+                13 : i32
+                15 : main::Shape
+                16 : void
+                17 : () -> void
+                l0 : i32
+                l1 : main::Shape
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Uncastable {
-                        from: Ty::Slice {
-                            sub_ty: Ty::IInt(32).into(),
-                        }
-                        .into(),
-                        to: Ty::Slice {
-                            sub_ty: Ty::Float(32).into(),
+            |i| {
+                let shape_ty: Intern<Ty> = Ty::Interface {
+                    fqn: Some(hir::Fqn {
+                        file: hir::FileName(i.intern("main.capy")),
+                        name: hir::Name(i.intern("Shape")),
+                    }),
+                    uid: 0,
+                    methods: vec![(
+                        hir::Name(i.intern("area")),
+                        Ty::Function {
+                            param_tys: vec![],
+                            return_ty: Ty::IInt(32).into(),
+                            is_variadic: false,
                         }
                         .into(),
+                    )],
+                }
+                .into();
+
+                [(
+                    TyDiagnosticKind::InterfaceFromNonPointer {
+                        found: Ty::IInt(32).into(),
+                        interface: shape_ty,
                     },
-                    120..133,
+                    272..282,
                     None,
                 )]
             },
@@ -6453,166 +7770,154 @@ mod tests {
     }
 
     #[test]
-    fn deref_any() {
+    fn interface_method_not_function() {
         check(
             r#"
-                get_any :: () {
-                    foo : i32 = 5;
-
-                    ptr : ^i32 = ^foo;
-                    ptr : ^any = ptr as ^any;
-
-                    foo : any = ptr^;
-                }
+                Shape :: interface {
+                    area: () -> i32,
+                    size: i32,
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : i32
-                4 : i32
-                5 : ^i32
-                8 : ^i32
-                11 : ^any
-                13 : ^any
-                14 : <unknown>
-                15 : void
-                16 : () -> void
-                l0 : i32
-                l1 : ^i32
-                l2 : ^any
-                l3 : any
+                main::Shape : type
+                4 : type
             "#]],
-            |_| [(TyDiagnosticKind::DerefAny, 187..191, None)],
+            |_| {
+                [(
+                    TyDiagnosticKind::InterfaceMethodNotFunction {
+                        found: Ty::IInt(32).into(),
+                    },
+                    101..104,
+                    None,
+                )]
+            },
         );
     }
 
     #[test]
-    fn index_any_slice() {
+    fn get_struct_field() {
         check(
             r#"
-                get_any :: () {
-                    foo : [3] i32 = i32.[5, 10, 15];
+                Person :: struct {
+                    name: str,
+                    age: i32
+                };
 
-                    ptr : [] i32 = foo;
-                    ptr : [] any = ptr as []any;
+                foo :: () -> i32 {
+                    some_guy := Person.{
+                        name = "Joe Schmoe",
+                        age = 31,
+                    };
 
-                    foo : any = ptr[0];
-                }
+                    some_guy.age
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                0 : usize
-                4 : i32
-                5 : i32
+                main::Person : type
+                main::foo : () -> i32
+                2 : type
+                5 : str
                 6 : i32
-                7 : [3]i32
-                10 : [3]i32
-                13 : []i32
-                16 : []any
-                18 : []any
-                19 : usize
-                20 : <unknown>
-                21 : void
-                22 : () -> void
-                l0 : [3]i32
-                l1 : []i32
-                l2 : []any
-                l3 : any
+                7 : main::Person
+                8 : main::Person
+                9 : i32
+                10 : i32
+                11 : () -> i32
+                l0 : main::Person
             "#]],
-            |_| [(TyDiagnosticKind::IndexAny { size: None }, 209..215, None)],
+            |_| [],
         );
     }
 
     #[test]
-    fn index_any_array() {
+    fn non_existent_field() {
         check(
             r#"
-                get_any :: (foo: [3] any) {
-                    foo : any = foo[0];
-                }
+                Person :: struct {
+                    name: str,
+                    age: i32
+                };
+
+                foo :: () -> i32 {
+                    some_guy := Person.{
+                        name = "Joe Schmoe",
+                        age = 31,
+                    };
+
+                    some_guy.height
+                };
             "#,
             expect![[r#"
-                main::get_any : ([3]any) -> void
-                0 : usize
-                4 : [3]any
-                5 : usize
-                6 : <unknown>
-                7 : void
-                8 : ([3]any) -> void
-                l0 : any
-            "#]],
-            |_| [(TyDiagnosticKind::IndexAny { size: Some(3) }, 77..83, None)],
-        );
-    }
-
-    #[test]
-    fn auto_real_ptr_to_any_ptr() {
-        check(
-            r#"
-                get_any :: () {
-                    foo : i32 = 5;
-                    ptr : ^i32 = ^foo;
-
-                    ptr : ^any = ptr;
-                }
-            "#,
-            expect![[r#"
-                main::get_any : () -> void
-                1 : i32
-                4 : i32
-                5 : ^i32
-                8 : ^i32
-                9 : void
-                10 : () -> void
-                l0 : i32
-                l1 : ^i32
-                l2 : ^any
+                main::Person : type
+                main::foo : () -> i32
+                2 : type
+                5 : str
+                6 : i32
+                7 : main::Person
+                8 : main::Person
+                9 : <unknown>
+                10 : <unknown>
+                11 : () -> i32
+                l0 : main::Person
             "#]],
-            |_| [],
+            |i| {
+                [(
+                    TyDiagnosticKind::NonExistentMember {
+                        member: i.intern("height"),
+                        found_ty: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Person")),
+                            }),
+                            uid: 0,
+                            members: vec![
+                                (hir::Name(i.intern("name")), Ty::String.into()),
+                                (hir::Name(i.intern("age")), Ty::IInt(32).into()),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                    },
+                    315..330,
+                    None,
+                )]
+            },
         );
     }
 
     #[test]
-    fn auto_any_ptr_to_real_ptr() {
+    fn if_mismatch() {
         check(
             r#"
-                get_any :: () {
-                    foo : i32 = 5;
-                    ptr : ^i32 = ^foo;
-                    ptr : ^any = ptr as ^any;
-
-                    ptr : ^i32 = ptr;
+                foo :: (bar: bool) -> bool {
+                    // this evaluates to `<unknown>`, so the function's return type isn't checked
+                    if bar {
+                        "Hello!"
+                    } else {
+                        100
+                    }
                 }
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : i32
-                4 : i32
-                5 : ^i32
-                8 : ^i32
-                11 : ^any
-                14 : ^any
-                15 : void
-                16 : () -> void
-                l0 : i32
-                l1 : ^i32
-                l2 : ^any
-                l3 : ^i32
+                main::foo : (bool) -> bool
+                2 : bool
+                3 : str
+                4 : str
+                5 : {uint}
+                6 : {uint}
+                7 : <unknown>
+                8 : <unknown>
+                9 : (bool) -> bool
             "#]],
             |_| {
                 [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::IInt(32).into(),
-                        }
-                        .into(),
-                        found: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::Any.into(),
-                        }
-                        .into(),
+                    TyDiagnosticKind::IfMismatch {
+                        found: Ty::UInt(0).into(),
+                        expected: Ty::String.into(),
                     },
-                    187..190,
+                    164..284,
                     None,
                 )]
             },
@@ -6620,385 +7925,436 @@ mod tests {
     }
 
     #[test]
-    fn auto_real_slice_to_any_slice() {
+    fn slice_range() {
         check(
             r#"
-                get_any :: () {
-                    foo : [] i32 = i32.[5, 10, 15];
-
-                    ptr : [] any = foo;
+                foo :: (nums: []i32, text: str) {
+                    arr := i32.[1, 2, 3];
+                    a := arr[1..];
+                    b := nums[..2];
+                    c := text[1..3];
+                    d := (^arr)[..];
                 }
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                3 : i32
+                main::foo : ([]i32, str) -> void
                 4 : i32
                 5 : i32
-                6 : [3]i32
-                9 : []i32
-                10 : void
-                11 : () -> void
-                l0 : []i32
-                l1 : []any
+                6 : i32
+                7 : [3]i32
+                8 : [3]i32
+                9 : usize
+                10 : []i32
+                11 : []i32
+                12 : usize
+                13 : []i32
+                14 : str
+                15 : usize
+                16 : usize
+                17 : str
+                18 : [3]i32
+                19 : ^[3]i32
+                20 : ^[3]i32
+                21 : []i32
+                22 : void
+                23 : ([]i32, str) -> void
+                l0 : [3]i32
+                l1 : []i32
+                l2 : []i32
+                l3 : str
+                l4 : []i32
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn auto_any_slice_to_real_slice() {
+    fn slice_out_of_bounds() {
         check(
             r#"
-                get_any :: () {
-                    foo : [] i32 = i32.[5, 10, 15];
-                    ptr : [] any = foo as [] any;
-
-                    ptr : [] i32 = ptr;
+                foo :: () {
+                    arr := i32.[1, 2, 3];
+                    a := arr[..3];
+                    b := arr[1..4];
+                    c := arr[2..1];
                 }
             "#,
             expect![[r#"
-                main::get_any : () -> void
+                main::foo : () -> void
+                1 : i32
+                2 : i32
                 3 : i32
-                4 : i32
-                5 : i32
-                6 : [3]i32
-                9 : []i32
-                12 : []any
-                15 : []any
+                4 : [3]i32
+                5 : [3]i32
+                6 : usize
+                7 : []i32
+                8 : [3]i32
+                9 : usize
+                10 : usize
+                11 : []i32
+                12 : [3]i32
+                13 : usize
+                14 : usize
+                15 : []i32
                 16 : void
                 17 : () -> void
-                l0 : []i32
-                l1 : []any
+                l0 : [3]i32
+                l1 : []i32
                 l2 : []i32
+                l3 : []i32
             "#]],
             |_| {
-                [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Slice {
-                            sub_ty: Ty::IInt(32).into(),
-                        }
-                        .into(),
-                        found: Ty::Slice {
-                            sub_ty: Ty::Any.into(),
-                        }
-                        .into(),
-                    },
-                    171..174,
-                    None,
-                )]
+                [
+                    (
+                        TyDiagnosticKind::IndexOutOfBounds {
+                            index: 4,
+                            actual_size: 3,
+                            array_ty: Ty::Array {
+                                anonymous: false,
+                                size: 3,
+                                sub_ty: Ty::IInt(32).into(),
+                            }
+                            .into(),
+                            is_slice_bound: true,
+                        },
+                        131..140,
+                        None,
+                    ),
+                    (
+                        TyDiagnosticKind::SliceStartAfterEnd { start: 2, end: 1 },
+                        167..176,
+                        None,
+                    ),
+                ]
             },
         );
     }
 
     #[test]
-    fn any_ptr_to_str() {
+    fn mutate_slice_of_immutable() {
         check(
             r#"
-                get_any :: () {
-                    data := char.['h', 'i', '\0'];
-                    ptr := ^data as ^any;
-                    str := ptr as str;
+                foo :: () {
+                    arr :: i32.[1, 2, 3];
+                    arr[1..][0] = 5;
                 }
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : char
-                2 : char
-                3 : char
-                4 : [3]char
-                5 : [3]char
-                6 : ^[3]char
-                9 : ^any
-                10 : ^any
-                12 : str
-                13 : void
-                14 : () -> void
-                l0 : [3]char
-                l1 : ^any
-                l2 : str
+                main::foo : () -> void
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : [3]i32
+                5 : [3]i32
+                6 : usize
+                7 : []i32
+                8 : usize
+                9 : i32
+                10 : {uint}
+                11 : void
+                12 : () -> void
+                l0 : [3]i32
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::CannotMutate,
+                    91..107,
+                    Some((TyDiagnosticHelpKind::ImmutableBinding, 49..70)),
+                )]
+            },
         );
     }
 
     #[test]
-    fn char_ptr_to_str() {
+    fn index_non_array() {
         check(
             r#"
-                get_any :: () {
-                    data := char.['h', 'i', '\0'];
-                    ptr := ^data as ^any as ^char;
-                    str := ptr as str;
-                }
+                foo :: () {
+                    bar := true;
+
+                    bar[0];
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : char
-                2 : char
-                3 : char
-                4 : [3]char
-                5 : [3]char
-                6 : ^[3]char
-                9 : ^any
-                12 : ^char
-                13 : ^char
-                15 : str
-                16 : void
-                17 : () -> void
-                l0 : [3]char
-                l1 : ^char
-                l2 : str
+                main::foo : () -> void
+                0 : bool
+                1 : bool
+                2 : usize
+                3 : <unknown>
+                4 : void
+                5 : () -> void
+                l0 : bool
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::IndexNonArray {
+                        found: Ty::Bool.into(),
+                    },
+                    83..89,
+                    None,
+                )]
+            },
         );
     }
 
     #[test]
-    fn u8_ptr_to_str() {
+    fn index_str() {
         check(
             r#"
-                get_any :: () {
-                    data := char.['h', 'i', '\0'];
-                    ptr := ^data as ^any as ^u8;
-                    str := ptr as str;
-                }
+                foo :: () {
+                    bar := "Hello!";
+
+                    bar[0];
+                    bar.len;
+                };
             "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : char
-                2 : char
-                3 : char
-                4 : [3]char
-                5 : [3]char
-                6 : ^[3]char
-                9 : ^any
-                12 : ^u8
-                13 : ^u8
-                15 : str
-                16 : void
-                17 : () -> void
-                l0 : [3]char
-                l1 : ^u8
-                l2 : str
+                main::foo : () -> void
+                0 : str
+                1 : str
+                2 : usize
+                3 : u8
+                4 : str
+                5 : usize
+                6 : void
+                7 : () -> void
+                l0 : str
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn char_array_to_str() {
+    fn mutate_str_index() {
         check(
-            r"
-                get_any :: () {
-                    data := char.['H', 'i', '\0'];
-                    str := data as str;
-                }
-            ",
+            r#"
+                foo :: () {
+                    bar := "Hello!";
+
+                    bar[0] = 0;
+                };
+            "#,
             expect![[r#"
-                main::get_any : () -> void
-                1 : char
-                2 : char
-                3 : char
-                4 : [3]char
-                5 : [3]char
-                7 : str
-                8 : void
-                9 : () -> void
-                l0 : [3]char
-                l1 : str
+                main::foo : () -> void
+                0 : str
+                1 : str
+                2 : usize
+                3 : u8
+                4 : {uint}
+                5 : void
+                6 : () -> void
+                l0 : str
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::CannotMutate,
+                    87..98,
+                    Some((TyDiagnosticHelpKind::FoundToBeImmutable, 87..90)),
+                )]
+            },
         );
     }
 
     #[test]
-    fn char() {
+    fn mismatch_arg_count() {
         check(
-            r"
+            r#"
+                bar :: (num: i32) {};
+
                 foo :: () {
-                    my_char := 'A';
+                    bar(1, 2, 3);
                 }
-            ",
+            "#,
             expect![[r#"
+                main::bar : (i32) -> void
                 main::foo : () -> void
-                0 : char
                 1 : void
-                2 : () -> void
-                l0 : char
+                2 : (i32) -> void
+                3 : (i32) -> void
+                4 : i32
+                5 : {uint}
+                6 : {uint}
+                7 : void
+                8 : void
+                9 : () -> void
             "#]],
-            |_| [],
-        )
+            |_| {
+                [(
+                    TyDiagnosticKind::MismatchedArgCount {
+                        found: 3,
+                        expected: 1,
+                    },
+                    88..100,
+                    None,
+                )]
+            },
+        );
     }
 
     #[test]
-    fn char_as_u8() {
+    fn named_args_and_param_defaults() {
         check(
-            r"
+            r#"
+                open :: (path: str, mode := "r", flags: u8 = 0) {};
+
                 foo :: () {
-                    my_char := 'A';
-                    my_u8 := my_char as u8;
+                    open("a.txt");
+                    open(flags = 2, path = "b.txt");
+                    open("c.txt", mode = "w");
                 }
-            ",
+            "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : char
-                1 : char
-                3 : u8
-                4 : void
-                5 : () -> void
-                l0 : char
-                l1 : u8
+                main::open : (str, str, u8) -> void
+                2 : str
+                4 : u8
+                5 : void
+                6 : (str, str, u8) -> void
+                7 : (str, str, u8) -> void
+                8 : str
+                9 : void
+                10 : (str, str, u8) -> void
+                11 : u8
+                12 : str
+                13 : void
+                14 : (str, str, u8) -> void
+                15 : str
+                16 : str
+                17 : void
+                18 : void
+                19 : () -> void
             "#]],
             |_| [],
-        )
+        );
     }
 
     #[test]
-    fn no_implicit_char_to_u8() {
+    fn named_args_wrong_params() {
         check(
-            r"
+            r#"
+                open :: (path: str, mode := "r") {};
+
                 foo :: () {
-                    my_char := 'A';
-                    my_u8 : u8 = my_char;
+                    open(path = "a.txt", colour = "red");
+                    open("b.txt", path = "c.txt");
+                    open(mode = "w");
                 }
-            ",
+            "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : char
-                2 : char
+                main::open : (str, str) -> void
+                2 : str
                 3 : void
-                4 : () -> void
-                l0 : char
-                l1 : u8
+                4 : (str, str) -> void
+                5 : (str, str) -> void
+                6 : str
+                7 : str
+                8 : void
+                9 : (str, str) -> void
+                10 : str
+                11 : str
+                12 : void
+                13 : (str, str) -> void
+                14 : str
+                15 : void
+                16 : void
+                17 : () -> void
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::UInt(8).into(),
-                        found: Ty::Char.into(),
-                    },
-                    98..105,
-                    None,
-                )]
+            |i| {
+                [
+                    (
+                        TyDiagnosticKind::UnknownParam {
+                            name: i.intern("colour"),
+                        },
+                        124..130,
+                        None,
+                    ),
+                    (
+                        TyDiagnosticKind::DuplicateArg {
+                            param: i.intern("path"),
+                        },
+                        175..179,
+                        None,
+                    ),
+                    (
+                        TyDiagnosticKind::MissingArg {
+                            param: i.intern("path"),
+                        },
+                        212..228,
+                        None,
+                    ),
+                ]
             },
-        )
+        );
     }
 
     #[test]
-    fn field_of_struct_ptr() {
+    fn named_args_on_fn_ptr() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32
-                };
-
-                main :: () {
-                    my_foo := Foo.{
-                        a = 25
-                    };
-
-                    ptr := ^my_foo;
-
-                    ptr.a;
+                foo :: (open: (path: str) -> void) {
+                    open(path = "a.txt");
                 }
             "#,
             expect![[r#"
-                main::Foo : type
-                main::main : () -> void
-                1 : type
-                3 : i32
-                4 : main::Foo
-                5 : main::Foo
-                6 : ^main::Foo
-                7 : ^main::Foo
-                8 : i32
-                9 : void
-                10 : () -> void
-                l0 : main::Foo
-                l1 : ^main::Foo
+                main::foo : ((str) -> void) -> void
+                4 : (str) -> void
+                5 : str
+                6 : void
+                7 : void
+                8 : ((str) -> void) -> void
             "#]],
-            |_| [],
+            |_| [(TyDiagnosticKind::NamedArgsOnIndirectCall, 74..94, None)],
         );
     }
 
     #[test]
-    fn field_of_struct_ptr_ptr() {
+    fn param_default_not_const() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32
-                };
-
-                main :: () {
-                    my_foo := Foo.{
-                        a = 25
-                    };
-
-                    ptr := ^^my_foo;
-
-                    ptr.a;
+                foo :: (x: i32) {
+                    bar :: (y: i32 = x) {};
                 }
             "#,
             expect![[r#"
-                main::Foo : type
-                main::main : () -> void
-                1 : type
-                3 : i32
-                4 : main::Foo
-                5 : main::Foo
-                6 : ^main::Foo
-                7 : ^^main::Foo
-                8 : ^^main::Foo
-                9 : i32
-                10 : void
-                11 : () -> void
-                l0 : main::Foo
-                l1 : ^^main::Foo
+                main::foo : (i32) -> void
+                2 : i32
+                3 : void
+                4 : (i32) -> void
+                5 : void
+                6 : (i32) -> void
+                l0 : (i32) -> void
             "#]],
-            |_| [],
+            |_| [(TyDiagnosticKind::DefaultNotConst, 72..73, None)],
         );
     }
 
     #[test]
-    fn field_of_non_struct_ptr_ptr() {
+    fn call_non_function() {
         check(
             r#"
-                main :: () {
-                    my_foo := 5;
-
-                    ptr := ^^my_foo;
+                foo :: () {
+                    wow := "Wow!";
 
-                    ptr.a;
+                    wow(42);
                 }
             "#,
             expect![[r#"
-                main::main : () -> void
-                0 : {uint}
-                1 : {uint}
-                2 : ^{uint}
-                3 : ^^{uint}
-                4 : ^^{uint}
-                5 : <unknown>
-                6 : void
-                7 : () -> void
-                l0 : {uint}
-                l1 : ^^{uint}
+                main::foo : () -> void
+                0 : str
+                1 : str
+                2 : {uint}
+                3 : <unknown>
+                4 : void
+                5 : () -> void
+                l0 : str
             "#]],
-            |i| {
+            |_| {
                 [(
-                    TyDiagnosticKind::NonExistentMember {
-                        member: i.intern("a"),
-                        found_ty: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::Pointer {
-                                mutable: false,
-                                sub_ty: Ty::UInt(0).into(),
-                            }
-                            .into(),
-                        }
-                        .into(),
+                    TyDiagnosticKind::CalledNonFunction {
+                        found: Ty::String.into(),
                     },
-                    122..127,
+                    85..92,
                     None,
                 )]
             },
@@ -7006,63 +8362,30 @@ mod tests {
     }
 
     #[test]
-    fn non_existent_field_of_struct_ptr_ptr() {
+    fn deref_non_pointer() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32
-                };
-
-                main :: () {
-                    my_foo := Foo.{
-                        a = 25
-                    };
-
-                    ptr := ^^my_foo;
+                foo :: () {
+                    wow := "Wow!";
 
-                    ptr.b;
+                    wow^;
                 }
             "#,
             expect![[r#"
-                main::Foo : type
-                main::main : () -> void
-                1 : type
-                3 : i32
-                4 : main::Foo
-                5 : main::Foo
-                6 : ^main::Foo
-                7 : ^^main::Foo
-                8 : ^^main::Foo
-                9 : <unknown>
-                10 : void
-                11 : () -> void
-                l0 : main::Foo
-                l1 : ^^main::Foo
+                main::foo : () -> void
+                0 : str
+                1 : str
+                2 : <unknown>
+                3 : void
+                4 : () -> void
+                l0 : str
             "#]],
-            |i| {
+            |_| {
                 [(
-                    TyDiagnosticKind::NonExistentMember {
-                        member: i.intern("b"),
-                        found_ty: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::Pointer {
-                                mutable: false,
-                                sub_ty: Ty::Struct {
-                                    anonymous: false,
-                                    fqn: Some(hir::Fqn {
-                                        file: hir::FileName(i.intern("main.capy")),
-                                        name: hir::Name(i.intern("Foo")),
-                                    }),
-                                    uid: 0,
-                                    members: vec![(hir::Name(i.intern("a")), Ty::IInt(32).into())],
-                                }
-                                .into(),
-                            }
-                            .into(),
-                        }
-                        .into(),
+                    TyDiagnosticKind::DerefNonPointer {
+                        found: Ty::String.into(),
                     },
-                    258..263,
+                    85..89,
                     None,
                 )]
             },
@@ -7070,149 +8393,124 @@ mod tests {
     }
 
     #[test]
-    fn mutate_field_of_struct_ptr_ptr() {
+    fn param_as_ty() {
         check(
             r#"
-                Foo :: struct {
-                    a: i32
-                };
-
-                main :: () {
-                    my_foo := Foo.{
-                        a = 25
-                    };
-
-                    ptr := ^mut ^mut my_foo;
-
-                    ptr.a = 5;
+                foo :: (T: type) {
+                    wow : T = "hello :)";
                 }
             "#,
             expect![[r#"
-                main::Foo : type
-                main::main : () -> void
-                1 : type
-                3 : i32
-                4 : main::Foo
-                5 : main::Foo
-                6 : ^mut main::Foo
-                7 : ^mut ^mut main::Foo
-                8 : ^mut ^mut main::Foo
-                9 : i32
-                10 : i32
-                11 : void
-                12 : () -> void
-                l0 : main::Foo
-                l1 : ^mut ^mut main::Foo
+                main::foo : (type) -> void
+                2 : str
+                3 : void
+                4 : (type) -> void
+                l0 : <unknown>
             "#]],
-            |_| [],
+            |_| [(TyDiagnosticKind::ParamNotATy, 62..63, None)],
         );
     }
 
     #[test]
-    fn index_of_array_ptr() {
+    fn cast_as_local_ty() {
         check(
             r#"
-                main :: () {
-                    arr := i32.[1, 2, 3];
+                foo :: () {
+                    imaginary :: distinct i32;
 
-                    ptr := ^arr;
+                    real : i32 = 5;
 
-                    ptr[0];
+                    real as imaginary;
                 }
             "#,
             expect![[r#"
-                main::main : () -> void
-                1 : i32
-                2 : i32
+                main::foo : () -> void
+                1 : type
                 3 : i32
-                4 : [3]i32
-                5 : [3]i32
-                6 : ^[3]i32
-                7 : ^[3]i32
-                8 : usize
-                9 : i32
-                10 : void
-                11 : () -> void
-                l0 : [3]i32
-                l1 : ^[3]i32
+                4 : i32
+                6 : distinct'0 i32
+                7 : void
+                8 : () -> void
+                l0 : type
+                l1 : i32
             "#]],
             |_| [],
         );
     }
 
     #[test]
-    fn index_of_array_ptr_ptr() {
+    fn no_implicit_struct_cast() {
         check(
             r#"
-                main :: () {
-                    arr := i32.[1, 2, 3];
-
-                    ptr := ^^arr;
+                Foo :: struct {
+                    a: i32,
+                    b: i8,
+                };
 
-                    ptr[0];
-                }
-            "#,
-            expect![[r#"
-                main::main : () -> void
-                1 : i32
-                2 : i32
-                3 : i32
-                4 : [3]i32
-                5 : [3]i32
-                6 : ^[3]i32
-                7 : ^^[3]i32
-                8 : ^^[3]i32
-                9 : usize
-                10 : i32
-                11 : void
-                12 : () -> void
-                l0 : [3]i32
-                l1 : ^^[3]i32
-            "#]],
-            |_| [],
-        );
-    }
+                Bar :: struct {
+                    a: i32,
+                    b: i8,
+                };
 
-    #[test]
-    fn index_of_non_array_ptr_ptr() {
-        check(
-            r#"
                 main :: () {
-                    arr := 5;
-
-                    ptr := ^^arr;
+                    my_foo : Foo = Foo.{
+                        a = 1,
+                        b = 2,
+                    };
 
-                    ptr[0];
-                }
+                    my_bar : Bar = my_foo;
+                };
             "#,
             expect![[r#"
+                main::Bar : type
+                main::Foo : type
                 main::main : () -> void
-                0 : {uint}
-                1 : {uint}
-                2 : ^{uint}
-                3 : ^^{uint}
-                4 : ^^{uint}
-                5 : usize
-                6 : <unknown>
-                7 : void
-                8 : () -> void
-                l0 : {uint}
-                l1 : ^^{uint}
-            "#]],
-            |_| {
+                2 : type
+                5 : type
+                8 : i32
+                9 : i8
+                10 : main::Foo
+                12 : main::Foo
+                13 : void
+                14 : () -> void
+                l0 : main::Foo
+                l1 : main::Bar
+            "#]],
+            |i| {
                 [(
-                    TyDiagnosticKind::IndexNonArray {
-                        found: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::Pointer {
-                                mutable: false,
-                                sub_ty: Ty::UInt(0).into(),
-                            }
-                            .into(),
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Bar")),
+                            }),
+                            uid: 1,
+                            members: vec![
+                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
+                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                        found: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Foo")),
+                            }),
+                            uid: 0,
+                            members: vec![
+                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
+                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                     },
-                    116..122,
+                    406..412,
                     None,
                 )]
             },
@@ -7220,536 +8518,2663 @@ mod tests {
     }
 
     #[test]
-    fn index_too_large_of_array_ptr_ptr() {
+    fn cast_struct_same_fields() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32,
+                    b: i8,
+                };
+
+                Bar :: struct {
+                    a: i32,
+                    b: i8,
+                };
+
+                main :: () {
+                    my_foo : Foo = Foo.{
+                        a = 1,
+                        b = 2,
+                    };
+
+                    my_bar : Bar = my_foo as Bar;
+                };
+            "#,
+            expect![[r#"
+                main::Bar : type
+                main::Foo : type
+                main::main : () -> void
+                2 : type
+                5 : type
+                8 : i32
+                9 : i8
+                10 : main::Foo
+                12 : main::Foo
+                14 : main::Bar
+                15 : void
+                16 : () -> void
+                l0 : main::Foo
+                l1 : main::Bar
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn cast_struct_diff_field_order() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32,
+                    b: i8,
+                };
+
+                Bar :: struct {
+                    b: i8,
+                    a: i32,
+                };
+
+                main :: () {
+                    my_foo : Foo = Foo.{
+                        a = 1,
+                        b = 2,
+                    };
+
+                    my_bar : Bar = my_foo as Bar;
+                };
+            "#,
+            expect![[r#"
+                main::Bar : type
+                main::Foo : type
+                main::main : () -> void
+                2 : type
+                5 : type
+                8 : i32
+                9 : i8
+                10 : main::Foo
+                12 : main::Foo
+                14 : main::Bar
+                15 : void
+                16 : () -> void
+                l0 : main::Foo
+                l1 : main::Bar
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn cast_struct_diff_field_ty_castable() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32,
+                    b: i8,
+                };
+
+                Bar :: struct {
+                    a: i32,
+                    b: i16,
+                };
+
+                main :: () {
+                    my_foo : Foo = Foo.{
+                        a = 1,
+                        b = 2,
+                    };
+
+                    my_bar : Bar = my_foo as Bar;
+                };
+            "#,
+            expect![[r#"
+                main::Bar : type
+                main::Foo : type
+                main::main : () -> void
+                2 : type
+                5 : type
+                8 : i32
+                9 : i8
+                10 : main::Foo
+                12 : main::Foo
+                14 : main::Bar
+                15 : void
+                16 : () -> void
+                l0 : main::Foo
+                l1 : main::Bar
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn cast_struct_diff_field_ty_uncastable() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32,
+                    b: [2]i32,
+                };
+
+                Bar :: struct {
+                    a: i32,
+                    b: [3]i32,
+                };
+
+                main :: () {
+                    my_foo : Foo = Foo.{
+                        a = 1,
+                        b = .[2, 3],
+                    };
+
+                    my_bar : Bar = my_foo as Bar;
+                };
+            "#,
+            expect![[r#"
+                main::Bar : type
+                main::Foo : type
+                main::main : () -> void
+                1 : usize
+                4 : type
+                6 : usize
+                9 : type
+                12 : i32
+                13 : i32
+                14 : i32
+                15 : [2]i32
+                16 : main::Foo
+                18 : main::Foo
+                20 : main::Bar
+                21 : void
+                22 : () -> void
+                l0 : main::Foo
+                l1 : main::Bar
+            "#]],
+            |i| {
+                [(
+                    TyDiagnosticKind::Uncastable {
+                        from: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Foo")),
+                            }),
+                            uid: 0,
+                            members: vec![
+                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
+                                (
+                                    hir::Name(i.intern("b")),
+                                    Ty::Array {
+                                        anonymous: false,
+                                        size: 2,
+                                        sub_ty: Ty::IInt(32).into(),
+                                    }
+                                    .into(),
+                                ),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                        to: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Bar")),
+                            }),
+                            uid: 1,
+                            members: vec![
+                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
+                                (
+                                    hir::Name(i.intern("b")),
+                                    Ty::Array {
+                                        anonymous: false,
+                                        size: 3,
+                                        sub_ty: Ty::IInt(32).into(),
+                                    }
+                                    .into(),
+                                ),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                    },
+                    420..433,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn cast_struct_diff_field_name() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32,
+                    b: i8,
+                };
+
+                Bar :: struct {
+                    x: i32,
+                    y: i8,
+                };
+
+                main :: () {
+                    my_foo : Foo = Foo.{
+                        a = 1,
+                        b = 2,
+                    };
+
+                    my_bar : Bar = my_foo as Bar;
+                };
+            "#,
+            expect![[r#"
+                main::Bar : type
+                main::Foo : type
+                main::main : () -> void
+                2 : type
+                5 : type
+                8 : i32
+                9 : i8
+                10 : main::Foo
+                12 : main::Foo
+                14 : main::Bar
+                15 : void
+                16 : () -> void
+                l0 : main::Foo
+                l1 : main::Bar
+            "#]],
+            |i| {
+                [(
+                    TyDiagnosticKind::Uncastable {
+                        from: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Foo")),
+                            }),
+                            uid: 0,
+                            members: vec![
+                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
+                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                        to: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Bar")),
+                            }),
+                            uid: 1,
+                            members: vec![
+                                (hir::Name(i.intern("x")), Ty::IInt(32).into()),
+                                (hir::Name(i.intern("y")), Ty::IInt(8).into()),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                    },
+                    406..419,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn cast_struct_diff_field_len() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32,
+                    b: i8,
+                };
+
+                Bar :: struct {
+                    a: i32,
+                    b: i8,
+                    c: str,
+                };
+
+                main :: () {
+                    my_foo : Foo = Foo.{
+                        a = 1,
+                        b = 2,
+                    };
+
+                    my_bar : Bar = my_foo as Bar;
+                };
+            "#,
+            expect![[r#"
+                main::Bar : type
+                main::Foo : type
+                main::main : () -> void
+                2 : type
+                6 : type
+                9 : i32
+                10 : i8
+                11 : main::Foo
+                13 : main::Foo
+                15 : main::Bar
+                16 : void
+                17 : () -> void
+                l0 : main::Foo
+                l1 : main::Bar
+            "#]],
+            |i| {
+                [(
+                    TyDiagnosticKind::Uncastable {
+                        from: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Foo")),
+                            }),
+                            uid: 0,
+                            members: vec![
+                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
+                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                        to: Ty::Struct {
+                            anonymous: false,
+                            fqn: Some(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("Bar")),
+                            }),
+                            uid: 1,
+                            members: vec![
+                                (hir::Name(i.intern("a")), Ty::IInt(32).into()),
+                                (hir::Name(i.intern("b")), Ty::IInt(8).into()),
+                                (hir::Name(i.intern("c")), Ty::String.into()),
+                            ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
+                        }
+                        .into(),
+                    },
+                    434..447,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn lambda_ty_annotation() {
+        check(
+            r#"
+                bar :: (s: str) {
+                    // do stuff
+                } 
+
+                foo :: () {
+                    a : (s: str) -> void = (s: str) {};
+
+                    a = bar;
+
+                    a("Hello!");
+                }
+            "#,
+            expect![[r#"
+                main::bar : (str) -> void
+                main::foo : () -> void
+                1 : void
+                2 : (str) -> void
+                8 : void
+                9 : (str) -> void
+                10 : (str) -> void
+                11 : (str) -> void
+                12 : (str) -> void
+                13 : str
+                14 : void
+                15 : void
+                16 : () -> void
+                l0 : (str) -> void
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn lambda_with_body_ty_annotation() {
+        check(
+            r#"
+                foo :: () {
+                    a : (s: str) -> void {} = (s: str) {};
+
+                    a("Hello!");
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                5 : void
+                6 : (str) -> void
+                7 : <unknown>
+                8 : str
+                9 : <unknown>
+                10 : void
+                11 : () -> void
+                l0 : <unknown>
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Type.into(),
+                        found: Ty::Function {
+                            param_tys: vec![Ty::String.into()],
+                            return_ty: Ty::Void.into(),
+                            is_variadic: false,
+                        }
+                        .into(),
+                    },
+                    53..72,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn lambda_ty_no_return() {
+        // this is only to make sure that `is_safe_to_compile` works correctly.
+        // this will panic if there's some error and `is_safe_to_compile`
+        // returns true. since I know for a fact that the parser is going to throw an
+        // error on the following input, and I know that the following did not panic,
+        // it's safe to say that `is_safe_to_compile` returned false and
+        // works correctly on this input.
+        check(
+            r#"
+                foo :: () {
+                    (a: i32, b: str);
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                2 : <unknown>
+                3 : (i32, str) -> void
+                4 : void
+                5 : () -> void
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn comptime() {
+        check(
+            r#"
+                foo :: () {
+                    foo : str = comptime {
+                        2 * 5
+                    };
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                1 : {uint}
+                2 : {uint}
+                3 : {uint}
+                4 : {uint}
+                5 : {uint}
+                6 : void
+                7 : () -> void
+                l0 : str
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Intern::new(Ty::String),
+                        found: Intern::new(Ty::UInt(0)),
+                    },
+                    61..123,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn comptime_pointer() {
+        check(
+            r#"
+                foo :: () {
+                    comptime {
+                        x := 5;
+
+                        ^x
+                    };
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                0 : {uint}
+                1 : {uint}
+                2 : ^{uint}
+                3 : ^{uint}
+                4 : <unknown>
+                5 : void
+                6 : () -> void
+                l0 : {uint}
+            "#]],
+            |_| [(TyDiagnosticKind::ComptimePointer, 49..141, None)],
+        );
+    }
+
+    #[test]
+    fn non_const_global() {
+        check(
+            r#"
+                foo :: 2 + 2;
+            "#,
+            expect![[r#"
+                main::foo : i32
+                0 : i32
+                1 : i32
+                2 : i32
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn global_init_cycle() {
+        check(
+            r#"
+                a :: get_b() + 1;
+
+                get_b :: () -> i32 {
+                    b
+                };
+
+                b :: a * 2;
+            "#,
+            expect![[r#"
+                main::a : i32
+                main::b : i32
+                main::get_b : () -> i32
+                0 : () -> i32
+                1 : i32
+                2 : i32
+                3 : i32
+                5 : i32
+                6 : i32
+                7 : () -> i32
+                8 : i32
+                9 : i32
+                10 : i32
+            "#]],
+            |i| {
+                let mut fqn = |name| hir::Fqn {
+                    file: hir::FileName(i.intern("main.capy")),
+                    name: hir::Name(i.intern(name)),
+                };
+
+                [(
+                    TyDiagnosticKind::GlobalInitCycle {
+                        path: vec![fqn("a"), fqn("get_b"), fqn("b"), fqn("a")],
+                    },
+                    17..34,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn runtime_global_in_comptime() {
+        check(
+            r#"
+                table :: make_table();
+
+                make_table :: () -> i32 {
+                    5
+                };
+
+                lookup :: () -> i32 {
+                    table
+                };
+
+                fine :: comptime make_table();
+                direct :: comptime table;
+                indirect :: comptime lookup();
+            "#,
+            expect![[r#"
+                main::direct : i32
+                main::fine : i32
+                main::indirect : i32
+                main::lookup : () -> i32
+                main::make_table : () -> i32
+                main::table : i32
+                0 : () -> i32
+                1 : i32
+                3 : i32
+                4 : i32
+                5 : () -> i32
+                7 : i32
+                8 : i32
+                9 : () -> i32
+                10 : () -> i32
+                11 : i32
+                12 : i32
+                13 : i32
+                14 : i32
+                15 : () -> i32
+                16 : i32
+                17 : i32
+            "#]],
+            |i| {
+                let mut fqn = |name| hir::Fqn {
+                    file: hir::FileName(i.intern("main.capy")),
+                    name: hir::Name(i.intern(name)),
+                };
+
+                [
+                    (
+                        TyDiagnosticKind::RuntimeGlobalInComptime {
+                            path: vec![fqn("table")],
+                        },
+                        282..296,
+                        None,
+                    ),
+                    (
+                        TyDiagnosticKind::RuntimeGlobalInComptime {
+                            path: vec![fqn("lookup"), fqn("table")],
+                        },
+                        326..343,
+                        None,
+                    ),
+                ]
+            },
+        );
+    }
+
+    #[test]
+    fn any_ptr() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : i32 = 5;
+
+                    ptr : ^i32 = ^foo;
+                    ptr : ^any = ptr as ^any;
+                    ptr : ^f32 = ptr as ^f32;
+
+                    foo : f32 = ptr^;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : i32
+                4 : i32
+                5 : ^i32
+                8 : ^i32
+                11 : ^any
+                14 : ^any
+                17 : ^f32
+                19 : ^f32
+                20 : f32
+                21 : void
+                22 : () -> void
+                l0 : i32
+                l1 : ^i32
+                l2 : ^any
+                l3 : ^f32
+                l4 : f32
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn any_slice() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : [] i32 = i32.[100, 200];
+
+                    ptr : [] any = foo as [] any;
+
+                    foo : [] f32 = ptr as [] f32;
+
+                    first : f32 = foo[0];
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                3 : i32
+                4 : i32
+                5 : [2]i32
+                8 : []i32
+                11 : []any
+                14 : []any
+                17 : []f32
+                19 : []f32
+                20 : usize
+                21 : f32
+                22 : void
+                23 : () -> void
+                l0 : []i32
+                l1 : []any
+                l2 : []f32
+                l3 : f32
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn cast_ptr_without_any() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : i32 = 5;
+
+                    ptr : ^i32 = ^foo;
+                    ptr : ^f32 = ptr as ^f32;
+
+                    foo : f32 = ptr^;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : i32
+                4 : i32
+                5 : ^i32
+                8 : ^i32
+                11 : ^f32
+                13 : ^f32
+                14 : f32
+                15 : void
+                16 : () -> void
+                l0 : i32
+                l1 : ^i32
+                l2 : ^f32
+                l3 : f32
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Uncastable {
+                        from: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::IInt(32).into(),
+                        }
+                        .into(),
+                        to: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Float(32).into(),
+                        }
+                        .into(),
+                    },
+                    141..152,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn cast_slice_without_any() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : [] i32 = i32.[100, 200];
+
+                    foo : [] f32 = foo as [] f32;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                3 : i32
+                4 : i32
+                5 : [2]i32
+                8 : []i32
+                11 : []f32
+                12 : void
+                13 : () -> void
+                l0 : []i32
+                l1 : []f32
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Uncastable {
+                        from: Ty::Slice {
+                            sub_ty: Ty::IInt(32).into(),
+                        }
+                        .into(),
+                        to: Ty::Slice {
+                            sub_ty: Ty::Float(32).into(),
+                        }
+                        .into(),
+                    },
+                    120..133,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn deref_any() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : i32 = 5;
+
+                    ptr : ^i32 = ^foo;
+                    ptr : ^any = ptr as ^any;
+
+                    foo : any = ptr^;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : i32
+                4 : i32
+                5 : ^i32
+                8 : ^i32
+                11 : ^any
+                13 : ^any
+                14 : <unknown>
+                15 : void
+                16 : () -> void
+                l0 : i32
+                l1 : ^i32
+                l2 : ^any
+                l3 : any
+            "#]],
+            |_| [(TyDiagnosticKind::DerefAny, 187..191, None)],
+        );
+    }
+
+    #[test]
+    fn index_any_slice() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : [3] i32 = i32.[5, 10, 15];
+
+                    ptr : [] i32 = foo;
+                    ptr : [] any = ptr as []any;
+
+                    foo : any = ptr[0];
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                0 : usize
+                4 : i32
+                5 : i32
+                6 : i32
+                7 : [3]i32
+                10 : [3]i32
+                13 : []i32
+                16 : []any
+                18 : []any
+                19 : usize
+                20 : <unknown>
+                21 : void
+                22 : () -> void
+                l0 : [3]i32
+                l1 : []i32
+                l2 : []any
+                l3 : any
+            "#]],
+            |_| [(TyDiagnosticKind::IndexAny { size: None }, 209..215, None)],
+        );
+    }
+
+    #[test]
+    fn index_any_array() {
+        check(
+            r#"
+                get_any :: (foo: [3] any) {
+                    foo : any = foo[0];
+                }
+            "#,
+            expect![[r#"
+                main::get_any : ([3]any) -> void
+                0 : usize
+                4 : [3]any
+                5 : usize
+                6 : <unknown>
+                7 : void
+                8 : ([3]any) -> void
+                l0 : any
+            "#]],
+            |_| [(TyDiagnosticKind::IndexAny { size: Some(3) }, 77..83, None)],
+        );
+    }
+
+    #[test]
+    fn auto_real_ptr_to_any_ptr() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : i32 = 5;
+                    ptr : ^i32 = ^foo;
+
+                    ptr : ^any = ptr;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : i32
+                4 : i32
+                5 : ^i32
+                8 : ^i32
+                9 : void
+                10 : () -> void
+                l0 : i32
+                l1 : ^i32
+                l2 : ^any
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn auto_any_ptr_to_real_ptr() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : i32 = 5;
+                    ptr : ^i32 = ^foo;
+                    ptr : ^any = ptr as ^any;
+
+                    ptr : ^i32 = ptr;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : i32
+                4 : i32
+                5 : ^i32
+                8 : ^i32
+                11 : ^any
+                14 : ^any
+                15 : void
+                16 : () -> void
+                l0 : i32
+                l1 : ^i32
+                l2 : ^any
+                l3 : ^i32
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::IInt(32).into(),
+                        }
+                        .into(),
+                        found: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Any.into(),
+                        }
+                        .into(),
+                    },
+                    187..190,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn auto_real_slice_to_any_slice() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : [] i32 = i32.[5, 10, 15];
+
+                    ptr : [] any = foo;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                3 : i32
+                4 : i32
+                5 : i32
+                6 : [3]i32
+                9 : []i32
+                10 : void
+                11 : () -> void
+                l0 : []i32
+                l1 : []any
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn auto_any_slice_to_real_slice() {
+        check(
+            r#"
+                get_any :: () {
+                    foo : [] i32 = i32.[5, 10, 15];
+                    ptr : [] any = foo as [] any;
+
+                    ptr : [] i32 = ptr;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                3 : i32
+                4 : i32
+                5 : i32
+                6 : [3]i32
+                9 : []i32
+                12 : []any
+                15 : []any
+                16 : void
+                17 : () -> void
+                l0 : []i32
+                l1 : []any
+                l2 : []i32
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Slice {
+                            sub_ty: Ty::IInt(32).into(),
+                        }
+                        .into(),
+                        found: Ty::Slice {
+                            sub_ty: Ty::Any.into(),
+                        }
+                        .into(),
+                    },
+                    171..174,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn str_to_cstr() {
+        check(
+            r#"
+                to_c :: (s: str) {
+                    literal := "hello" as cstr;
+                    other := s as cstr;
+                }
+            "#,
+            expect![[r#"
+                main::to_c : (str) -> void
+                1 : str
+                3 : cstr
+                4 : str
+                6 : cstr
+                7 : void
+                8 : (str) -> void
+                l0 : cstr
+                l1 : cstr
+            "#]],
+            |_| [(TyDiagnosticKind::NonLiteralToCStr, 113..122, None)],
+        );
+    }
+
+    #[test]
+    fn any_ptr_to_cstr() {
+        check(
+            r#"
+                get_any :: () {
+                    data := char.['h', 'i', '\0'];
+                    ptr := ^data as ^any;
+                    str := ptr as cstr;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : char
+                2 : char
+                3 : char
+                4 : [3]char
+                5 : [3]char
+                6 : ^[3]char
+                9 : ^any
+                10 : ^any
+                12 : cstr
+                13 : void
+                14 : () -> void
+                l0 : [3]char
+                l1 : ^any
+                l2 : cstr
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn char_ptr_to_cstr() {
+        check(
+            r#"
+                get_any :: () {
+                    data := char.['h', 'i', '\0'];
+                    ptr := ^data as ^any as ^char;
+                    str := ptr as cstr;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : char
+                2 : char
+                3 : char
+                4 : [3]char
+                5 : [3]char
+                6 : ^[3]char
+                9 : ^any
+                12 : ^char
+                13 : ^char
+                15 : cstr
+                16 : void
+                17 : () -> void
+                l0 : [3]char
+                l1 : ^char
+                l2 : cstr
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn u8_ptr_to_cstr() {
+        check(
+            r#"
+                get_any :: () {
+                    data := char.['h', 'i', '\0'];
+                    ptr := ^data as ^any as ^u8;
+                    str := ptr as cstr;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : char
+                2 : char
+                3 : char
+                4 : [3]char
+                5 : [3]char
+                6 : ^[3]char
+                9 : ^any
+                12 : ^u8
+                13 : ^u8
+                15 : cstr
+                16 : void
+                17 : () -> void
+                l0 : [3]char
+                l1 : ^u8
+                l2 : cstr
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn ptr_to_str() {
+        check(
+            r#"
+                get_any :: () {
+                    data := char.['h', 'i', '\0'];
+                    ptr := ^data as ^any as ^char;
+                    str := ptr as str;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                1 : char
+                2 : char
+                3 : char
+                4 : [3]char
+                5 : [3]char
+                6 : ^[3]char
+                9 : ^any
+                12 : ^char
+                13 : ^char
+                15 : str
+                16 : void
+                17 : () -> void
+                l0 : [3]char
+                l1 : ^char
+                l2 : str
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Uncastable {
+                        from: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Char.into(),
+                        }
+                        .into(),
+                        to: Ty::String.into(),
+                    },
+                    162..172,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn str_to_cstr_and_back() {
+        check(
+            r#"
+                get_any :: () {
+                    c := "hi" as cstr;
+                    s := c as str;
+                }
+            "#,
+            expect![[r#"
+                main::get_any : () -> void
+                0 : str
+                2 : cstr
+                3 : cstr
+                5 : str
+                6 : void
+                7 : () -> void
+                l0 : cstr
+                l1 : str
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn char_array_to_str() {
+        check(
+            r"
+                get_any :: () {
+                    data := char.['H', 'i', '\0'];
+                    str := data as str;
+                }
+            ",
+            expect![[r#"
+                main::get_any : () -> void
+                1 : char
+                2 : char
+                3 : char
+                4 : [3]char
+                5 : [3]char
+                7 : str
+                8 : void
+                9 : () -> void
+                l0 : [3]char
+                l1 : str
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn char() {
+        check(
+            r"
+                foo :: () {
+                    my_char := 'A';
+                }
+            ",
+            expect![[r#"
+                main::foo : () -> void
+                0 : char
+                1 : void
+                2 : () -> void
+                l0 : char
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn char_as_u8() {
+        check(
+            r"
+                foo :: () {
+                    my_char := 'A';
+                    my_u8 := my_char as u8;
+                }
+            ",
+            expect![[r#"
+                main::foo : () -> void
+                0 : char
+                1 : char
+                3 : u8
+                4 : void
+                5 : () -> void
+                l0 : char
+                l1 : u8
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn no_implicit_char_to_u8() {
+        check(
+            r"
+                foo :: () {
+                    my_char := 'A';
+                    my_u8 : u8 = my_char;
+                }
+            ",
+            expect![[r#"
+                main::foo : () -> void
+                0 : char
+                2 : char
+                3 : void
+                4 : () -> void
+                l0 : char
+                l1 : u8
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::UInt(8).into(),
+                        found: Ty::Char.into(),
+                    },
+                    98..105,
+                    None,
+                )]
+            },
+        )
+    }
+
+    #[test]
+    fn field_of_struct_ptr() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32
+                };
+
+                main :: () {
+                    my_foo := Foo.{
+                        a = 25
+                    };
+
+                    ptr := ^my_foo;
+
+                    ptr.a;
+                }
+            "#,
+            expect![[r#"
+                main::Foo : type
+                main::main : () -> void
+                1 : type
+                3 : i32
+                4 : main::Foo
+                5 : main::Foo
+                6 : ^main::Foo
+                7 : ^main::Foo
+                8 : i32
+                9 : void
+                10 : () -> void
+                l0 : main::Foo
+                l1 : ^main::Foo
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn field_of_struct_ptr_ptr() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32
+                };
+
+                main :: () {
+                    my_foo := Foo.{
+                        a = 25
+                    };
+
+                    ptr := ^^my_foo;
+
+                    ptr.a;
+                }
+            "#,
+            expect![[r#"
+                main::Foo : type
+                main::main : () -> void
+                1 : type
+                3 : i32
+                4 : main::Foo
+                5 : main::Foo
+                6 : ^main::Foo
+                7 : ^^main::Foo
+                8 : ^^main::Foo
+                9 : i32
+                10 : void
+                11 : () -> void
+                l0 : main::Foo
+                l1 : ^^main::Foo
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn field_of_non_struct_ptr_ptr() {
+        check(
+            r#"
+                main :: () {
+                    my_foo := 5;
+
+                    ptr := ^^my_foo;
+
+                    ptr.a;
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> void
+                0 : {uint}
+                1 : {uint}
+                2 : ^{uint}
+                3 : ^^{uint}
+                4 : ^^{uint}
+                5 : <unknown>
+                6 : void
+                7 : () -> void
+                l0 : {uint}
+                l1 : ^^{uint}
+            "#]],
+            |i| {
+                [(
+                    TyDiagnosticKind::NonExistentMember {
+                        member: i.intern("a"),
+                        found_ty: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Pointer {
+                                mutable: false,
+                                sub_ty: Ty::UInt(0).into(),
+                            }
+                            .into(),
+                        }
+                        .into(),
+                    },
+                    122..127,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn non_existent_field_of_struct_ptr_ptr() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32
+                };
+
+                main :: () {
+                    my_foo := Foo.{
+                        a = 25
+                    };
+
+                    ptr := ^^my_foo;
+
+                    ptr.b;
+                }
+            "#,
+            expect![[r#"
+                main::Foo : type
+                main::main : () -> void
+                1 : type
+                3 : i32
+                4 : main::Foo
+                5 : main::Foo
+                6 : ^main::Foo
+                7 : ^^main::Foo
+                8 : ^^main::Foo
+                9 : <unknown>
+                10 : void
+                11 : () -> void
+                l0 : main::Foo
+                l1 : ^^main::Foo
+            "#]],
+            |i| {
+                [(
+                    TyDiagnosticKind::NonExistentMember {
+                        member: i.intern("b"),
+                        found_ty: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Pointer {
+                                mutable: false,
+                                sub_ty: Ty::Struct {
+                                    anonymous: false,
+                                    fqn: Some(hir::Fqn {
+                                        file: hir::FileName(i.intern("main.capy")),
+                                        name: hir::Name(i.intern("Foo")),
+                                    }),
+                                    uid: 0,
+                                    members: vec![(hir::Name(i.intern("a")), Ty::IInt(32).into())],
+                                    defaults: Vec::new(),
+                                    repr: hir::StructRepr::default(),
+                                }
+                                .into(),
+                            }
+                            .into(),
+                        }
+                        .into(),
+                    },
+                    258..263,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn mutate_field_of_struct_ptr_ptr() {
+        check(
+            r#"
+                Foo :: struct {
+                    a: i32
+                };
+
+                main :: () {
+                    my_foo := Foo.{
+                        a = 25
+                    };
+
+                    ptr := ^mut ^mut my_foo;
+
+                    ptr.a = 5;
+                }
+            "#,
+            expect![[r#"
+                main::Foo : type
+                main::main : () -> void
+                1 : type
+                3 : i32
+                4 : main::Foo
+                5 : main::Foo
+                6 : ^mut main::Foo
+                7 : ^mut ^mut main::Foo
+                8 : ^mut ^mut main::Foo
+                9 : i32
+                10 : i32
+                11 : void
+                12 : () -> void
+                l0 : main::Foo
+                l1 : ^mut ^mut main::Foo
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn index_of_array_ptr() {
+        check(
+            r#"
+                main :: () {
+                    arr := i32.[1, 2, 3];
+
+                    ptr := ^arr;
+
+                    ptr[0];
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> void
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : [3]i32
+                5 : [3]i32
+                6 : ^[3]i32
+                7 : ^[3]i32
+                8 : usize
+                9 : i32
+                10 : void
+                11 : () -> void
+                l0 : [3]i32
+                l1 : ^[3]i32
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn index_of_array_ptr_ptr() {
+        check(
+            r#"
+                main :: () {
+                    arr := i32.[1, 2, 3];
+
+                    ptr := ^^arr;
+
+                    ptr[0];
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> void
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : [3]i32
+                5 : [3]i32
+                6 : ^[3]i32
+                7 : ^^[3]i32
+                8 : ^^[3]i32
+                9 : usize
+                10 : i32
+                11 : void
+                12 : () -> void
+                l0 : [3]i32
+                l1 : ^^[3]i32
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn index_of_non_array_ptr_ptr() {
+        check(
+            r#"
+                main :: () {
+                    arr := 5;
+
+                    ptr := ^^arr;
+
+                    ptr[0];
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> void
+                0 : {uint}
+                1 : {uint}
+                2 : ^{uint}
+                3 : ^^{uint}
+                4 : ^^{uint}
+                5 : usize
+                6 : <unknown>
+                7 : void
+                8 : () -> void
+                l0 : {uint}
+                l1 : ^^{uint}
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::IndexNonArray {
+                        found: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Pointer {
+                                mutable: false,
+                                sub_ty: Ty::UInt(0).into(),
+                            }
+                            .into(),
+                        }
+                        .into(),
+                    },
+                    116..122,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn index_too_large_of_array_ptr_ptr() {
+        check(
+            r#"
+                main :: () {
+                    arr := i32.[1, 2, 3];
+
+                    ptr := ^^arr;
+
+                    ptr[10];
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> void
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : [3]i32
+                5 : [3]i32
+                6 : ^[3]i32
+                7 : ^^[3]i32
+                8 : ^^[3]i32
+                9 : usize
+                10 : i32
+                11 : void
+                12 : () -> void
+                l0 : [3]i32
+                l1 : ^^[3]i32
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::IndexOutOfBounds {
+                        index: 10,
+                        actual_size: 3,
+                        array_ty: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Pointer {
+                                mutable: false,
+                                sub_ty: Ty::Array {
+                                    anonymous: false,
+                                    size: 3,
+                                    sub_ty: Ty::IInt(32).into(),
+                                }
+                                .into(),
+                            }
+                            .into(),
+                        }
+                        .into(),
+                        is_slice_bound: false,
+                    },
+                    128..135,
+                    None,
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn mutable_index_of_array_ptr_ptr() {
+        check(
+            r#"
+                main :: () {
+                    arr := i32.[1, 2, 3];
+
+                    ptr :: ^mut ^mut arr;
+
+                    ptr[1] = 50;
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> void
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : [3]i32
+                5 : [3]i32
+                6 : ^mut [3]i32
+                7 : ^mut ^mut [3]i32
+                8 : ^mut ^mut [3]i32
+                9 : usize
+                10 : i32
+                11 : i32
+                12 : void
+                13 : () -> void
+                l0 : [3]i32
+                l1 : ^mut ^mut [3]i32
+            "#]],
+            |_| [],
+        );
+    }
+
+    #[test]
+    fn immutable_index_of_array_ptr_ptr() {
+        check(
+            r#"
+                main :: () {
+                    arr :: i32.[1, 2, 3];
+
+                    ptr := ^^arr;
+
+                    ptr[1] = 50;
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> void
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : [3]i32
+                5 : [3]i32
+                6 : ^[3]i32
+                7 : ^^[3]i32
+                8 : ^^[3]i32
+                9 : usize
+                10 : i32
+                11 : {uint}
+                12 : void
+                13 : () -> void
+                l0 : [3]i32
+                l1 : ^^[3]i32
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::CannotMutate,
+                    128..140,
+                    Some((TyDiagnosticHelpKind::ImmutableRef, 100..105)),
+                )]
+            },
+        );
+    }
+
+    #[test]
+    fn entry_point_void() {
+        check_impl(
+            r#"
+                start :: () {};
+            "#,
+            expect![[r#"
+                main::start : () -> void
+                0 : void
+                1 : () -> void
+            "#]],
+            |_| [],
+            Some("start"),
+        )
+    }
+
+    #[test]
+    fn entry_point_int() {
+        check_impl(
+            r#"
+                entry :: () -> i16 { 0 };
+            "#,
+            expect![[r#"
+                main::entry : () -> i16
+                1 : i16
+                2 : i16
+                3 : () -> i16
+            "#]],
+            |_| [],
+            Some("entry"),
+        )
+    }
+
+    #[test]
+    fn entry_point_uint() {
+        check_impl(
+            r#"
+                main :: () -> usize { 0 };
+            "#,
+            expect![[r#"
+                main::main : () -> usize
+                1 : usize
+                2 : usize
+                3 : () -> usize
+            "#]],
+            |_| [],
+            Some("main"),
+        )
+    }
+
+    #[test]
+    fn entry_point_args() {
+        check_impl(
+            r#"
+                main :: (args: []str) -> i32 {
+                    args.len as i32
+                };
+            "#,
+            expect![[r#"
+                main::main : ([]str) -> i32
+                3 : []str
+                4 : usize
+                6 : i32
+                7 : i32
+                8 : ([]str) -> i32
+            "#]],
+            |_| [],
+            Some("main"),
+        )
+    }
+
+    #[test]
+    fn entry_point_bad_args() {
+        check_impl(
+            r#"
+                main :: (args: []i32) {};
+            "#,
+            expect![[r#"
+                main::main : ([]i32) -> void
+                2 : void
+                3 : ([]i32) -> void
+            "#]],
+            |_| [(TyDiagnosticKind::EntryBadParams, 25..38, None)],
+            Some("main"),
+        )
+    }
+
+    #[test]
+    fn entry_point_non_function() {
+        check_impl(
+            r#"
+                main :: 5;
+            "#,
+            expect![[r#"
+                main::main : i32
+                0 : i32
+            "#]],
+            |_| [(TyDiagnosticKind::EntryNotFunction, 17..27, None)],
+            Some("main"),
+        )
+    }
+
+    #[test]
+    fn entry_point_bad_params_and_return() {
+        check_impl(
+            r#"
+                foo :: (x: i32, y: bool) -> str {
+                    "Hello!"
+                }
+            "#,
+            expect![[r#"
+                main::foo : (i32, bool) -> str
+                3 : str
+                4 : str
+                5 : (i32, bool) -> str
+            "#]],
+            |_| {
+                [
+                    (TyDiagnosticKind::EntryBadParams, 24..41, None),
+                    (TyDiagnosticKind::EntryBadReturn, 45..48, None),
+                ]
+            },
+            Some("foo"),
+        )
+    }
+
+    #[test]
+    fn array_of_local_ty() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    int :: i32;
+                    imaginary :: distinct int;
+                    imaginary_vec3 :: distinct [3] imaginary;
+
+                    arr : imaginary_vec3 = imaginary.[1, 2, 3];
+
+                    arr[0] as i32
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> i32
+                1 : type
+                3 : type
+                4 : usize
+                7 : type
+                10 : {uint}
+                11 : {uint}
+                12 : {uint}
+                13 : distinct'1 [3]distinct'0 i32
+                14 : distinct'1 [3]distinct'0 i32
+                15 : usize
+                16 : distinct'0 i32
+                18 : i32
+                19 : i32
+                20 : () -> i32
+                l0 : type
+                l1 : type
+                l2 : type
+                l3 : distinct'1 [3]distinct'0 i32
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn struct_of_local_tys() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    int :: i32;
+                    imaginary :: distinct int;
+                    complex :: struct {
+                        real_part: int,
+                        imaginary_part: imaginary,
+                    };
+
+                    my_complex := complex.{
+                        real_part = 5,
+                        imaginary_part = 42,
+                    };
+
+                    my_complex.real_part as i32 + my_complex.imaginary_part as i32
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> i32
+                1 : type
+                3 : type
+                6 : type
+                8 : i32
+                9 : distinct'0 i32
+                10 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
+                11 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
+                12 : i32
+                14 : i32
+                15 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
+                16 : distinct'0 i32
+                18 : i32
+                19 : i32
+                20 : i32
+                21 : () -> i32
+                l0 : type
+                l1 : type
+                l2 : type
+                l3 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn lambda_with_local_ty() {
+        check(
+            r#"
+                main :: () -> i32 {
+                    int :: i32;
+                    imaginary :: distinct int;
+                    imaginary_vec3 :: distinct [3] imaginary;
+                    complex :: struct {
+                        real_part: int,
+                        imaginary_part: imaginary,
+                    };
+                
+                    my_complex := complex.{
+                        real_part = 5,
+                        imaginary_part = 42,
+                    };
+                
+                    do_math :: (c: complex) -> imaginary_vec3 {
+                        // this is kind of akward because while we can access locals
+                        // in the parameters and return type, we can't access `imaginary`
+                        // from inside the body of this lambda
+                        // this could be alleviated by adding a `type_of` builtin
+                        i32.[1, c.real_part * c.imaginary_part as i32, 3]
+                    };
+                
+                    do_math(my_complex)[1] as i32
+                }
+            "#,
+            expect![[r#"
+                main::main : () -> i32
+                1 : type
+                3 : type
+                4 : usize
+                7 : type
+                10 : type
+                12 : i32
+                13 : distinct'0 i32
+                14 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
+                18 : i32
+                19 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
+                20 : i32
+                21 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
+                22 : distinct'0 i32
+                24 : i32
+                25 : i32
+                26 : i32
+                27 : [3]i32
+                28 : [3]i32
+                29 : (struct'2 {real_part: i32, imaginary_part: distinct'0 i32}) -> distinct'1 [3]distinct'0 i32
+                30 : (struct'2 {real_part: i32, imaginary_part: distinct'0 i32}) -> distinct'1 [3]distinct'0 i32
+                31 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
+                32 : distinct'1 [3]distinct'0 i32
+                33 : usize
+                34 : distinct'0 i32
+                36 : i32
+                37 : i32
+                38 : () -> i32
+                l0 : type
+                l1 : type
+                l2 : type
+                l3 : type
+                l4 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
+                l5 : (struct'2 {real_part: i32, imaginary_part: distinct'0 i32}) -> distinct'1 [3]distinct'0 i32
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn break_void_block_no_tail_match() {
+        check(
+            r#"
+                foo :: () {
+                    {
+                        break;
+                        break {};
+                        break {};
+                    }               
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                0 : void
+                1 : void
+                2 : void
+                3 : void
+                4 : () -> void
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn break_i32_block_no_tail_mismatch() {
         check(
             r#"
-                main :: () {
-                    arr := i32.[1, 2, 3];
-
-                    ptr := ^^arr;
-
-                    ptr[10];
+                foo :: () {
+                    {
+                        break 123;
+                        break {};
+                        break true;
+                    }               
                 }
             "#,
             expect![[r#"
-                main::main : () -> void
-                1 : i32
-                2 : i32
-                3 : i32
-                4 : [3]i32
-                5 : [3]i32
-                6 : ^[3]i32
-                7 : ^^[3]i32
-                8 : ^^[3]i32
-                9 : usize
-                10 : i32
-                11 : void
-                12 : () -> void
-                l0 : [3]i32
-                l1 : ^^[3]i32
+                main::foo : () -> void
+                0 : {uint}
+                1 : void
+                2 : bool
+                3 : <unknown>
+                4 : <unknown>
+                5 : () -> void
             "#]],
             |_| {
                 [(
-                    TyDiagnosticKind::IndexOutOfBounds {
-                        index: 10,
-                        actual_size: 3,
-                        array_ty: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::Pointer {
-                                mutable: false,
-                                sub_ty: Ty::Array {
-                                    anonymous: false,
-                                    size: 3,
-                                    sub_ty: Ty::IInt(32).into(),
-                                }
-                                .into(),
-                            }
-                            .into(),
-                        }
-                        .into(),
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::UInt(0).into(),
+                        found: Ty::Void.into(),
                     },
-                    128..135,
-                    None,
+                    116..118,
+                    Some((
+                        TyDiagnosticHelpKind::BreakHere {
+                            break_ty: Ty::UInt(0).into(),
+                        },
+                        75..85,
+                    )),
                 )]
             },
-        );
+        )
     }
 
     #[test]
-    fn mutable_index_of_array_ptr_ptr() {
+    fn paren_infer() {
         check(
             r#"
-                main :: () {
-                    arr := i32.[1, 2, 3];
-
-                    ptr :: ^mut ^mut arr;
+                foo :: () -> u16 {
+                    (42 * (11 / 2))
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> u16
+                1 : u16
+                2 : u16
+                3 : u16
+                4 : u16
+                5 : u16
+                6 : u16
+                7 : u16
+                8 : u16
+                9 : () -> u16
+            "#]],
+            |_| [],
+        )
+    }
 
-                    ptr[1] = 50;
+    #[test]
+    fn paren_spread() {
+        check(
+            r#"
+                foo :: () {
+                    x : i8 = 42;
+                    (42 * ((2 >> x) / 2));
                 }
             "#,
             expect![[r#"
-                main::main : () -> void
-                1 : i32
-                2 : i32
-                3 : i32
-                4 : [3]i32
-                5 : [3]i32
-                6 : ^mut [3]i32
-                7 : ^mut ^mut [3]i32
-                8 : ^mut ^mut [3]i32
-                9 : usize
-                10 : i32
-                11 : i32
+                main::foo : () -> void
+                1 : i8
+                2 : i8
+                3 : i8
+                4 : i8
+                5 : i8
+                6 : i8
+                7 : i8
+                8 : i8
+                9 : i8
+                10 : i8
+                11 : i8
                 12 : void
                 13 : () -> void
-                l0 : [3]i32
-                l1 : ^mut ^mut [3]i32
+                l0 : i8
             "#]],
             |_| [],
-        );
+        )
     }
 
     #[test]
-    fn immutable_index_of_array_ptr_ptr() {
+    fn break_i32_block_tail_match() {
         check(
             r#"
-                main :: () {
-                    arr :: i32.[1, 2, 3];
-
-                    ptr := ^^arr;
-
-                    ptr[1] = 50;
+                foo :: () -> i32 {
+                    {
+                        break 123;
+                        42
+                    }               
                 }
             "#,
             expect![[r#"
-                main::main : () -> void
+                main::foo : () -> i32
                 1 : i32
                 2 : i32
                 3 : i32
-                4 : [3]i32
-                5 : [3]i32
-                6 : ^[3]i32
-                7 : ^^[3]i32
-                8 : ^^[3]i32
-                9 : usize
-                10 : i32
-                11 : {uint}
-                12 : void
-                13 : () -> void
-                l0 : [3]i32
-                l1 : ^^[3]i32
+                4 : i32
+                5 : () -> i32
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::CannotMutate,
-                    128..140,
-                    Some((TyDiagnosticHelpKind::ImmutableRef, 100..105)),
-                )]
-            },
-        );
+            |_| [],
+        )
     }
 
     #[test]
-    fn entry_point_void() {
-        check_impl(
+    fn break_void_block_tail_mismatch() {
+        check(
             r#"
-                start :: () {};
+                foo :: () {
+                    {
+                        break {};
+                        42
+                    }
+                }
             "#,
             expect![[r#"
-                main::start : () -> void
+                main::foo : () -> void
                 0 : void
-                1 : () -> void
+                1 : {uint}
+                2 : <unknown>
+                3 : <unknown>
+                4 : () -> void
             "#]],
-            |_| [],
-            Some("start"),
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Void.into(),
+                        found: Ty::UInt(0).into(),
+                    },
+                    109..111,
+                    Some((
+                        TyDiagnosticHelpKind::BreakHere {
+                            break_ty: Ty::Void.into(),
+                        },
+                        75..84,
+                    )),
+                )]
+            },
         )
     }
 
     #[test]
-    fn entry_point_int() {
-        check_impl(
+    fn break_i32_block_from_inner() {
+        check(
             r#"
-                entry :: () -> i16 { 0 };
+                foo :: () {
+                    `blk {
+                        {
+                            break blk` {};
+                        }
+
+                        42
+                    }               
+                }
             "#,
             expect![[r#"
-                main::entry : () -> i16
-                1 : i16
-                2 : i16
-                3 : () -> i16
+                main::foo : () -> void
+                0 : void
+                1 : noeval
+                2 : {uint}
+                3 : <unknown>
+                4 : <unknown>
+                5 : () -> void
             "#]],
-            |_| [],
-            Some("entry"),
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Void.into(),
+                        found: Ty::UInt(0).into(),
+                    },
+                    176..178,
+                    Some((
+                        TyDiagnosticHelpKind::BreakHere {
+                            break_ty: Ty::Void.into(),
+                        },
+                        110..124,
+                    )),
+                )]
+            },
         )
     }
 
     #[test]
-    fn entry_point_uint() {
-        check_impl(
+    fn break_i32_block_from_inner_tail() {
+        check(
             r#"
-                main :: () -> usize { 0 };
+            foo :: () -> i32 {
+                `blk {
+                    {
+                        break blk` true;
+                        break 5;
+                        42
+                    }
+                }
+            }
             "#,
             expect![[r#"
-                main::main : () -> usize
-                1 : usize
-                2 : usize
-                3 : () -> usize
+                main::foo : () -> i32
+                1 : bool
+                2 : {uint}
+                3 : {uint}
+                4 : {uint}
+                5 : <unknown>
+                6 : <unknown>
+                7 : () -> i32
             "#]],
-            |_| [],
-            Some("main"),
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Bool.into(),
+                        found: Ty::UInt(0).into(),
+                    },
+                    75..199,
+                    Some((
+                        TyDiagnosticHelpKind::BreakHere {
+                            break_ty: Ty::Bool.into(),
+                        },
+                        101..117,
+                    )),
+                )]
+            },
         )
     }
 
     #[test]
-    fn entry_point_non_function() {
-        check_impl(
+    fn break_unknown_label() {
+        check(
             r#"
-                main :: 5;
+                foo :: () {
+                    break blk` 42;
+                }
             "#,
             expect![[r#"
-                main::main : i32
-                0 : i32
+                main::foo : () -> void
+                0 : {uint}
+                1 : noeval
+                2 : () -> void
             "#]],
-            |_| [(TyDiagnosticKind::EntryNotFunction, 17..27, None)],
-            Some("main"),
+            |_| [],
         )
     }
 
     #[test]
-    fn entry_point_bad_params_and_return() {
-        check_impl(
+    fn return_match() {
+        check(
             r#"
-                foo :: (x: i32, y: bool) -> str {
-                    "Hello!"
+                foo :: () -> i32 {
+                    return 100;
+                    42
                 }
             "#,
             expect![[r#"
-                main::foo : (i32, bool) -> str
-                3 : str
-                4 : str
-                5 : (i32, bool) -> str
+                main::foo : () -> i32
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : () -> i32
             "#]],
-            |_| {
-                [
-                    (TyDiagnosticKind::EntryHasParams, 24..41, None),
-                    (TyDiagnosticKind::EntryBadReturn, 45..48, None),
-                ]
-            },
-            Some("foo"),
+            |_| [],
         )
     }
 
     #[test]
-    fn array_of_local_ty() {
+    fn return_mismatch() {
         check(
             r#"
-                main :: () -> i32 {
-                    int :: i32;
-                    imaginary :: distinct int;
-                    imaginary_vec3 :: distinct [3] imaginary;
-
-                    arr : imaginary_vec3 = imaginary.[1, 2, 3];
-
-                    arr[0] as i32
+                foo :: () -> i32 {
+                    return "hello";
+                    42
                 }
             "#,
             expect![[r#"
-                main::main : () -> i32
-                1 : type
-                3 : type
-                4 : usize
-                7 : type
-                10 : {uint}
-                11 : {uint}
-                12 : {uint}
-                13 : distinct'1 [3]distinct'0 i32
-                14 : distinct'1 [3]distinct'0 i32
-                15 : usize
-                16 : distinct'0 i32
-                18 : i32
-                19 : i32
-                20 : () -> i32
-                l0 : type
-                l1 : type
-                l2 : type
-                l3 : distinct'1 [3]distinct'0 i32
+                main::foo : () -> i32
+                1 : str
+                2 : {uint}
+                3 : <unknown>
+                4 : () -> i32
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::String.into(),
+                        found: Ty::UInt(0).into(),
+                    },
+                    92..94,
+                    Some((
+                        TyDiagnosticHelpKind::BreakHere {
+                            break_ty: Ty::String.into(),
+                        },
+                        56..71,
+                    )),
+                )]
+            },
         )
     }
 
     #[test]
-    fn struct_of_local_tys() {
+    fn return_only() {
         check(
             r#"
-                main :: () -> i32 {
-                    int :: i32;
-                    imaginary :: distinct int;
-                    complex :: struct {
-                        real_part: int,
-                        imaginary_part: imaginary,
-                    };
-
-                    my_complex := complex.{
-                        real_part = 5,
-                        imaginary_part = 42,
-                    };
-
-                    my_complex.real_part as i32 + my_complex.imaginary_part as i32
+                foo :: () -> i32 {
+                    return 42;
                 }
             "#,
             expect![[r#"
-                main::main : () -> i32
-                1 : type
-                3 : type
-                6 : type
-                8 : i32
-                9 : distinct'0 i32
-                10 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
-                11 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
-                12 : i32
-                14 : i32
-                15 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
-                16 : distinct'0 i32
-                18 : i32
-                19 : i32
-                20 : i32
-                21 : () -> i32
-                l0 : type
-                l1 : type
-                l2 : type
-                l3 : struct'1 {real_part: i32, imaginary_part: distinct'0 i32}
+                main::foo : () -> i32
+                1 : i32
+                2 : i32
+                3 : () -> i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn lambda_with_local_ty() {
+    fn return_with_globals() {
         check(
             r#"
-                main :: () -> i32 {
-                    int :: i32;
-                    imaginary :: distinct int;
-                    imaginary_vec3 :: distinct [3] imaginary;
-                    complex :: struct {
-                        real_part: int,
-                        imaginary_part: imaginary,
-                    };
-                
-                    my_complex := complex.{
-                        real_part = 5,
-                        imaginary_part = 42,
-                    };
-                
-                    do_math :: (c: complex) -> imaginary_vec3 {
-                        // this is kind of akward because while we can access locals
-                        // in the parameters and return type, we can't access `imaginary`
-                        // from inside the body of this lambda
-                        // this could be alleviated by adding a `type_of` builtin
-                        i32.[1, c.real_part * c.imaginary_part as i32, 3]
-                    };
-                
-                    do_math(my_complex)[1] as i32
+                a :: 42;
+                b :: comptime a;
+                c :: comptime b;
+                d :: comptime c;
+
+                foo :: () -> i32 {
+                    return 42;
+                    d;
+                    return 5;
                 }
             "#,
             expect![[r#"
-                main::main : () -> i32
-                1 : type
-                3 : type
-                4 : usize
-                7 : type
-                10 : type
-                12 : i32
-                13 : distinct'0 i32
-                14 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
-                18 : i32
-                19 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
-                20 : i32
-                21 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
-                22 : distinct'0 i32
-                24 : i32
-                25 : i32
-                26 : i32
-                27 : [3]i32
-                28 : [3]i32
-                29 : (struct'2 {real_part: i32, imaginary_part: distinct'0 i32}) -> distinct'1 [3]distinct'0 i32
-                30 : (struct'2 {real_part: i32, imaginary_part: distinct'0 i32}) -> distinct'1 [3]distinct'0 i32
-                31 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
-                32 : distinct'1 [3]distinct'0 i32
-                33 : usize
-                34 : distinct'0 i32
-                36 : i32
-                37 : i32
-                38 : () -> i32
-                l0 : type
-                l1 : type
-                l2 : type
-                l3 : type
-                l4 : struct'2 {real_part: i32, imaginary_part: distinct'0 i32}
-                l5 : (struct'2 {real_part: i32, imaginary_part: distinct'0 i32}) -> distinct'1 [3]distinct'0 i32
+                main::a : i32
+                main::b : i32
+                main::c : i32
+                main::d : i32
+                main::foo : () -> i32
+                0 : i32
+                1 : i32
+                2 : i32
+                3 : i32
+                4 : i32
+                5 : i32
+                6 : i32
+                8 : i32
+                9 : i32
+                10 : i32
+                11 : i32
+                12 : () -> i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_void_block_no_tail_match() {
+    fn break_from_loop() {
         check(
             r#"
                 foo :: () {
-                    {
-                        break;
-                        break {};
-                        break {};
-                    }               
+                    `my_loop loop {
+                        break my_loop`;
+                    }
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : void
+                0 : noeval
                 1 : void
                 2 : void
-                3 : void
-                4 : () -> void
+                3 : () -> void
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_i32_block_no_tail_mismatch() {
+    fn break_from_loop_with_value() {
         check(
             r#"
-                foo :: () {
-                    {
-                        break 123;
-                        break {};
-                        break true;
-                    }               
+                foo :: () -> i32 {
+                    `my_loop loop {
+                        break my_loop` 42;
+                    }
                 }
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : {uint}
-                1 : void
-                2 : bool
-                3 : <unknown>
-                4 : <unknown>
-                5 : () -> void
+                main::foo : () -> i32
+                1 : i32
+                2 : noeval
+                3 : i32
+                4 : i32
+                5 : () -> i32
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::UInt(0).into(),
-                        found: Ty::Void.into(),
-                    },
-                    116..118,
-                    Some((
-                        TyDiagnosticHelpKind::BreakHere {
-                            break_ty: Ty::UInt(0).into(),
-                        },
-                        75..85,
-                    )),
-                )]
-            },
+            |_| [],
         )
     }
 
     #[test]
-    fn paren_infer() {
+    fn break_from_loop_with_multiple_values() {
         check(
             r#"
-                foo :: () -> u16 {
-                    (42 * (11 / 2))
+                foo :: () {
+                    loop {
+                        x : i16 = 42;
+                        break x;
+
+                        break 15;
+
+                        x : i32 = 23;
+                        break x;
+                    };
                 }
             "#,
             expect![[r#"
-                main::foo : () -> u16
-                1 : u16
-                2 : u16
-                3 : u16
-                4 : u16
-                5 : u16
-                6 : u16
-                7 : u16
-                8 : u16
-                9 : () -> u16
+                main::foo : () -> void
+                1 : i16
+                2 : i16
+                3 : i16
+                5 : i32
+                6 : i32
+                7 : noeval
+                8 : i32
+                9 : void
+                10 : () -> void
+                l0 : i16
+                l1 : i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn paren_spread() {
+    fn break_from_while() {
         check(
             r#"
                 foo :: () {
-                    x : i8 = 42;
-                    (42 * ((2 >> x) / 2));
+                    `my_loop while 2 + 2 == 4 {
+                        break my_loop`;
+                    }
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                1 : i8
-                2 : i8
-                3 : i8
-                4 : i8
-                5 : i8
-                6 : i8
-                7 : i8
-                8 : i8
-                9 : i8
-                10 : i8
-                11 : i8
-                12 : void
-                13 : () -> void
-                l0 : i8
+                0 : {uint}
+                1 : {uint}
+                2 : {uint}
+                3 : {uint}
+                4 : bool
+                5 : noeval
+                6 : void
+                7 : void
+                8 : () -> void
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_i32_block_tail_match() {
+    fn break_from_while_with_void() {
         check(
             r#"
-                foo :: () -> i32 {
-                    {
-                        break 123;
-                        42
-                    }               
+                foo :: () {
+                    `my_loop while 2 + 2 == 4 {
+                        break my_loop` {};
+                    }
                 }
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : i32
-                2 : i32
-                3 : i32
-                4 : i32
-                5 : () -> i32
+                main::foo : () -> void
+                0 : {uint}
+                1 : {uint}
+                2 : {uint}
+                3 : {uint}
+                4 : bool
+                5 : void
+                6 : noeval
+                7 : void
+                8 : void
+                9 : () -> void
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_void_block_tail_mismatch() {
+    fn break_from_while_with_value() {
         check(
             r#"
                 foo :: () {
-                    {
-                        break {};
-                        42
+                    `my_loop while 2 + 2 == 4 {
+                        break my_loop` 42;
                     }
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : void
+                0 : {uint}
                 1 : {uint}
-                2 : <unknown>
-                3 : <unknown>
-                4 : () -> void
+                2 : {uint}
+                3 : {uint}
+                4 : bool
+                5 : {uint}
+                6 : noeval
+                7 : <unknown>
+                8 : <unknown>
+                9 : () -> void
             "#]],
             |_| {
                 [(
@@ -7757,693 +11182,750 @@ mod tests {
                         expected: Ty::Void.into(),
                         found: Ty::UInt(0).into(),
                     },
-                    109..111,
-                    Some((
-                        TyDiagnosticHelpKind::BreakHere {
-                            break_ty: Ty::Void.into(),
-                        },
-                        75..84,
-                    )),
+                    116..118,
+                    None,
                 )]
             },
         )
     }
 
     #[test]
-    fn break_i32_block_from_inner() {
+    fn continue_works() {
         check(
             r#"
-                foo :: () {
-                    `blk {
-                        {
-                            break blk` {};
-                        }
-
-                        42
-                    }               
+                foo :: () -> i32 {
+                    loop {
+                        continue;
+                    }
+                    42
                 }
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : void
+                main::foo : () -> i32
                 1 : noeval
-                2 : {uint}
-                3 : <unknown>
-                4 : <unknown>
-                5 : () -> void
+                2 : void
+                3 : i32
+                4 : i32
+                5 : () -> i32
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Void.into(),
-                        found: Ty::UInt(0).into(),
-                    },
-                    176..178,
-                    Some((
-                        TyDiagnosticHelpKind::BreakHere {
-                            break_ty: Ty::Void.into(),
-                        },
-                        110..124,
-                    )),
-                )]
-            },
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn break_inner_if_no_else() {
+        check(
+            r#"
+                foo :: () -> i32 {
+                    {
+                        if true {
+                            return 123;
+                        }
+                    }
+
+                    0
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> i32
+                1 : bool
+                2 : i32
+                3 : noeval
+                4 : void
+                5 : void
+                6 : i32
+                7 : i32
+                8 : () -> i32
+            "#]],
+            |_| [],
         )
     }
 
     #[test]
-    fn break_i32_block_from_inner_tail() {
+    fn break_inner_if_with_else_no_break() {
         check(
             r#"
-            foo :: () -> i32 {
-                `blk {
+                foo :: () -> i32 {
                     {
-                        break blk` true;
-                        break 5;
-                        42
+                        if true {
+                            return 123;
+                        } else {
+
+                        }
                     }
+
+                    0
                 }
-            }
             "#,
             expect![[r#"
                 main::foo : () -> i32
                 1 : bool
-                2 : {uint}
-                3 : {uint}
-                4 : {uint}
-                5 : <unknown>
-                6 : <unknown>
-                7 : () -> i32
+                2 : i32
+                3 : noeval
+                4 : void
+                5 : void
+                6 : void
+                7 : i32
+                8 : i32
+                9 : () -> i32
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Bool.into(),
-                        found: Ty::UInt(0).into(),
-                    },
-                    75..199,
-                    Some((
-                        TyDiagnosticHelpKind::BreakHere {
-                            break_ty: Ty::Bool.into(),
-                        },
-                        101..117,
-                    )),
-                )]
-            },
+            |_| [],
         )
     }
 
     #[test]
-    fn break_unknown_label() {
+    fn break_inner_if_with_else_break() {
         check(
             r#"
-                foo :: () {
-                    break blk` 42;
+                foo :: () -> i32 {
+                    {
+                        if true {
+                            return 123;
+                        } else {
+                            return 456;
+                        }
+                    }
+
+                    0
                 }
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : {uint}
-                1 : noeval
-                2 : () -> void
+                main::foo : () -> i32
+                1 : bool
+                2 : i32
+                3 : noeval
+                4 : i32
+                5 : noeval
+                6 : noeval
+                7 : noeval
+                8 : i32
+                9 : i32
+                10 : () -> i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn return_match() {
+    fn reinfer_break_usages() {
         check(
             r#"
-                foo :: () -> i32 {
-                    return 100;
-                    42
+                foo :: () {
+                    {
+                        x := 0;
+                        break x;
+                        y : i8 = x;
+                    };
                 }
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : i32
-                2 : i32
-                3 : i32
-                4 : () -> i32
+                main::foo : () -> void
+                0 : i8
+                1 : i8
+                3 : i8
+                4 : i8
+                5 : void
+                6 : () -> void
+                l0 : i8
+                l1 : i8
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn return_mismatch() {
+    fn implicit_weak_ptr_to_any() {
         check(
             r#"
-                foo :: () -> i32 {
-                    return "hello";
-                    42
+                foo :: () {
+                    x : ^any = ^42;
                 }
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : str
+                main::foo : () -> void
                 2 : {uint}
-                3 : <unknown>
-                4 : () -> i32
+                3 : ^{uint}
+                4 : void
+                5 : () -> void
+                l0 : ^any
             "#]],
             |_| {
                 [(
                     TyDiagnosticKind::Mismatch {
-                        expected: Ty::String.into(),
-                        found: Ty::UInt(0).into(),
+                        expected: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::Any.into(),
+                        }
+                        .into(),
+                        found: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::UInt(0).into(),
+                        }
+                        .into(),
                     },
-                    92..94,
-                    Some((
-                        TyDiagnosticHelpKind::BreakHere {
-                            break_ty: Ty::String.into(),
-                        },
-                        56..71,
-                    )),
+                    60..63,
+                    None,
                 )]
             },
         )
     }
 
     #[test]
-    fn return_only() {
+    fn explicit_weak_ptr_to_i32_to_any() {
         check(
             r#"
-                foo :: () -> i32 {
-                    return 42;
+                foo :: () {
+                    x : ^any = ^42 as ^i32;
                 }
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : i32
+                main::foo : () -> void
                 2 : i32
-                3 : () -> i32
+                3 : ^i32
+                6 : ^i32
+                7 : void
+                8 : () -> void
+                l0 : ^any
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn return_with_globals() {
+    fn slice() {
         check(
             r#"
-                a :: 42;
-                b :: comptime a;
-                c :: comptime b;
-                d :: comptime c;
+                foo :: () {
+                    x : [] i32 = i32.[1, 2, 3];
 
-                foo :: () -> i32 {
-                    return 42;
-                    d;
-                    return 5;
+                    y : [] i32 = i32.[4, 6, 7, 8];
                 }
             "#,
             expect![[r#"
-                main::a : i32
-                main::b : i32
-                main::c : i32
-                main::d : i32
-                main::foo : () -> i32
-                0 : i32
-                1 : i32
-                2 : i32
+                main::foo : () -> void
                 3 : i32
                 4 : i32
                 5 : i32
-                6 : i32
-                8 : i32
-                9 : i32
+                6 : [3]i32
                 10 : i32
                 11 : i32
-                12 : () -> i32
+                12 : i32
+                13 : i32
+                14 : [4]i32
+                15 : void
+                16 : () -> void
+                l0 : []i32
+                l1 : []i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_from_loop() {
+    fn explicit_slice_to_array() {
         check(
             r#"
                 foo :: () {
-                    `my_loop loop {
-                        break my_loop`;
-                    }
+                    x : [] i32 = i32.[1, 2, 3];
+                    
+                    y := x as [3] i32;
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : noeval
-                1 : void
-                2 : void
-                3 : () -> void
+                3 : i32
+                4 : i32
+                5 : i32
+                6 : [3]i32
+                7 : []i32
+                8 : usize
+                11 : [3]i32
+                12 : void
+                13 : () -> void
+                l0 : []i32
+                l1 : [3]i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_from_loop_with_value() {
+    fn implicit_slice_to_array() {
+        check(
+            r#"
+                foo :: () {
+                    x : [] i32 = i32.[1, 2, 3];
+                    
+                    y : [3] i32 = x;
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> void
+                3 : i32
+                4 : i32
+                5 : i32
+                6 : [3]i32
+                7 : usize
+                10 : []i32
+                11 : void
+                12 : () -> void
+                l0 : []i32
+                l1 : [3]i32
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Array {
+                            anonymous: false,
+                            size: 3,
+                            sub_ty: Ty::IInt(32).into(),
+                        }
+                        .into(),
+                        found: Ty::Slice {
+                            sub_ty: Ty::IInt(32).into(),
+                        }
+                        .into(),
+                    },
+                    132..133,
+                    None,
+                )]
+            },
+        )
+    }
+
+    #[test]
+    fn explicit_array_to_slice() {
         check(
             r#"
-                foo :: () -> i32 {
-                    `my_loop loop {
-                        break my_loop` 42;
-                    }
+                foo :: () {
+                    x := i32.[1, 2, 3];
+                    
+                    y := x as [] i32;
                 }
             "#,
             expect![[r#"
-                main::foo : () -> i32
+                main::foo : () -> void
                 1 : i32
-                2 : noeval
+                2 : i32
                 3 : i32
-                4 : i32
-                5 : () -> i32
+                4 : [3]i32
+                5 : [3]i32
+                8 : []i32
+                9 : void
+                10 : () -> void
+                l0 : [3]i32
+                l1 : []i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_from_loop_with_multiple_values() {
+    fn implicit_array_to_slice() {
         check(
             r#"
                 foo :: () {
-                    loop {
-                        x : i16 = 42;
-                        break x;
-
-                        break 15;
-
-                        x : i32 = 23;
-                        break x;
-                    };
+                    x : [3] i32 = i32.[1, 2, 3];
+                    
+                    y : [] i32 = x;
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                1 : i16
-                2 : i16
-                3 : i16
+                0 : usize
+                4 : i32
                 5 : i32
                 6 : i32
-                7 : noeval
-                8 : i32
-                9 : void
-                10 : () -> void
-                l0 : i16
-                l1 : i32
+                7 : [3]i32
+                10 : [3]i32
+                11 : void
+                12 : () -> void
+                l0 : [3]i32
+                l1 : []i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_from_while() {
+    fn extern_function() {
         check(
             r#"
-                foo :: () {
-                    `my_loop while 2 + 2 == 4 {
-                        break my_loop`;
-                    }
-                }
+                foo :: (s: str) -> void extern;
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : {uint}
-                1 : {uint}
-                2 : {uint}
-                3 : {uint}
-                4 : bool
-                5 : noeval
-                6 : void
-                7 : void
-                8 : () -> void
+                main::foo : (str) -> void
+                3 : (str) -> void
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_from_while_with_void() {
+    fn variadic_extern_function() {
         check(
             r#"
+                printf :: (fmt: cstr, ..) -> i32 extern;
+
                 foo :: () {
-                    `my_loop while 2 + 2 == 4 {
-                        break my_loop` {};
-                    }
+                    small : u8 = 5;
+                    printf("%d %f %c %d\n" as cstr, 42, 1.5, 'a', small);
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : {uint}
-                1 : {uint}
-                2 : {uint}
-                3 : {uint}
-                4 : bool
-                5 : void
-                6 : noeval
-                7 : void
-                8 : void
-                9 : () -> void
+                main::printf : (cstr, ..) -> i32
+                3 : (cstr, ..) -> i32
+                5 : u8
+                6 : (cstr, ..) -> i32
+                7 : str
+                9 : cstr
+                10 : i32
+                11 : f64
+                12 : char
+                13 : u8
+                14 : i32
+                15 : void
+                16 : () -> void
+                l0 : u8
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_from_while_with_value() {
+    fn variadic_literal_sizes() {
         check(
             r#"
+                printf :: (fmt: cstr, ..) -> i32 extern;
+
                 foo :: () {
-                    `my_loop while 2 + 2 == 4 {
-                        break my_loop` 42;
-                    }
+                    printf(
+                        "%d %d %lld %lld %llu\n" as cstr,
+                        2_147_483_647,
+                        -2_147_483_648,
+                        5_000_000_000,
+                        -5_000_000_000,
+                        18_446_744_073_709_551_615,
+                    );
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                0 : {uint}
-                1 : {uint}
-                2 : {uint}
-                3 : {uint}
-                4 : bool
-                5 : {uint}
-                6 : noeval
-                7 : <unknown>
-                8 : <unknown>
-                9 : () -> void
+                main::printf : (cstr, ..) -> i32
+                3 : (cstr, ..) -> i32
+                4 : (cstr, ..) -> i32
+                5 : str
+                7 : cstr
+                8 : i32
+                9 : i32
+                10 : i32
+                11 : i64
+                12 : i64
+                13 : i64
+                14 : u64
+                15 : i32
+                16 : void
+                17 : () -> void
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Void.into(),
-                        found: Ty::UInt(0).into(),
-                    },
-                    116..118,
-                    None,
-                )]
-            },
+            |_| [],
         )
     }
 
     #[test]
-    fn continue_works() {
+    fn variadic_not_enough_args() {
         check(
             r#"
-                foo :: () -> i32 {
-                    loop {
-                        continue;
-                    }
-                    42
+                printf :: (fmt: cstr, ..) -> i32 extern;
+
+                foo :: () {
+                    printf();
                 }
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : noeval
-                2 : void
-                3 : i32
-                4 : i32
-                5 : () -> i32
+                main::foo : () -> void
+                main::printf : (cstr, ..) -> i32
+                3 : (cstr, ..) -> i32
+                4 : (cstr, ..) -> i32
+                5 : i32
+                6 : void
+                7 : () -> void
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::NotEnoughVarArgs {
+                        found: 0,
+                        expected: 1,
+                    },
+                    107..115,
+                    None,
+                )]
+            },
         )
     }
 
     #[test]
-    fn break_inner_if_no_else() {
+    fn variadic_invalid_arg() {
         check(
             r#"
-                foo :: () -> i32 {
-                    {
-                        if true {
-                            return 123;
-                        }
-                    }
+                printf :: (fmt: cstr, ..) -> i32 extern;
 
-                    0
+                foo :: () {
+                    printf("%s" as cstr, "hello");
                 }
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : bool
-                2 : i32
-                3 : noeval
-                4 : void
-                5 : void
-                6 : i32
-                7 : i32
-                8 : () -> i32
+                main::foo : () -> void
+                main::printf : (cstr, ..) -> i32
+                3 : (cstr, ..) -> i32
+                4 : (cstr, ..) -> i32
+                5 : str
+                7 : cstr
+                8 : str
+                9 : i32
+                10 : void
+                11 : () -> void
             "#]],
-            |_| [],
+            |_| {
+                [(
+                    TyDiagnosticKind::InvalidVarArg {
+                        found: Ty::String.into(),
+                    },
+                    128..135,
+                    None,
+                )]
+            },
         )
     }
 
     #[test]
-    fn break_inner_if_with_else_no_break() {
+    fn extern_global_with_type() {
         check(
             r#"
-                foo :: () -> i32 {
-                    {
-                        if true {
-                            return 123;
-                        } else {
-
-                        }
-                    }
-
-                    0
-                }
+                foo : i32 : extern;
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : bool
-                2 : i32
-                3 : noeval
-                4 : void
-                5 : void
-                6 : void
-                7 : i32
-                8 : i32
-                9 : () -> i32
+                main::foo : i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn break_inner_if_with_else_break() {
+    fn extern_global_without_type() {
         check(
             r#"
-                foo :: () -> i32 {
-                    {
-                        if true {
-                            return 123;
-                        } else {
-                            return 456;
-                        }
-                    }
-
-                    0
-                }
+                foo :: extern;
             "#,
             expect![[r#"
-                main::foo : () -> i32
-                1 : bool
-                2 : i32
-                3 : noeval
-                4 : i32
-                5 : noeval
-                6 : noeval
-                7 : noeval
-                8 : i32
-                9 : i32
-                10 : () -> i32
+                main::foo : <unknown>
             "#]],
-            |_| [],
+            |_| [(TyDiagnosticKind::ExternGlobalMissingTy, 17..31, None)],
         )
     }
 
     #[test]
-    fn reinfer_break_usages() {
+    fn extern_global_reference() {
+        // mainly just for checking `is_safe_to_compile`
         check(
-            r#"
-                foo :: () {
-                    {
-                        x := 0;
-                        break x;
-                        y : i8 = x;
-                    };
-                }
+            r#"
+                #- main.capy
+                other :: import "other.capy";
+
+                foo : i32 : extern;
+
+                bar :: () {
+                    foo;
+                    other.baz;
+                };
+                #- other.capy
+                baz : i32 : extern;
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : i8
-                1 : i8
-                3 : i8
-                4 : i8
-                5 : void
-                6 : () -> void
-                l0 : i8
-                l1 : i8
+                main::bar : () -> void
+                main::foo : i32
+                main::other : file other
+                other::baz : i32
+                other:
+                main:
+                  0 : file other
+                  2 : i32
+                  3 : file other
+                  4 : i32
+                  5 : void
+                  6 : () -> void
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn implicit_weak_ptr_to_any() {
+    fn defers() {
         check(
             r#"
                 foo :: () {
-                    x : ^any = ^42;
-                }
+                    defer 5 + 5;
+                    defer foo();
+                    defer {
+                        defer !true;
+                    };
+                    defer {
+                        break "owo";
+                    };
+                };
             "#,
             expect![[r#"
                 main::foo : () -> void
+                0 : {uint}
+                1 : {uint}
                 2 : {uint}
-                3 : ^{uint}
+                3 : () -> void
                 4 : void
-                5 : () -> void
-                l0 : ^any
+                5 : bool
+                6 : bool
+                7 : void
+                8 : str
+                9 : str
+                10 : void
+                11 : () -> void
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::Any.into(),
-                        }
-                        .into(),
-                        found: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::UInt(0).into(),
-                        }
-                        .into(),
-                    },
-                    60..63,
-                    None,
-                )]
-            },
+            |_| [],
         )
     }
 
     #[test]
-    fn explicit_weak_ptr_to_i32_to_any() {
+    fn comptime_types() {
         check(
             r#"
-                foo :: () {
-                    x : ^any = ^42 as ^i32;
-                }
+                Foo :: comptime {
+                    Bar :: comptime str;
+                    Baz :: comptime i32;
+
+                    struct {
+                        a: Bar,
+                        b : Baz,
+                    }
+                };
+
+                run :: () {
+                    my_foo := Foo.{
+                        a = "hello",
+                        b = 42,
+                    };
+                };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                2 : i32
-                3 : ^i32
-                6 : ^i32
-                7 : void
-                8 : () -> void
-                l0 : ^any
+                main::Foo : type
+                main::run : () -> void
+                0 : type
+                1 : type
+                2 : type
+                3 : type
+                6 : type
+                7 : type
+                8 : type
+                10 : str
+                11 : i32
+                12 : main::Foo
+                13 : void
+                14 : () -> void
+                l0 : type
+                l1 : type
+                l2 : main::Foo
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn slice() {
+    fn reinfer_params() {
+        // usually an argument will replace the weak type of a variable.
+        // in this case it doesn't and so more advanced replacing is needed.
         check(
             r#"
+                Any :: struct {
+                    ty: type,
+                    data: ^any,
+                };
+                
+                accept_any :: (val: Any) {}
+                
                 foo :: () {
-                    x : [] i32 = i32.[1, 2, 3];
-
-                    y : [] i32 = i32.[4, 6, 7, 8];
+                    x := 0;
+                    accept_any(x);
+                    x as i16;
                 }
             "#,
             expect![[r#"
+                main::Any : type
+                main::accept_any : (main::Any) -> void
                 main::foo : () -> void
-                3 : i32
-                4 : i32
-                5 : i32
-                6 : [3]i32
-                10 : i32
-                11 : i32
-                12 : i32
-                13 : i32
-                14 : [4]i32
-                15 : void
-                16 : () -> void
-                l0 : []i32
-                l1 : []i32
+                3 : type
+                5 : void
+                6 : (main::Any) -> void
+                7 : i16
+                8 : (main::Any) -> void
+                9 : i16
+                10 : void
+                11 : i16
+                13 : i16
+                14 : void
+                15 : () -> void
+                l0 : i16
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn explicit_slice_to_array() {
+    fn ty_ptr_becomes_ty() {
         check(
             r#"
                 foo :: () {
-                    x : [] i32 = i32.[1, 2, 3];
-                    
-                    y := x as [3] i32;
+                    x : type = ^i32;
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                3 : i32
-                4 : i32
-                5 : i32
-                6 : [3]i32
-                7 : []i32
-                8 : usize
-                11 : [3]i32
-                12 : void
-                13 : () -> void
-                l0 : []i32
-                l1 : [3]i32
+                1 : type
+                2 : type
+                3 : void
+                4 : () -> void
+                l0 : type
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn implicit_slice_to_array() {
+    fn array_literal_as_type() {
+        // this is just to make sure that the compiler doens't show a diagnostic
+        // like "expected `type` but found `<unknown>`"
         check(
             r#"
                 foo :: () {
-                    x : [] i32 = i32.[1, 2, 3];
-                    
-                    y : [3] i32 = x;
+                    x : i32.[];
                 }
             "#,
             expect![[r#"
                 main::foo : () -> void
-                3 : i32
-                4 : i32
-                5 : i32
-                6 : [3]i32
-                7 : usize
-                10 : []i32
-                11 : void
-                12 : () -> void
-                l0 : []i32
-                l1 : [3]i32
+                1 : [0]i32
+                2 : void
+                3 : () -> void
+                l0 : <unknown>
             "#]],
             |_| {
                 [(
                     TyDiagnosticKind::Mismatch {
-                        expected: Ty::Array {
+                        expected: Ty::Type.into(),
+                        found: Ty::Array {
                             anonymous: false,
-                            size: 3,
-                            sub_ty: Ty::IInt(32).into(),
-                        }
-                        .into(),
-                        found: Ty::Slice {
+                            size: 0,
                             sub_ty: Ty::IInt(32).into(),
                         }
                         .into(),
                     },
-                    132..133,
+                    53..59,
                     None,
                 )]
             },
@@ -8451,398 +11933,571 @@ mod tests {
     }
 
     #[test]
-    fn explicit_array_to_slice() {
-        check(
-            r#"
-                foo :: () {
-                    x := i32.[1, 2, 3];
-                    
-                    y := x as [] i32;
-                }
-            "#,
-            expect![[r#"
-                main::foo : () -> void
-                1 : i32
-                2 : i32
-                3 : i32
-                4 : [3]i32
-                5 : [3]i32
-                8 : []i32
-                9 : void
-                10 : () -> void
-                l0 : [3]i32
-                l1 : []i32
-            "#]],
-            |_| [],
-        )
-    }
-
-    #[test]
-    fn implicit_array_to_slice() {
+    fn default_value_i32() {
         check(
             r#"
-                foo :: () {
-                    x : [3] i32 = i32.[1, 2, 3];
-                    
-                    y : [] i32 = x;
+                defaults :: () {
+                    x : i32;
                 }
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : usize
-                4 : i32
-                5 : i32
-                6 : i32
-                7 : [3]i32
-                10 : [3]i32
-                11 : void
-                12 : () -> void
-                l0 : [3]i32
-                l1 : []i32
+                main::defaults : () -> void
+                1 : void
+                2 : () -> void
+                l0 : i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn extern_function() {
+    fn default_value_char_array() {
         check(
             r#"
-                foo :: (s: str) -> void extern;
+                defaults :: () {
+                    x : [10]char;
+                }
             "#,
             expect![[r#"
-                main::foo : (str) -> void
-                3 : (str) -> void
+                main::defaults : () -> void
+                0 : usize
+                3 : void
+                4 : () -> void
+                l0 : [10]char
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn extern_global_with_type() {
+    fn default_value_distinct_distinct_struct_with_valid_types() {
         check(
             r#"
-                foo : i32 : extern;
+                defaults :: () {
+                    x : distinct distinct struct {
+                        a: [2][4]u8,
+                        b: i16,
+                        c: distinct f32,
+                        d: bool,
+                        e: char,
+                        f: void,
+                    };
+                }
             "#,
             expect![[r#"
-                main::foo : i32
+                main::defaults : () -> void
+                0 : usize
+                1 : usize
+                14 : void
+                15 : () -> void
+                l0 : distinct'3 distinct'2 struct'1 {a: [2][4]u8, b: i16, c: distinct'0 f32, d: bool, e: char, f: void}
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn extern_global_without_type() {
+    fn default_value_i32_ptr() {
         check(
             r#"
-                foo :: extern;
+                defaults :: () {
+                    x : ^i32;
+                }
             "#,
             expect![[r#"
-                main::foo : <unknown>
+                main::defaults : () -> void
+                2 : void
+                3 : () -> void
+                l0 : ^i32
             "#]],
-            |_| [(TyDiagnosticKind::ExternGlobalMissingTy, 17..31, None)],
+            |_| {
+                [(
+                    TyDiagnosticKind::DeclTypeHasNoDefault {
+                        ty: Ty::Pointer {
+                            mutable: false,
+                            sub_ty: Ty::IInt(32).into(),
+                        }
+                        .into(),
+                    },
+                    58..62,
+                    None,
+                )]
+            },
         )
     }
 
     #[test]
-    fn extern_global_reference() {
-        // mainly just for checking `is_safe_to_compile`
+    fn uninit_has_no_default() {
         check(
             r#"
-                #- main.capy
-                other :: import "other.capy";
-
-                foo : i32 : extern;
-
-                bar :: () {
-                    foo;
-                    other.baz;
-                };
-                #- other.capy
-                baz : i32 : extern;
+                defaults :: () {
+                    x : ^i32 = ---;
+                }
             "#,
             expect![[r#"
-                main::bar : () -> void
-                main::foo : i32
-                main::other : file other
-                other::baz : i32
-                other:
-                main:
-                  0 : file other
-                  2 : i32
-                  3 : file other
-                  4 : i32
-                  5 : void
-                  6 : () -> void
+                main::defaults : () -> void
+                2 : void
+                3 : () -> void
+                l0 : ^i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn defers() {
+    fn uninit_written_before_read() {
         check(
             r#"
-                foo :: () {
-                    defer 5 + 5;
-                    defer foo();
-                    defer {
-                        defer !true;
-                    };
-                    defer {
-                        break "owo";
-                    };
+                fill :: (buf: ^mut [4]u8) {}
+
+                foo :: (cond: bool) -> i32 {
+                    buf : [4]u8 = ---;
+                    fill(^mut buf);
+                    buf[0];
+
+                    x : i32 = ---;
+                    if cond {
+                        x = 1;
+                    } else {
+                        x = 2;
+                    }
+
+                    y : i32 = ---;
+                    loop {
+                        y = 3;
+                        break;
+                    }
+
+                    z : [2]i32 = ---;
+                    z[1] = 4;
+
+                    x + y + z[0]
                 };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                0 : {uint}
-                1 : {uint}
-                2 : {uint}
-                3 : () -> void
+                main::fill : (^mut [4]u8) -> void
+                main::foo : (bool) -> i32
+                0 : usize
                 4 : void
-                5 : bool
-                6 : bool
-                7 : void
-                8 : str
-                9 : str
-                10 : void
-                11 : () -> void
+                5 : (^mut [4]u8) -> void
+                8 : usize
+                11 : (^mut [4]u8) -> void
+                12 : [4]u8
+                13 : ^mut [4]u8
+                14 : void
+                15 : [4]u8
+                16 : usize
+                17 : u8
+                19 : bool
+                20 : i32
+                21 : i32
+                22 : void
+                23 : i32
+                24 : i32
+                25 : void
+                26 : void
+                28 : i32
+                29 : i32
+                30 : noeval
+                31 : void
+                32 : usize
+                35 : [2]i32
+                36 : usize
+                37 : i32
+                38 : i32
+                39 : i32
+                40 : i32
+                41 : i32
+                42 : [2]i32
+                43 : usize
+                44 : i32
+                45 : i32
+                46 : i32
+                47 : (bool) -> i32
+                l0 : [4]u8
+                l1 : i32
+                l2 : i32
+                l3 : [2]i32
             "#]],
             |_| [],
         )
     }
 
     #[test]
-    fn comptime_types() {
+    fn uninit_read_before_write() {
         check(
             r#"
-                Foo :: comptime {
-                    Bar :: comptime str;
-                    Baz :: comptime i32;
+                foo :: (cond: bool) -> i32 {
+                    x : i32 = ---;
+                    if cond {
+                        x = 1;
+                    }
 
-                    struct {
-                        a: Bar,
-                        b : Baz,
+                    y : i32 = ---;
+                    while cond {
+                        y = 2;
                     }
-                };
 
-                run :: () {
-                    my_foo := Foo.{
-                        a = "hello",
-                        b = 42,
-                    };
+                    z : i32 = ---;
+                    z = z + 1;
+
+                    x + y + z
                 };
             "#,
             expect![[r#"
-                main::Foo : type
-                main::run : () -> void
-                0 : type
-                1 : type
-                2 : type
-                3 : type
-                6 : type
-                7 : type
-                8 : type
-                10 : str
+                main::foo : (bool) -> i32
+                3 : bool
+                4 : i32
+                5 : i32
+                6 : void
+                7 : void
+                9 : bool
+                10 : i32
                 11 : i32
-                12 : main::Foo
+                12 : void
                 13 : void
-                14 : () -> void
-                l0 : type
-                l1 : type
-                l2 : main::Foo
+                15 : i32
+                16 : i32
+                17 : i32
+                18 : i32
+                19 : i32
+                20 : i32
+                21 : i32
+                22 : i32
+                23 : i32
+                24 : i32
+                25 : (bool) -> i32
+                l0 : i32
+                l1 : i32
+                l2 : i32
             "#]],
-            |_| [],
+            |_| {
+                [
+                    (
+                        TyDiagnosticKind::ReadBeforeWrite,
+                        346..347,
+                        Some((TyDiagnosticHelpKind::LeftUninitHere, 307..321)),
+                    ),
+                    (
+                        TyDiagnosticKind::ReadBeforeWrite,
+                        374..375,
+                        Some((TyDiagnosticHelpKind::LeftUninitHere, 66..80)),
+                    ),
+                    (
+                        TyDiagnosticKind::ReadBeforeWrite,
+                        378..379,
+                        Some((TyDiagnosticHelpKind::LeftUninitHere, 185..199)),
+                    ),
+                ]
+            },
         )
     }
 
     #[test]
-    fn reinfer_params() {
-        // usually an argument will replace the weak type of a variable.
-        // in this case it doesn't and so more advanced replacing is needed.
+    fn local_ref_returned() {
         check(
             r#"
-                Any :: struct {
-                    ty: type,
-                    data: ^any,
+                get :: () -> ^i32 {
+                    x := 5;
+                    ^x
+                };
+
+                get_indirect :: () -> ^i32 {
+                    x := 5;
+                    a := ^x;
+                    b := a;
+                    return b;
                 };
-                
-                accept_any :: (val: Any) {}
-                
-                foo :: () {
-                    x := 0;
-                    accept_any(x);
-                    x as i16;
-                }
             "#,
             expect![[r#"
-                main::Any : type
-                main::accept_any : (main::Any) -> void
-                main::foo : () -> void
-                3 : type
-                5 : void
-                6 : (main::Any) -> void
-                7 : i16
-                8 : (main::Any) -> void
-                9 : i16
-                10 : void
-                11 : i16
-                13 : i16
-                14 : void
-                15 : () -> void
-                l0 : i16
+                main::get : () -> ^i32
+                main::get_indirect : () -> ^i32
+                2 : i32
+                3 : i32
+                4 : ^i32
+                5 : ^i32
+                6 : () -> ^i32
+                9 : i32
+                10 : i32
+                11 : ^i32
+                12 : ^i32
+                13 : ^i32
+                14 : ^i32
+                15 : () -> ^i32
+                l0 : i32
+                l1 : i32
+                l2 : ^i32
+                l3 : ^i32
             "#]],
-            |_| [],
+            |i| {
+                [
+                    (
+                        TyDiagnosticKind::LocalRefEscapes {
+                            path: vec![],
+                            into: RefEscape::Return,
+                        },
+                        85..87,
+                        Some((TyDiagnosticHelpKind::EscapesHere, 85..87)),
+                    ),
+                    (
+                        TyDiagnosticKind::LocalRefEscapes {
+                            path: vec![i.intern("a"), i.intern("b")],
+                            into: RefEscape::Return,
+                        },
+                        206..208,
+                        Some((TyDiagnosticHelpKind::EscapesHere, 258..267)),
+                    ),
+                ]
+            },
         )
     }
 
     #[test]
-    fn ty_ptr_becomes_ty() {
+    fn local_ref_stored() {
         check(
             r#"
-                foo :: () {
-                    x : type = ^i32;
-                }
+                Holder :: struct {
+                    ptr: ^i32,
+                };
+
+                slot : ^mut ^i32 : extern;
+
+                link :: (holder: ^mut Holder, value: i32) {
+                    holder.ptr = ^value;
+                };
+
+                remember :: () {
+                    y := 1;
+                    slot^ = ^y;
+                };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                1 : type
+                main::Holder : type
+                main::link : (^mut main::Holder, i32) -> void
+                main::remember : () -> void
+                main::slot : ^mut ^i32
                 2 : type
-                3 : void
-                4 : () -> void
-                l0 : type
+                9 : ^mut main::Holder
+                10 : ^i32
+                11 : i32
+                12 : ^i32
+                13 : void
+                14 : (^mut main::Holder, i32) -> void
+                15 : {uint}
+                16 : ^mut ^i32
+                17 : ^i32
+                18 : {uint}
+                19 : ^{uint}
+                20 : void
+                21 : () -> void
+                l0 : {uint}
             "#]],
-            |_| [],
+            |i| {
+                [
+                    (
+                        TyDiagnosticKind::LocalRefEscapes {
+                            path: vec![],
+                            into: RefEscape::Param(i.intern("holder")),
+                        },
+                        224..230,
+                        Some((TyDiagnosticHelpKind::EscapesHere, 211..231)),
+                    ),
+                    (
+                        TyDiagnosticKind::CannotMutate,
+                        333..344,
+                        Some((TyDiagnosticHelpKind::ImmutableGlobal, 103..129)),
+                    ),
+                    (
+                        TyDiagnosticKind::LocalRefEscapes {
+                            path: vec![],
+                            into: RefEscape::Global(hir::Fqn {
+                                file: hir::FileName(i.intern("main.capy")),
+                                name: hir::Name(i.intern("slot")),
+                            }),
+                        },
+                        341..343,
+                        Some((TyDiagnosticHelpKind::EscapesHere, 333..344)),
+                    ),
+                ]
+            },
         )
     }
 
     #[test]
-    fn array_literal_as_type() {
-        // this is just to make sure that the compiler doens't show a diagnostic
-        // like "expected `type` but found `<unknown>`"
+    fn local_ref_escapes_in_defer() {
         check(
             r#"
-                foo :: () {
-                    x : i32.[];
-                }
+                keep :: (out: ^mut ^i32) {
+                    x := 5;
+                    defer {
+                        out^ = ^x;
+                    };
+                };
             "#,
             expect![[r#"
-                main::foo : () -> void
-                1 : [0]i32
-                2 : void
-                3 : () -> void
-                l0 : <unknown>
+                main::keep : (^mut ^i32) -> void
+                3 : i32
+                4 : ^mut ^i32
+                5 : ^i32
+                6 : i32
+                7 : ^i32
+                8 : void
+                9 : void
+                10 : (^mut ^i32) -> void
+                l0 : i32
             "#]],
-            |_| {
+            |i| {
                 [(
-                    TyDiagnosticKind::Mismatch {
-                        expected: Ty::Type.into(),
-                        found: Ty::Array {
-                            anonymous: false,
-                            size: 0,
-                            sub_ty: Ty::IInt(32).into(),
-                        }
-                        .into(),
+                    TyDiagnosticKind::LocalRefEscapes {
+                        path: vec![],
+                        into: RefEscape::Param(i.intern("out")),
                     },
-                    53..59,
-                    None,
+                    131..133,
+                    Some((TyDiagnosticHelpKind::EscapesHere, 124..134)),
                 )]
             },
         )
     }
 
     #[test]
-    fn default_value_i32() {
+    fn closure_escapes() {
         check(
             r#"
-                defaults :: () {
-                    x : i32;
-                }
+                Counter :: () [] -> i32;
+
+                make :: () -> Counter {
+                    x := 5;
+                    f :: () -> i32 { x };
+                    f
+                };
+
+                keep :: (out: ^mut Counter) {
+                    n := 1;
+                    out^ = () [n] -> i32 { n };
+                };
+
+                Getter :: () -> i32;
+
+                plain :: () -> Getter {
+                    f :: () -> i32 { 42 };
+                    f
+                };
             "#,
             expect![[r#"
-                main::defaults : () -> void
-                1 : void
-                2 : () -> void
+                main::Counter : type
+                main::Getter : type
+                main::keep : (^mut () [] -> i32) -> void
+                main::make : () -> () [] -> i32
+                main::plain : () -> () -> i32
+                2 : type
+                4 : i32
+                6 : i32
+                7 : i32
+                8 : i32
+                9 : () [] -> i32
+                10 : () [] -> i32
+                11 : () [] -> i32
+                12 : () -> () [] -> i32
+                15 : i32
+                16 : ^mut () [] -> i32
+                17 : () [] -> i32
+                19 : i32
+                20 : i32
+                21 : i32
+                22 : () [] -> i32
+                23 : void
+                24 : (^mut () [] -> i32) -> void
+                27 : type
+                30 : i32
+                31 : i32
+                32 : () -> i32
+                33 : () -> i32
+                34 : () -> i32
+                35 : () -> () -> i32
                 l0 : i32
+                l1 : () [] -> i32
+                l2 : i32
+                l3 : () -> i32
             "#]],
-            |_| [],
+            |i| {
+                [
+                    (
+                        TyDiagnosticKind::ClosureEscapes {
+                            path: vec![],
+                            into: RefEscape::Param(i.intern("out")),
+                        },
+                        296..315,
+                        Some((TyDiagnosticHelpKind::EscapesHere, 289..316)),
+                    ),
+                    (
+                        TyDiagnosticKind::ClosureEscapes {
+                            path: vec![i.intern("f")],
+                            into: RefEscape::Return,
+                        },
+                        136..151,
+                        Some((TyDiagnosticHelpKind::EscapesHere, 173..174)),
+                    ),
+                ]
+            },
         )
     }
 
     #[test]
-    fn default_value_char_array() {
+    fn local_ref_not_escaping() {
         check(
             r#"
-                defaults :: () {
-                    x : [10]char;
-                }
-            "#,
-            expect![[r#"
-                main::defaults : () -> void
-                0 : usize
-                3 : void
-                4 : () -> void
-                l0 : [10]char
-            "#]],
-            |_| [],
-        )
-    }
+                Holder :: struct {
+                    ptr: ^i32,
+                };
 
-    #[test]
-    fn default_value_distinct_distinct_struct_with_valid_types() {
-        check(
-            r#"
-                defaults :: () {
-                    x : distinct distinct struct {
-                        a: [2][4]u8,
-                        b: i16,
-                        c: distinct f32,
-                        d: bool,
-                        e: char,
-                        f: void,
-                    };
-                }
-            "#,
-            expect![[r#"
-                main::defaults : () -> void
-                0 : usize
-                1 : usize
-                14 : void
-                15 : () -> void
-                l0 : distinct'3 distinct'2 struct'1 {a: [2][4]u8, b: i16, c: distinct'0 f32, d: bool, e: char, f: void}
-            "#]],
-            |_| [],
-        )
-    }
+                read :: (ptr: ^i32) -> i32 { ptr^ };
 
-    #[test]
-    fn default_value_i32_ptr() {
-        check(
-            r#"
-                defaults :: () {
-                    x : ^i32;
-                }
+                fine :: (holder: ^mut Holder, out: ^mut ^i32, p: ^i32) -> ^i32 {
+                    x := 5;
+                    local_ptr := ^x;
+                    local_holder := Holder.{ ptr = ^x };
+                    read(^x);
+                    holder.ptr = p;
+                    out^ = ^holder.ptr^;
+                    p
+                };
             "#,
             expect![[r#"
-                main::defaults : () -> void
-                2 : void
-                3 : () -> void
-                l0 : ^i32
+                main::Holder : type
+                main::fine : (^mut main::Holder, ^mut ^i32, ^i32) -> ^i32
+                main::read : (^i32) -> i32
+                2 : type
+                6 : ^i32
+                7 : i32
+                8 : i32
+                9 : (^i32) -> i32
+                19 : i32
+                20 : i32
+                21 : ^i32
+                23 : i32
+                24 : ^i32
+                25 : main::Holder
+                26 : (^i32) -> i32
+                27 : i32
+                28 : ^i32
+                29 : i32
+                30 : ^mut main::Holder
+                31 : ^i32
+                32 : ^i32
+                33 : ^mut ^i32
+                34 : ^i32
+                35 : ^mut main::Holder
+                36 : ^i32
+                37 : ^^i32
+                38 : ^i32
+                39 : ^i32
+                40 : ^i32
+                41 : (^mut main::Holder, ^mut ^i32, ^i32) -> ^i32
+                l0 : i32
+                l1 : ^i32
+                l2 : main::Holder
             "#]],
-            |_| {
-                [(
-                    TyDiagnosticKind::DeclTypeHasNoDefault {
-                        ty: Ty::Pointer {
-                            mutable: false,
-                            sub_ty: Ty::IInt(32).into(),
-                        }
-                        .into(),
-                    },
-                    58..62,
-                    None,
-                )]
-            },
+            |_| [],
         )
     }
 
@@ -8911,6 +12566,8 @@ mod tests {
                                     (hir::Name(i.intern("foo")), Ty::String.into()),
                                     (hir::Name(i.intern("bar")), Ty::UInt(8).into()),
                                 ],
+                                defaults: Vec::new(),
+                                repr: hir::StructRepr::default(),
                             }
                             .into(),
                         }
@@ -9223,6 +12880,8 @@ mod tests {
                                 (hir::Name(i.intern("b")), Ty::String.into()),
                                 (hir::Name(i.intern("c")), Ty::Float(64).into()),
                             ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                         found: Ty::Struct {
@@ -9233,6 +12892,8 @@ mod tests {
                                 (hir::Name(i.intern("a")), Ty::UInt(0).into()),
                                 (hir::Name(i.intern("b")), Ty::String.into()),
                             ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                     },
@@ -9290,6 +12951,8 @@ mod tests {
                                 (hir::Name(i.intern("b")), Ty::String.into()),
                                 (hir::Name(i.intern("c")), Ty::Float(64).into()),
                             ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                         found: Ty::Struct {
@@ -9302,6 +12965,8 @@ mod tests {
                                 (hir::Name(i.intern("c")), Ty::Float(0).into()),
                                 (hir::Name(i.intern("d")), Ty::Bool.into()),
                             ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                     },
@@ -9357,6 +13022,8 @@ mod tests {
                                 (hir::Name(i.intern("b")), Ty::String.into()),
                                 (hir::Name(i.intern("c")), Ty::Float(64).into()),
                             ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                         found: Ty::Struct {
@@ -9368,6 +13035,8 @@ mod tests {
                                 (hir::Name(i.intern("b")), Ty::String.into()),
                                 (hir::Name(i.intern("last")), Ty::Float(0).into()),
                             ],
+                            defaults: Vec::new(),
+                            repr: hir::StructRepr::default(),
                         }
                         .into(),
                     },
@@ -9772,21 +13441,120 @@ mod tests {
                 7 : i32
                 8 : i32
             "#]],
+            |i| {
+                [(
+                    TyDiagnosticKind::NotYetResolved {
+                        fqn: hir::Fqn {
+                            file: hir::FileName(i.intern("main.capy")),
+                            name: hir::Name(i.intern("a")),
+                        },
+                    },
+                    102..103,
+                    None,
+                )]
+            },
+        )
+    }
+
+    #[test]
+    fn inactive_static_if_branch() {
+        check(
+            r#"
+                #if #pointer_width == 0 {
+                    other :: import "other.capy";
+
+                    foo :: () -> i32 {
+                        "this isn't an i32"
+                    }
+                } else {
+                    foo :: () -> i32 {
+                        42
+                    }
+                }
+            "#,
+            expect![[r#"
+                main::foo : () -> i32
+                1 : i32
+                2 : i32
+                3 : () -> i32
+            "#]],
+            |_| [],
+        )
+    }
+
+    #[test]
+    fn static_assert() {
+        check(
+            r#"
+                size_of_header :: () -> u64 {
+                    8 + 8
+                };
+
+                #assert size_of_header() == 16, "the header should be 16 bytes";
+                #assert size_of_header() > 16, "the header should be bigger than 16 bytes";
+                #assert false;
+            "#,
+            expect![[r#"
+                main::size_of_header : () -> u64
+                1 : u64
+                2 : u64
+                3 : u64
+                4 : u64
+                5 : () -> u64
+                6 : () -> u64
+                7 : u64
+                8 : u64
+                9 : bool
+                10 : bool
+                11 : () -> u64
+                12 : u64
+                13 : u64
+                14 : bool
+                15 : bool
+                16 : bool
+                17 : bool
+            "#]],
             |i| {
                 [
                     (
-                        TyDiagnosticKind::NotYetResolved {
-                            fqn: hir::Fqn {
-                                file: hir::FileName(i.intern("main.capy")),
-                                name: hir::Name(i.intern("a")),
-                            },
+                        TyDiagnosticKind::StaticAssertFailed { message: None },
+                        282..296,
+                        None,
+                    ),
+                    (
+                        TyDiagnosticKind::StaticAssertFailed {
+                            message: Some(i.intern("the header should be bigger than 16 bytes")),
                         },
-                        102..103,
+                        190..265,
                         None,
                     ),
-                    (TyDiagnosticKind::GlobalNotConst, 169..176, None),
                 ]
             },
         )
     }
+
+    #[test]
+    fn static_assert_non_bool() {
+        check(
+            r#"
+                #assert 1 + 2, "that isn't a bool";
+            "#,
+            expect![[r#"
+                0 : {uint}
+                1 : {uint}
+                2 : {uint}
+                3 : {uint}
+            "#]],
+            |_| {
+                [(
+                    TyDiagnosticKind::Mismatch {
+                        expected: Ty::Bool.into(),
+                        found: Ty::UInt(0).into(),
+                    },
+                    25..30,
+                    None,
+                )]
+            },
+        )
+    }
 }
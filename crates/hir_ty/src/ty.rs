@@ -1,5 +1,6 @@
 use hir::{PrimitiveTy, UnaryOp};
 use internment::Intern;
+use la_arena::Idx;
 use rustc_hash::FxHashMap;
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
@@ -16,7 +17,10 @@ pub enum Ty {
     /// a bit-width of 0 represents ANY float type
     Float(u8),
     Bool,
+    /// laid out exactly like a `[]u8`
     String,
+    /// a pointer to null-terminated bytes, for talking to C
+    CString,
     Char,
     Array {
         anonymous: bool,
@@ -42,6 +46,14 @@ pub enum Ty {
     Function {
         param_tys: Vec<Intern<Ty>>,
         return_ty: Intern<Ty>,
+        /// C variadic functions (`..`) accept extra arguments after `param_tys`
+        is_variadic: bool,
+    },
+    /// a function pointer alongside a pointer to the locals it captured.
+    /// the function gets the environment pointer after all of its other arguments
+    Closure {
+        param_tys: Vec<Intern<Ty>>,
+        return_ty: Intern<Ty>,
     },
     Struct {
         // if anonymous is set to `true`, `uid` is useless
@@ -49,7 +61,35 @@ pub enum Ty {
         fqn: Option<hir::Fqn>,
         uid: u32,
         members: Vec<(hir::Name, Intern<Ty>)>,
+        /// lines up with `members`, empty if no member has a default value
+        defaults: Vec<Option<DefaultValue>>,
+        /// `field_aligns` lines up with `members`
+        repr: hir::StructRepr,
+    },
+    Union {
+        fqn: Option<hir::Fqn>,
+        uid: u32,
+        members: Vec<(hir::Name, Intern<Ty>)>,
+    },
+    /// a pointer to some data alongside a pointer to the table of functions which
+    /// implement `methods` for that data.
+    /// the `methods` are functions without their first parameter, which gets the data pointer
+    Interface {
+        fqn: Option<hir::Fqn>,
+        uid: u32,
+        methods: Vec<(hir::Name, Intern<Ty>)>,
+    },
+    /// laid out like an anonymous struct with a member for each item
+    Tuple {
+        item_tys: Vec<Intern<Ty>>,
     },
+    /// `?^i32` is just a pointer which might be zero,
+    /// anything else is followed by a `bool` saying whether it's there
+    Optional {
+        sub_ty: Intern<Ty>,
+    },
+    /// the type of `null` before it finds out which optional it is
+    Null,
     Void,
     // only used for blocks that always break.
     // kind of like a "noreturn" type.
@@ -58,6 +98,14 @@ pub enum Ty {
     NoEval,
 }
 
+/// the constant expression given to a struct member or a parameter, e.g. `retries: i32 = 3`.
+/// it gets compiled wherever the default is used
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
+pub struct DefaultValue {
+    pub file: hir::FileName,
+    pub expr: Idx<hir::Expr>,
+}
+
 pub(crate) struct BinaryOutputTy {
     pub(crate) max_ty: Ty,
     pub(crate) final_output_ty: Ty,
@@ -71,6 +119,7 @@ impl Ty {
             PrimitiveTy::Float { bit_width, .. } => Self::Float(bit_width),
             PrimitiveTy::Bool { .. } => Self::Bool,
             PrimitiveTy::String { .. } => Self::String,
+            PrimitiveTy::CString { .. } => Self::CString,
             PrimitiveTy::Char { .. } => Self::Char,
             PrimitiveTy::Type { .. } => Self::Type,
             PrimitiveTy::Any { .. } => Self::Any,
@@ -87,6 +136,7 @@ impl Ty {
             Ty::Float(_) => true,
             Ty::Bool => true,
             Ty::String => false,
+            Ty::CString => false,
             Ty::Char => true,
             Ty::Array { sub_ty, .. } => sub_ty.has_default_value(),
             Ty::Slice { .. } => false,
@@ -96,12 +146,41 @@ impl Ty {
             Ty::Any => false,
             Ty::File(_) => false,
             Ty::Function { .. } => false,
-            Ty::Struct { members, .. } => members.iter().all(|(_, ty)| ty.has_default_value()),
+            Ty::Closure { .. } => false,
+            Ty::Struct {
+                members, defaults, ..
+            } => members.iter().enumerate().all(|(idx, (_, ty))| {
+                matches!(defaults.get(idx), Some(Some(_))) || ty.has_default_value()
+            }),
+            Ty::Union { members, .. } => members.iter().all(|(_, ty)| ty.has_default_value()),
+            Ty::Interface { .. } => false,
+            Ty::Tuple { item_tys } => item_tys.iter().all(|ty| ty.has_default_value()),
+            Ty::Optional { .. } => true,
+            Ty::Null => true,
             Ty::Void => true,
             Ty::NoEval => true,
         }
     }
 
+    /// If self is a struct, this returns its layout directives
+    pub fn struct_repr(&self) -> Option<&hir::StructRepr> {
+        match self {
+            Ty::Struct { repr, .. } => Some(repr),
+            Ty::Distinct { sub_ty, .. } => sub_ty.as_ref().struct_repr(),
+            _ => None,
+        }
+    }
+
+    /// If self is a struct, this returns the default values of its fields.
+    /// This lines up with the fields, but is empty if none of them have defaults
+    pub fn struct_defaults(&self) -> Option<&[Option<DefaultValue>]> {
+        match self {
+            Ty::Struct { defaults, .. } => Some(defaults),
+            Ty::Distinct { sub_ty, .. } => sub_ty.as_ref().struct_defaults(),
+            _ => None,
+        }
+    }
+
     /// If self is a struct, this returns the fields
     pub fn as_struct(&self) -> Option<Vec<(hir::Name, Intern<Ty>)>> {
         match self {
@@ -111,18 +190,67 @@ impl Ty {
         }
     }
 
+    /// If self is a union, this returns the members
+    pub fn as_union(&self) -> Option<Vec<(hir::Name, Intern<Ty>)>> {
+        match self {
+            Ty::Union { members, .. } => Some(members.clone()),
+            Ty::Distinct { sub_ty, .. } => sub_ty.as_union(),
+            _ => None,
+        }
+    }
+
+    /// If self is an interface, this returns the methods
+    pub fn as_interface(&self) -> Option<Vec<(hir::Name, Intern<Ty>)>> {
+        match self {
+            Ty::Interface { methods, .. } => Some(methods.clone()),
+            Ty::Distinct { sub_ty, .. } => sub_ty.as_interface(),
+            _ => None,
+        }
+    }
+
+    /// If self is a tuple, this returns the types of its items
+    pub fn as_tuple(&self) -> Option<Vec<Intern<Ty>>> {
+        match self {
+            Ty::Tuple { item_tys } => Some(item_tys.clone()),
+            Ty::Distinct { sub_ty, .. } => sub_ty.as_tuple(),
+            _ => None,
+        }
+    }
+
+    /// If self is an optional, this returns the type inside of it
+    pub fn as_optional(&self) -> Option<Intern<Ty>> {
+        match self {
+            Ty::Optional { sub_ty } => Some(*sub_ty),
+            Ty::Distinct { sub_ty, .. } => sub_ty.as_optional(),
+            _ => None,
+        }
+    }
+
     /// If self is a function, this returns the parameters and return type
     pub fn as_function(&self) -> Option<(Vec<Intern<Ty>>, Intern<Ty>)> {
         match self {
             Ty::Function {
                 param_tys: params,
                 return_ty,
+                ..
             } => Some((params.clone(), *return_ty)),
             Ty::Distinct { sub_ty, .. } => sub_ty.as_function(),
             _ => None,
         }
     }
 
+    /// If self is a closure, this returns the parameters and return type
+    pub fn as_closure(&self) -> Option<(Vec<Intern<Ty>>, Intern<Ty>)> {
+        match self {
+            Ty::Closure {
+                param_tys,
+                return_ty,
+            } => Some((param_tys.clone(), *return_ty)),
+            Ty::Distinct { sub_ty, .. } => sub_ty.as_closure(),
+            _ => None,
+        }
+    }
+
     /// If self is a pointer, this returns the mutability and sub type
     pub fn as_pointer(&self) -> Option<(bool, Intern<Ty>)> {
         match self {
@@ -142,9 +270,12 @@ impl Ty {
     }
 
     /// If self is a slice, this returns the sub type
+    ///
+    /// `str` counts as a `[]u8`
     pub fn as_slice(&self) -> Option<Intern<Ty>> {
         match self {
             Ty::Slice { sub_ty } => Some(*sub_ty),
+            Ty::String => Some(Ty::UInt(8).into()),
             Ty::Distinct { sub_ty, .. } => sub_ty.as_slice(),
             _ => None,
         }
@@ -161,8 +292,14 @@ impl Ty {
     pub fn is_aggregate(&self) -> bool {
         match self {
             Ty::Struct { .. } => true,
+            Ty::Union { .. } => true,
+            Ty::Tuple { .. } => true,
+            Ty::Closure { .. } => true,
+            Ty::Interface { .. } => true,
+            Ty::Optional { sub_ty } => !sub_ty.has_null_niche(),
             Ty::Array { .. } => true,
             Ty::Slice { .. } => true,
+            Ty::String => true,
             Ty::Distinct { sub_ty, .. } => sub_ty.is_aggregate(),
             _ => false,
         }
@@ -176,9 +313,10 @@ impl Ty {
         }
     }
 
+    /// `str` counts as a slice
     pub fn is_slice(&self) -> bool {
         match self {
-            Ty::Slice { .. } => true,
+            Ty::Slice { .. } | Ty::String => true,
             Ty::Distinct { sub_ty, .. } => sub_ty.is_slice(),
             _ => false,
         }
@@ -200,6 +338,22 @@ impl Ty {
         }
     }
 
+    pub fn is_closure(&self) -> bool {
+        match self {
+            Ty::Closure { .. } => true,
+            Ty::Distinct { sub_ty, .. } => sub_ty.is_closure(),
+            _ => false,
+        }
+    }
+
+    pub fn is_variadic_function(&self) -> bool {
+        match self {
+            Ty::Function { is_variadic, .. } => *is_variadic,
+            Ty::Distinct { sub_ty, .. } => sub_ty.is_variadic_function(),
+            _ => false,
+        }
+    }
+
     pub fn is_struct(&self) -> bool {
         match self {
             Ty::Struct { .. } => true,
@@ -208,6 +362,48 @@ impl Ty {
         }
     }
 
+    pub fn is_tuple(&self) -> bool {
+        match self {
+            Ty::Tuple { .. } => true,
+            Ty::Distinct { sub_ty, .. } => sub_ty.is_tuple(),
+            _ => false,
+        }
+    }
+
+    pub fn is_optional(&self) -> bool {
+        match self {
+            Ty::Optional { .. } => true,
+            Ty::Distinct { sub_ty, .. } => sub_ty.is_optional(),
+            _ => false,
+        }
+    }
+
+    /// pointers can never be zero, so an optional pointer uses zero to mean `null`.
+    /// `cstr`s and functions are pointers too, which matches how C passes nullable pointers
+    pub fn has_null_niche(&self) -> bool {
+        match self {
+            Ty::Pointer { .. } | Ty::CString | Ty::Function { .. } => true,
+            Ty::Distinct { sub_ty, .. } => sub_ty.has_null_niche(),
+            _ => false,
+        }
+    }
+
+    pub fn is_union(&self) -> bool {
+        match self {
+            Ty::Union { .. } => true,
+            Ty::Distinct { sub_ty, .. } => sub_ty.is_union(),
+            _ => false,
+        }
+    }
+
+    pub fn is_interface(&self) -> bool {
+        match self {
+            Ty::Interface { .. } => true,
+            Ty::Distinct { sub_ty, .. } => sub_ty.is_interface(),
+            _ => false,
+        }
+    }
+
     /// Returns `true` if the struct contains only a `^any` and a `type`
     pub fn is_any_struct(&self) -> bool {
         match self {
@@ -233,10 +429,12 @@ impl Ty {
             Ty::Void => true,
             Ty::File(_) => true,
             Ty::NoEval => true,
+            Ty::Null => true,
             Ty::Array { size, sub_ty, .. } => *size == 0 || sub_ty.is_zero_sized(),
-            Ty::Struct { members, .. } => {
+            Ty::Struct { members, .. } | Ty::Union { members, .. } => {
                 members.is_empty() || members.iter().all(|(_, ty)| ty.is_zero_sized())
             }
+            Ty::Tuple { item_tys } => item_tys.iter().all(|ty| ty.is_zero_sized()),
             Ty::Distinct { sub_ty: ty, .. } => ty.is_zero_sized(),
             _ => false,
         }
@@ -273,11 +471,22 @@ impl Ty {
             Ty::Unknown => true,
             Ty::Pointer { sub_ty, .. } => sub_ty.is_unknown(),
             Ty::Array { sub_ty, .. } => sub_ty.is_unknown(),
-            Ty::Struct { members, .. } => members.iter().any(|(_, ty)| ty.is_unknown()),
+            Ty::Optional { sub_ty } => sub_ty.is_unknown(),
+            Ty::Struct { members, .. }
+            | Ty::Union { members, .. }
+            | Ty::Interface {
+                methods: members, ..
+            } => members.iter().any(|(_, ty)| ty.is_unknown()),
+            Ty::Tuple { item_tys } => item_tys.iter().any(|ty| ty.is_unknown()),
             Ty::Distinct { sub_ty, .. } => sub_ty.is_unknown(),
             Ty::Function {
                 param_tys,
                 return_ty,
+                ..
+            }
+            | Ty::Closure {
+                param_tys,
+                return_ty,
             } => param_tys.iter().any(|p| p.is_unknown()) || return_ty.is_unknown(),
             _ => false,
         }
@@ -313,14 +522,46 @@ impl Ty {
                 },
             ) => first_mutable == second_mutable && first_sub_ty.is_equal_to(second_sub_ty),
             (Ty::Distinct { uid: first, .. }, Ty::Distinct { uid: second, .. }) => first == second,
+            (Ty::Optional { sub_ty: first }, Ty::Optional { sub_ty: second }) => {
+                first.is_equal_to(second)
+            }
+            (Ty::Tuple { item_tys: first }, Ty::Tuple { item_tys: second }) => {
+                first.len() == second.len()
+                    && first
+                        .iter()
+                        .zip(second.iter())
+                        .all(|(first, second)| first.is_equal_to(second))
+            }
             (
                 Ty::Function {
                     param_tys: first_params,
                     return_ty: first_return_ty,
+                    is_variadic: first_variadic,
+                    ..
                 },
                 Ty::Function {
                     param_tys: second_params,
                     return_ty: second_return_ty,
+                    is_variadic: second_variadic,
+                    ..
+                },
+            ) => {
+                first_variadic == second_variadic
+                    && first_return_ty.is_equal_to(second_return_ty)
+                    && first_params.len() == second_params.len()
+                    && first_params
+                        .iter()
+                        .zip(second_params.iter())
+                        .all(|(first_param, second_param)| first_param.is_equal_to(second_param))
+            }
+            (
+                Ty::Closure {
+                    param_tys: first_params,
+                    return_ty: first_return_ty,
+                },
+                Ty::Closure {
+                    param_tys: second_params,
+                    return_ty: second_return_ty,
                 },
             ) => {
                 first_return_ty.is_equal_to(second_return_ty)
@@ -361,6 +602,18 @@ impl Ty {
                     ..
                 },
             ) => first_uid == second_uid,
+            (
+                Ty::Union { uid: first_uid, .. },
+                Ty::Union {
+                    uid: second_uid, ..
+                },
+            ) => first_uid == second_uid,
+            (
+                Ty::Interface { uid: first_uid, .. },
+                Ty::Interface {
+                    uid: second_uid, ..
+                },
+            ) => first_uid == second_uid,
             _ => self == other,
         }
     }
@@ -407,6 +660,16 @@ impl Ty {
             (Ty::Distinct { sub_ty: first, .. }, Ty::Distinct { sub_ty: second, .. }) => {
                 first.is_functionally_equivalent_to(second, two_way)
             }
+            (Ty::Optional { sub_ty: first }, Ty::Optional { sub_ty: second }) => {
+                first.is_functionally_equivalent_to(second, two_way)
+            }
+            (Ty::Tuple { item_tys: first }, Ty::Tuple { item_tys: second }) => {
+                first.len() == second.len()
+                    && first
+                        .iter()
+                        .zip(second.iter())
+                        .all(|(first, second)| first.is_functionally_equivalent_to(second, two_way))
+            }
             (
                 Ty::Distinct {
                     sub_ty: distinct_inner,
@@ -427,14 +690,17 @@ impl Ty {
             (
                 Ty::Struct {
                     members: first_members,
+                    repr: first_repr,
                     ..
                 },
                 Ty::Struct {
                     members: second_members,
+                    repr: second_repr,
                     ..
                 },
             ) => {
-                first_members.len() == second_members.len()
+                first_repr == second_repr
+                    && first_members.len() == second_members.len()
                     && first_members.iter().zip(second_members.iter()).all(
                         |((first_name, first_ty), (second_name, second_ty))| {
                             first_name == second_name
@@ -449,17 +715,13 @@ impl Ty {
     pub(crate) fn get_max_int_size(&self) -> Option<u64> {
         match self {
             Ty::IInt(bit_width) => match bit_width {
-                8 => Some(i8::MAX as u64),
-                16 => Some(i16::MAX as u64),
-                32 => Some(i32::MAX as u64),
-                64 | 128 => Some(i64::MAX as u64),
+                1..=64 => Some((1 << (bit_width - 1)) - 1),
+                65..=128 => Some(i64::MAX as u64),
                 _ => None,
             },
             Ty::UInt(bit_width) => match bit_width {
-                8 => Some(u8::MAX as u64),
-                16 => Some(u16::MAX as u64),
-                32 => Some(u32::MAX as u64),
-                64 | 128 => Some(u64::MAX),
+                1..=63 => Some((1 << bit_width) - 1),
+                64..=128 => Some(u64::MAX),
                 _ => None,
             },
             Ty::Distinct { sub_ty: ty, .. } => ty.get_max_int_size(),
@@ -467,6 +729,39 @@ impl Ty {
         }
     }
 
+    /// how far below zero a signed int can go, as a positive number.
+    /// this is one more than `get_max_int_size`
+    pub(crate) fn get_min_int_size(&self) -> Option<u64> {
+        match self {
+            Ty::IInt(bit_width) => match bit_width {
+                1..=64 => Some(1 << (bit_width - 1)),
+                65..=128 => Some(1 << 63),
+                _ => None,
+            },
+            Ty::Distinct { sub_ty: ty, .. } => ty.get_min_int_size(),
+            _ => None,
+        }
+    }
+
+    /// The type a value gets promoted to when it's passed as a C variadic argument.
+    ///
+    /// Like in C, floats become `f64` and anything smaller than an `int` becomes `i32`.
+    /// Untyped integer literals get sized by their value instead, the same way C types them.
+    /// Returns `None` if the value can't be passed through `..` at all (e.g. structs)
+    pub fn var_arg_promotion(&self) -> Option<Ty> {
+        match self {
+            Ty::Unknown | Ty::NotYetResolved => Some(self.clone()),
+            Ty::IInt(0) | Ty::UInt(0) | Ty::Bool | Ty::Char => Some(Ty::IInt(32)),
+            Ty::IInt(bit_width) | Ty::UInt(bit_width) if *bit_width < 32 => Some(Ty::IInt(32)),
+            Ty::IInt(65..=128) | Ty::UInt(65..=128) => None,
+            Ty::IInt(_) | Ty::UInt(_) => Some(self.clone()),
+            Ty::Float(_) => Some(Ty::Float(64)),
+            Ty::CString | Ty::Pointer { .. } | Ty::Function { .. } => Some(self.clone()),
+            Ty::Distinct { sub_ty, .. } => sub_ty.var_arg_promotion(),
+            _ => None,
+        }
+    }
+
     /// automagically converts two types into the type that can represent both.
     ///
     /// this function accepts unknown types.
@@ -555,6 +850,49 @@ impl Ty {
                     None
                 }
             }
+            (Ty::Null, optional @ Ty::Optional { .. })
+            | (optional @ Ty::Optional { .. }, Ty::Null) => Some(optional.clone()),
+            // `if foo { 5 } else { null }` is a `?{uint}`
+            (Ty::Null, other) | (other, Ty::Null) if !matches!(other, Ty::Unknown | Ty::NoEval) => {
+                Some(Ty::Optional {
+                    sub_ty: other.clone().into(),
+                })
+            }
+            (Ty::Optional { sub_ty }, other) | (other, Ty::Optional { sub_ty })
+                if !matches!(other, Ty::Unknown | Ty::NoEval | Ty::Optional { .. }) =>
+            {
+                // a strong optional can't change its layout
+                let max = sub_ty.max(other)?;
+                if max.is_equal_to(sub_ty) || sub_ty.is_weak_replaceable_by(&max) {
+                    Some(Ty::Optional { sub_ty: max.into() })
+                } else {
+                    None
+                }
+            }
+            // `if foo { (5, true) } else { (x, false) }`,
+            // but a strong item can't change its layout
+            (Ty::Tuple { item_tys: first }, Ty::Tuple { item_tys: second })
+                if first.len() == second.len() =>
+            {
+                let item_tys = first
+                    .iter()
+                    .zip(second.iter())
+                    .map(|(first, second)| {
+                        let max = first.max(second)?;
+                        let keeps_layout = |item: &Intern<Ty>| {
+                            max.is_equal_to(item) || item.is_weak_replaceable_by(&max)
+                        };
+
+                        if keeps_layout(first) && keeps_layout(second) {
+                            Some(max.into())
+                        } else {
+                            None
+                        }
+                    })
+                    .collect::<Option<_>>()?;
+
+                Some(Ty::Tuple { item_tys })
+            }
             (Ty::Unknown | Ty::NoEval, other) | (other, Ty::Unknown | Ty::NoEval) => {
                 Some(other.clone())
             }
@@ -682,6 +1020,40 @@ impl Ty {
                     && found_size == expected_size
                     && found_ty.is_functionally_equivalent_to(expected_ty, false)
             }
+            // the tuple has to be laid out the same way
+            (
+                Ty::Tuple {
+                    item_tys: found_tys,
+                },
+                Ty::Tuple {
+                    item_tys: expected_tys,
+                },
+            ) => {
+                found_tys.len() == expected_tys.len()
+                    && found_tys
+                        .iter()
+                        .zip(expected_tys.iter())
+                        .all(|(found_ty, expected_ty)| {
+                            found_ty.is_weak_replaceable_by(expected_ty)
+                                || found_ty.is_functionally_equivalent_to(expected_ty, false)
+                        })
+            }
+            (Ty::Null, Ty::Optional { .. }) => true,
+            // the optional has to be laid out the same way, so only pointers can change type
+            (
+                Ty::Optional { sub_ty: found_ty },
+                Ty::Optional {
+                    sub_ty: expected_ty,
+                },
+            ) => {
+                found_ty.is_functionally_equivalent_to(expected_ty, false)
+                    || found_ty.is_weak_replaceable_by(expected_ty)
+                    || (found_ty.has_null_niche()
+                        && expected_ty.has_null_niche()
+                        && found_ty.can_fit_into(expected_ty))
+            }
+            // anything can be wrapped in an optional
+            (found, Ty::Optional { sub_ty }) => found.can_fit_into(sub_ty),
             (_, expected) if expected.is_any_struct() => true,
             (
                 Ty::Struct {
@@ -761,6 +1133,11 @@ impl Ty {
             }
             (Ty::Distinct { sub_ty: from, .. }, to) => from.can_cast_to(to),
             (from, Ty::Distinct { sub_ty: to, .. }) => from.can_cast_to(to),
+            (Ty::Optional { sub_ty: from }, Ty::Optional { sub_ty: to })
+                if from.has_null_niche() && to.has_null_niche() =>
+            {
+                from.can_cast_to(to)
+            }
             (
                 Ty::Pointer {
                     mutable: found_mutable,
@@ -779,14 +1156,19 @@ impl Ty {
                     || **expected_sub_ty == Ty::Any
                     || found_sub_ty.is_weak_replaceable_by(expected_sub_ty))
             }
-            // string to and from ^any and ^char and ^u8
-            (Ty::String, Ty::Pointer { sub_ty, .. }) | (Ty::Pointer { sub_ty, .. }, Ty::String) => {
+            // cstr to and from ^any and ^char and ^u8
+            (Ty::CString, Ty::Pointer { sub_ty, .. })
+            | (Ty::Pointer { sub_ty, .. }, Ty::CString) => {
                 matches!(sub_ty.as_ref(), Ty::Any | Ty::Char | Ty::UInt(8))
             }
-            // string to and from [_]char and [_]u8
-            (Ty::String, Ty::Array { sub_ty, .. }) | (Ty::Array { sub_ty, .. }, Ty::String) => {
+            // str to and from [_]char and [_]u8 and []char and []u8
+            (Ty::String, Ty::Array { sub_ty, .. } | Ty::Slice { sub_ty })
+            | (Ty::Array { sub_ty, .. } | Ty::Slice { sub_ty }, Ty::String) => {
                 matches!(sub_ty.as_ref(), Ty::Char | Ty::UInt(8))
             }
+            // `as cstr` only works on string literals (which end with a null),
+            // and `as str` counts the bytes before the null
+            (Ty::String, Ty::CString) | (Ty::CString, Ty::String) => true,
             // `[]any` acts like `^any`
             (Ty::Slice { sub_ty: from, .. }, Ty::Slice { sub_ty: to }) => {
                 from == to
@@ -889,6 +1271,9 @@ impl Ty {
     pub(crate) fn might_be_weak(&self) -> bool {
         match self {
             Ty::IInt(0) | Ty::UInt(0) | Ty::Float(0) => true,
+            Ty::Null => true,
+            Ty::Optional { sub_ty } => sub_ty.might_be_weak(),
+            Ty::Tuple { item_tys } => item_tys.iter().any(|ty| ty.might_be_weak()),
             Ty::Array { sub_ty, .. } => sub_ty.might_be_weak(),
             Ty::Pointer { sub_ty, .. } => sub_ty.might_be_weak(),
             _ => false,
@@ -952,6 +1337,31 @@ impl Ty {
                 },
             ) => found_uid == expected_uid,
             (found, Ty::Distinct { sub_ty: ty, .. }) => found.is_weak_replaceable_by(ty),
+            (Ty::Null, Ty::Optional { .. }) => true,
+            (
+                Ty::Optional { sub_ty: found_ty },
+                Ty::Optional {
+                    sub_ty: expected_ty,
+                },
+            ) => found_ty.is_weak_replaceable_by(expected_ty),
+            (
+                Ty::Tuple {
+                    item_tys: found_tys,
+                },
+                Ty::Tuple {
+                    item_tys: expected_tys,
+                },
+            ) => {
+                let mut items = found_tys.iter().zip(expected_tys.iter());
+
+                found_tys.len() == expected_tys.len()
+                    && items.clone().all(|(found_ty, expected_ty)| {
+                        found_ty.is_equal_to(expected_ty)
+                            || found_ty.is_weak_replaceable_by(expected_ty)
+                    })
+                    && items
+                        .any(|(found_ty, expected_ty)| found_ty.is_weak_replaceable_by(expected_ty))
+            }
             _ => false,
         }
     }
@@ -1024,6 +1434,9 @@ pub(crate) trait TypedOp {
     fn can_perform(&self, ty: &Ty) -> bool;
 
     fn default_ty(&self) -> Ty;
+
+    /// the name of the function which overloads this operator
+    fn overload_name(&self) -> Option<&'static str>;
 }
 
 impl TypedOp for hir::BinaryOp {
@@ -1040,7 +1453,23 @@ impl TypedOp for hir::BinaryOp {
                 &[Ty::IInt(0), Ty::Float(0)]
             }
             hir::BinaryOp::Eq | hir::BinaryOp::Ne => {
-                &[Ty::Char, Ty::IInt(0), Ty::Float(0), Ty::Type]
+                // aggregates are compared member by member
+                match found {
+                    Ty::String => return true,
+                    Ty::Array { sub_ty, .. } | Ty::Slice { sub_ty } => {
+                        return self.can_perform(sub_ty)
+                    }
+                    Ty::Distinct { sub_ty, .. } => return self.can_perform(sub_ty),
+                    Ty::Struct { members, .. } => {
+                        return members.iter().all(|(_, ty)| self.can_perform(ty))
+                    }
+                    Ty::Tuple { item_tys } => {
+                        return item_tys.iter().all(|ty| self.can_perform(ty))
+                    }
+                    _ => {}
+                }
+
+                &[Ty::Char, Ty::IInt(0), Ty::Float(0), Ty::Bool, Ty::Type]
             }
             hir::BinaryOp::LAnd | hir::BinaryOp::LOr => &[Ty::Bool],
         };
@@ -1069,6 +1498,29 @@ impl TypedOp for hir::BinaryOp {
             hir::BinaryOp::LAnd | hir::BinaryOp::LOr => Ty::Bool,
         }
     }
+
+    fn overload_name(&self) -> Option<&'static str> {
+        match self {
+            hir::BinaryOp::Add => Some("op_add"),
+            hir::BinaryOp::Sub => Some("op_sub"),
+            hir::BinaryOp::Mul => Some("op_mul"),
+            hir::BinaryOp::Div => Some("op_div"),
+            hir::BinaryOp::Mod => Some("op_mod"),
+            hir::BinaryOp::Lt => Some("op_lt"),
+            hir::BinaryOp::Gt => Some("op_gt"),
+            hir::BinaryOp::Le => Some("op_le"),
+            hir::BinaryOp::Ge => Some("op_ge"),
+            // `a != b` is `!op_eq(a, b)`
+            hir::BinaryOp::Eq | hir::BinaryOp::Ne => Some("op_eq"),
+            hir::BinaryOp::BAnd => Some("op_bit_and"),
+            hir::BinaryOp::BOr => Some("op_bit_or"),
+            hir::BinaryOp::Xor => Some("op_bit_xor"),
+            hir::BinaryOp::LShift => Some("op_shl"),
+            hir::BinaryOp::RShift => Some("op_shr"),
+            // these short-circuit, which a function call can't do
+            hir::BinaryOp::LAnd | hir::BinaryOp::LOr => None,
+        }
+    }
 }
 
 impl TypedOp for hir::UnaryOp {
@@ -1091,4 +1543,13 @@ impl TypedOp for hir::UnaryOp {
             hir::UnaryOp::LNot => Ty::Bool,
         }
     }
+
+    fn overload_name(&self) -> Option<&'static str> {
+        match self {
+            hir::UnaryOp::Pos => Some("op_pos"),
+            hir::UnaryOp::Neg => Some("op_neg"),
+            hir::UnaryOp::BNot => Some("op_bit_not"),
+            hir::UnaryOp::LNot => Some("op_not"),
+        }
+    }
 }
@@ -0,0 +1,300 @@
+use hir::{Expr, LocalDef, ScopeId, Stmt};
+use la_arena::Idx;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::FileInference;
+
+/// finds the places where a local which was declared with `x : T = ---` gets read before anything
+/// could've been written to it.
+///
+/// a local counts as written as soon as any part of it is assigned to, or a `^mut` of it is taken.
+/// a local is only written after an `if` when both branches write to it, and writes inside the
+/// body of a `while` loop don't count after the loop since the body might never run.
+///
+/// returns the expression which reads the local, alongside the local itself.
+/// each local is only reported once
+pub(crate) fn find_uninit_reads(
+    bodies: &hir::Bodies,
+    tys: &FileInference,
+    body: Idx<Expr>,
+) -> Vec<(Idx<Expr>, Idx<LocalDef>)> {
+    let mut checker = UninitChecker {
+        bodies,
+        tys,
+        unwritten: FxHashSet::default(),
+        exits: FxHashMap::default(),
+        reported: FxHashSet::default(),
+        reads: Vec::new(),
+    };
+
+    checker.read(body);
+
+    checker.reads
+}
+
+struct UninitChecker<'a> {
+    bodies: &'a hir::Bodies,
+    tys: &'a FileInference,
+    /// the uninitialized locals which haven't been written to yet
+    unwritten: FxHashSet<Idx<LocalDef>>,
+    /// the locals which are still unwritten at any `break` out of a block or loop
+    exits: FxHashMap<ScopeId, FxHashSet<Idx<LocalDef>>>,
+    reported: FxHashSet<Idx<LocalDef>>,
+    reads: Vec<(Idx<Expr>, Idx<LocalDef>)>,
+}
+
+impl UninitChecker<'_> {
+    /// whether `expr` is a method call or operator which takes its receiver by `^mut`
+    fn auto_refs_mut(&self, expr: Idx<Expr>) -> bool {
+        self.tys
+            .get_method_call(expr)
+            .is_some_and(|call| call.auto_ref == Some(true))
+    }
+
+    fn stmt(&mut self, stmt: Idx<Stmt>) {
+        let bodies = self.bodies;
+
+        match bodies[stmt] {
+            Stmt::Expr(expr) => self.read(expr),
+            Stmt::LocalDef(local_def) => {
+                let local_def_body = &bodies[local_def];
+
+                if let Some(value) = local_def_body.value {
+                    self.read(value);
+                }
+
+                if local_def_body.uninit {
+                    self.unwritten.insert(local_def);
+                }
+            }
+            Stmt::Assign(assign) => {
+                let assign = &bodies[assign];
+
+                self.read(assign.value);
+                self.write(assign.dest);
+            }
+            Stmt::Break { label, value, .. } => {
+                if let Some(value) = value {
+                    self.read(value);
+                }
+
+                if let Some(label) = label {
+                    self.exits
+                        .entry(label)
+                        .or_default()
+                        .extend(self.unwritten.iter().copied());
+                }
+
+                // nothing after a `break` runs
+                self.unwritten.clear();
+            }
+            Stmt::Continue { .. } => self.unwritten.clear(),
+            // deferred expressions run at the end of the block, after everything else
+            Stmt::Defer { .. } => {}
+        }
+    }
+
+    /// `expr` is the destination of an assignment or gets referenced with `^mut`
+    fn write(&mut self, expr: Idx<Expr>) {
+        let bodies = self.bodies;
+
+        match &bodies[expr] {
+            Expr::Local(local_def) => {
+                self.unwritten.remove(local_def);
+            }
+            Expr::Paren(Some(inner)) => self.write(*inner),
+            // writing to `foo[i]` or `foo.bar` writes to `foo`,
+            // unless it's a pointer or slice which only gets read
+            Expr::Index { source, index } => {
+                self.read(*index);
+
+                let is_array = self
+                    .tys
+                    .expr_tys
+                    .get(*source)
+                    .is_some_and(|ty| ty.is_array());
+
+                if is_array {
+                    self.write(*source);
+                } else {
+                    self.read(*source);
+                }
+            }
+            Expr::Member { previous, .. }
+                if self
+                    .tys
+                    .expr_tys
+                    .get(*previous)
+                    .is_some_and(|ty| !ty.is_pointer()) =>
+            {
+                self.write(*previous)
+            }
+            _ => self.read(expr),
+        }
+    }
+
+    fn read(&mut self, expr: Idx<Expr>) {
+        let bodies = self.bodies;
+
+        match &bodies[expr] {
+            Expr::Local(local_def) => {
+                if self.unwritten.contains(local_def) && self.reported.insert(*local_def) {
+                    self.reads.push((expr, *local_def));
+                }
+            }
+            Expr::Ref {
+                mutable: true,
+                expr: inner,
+            } => self.write(*inner),
+            Expr::Member { previous, .. } => {
+                if self.auto_refs_mut(expr) {
+                    self.write(*previous);
+                } else {
+                    self.read(*previous);
+                }
+            }
+            Expr::Binary { lhs, rhs, op } => {
+                if self.auto_refs_mut(expr) {
+                    self.write(*lhs);
+                } else {
+                    self.read(*lhs);
+                }
+
+                if matches!(op, hir::BinaryOp::LAnd | hir::BinaryOp::LOr) {
+                    // the rhs might not run
+                    let before = self.unwritten.clone();
+                    self.read(*rhs);
+                    self.unwritten.extend(before);
+                } else {
+                    self.read(*rhs);
+                }
+            }
+            Expr::Unary { expr: inner, .. } => {
+                if self.auto_refs_mut(expr) {
+                    self.write(*inner);
+                } else {
+                    self.read(*inner);
+                }
+            }
+            Expr::Block { stmts, tail_expr } => {
+                for stmt in stmts {
+                    self.stmt(*stmt);
+                }
+                if let Some(tail_expr) = tail_expr {
+                    self.read(*tail_expr);
+                }
+
+                if let Some(exits) = self
+                    .bodies
+                    .block_to_scope_id(expr)
+                    .and_then(|scope_id| self.exits.remove(&scope_id))
+                {
+                    self.unwritten.extend(exits);
+                }
+            }
+            Expr::If {
+                condition,
+                body,
+                else_branch,
+                ..
+            } => {
+                self.read(*condition);
+
+                let before = self.unwritten.clone();
+                self.read(*body);
+
+                let after_body = std::mem::replace(&mut self.unwritten, before);
+                if let Some(else_branch) = else_branch {
+                    self.read(*else_branch);
+                }
+
+                self.unwritten.extend(after_body);
+            }
+            Expr::While { condition, body } => {
+                // the loop is left when the condition is false, or by a `break`
+                let mut after_loop = FxHashSet::default();
+
+                if let Some(condition) = condition {
+                    self.read(*condition);
+                    after_loop.clone_from(&self.unwritten);
+                }
+
+                self.read(*body);
+
+                if let Some(exits) = self
+                    .bodies
+                    .block_to_scope_id(expr)
+                    .and_then(|scope_id| self.exits.remove(&scope_id))
+                {
+                    after_loop.extend(exits);
+                }
+
+                self.unwritten = after_loop;
+            }
+            Expr::Lambda(lambda) => {
+                for capture in &bodies[*lambda].captures {
+                    if !capture.by_value && capture.mutable {
+                        self.write(capture.value);
+                    } else {
+                        self.read(capture.value);
+                    }
+                }
+            }
+            Expr::Comptime(comptime) => self.read(bodies[*comptime].body),
+            Expr::Cast { expr: inner, .. }
+            | Expr::Ref { expr: inner, .. }
+            | Expr::Deref { pointer: inner }
+            | Expr::Unwrap {
+                optional: inner, ..
+            }
+            | Expr::Paren(Some(inner))
+            | Expr::TupleItem { tuple: inner, .. } => self.read(*inner),
+            Expr::Index { source, index } => {
+                self.read(*source);
+                self.read(*index);
+            }
+            Expr::Slice { source, start, end } => {
+                self.read(*source);
+                for bound in start.iter().chain(end) {
+                    self.read(*bound);
+                }
+            }
+            Expr::ArrayLiteral { items, .. } | Expr::Tuple { items } => {
+                for item in items {
+                    self.read(*item);
+                }
+            }
+            Expr::StructLiteral { members, .. } => {
+                for (_, value) in members {
+                    self.read(*value);
+                }
+            }
+            Expr::Call { callee, args, .. } => {
+                self.read(*callee);
+                for arg in args {
+                    self.read(*arg);
+                }
+            }
+            Expr::Missing
+            | Expr::IntLiteral(_)
+            | Expr::FloatLiteral(_)
+            | Expr::BoolLiteral(_)
+            | Expr::NullLiteral
+            | Expr::StringLiteral(_)
+            | Expr::Embed(_)
+            | Expr::CharLiteral(_)
+            | Expr::Paren(None)
+            | Expr::LocalGlobal(_)
+            | Expr::Param { .. }
+            | Expr::Capture { .. }
+            | Expr::PrimitiveTy(_)
+            | Expr::ArrayDecl { .. }
+            | Expr::Distinct { .. }
+            | Expr::OptionalDecl { .. }
+            | Expr::StructDecl { .. }
+            | Expr::UnionDecl { .. }
+            | Expr::InterfaceDecl { .. }
+            | Expr::Import(_) => {}
+        }
+    }
+}
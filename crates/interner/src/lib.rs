@@ -52,6 +52,7 @@ impl_interner! {
     char => "char",
     r#type => "type",
     any => "any",
+    cstr => "cstr",
 }
 
 impl Interner {
@@ -62,6 +63,11 @@ impl Interner {
     pub fn lookup(&self, key: Key) -> &str {
         self.0.resolve(&key.0)
     }
+
+    /// returns the key of `s` without interning it
+    pub fn get(&self, s: &str) -> Option<Key> {
+        self.0.get(s).map(Key)
+    }
 }
 
 impl Key {
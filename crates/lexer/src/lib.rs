@@ -24,6 +24,7 @@ pub fn lex(text: &str) -> Tokens {
             LexerTokenKind::__InternalChar => lex_char(lexer.slice(), start, handler),
             LexerTokenKind::__InternalString => lex_string(lexer.slice(), start, handler),
             LexerTokenKind::__InternalComment => lex_comment(start, range.len(), handler),
+            LexerTokenKind::TripleHyphen => lex_triple_hyphen(lexer.remainder(), start, handler),
             _ => {
                 let transmuted = unsafe { mem::transmute::<LexerTokenKind, TokenKind>(kind) };
                 // we compare the debug names of the two values to ensure that no transmutation bugs occurred
@@ -115,6 +116,21 @@ fn lex_comment(offset: TextSize, len: usize, mut f: impl FnMut(TokenKind, TextSi
     }
 }
 
+/// `---` is only the uninitialized literal when nothing comes after it (`x : i32 = ---;`),
+/// otherwise it's three negations (`---4`)
+fn lex_triple_hyphen(remainder: &str, offset: TextSize, mut f: impl FnMut(TokenKind, TextSize)) {
+    let rest = remainder.trim_start();
+
+    if rest.is_empty() || rest.starts_with("//") || rest.starts_with([';', ',', ')', ']', '}']) {
+        f(TokenKind::TripleHyphen, offset);
+        return;
+    }
+
+    for idx in 0..3 {
+        f(TokenKind::Hyphen, offset + TextSize::from(idx));
+    }
+}
+
 capy_macros::define_token_enum! {
     LexerTokenKind, full, "../../tokenizer.txt"
 }
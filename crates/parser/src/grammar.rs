@@ -11,23 +11,33 @@ pub(crate) fn source_file(p: &mut Parser<'_>) {
     let m = p.start();
 
     while !p.at_eof() {
-        // just skip over semicolons
-        if p.at(TokenKind::Semicolon) {
-            p.bump();
-            continue;
-        }
-        // if we didn't get a semicolon, we definitely shouldn't be getting something else from the default recovery set
-        if p.at_default_recovery_set() {
-            let _guard = p.expected_syntax_name("definition");
-            p.error_with_recovery_set_no_default(TokenSet::NONE);
-            continue;
-        }
-        stmt::parse_decl(p, true);
+        parse_global(p);
     }
 
     m.complete(p, NodeKind::Root);
 }
 
+/// parses a single item at the global scope,
+/// either a definition or a directive like `#if`
+fn parse_global(p: &mut Parser<'_>) {
+    // just skip over semicolons
+    if p.at(TokenKind::Semicolon) {
+        p.bump();
+        return;
+    }
+    // if we didn't get a semicolon, we definitely shouldn't be getting something else from the default recovery set
+    if p.at_default_recovery_set() {
+        let _guard = p.expected_syntax_name("definition");
+        p.error_with_recovery_set_no_default(TokenSet::NONE);
+        return;
+    }
+    if p.at(TokenKind::Hash) {
+        stmt::parse_global_directive(p);
+        return;
+    }
+    stmt::parse_decl(p, true);
+}
+
 pub(crate) fn repl_line(p: &mut Parser<'_>) {
     let m = p.start();
 
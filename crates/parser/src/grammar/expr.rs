@@ -124,6 +124,10 @@ fn parse_lhs(
         parse_float_literal(p)
     } else if p.at(TokenKind::Bool) {
         parse_bool_literal(p)
+    } else if p.at(TokenKind::Null) {
+        parse_null_literal(p)
+    } else if p.at(TokenKind::TripleHyphen) {
+        parse_uninit_literal(p)
     } else if p.at(TokenKind::DoubleQuote) {
         parse_string_literal(p)
     } else if p.at(TokenKind::SingleQuote) {
@@ -134,11 +138,19 @@ fn parse_lhs(
         parse_ref(p, recovery_set)
     } else if p.at(TokenKind::Distinct) {
         parse_distinct(p, recovery_set)
+    } else if p.at(TokenKind::Question) {
+        parse_optional_decl(p, recovery_set)
     } else if p.at_set(TokenSet::new([TokenKind::Import, TokenKind::Mod])) {
         parse_import_or_mod(p)
     } else if p.at(TokenKind::Comptime) {
         parse_comptime(p)
-    } else if p.at(TokenKind::Struct) {
+    } else if p.at(TokenKind::Hash) {
+        parse_directive(p)
+    } else if p.at_set(TokenSet::new([
+        TokenKind::Struct,
+        TokenKind::Union,
+        TokenKind::Interface,
+    ])) {
         parse_struct_decl(p, recovery_set)
     } else if p.at_set(PREFIX_TOKENS) {
         parse_prefix_expr(p, recovery_set)
@@ -214,43 +226,53 @@ fn parse_post_operators(
                     let indexing_expr = cm.precede(p).complete(p, NodeKind::Source).precede(p);
                     p.bump();
 
-                    let real_index = p.start();
-                    parse_expr(p, "array index");
-                    real_index.complete(p, NodeKind::Index);
+                    // `foo[..hi]` has no start
+                    let is_slice = p.at(TokenKind::DoubleDot) || {
+                        let real_index = p.start();
+                        parse_expr(p, "array index");
+
+                        if p.at(TokenKind::DoubleDot) {
+                            real_index.complete(p, NodeKind::SliceStart);
+                            true
+                        } else {
+                            real_index.complete(p, NodeKind::Index);
+                            false
+                        }
+                    };
+
+                    if is_slice {
+                        p.bump();
+
+                        // `foo[lo..]` has no end
+                        if !p.at(TokenKind::RBrack) {
+                            let end = p.start();
+                            parse_expr(p, "slice end");
+                            end.complete(p, NodeKind::SliceEnd);
+                        }
+                    }
 
                     p.expect_with_no_skip(TokenKind::RBrack);
 
-                    cm = indexing_expr.complete(p, NodeKind::IndexExpr);
+                    cm = indexing_expr.complete(
+                        p,
+                        if is_slice {
+                            NodeKind::SliceExpr
+                        } else {
+                            NodeKind::IndexExpr
+                        },
+                    );
                 }
             }
+            // `while x { ... } (a, b)` is a loop followed by a tuple, not a call
+            Some(TokenKind::LParen)
+                if matches!(cm.kind(), NodeKind::IfExpr | NodeKind::WhileExpr) =>
+            {
+                break
+            }
             Some(TokenKind::LParen) => {
                 let call = cm.precede(p);
 
-                let arg_list_m = p.start();
-
-                p.bump();
-
-                // collect arguments
-                loop {
-                    if p.at(TokenKind::RParen) {
-                        break;
-                    }
-                    if let Some(arg_m) = expr::parse_expr(p, "argument") {
-                        arg_m.precede(p).complete(p, NodeKind::Arg);
-                    }
-
-                    if p.at_eof() || p.at_default_recovery_set() {
-                        break;
-                    }
-
-                    if !p.at(TokenKind::RParen) {
-                        p.expect_with_no_skip(TokenKind::Comma);
-                    }
-                }
-
-                p.expect(TokenKind::RParen);
-
-                arg_list_m.complete(p, NodeKind::ArgList);
+                parse_arg_list(p);
 
                 cm = call.complete(p, NodeKind::Call);
             }
@@ -274,6 +296,11 @@ fn parse_post_operators(
                 } else if p.at_ahead(1, TokenSet::new([TokenKind::LBrack])) {
                     let ty_cm = cm.precede(p).complete(p, NodeKind::Ty);
                     cm = parse_array_literal(p, Some(ty_cm), recovery_set, None)
+                } else if p.at_ahead(1, TokenSet::new([TokenKind::Question])) {
+                    let unwrap = cm.precede(p);
+                    p.bump();
+                    p.expect_with_no_skip(TokenKind::Question);
+                    cm = unwrap.complete(p, NodeKind::UnwrapExpr);
                 } else {
                     let path = cm.precede(p);
                     p.bump();
@@ -305,6 +332,47 @@ fn parse_post_operators(
     cm
 }
 
+fn parse_arg_list(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::LParen));
+
+    let arg_list_m = p.start();
+
+    p.bump();
+
+    // collect arguments
+    loop {
+        if p.at(TokenKind::RParen) {
+            break;
+        }
+        // `mode = "w"`
+        if p.at(TokenKind::Ident) && p.at_ahead(1, TokenSet::new([TokenKind::Equals])) {
+            let arg_m = p.start();
+            p.bump();
+            p.expect(TokenKind::Equals);
+            parse_expr_with_recovery_set(
+                p,
+                "argument",
+                TokenSet::new([TokenKind::Comma, TokenKind::RParen]),
+            );
+            arg_m.complete(p, NodeKind::Arg);
+        } else if let Some(arg_m) = expr::parse_expr(p, "argument") {
+            arg_m.precede(p).complete(p, NodeKind::Arg);
+        }
+
+        if p.at_eof() || p.at_default_recovery_set() {
+            break;
+        }
+
+        if !p.at(TokenKind::RParen) {
+            p.expect_with_no_skip(TokenKind::Comma);
+        }
+    }
+
+    p.expect(TokenKind::RParen);
+
+    arg_list_m.complete(p, NodeKind::ArgList)
+}
+
 fn parse_int_literal(p: &mut Parser) -> CompletedMarker {
     assert!(p.at(TokenKind::Int) || p.at(TokenKind::Hex) || p.at(TokenKind::Bin));
     let m = p.start();
@@ -326,6 +394,20 @@ fn parse_bool_literal(p: &mut Parser) -> CompletedMarker {
     m.complete(p, NodeKind::BoolLiteral)
 }
 
+fn parse_null_literal(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::Null));
+    let m = p.start();
+    p.bump();
+    m.complete(p, NodeKind::NullLiteral)
+}
+
+fn parse_uninit_literal(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::TripleHyphen));
+    let m = p.start();
+    p.bump();
+    m.complete(p, NodeKind::UninitLiteral)
+}
+
 pub(crate) fn parse_string_literal(p: &mut Parser) -> CompletedMarker {
     assert!(p.at(TokenKind::DoubleQuote));
     let m = p.start();
@@ -376,6 +458,16 @@ fn parse_distinct(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
     m.complete(p, NodeKind::Distinct)
 }
 
+fn parse_optional_decl(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
+    assert!(p.at(TokenKind::Question));
+    let m = p.start();
+    p.bump();
+
+    parse_ty(p, "type", recovery_set);
+
+    m.complete(p, NodeKind::OptionalDecl)
+}
+
 fn parse_import_or_mod(p: &mut Parser) -> CompletedMarker {
     assert!(p.at_set(TokenSet::new([TokenKind::Import, TokenKind::Mod])));
     let m = p.start();
@@ -391,6 +483,24 @@ fn parse_import_or_mod(p: &mut Parser) -> CompletedMarker {
     m.complete(p, NodeKind::ImportExpr)
 }
 
+fn parse_directive(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::Hash));
+    let m = p.start();
+    p.bump();
+
+    let _guard = p.expected_syntax_name("directive name");
+    p.expect_with_no_skip(TokenKind::Ident);
+
+    if p.at(TokenKind::LParen) {
+        parse_arg_list(p);
+    } else if p.at(TokenKind::DoubleQuote) {
+        // `#embed "file.txt"`
+        parse_string_literal(p);
+    }
+
+    m.complete(p, NodeKind::Directive)
+}
+
 fn parse_var_ref(p: &mut Parser) -> CompletedMarker {
     assert!(p.at(TokenKind::Ident));
     let m = p.start();
@@ -426,6 +536,8 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
 
         let mut had_param_tokens = false;
         let mut had_non_param_tokens = false;
+        // both parameter lists and tuples have commas
+        let mut had_commas = false;
 
         // this is a hard distinction to make, the main telltale is if the lambda has a return type or
         // if it has a body
@@ -438,7 +550,7 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
 
             let Some(kind) = p.peek() else {
                 p.token_idx = saved_idx;
-                if !had_param_tokens && had_non_param_tokens {
+                if !had_param_tokens && (had_non_param_tokens || had_commas) {
                     return parse_paren(p, recovery_set);
                 } else {
                     break 'detect_paren;
@@ -446,10 +558,12 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
             };
 
             // only the top level of a parameter list could have these tokens
-            const PARAM_ONLY: TokenSet = TokenSet::new([TokenKind::Colon, TokenKind::Comma]);
+            const PARAM_ONLY: TokenSet = TokenSet::new([TokenKind::Colon, TokenKind::DoubleDot]);
 
             if depth == 1 && kind != TokenKind::RParen {
-                if PARAM_ONLY.contains(kind) {
+                if kind == TokenKind::Comma {
+                    had_commas = true;
+                } else if PARAM_ONLY.contains(kind) {
                     had_param_tokens = true;
                 } else {
                     had_non_param_tokens = true;
@@ -467,8 +581,15 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
             }
         }
 
-        // the top level of the parentheses contained no idents, colons, or commas
-        if !had_param_tokens && had_non_param_tokens {
+        // `(x, y) -> i8` is still a lambda, even though its parameters are missing their types
+        let missing_param_tys = had_commas
+            && p.peek().is_some_and(|kind| {
+                TokenSet::new([TokenKind::Arrow, TokenKind::Extern, TokenKind::Export])
+                    .contains(kind)
+            });
+
+        // the top level of the parentheses contained no colons or `..`
+        if !had_param_tokens && (had_non_param_tokens || had_commas) && !missing_param_tys {
             p.token_idx = saved_idx;
             return parse_paren(p, recovery_set);
         }
@@ -483,8 +604,14 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
             return parse_paren(p, recovery_set);
         };
 
-        const AFTER_PARAMS: TokenSet =
-            TokenSet::new([TokenKind::Arrow, TokenKind::LBrace, TokenKind::Extern]);
+        // `()` can't be indexed, so a `[` after it has to be a capture list
+        const AFTER_PARAMS: TokenSet = TokenSet::new([
+            TokenKind::Arrow,
+            TokenKind::LBrace,
+            TokenKind::LBrack,
+            TokenKind::Extern,
+            TokenKind::Export,
+        ]);
 
         if !AFTER_PARAMS.contains(kind) {
             p.token_idx = saved_idx;
@@ -505,19 +632,38 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
             break;
         }
 
-        let param_m = p.start();
-        let _guard = p.expected_syntax_name("parameter name");
-        p.expect(TokenKind::Ident);
-
-        p.expect_with_no_skip(TokenKind::Colon);
+        if p.at(TokenKind::DoubleDot) {
+            // whether or not this is the last parameter is checked during lowering
+            let var_args_m = p.start();
+            p.bump();
+            var_args_m.complete(p, NodeKind::VarArgs);
+        } else {
+            let param_m = p.start();
+            let _guard = p.expected_syntax_name("parameter name");
+            p.expect(TokenKind::Ident);
+
+            p.expect_with_no_skip(TokenKind::Colon);
+
+            // the type can be left out if there's a default value, `mode := "r"`
+            if !p.at(TokenKind::Equals) {
+                parse_ty(
+                    p,
+                    "parameter type",
+                    TokenSet::new([TokenKind::Comma, TokenKind::RParen, TokenKind::Equals]),
+                );
+            }
 
-        parse_ty(
-            p,
-            "parameter type",
-            TokenSet::new([TokenKind::Comma, TokenKind::RParen]),
-        );
+            if p.at(TokenKind::Equals) {
+                p.bump();
+                parse_expr_with_recovery_set(
+                    p,
+                    "default value",
+                    TokenSet::new([TokenKind::Comma, TokenKind::RParen]),
+                );
+            }
 
-        param_m.complete(p, NodeKind::Param);
+            param_m.complete(p, NodeKind::Param);
+        }
 
         if p.at_eof() || p.at_default_recovery_set() {
             break;
@@ -534,7 +680,11 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
 
     param_list_m.complete(p, NodeKind::ParamList);
 
-    const BODY: TokenSet = TokenSet::new([TokenKind::LBrace, TokenKind::Extern]);
+    if p.at(TokenKind::LBrack) {
+        parse_capture_list(p);
+    }
+
+    const BODY: TokenSet = TokenSet::new([TokenKind::LBrace, TokenKind::Extern, TokenKind::Export]);
 
     if !p.at_set(BODY) {
         p.expect_with_no_skip(TokenKind::Arrow);
@@ -551,6 +701,11 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
         }
     }
 
+    // whether or not there's actually a body to export is checked during lowering
+    if p.at(TokenKind::Export) {
+        p.bump();
+    }
+
     if p.at(TokenKind::LBrace) {
         parse_block(p, None, recovery_set);
     } else if p.at(TokenKind::Extern) {
@@ -560,6 +715,36 @@ fn parse_lambda(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
     m.complete(p, NodeKind::Lambda)
 }
 
+/// `[x, y]`, the locals which a closure copies instead of referencing
+fn parse_capture_list(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::LBrack));
+
+    const AFTER_CAPTURES: TokenSet = TokenSet::new([TokenKind::Arrow, TokenKind::LBrace]);
+
+    let m = p.start();
+    p.bump();
+
+    loop {
+        if p.at(TokenKind::RBrack) || p.at_set(AFTER_CAPTURES) {
+            break;
+        }
+
+        let _guard = p.expected_syntax_name("captured name");
+        p.expect(TokenKind::Ident);
+
+        if p.at_eof() || p.at_default_recovery_set() || p.at_set(AFTER_CAPTURES) {
+            break;
+        }
+
+        if !p.at(TokenKind::RBrack) {
+            p.expect_with_no_skip(TokenKind::Comma);
+        }
+    }
+    p.expect_with_recovery_set(TokenKind::RBrack, AFTER_CAPTURES);
+
+    m.complete(p, NodeKind::CaptureList)
+}
+
 fn parse_paren(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
     assert!(p.at(TokenKind::LParen));
 
@@ -571,26 +756,69 @@ fn parse_paren(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
         return m.complete(p, NodeKind::ParenExpr);
     }
 
-    parse_expr_with_recovery_set(p, "expression", recovery_set);
+    parse_expr_with_recovery_set(
+        p,
+        "expression",
+        recovery_set.union(TokenSet::new([TokenKind::Comma])),
+    );
+
+    if !p.at(TokenKind::Comma) {
+        p.expect_with_no_skip(TokenKind::RParen);
+        return m.complete(p, NodeKind::ParenExpr);
+    }
+
+    // `(a, b)` is a tuple, and so is `(a,)`
+    while p.at(TokenKind::Comma) {
+        p.bump();
+
+        if p.at(TokenKind::RParen) || p.at_eof() {
+            break;
+        }
+
+        parse_expr_with_recovery_set(
+            p,
+            "tuple item",
+            recovery_set.union(TokenSet::new([TokenKind::Comma])),
+        );
+    }
 
     p.expect_with_no_skip(TokenKind::RParen);
-    m.complete(p, NodeKind::ParenExpr)
+    m.complete(p, NodeKind::TupleExpr)
 }
 
+/// unions and interfaces are parsed the same way as structs, but they don't take layout directives
 fn parse_struct_decl(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
-    assert!(p.at(TokenKind::Struct));
+    assert!(p.at_set(TokenSet::new([
+        TokenKind::Struct,
+        TokenKind::Union,
+        TokenKind::Interface,
+    ])));
+
+    let (kind, body_name) = if p.at(TokenKind::Union) {
+        (NodeKind::UnionDecl, "union body")
+    } else if p.at(TokenKind::Interface) {
+        (NodeKind::InterfaceDecl, "interface body")
+    } else {
+        (NodeKind::StructDecl, "struct body")
+    };
+    let is_struct = kind == NodeKind::StructDecl;
 
     let m = p.start();
 
     p.bump();
 
+    // layout directives like `#packed` or `#align(16)`
+    while is_struct && p.at(TokenKind::Hash) {
+        parse_directive(p);
+    }
+
     if p.at(TokenKind::LBrace) {
         p.bump();
     } else {
-        let _guard = p.expected_syntax_name("struct body");
+        let _guard = p.expected_syntax_name(body_name);
         p.error_with_recovery_set(recovery_set);
 
-        return m.complete(p, NodeKind::StructDecl);
+        return m.complete(p, kind);
     }
 
     loop {
@@ -599,6 +827,12 @@ fn parse_struct_decl(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker
         }
 
         let field_m = p.start();
+
+        // `#align(8)` before a field
+        while is_struct && p.at(TokenKind::Hash) {
+            parse_directive(p);
+        }
+
         let _guard = p.expected_syntax_name("field name");
         p.expect(TokenKind::Ident);
 
@@ -607,9 +841,23 @@ fn parse_struct_decl(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker
         parse_ty(
             p,
             "field type",
-            recovery_set.union(TokenSet::new([TokenKind::Comma, TokenKind::RBrace])),
+            recovery_set.union(TokenSet::new([
+                TokenKind::Comma,
+                TokenKind::RBrace,
+                TokenKind::Equals,
+            ])),
         );
 
+        // `retries: i32 = 3`
+        if is_struct && p.at(TokenKind::Equals) {
+            p.bump();
+            parse_expr_with_recovery_set(
+                p,
+                "default value",
+                recovery_set.union(TokenSet::new([TokenKind::Comma, TokenKind::RBrace])),
+            );
+        }
+
         field_m.complete(p, NodeKind::MemberDecl);
 
         if p.at_eof() || p.at_default_recovery_set() {
@@ -622,7 +870,7 @@ fn parse_struct_decl(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker
     }
     p.expect(TokenKind::RBrace);
 
-    m.complete(p, NodeKind::StructDecl)
+    m.complete(p, kind)
 }
 
 fn parse_struct_literal(
@@ -795,11 +1043,30 @@ fn parse_if(p: &mut Parser, recovery_set: TokenSet) -> CompletedMarker {
     let m = p.start();
     p.bump();
 
-    parse_expr_with_recovery_set(
-        p,
-        "condition",
-        recovery_set.union(TokenSet::new([TokenKind::LBrace])),
-    );
+    // `if x := foo { ... }` only runs the body when the optional `foo` isn't null
+    if p.at(TokenKind::Ident)
+        && p.at_ahead(1, TokenSet::new([TokenKind::Colon]))
+        && p.at_ahead(2, TokenSet::new([TokenKind::Equals]))
+    {
+        let binding = p.start();
+        p.bump();
+        p.expect_with_no_skip(TokenKind::Colon);
+        p.expect_with_no_skip(TokenKind::Equals);
+
+        parse_expr_with_recovery_set(
+            p,
+            "value",
+            recovery_set.union(TokenSet::new([TokenKind::LBrace])),
+        );
+
+        binding.complete(p, NodeKind::VarDef);
+    } else {
+        parse_expr_with_recovery_set(
+            p,
+            "condition",
+            recovery_set.union(TokenSet::new([TokenKind::LBrace])),
+        );
+    }
 
     if p.at(TokenKind::LBrace) {
         parse_block(p, None, recovery_set);
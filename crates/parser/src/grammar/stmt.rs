@@ -82,6 +82,14 @@ pub(crate) fn parse_stmt(p: &mut Parser, repl: bool) -> Option<CompletedMarker>
         return Some(res);
     }
 
+    if p.at(TokenKind::Ident) && p.at_ahead(1, TokenSet::new([TokenKind::Comma])) {
+        let res = parse_destructure(p);
+        while p.at(TokenKind::Semicolon) {
+            p.bump();
+        }
+        return Some(res);
+    }
+
     // now we know that it's just an expression
     let expr_cm = expr::parse_expr(p, "statement")?;
 
@@ -171,3 +179,123 @@ pub(crate) fn parse_decl(p: &mut Parser, top_level: bool) -> CompletedMarker {
 
     m.complete(p, def_kind)
 }
+
+/// `x, y := foo`, each name is its own `VarDef` without a value
+fn parse_destructure(p: &mut Parser) -> CompletedMarker {
+    let m = p.start();
+
+    loop {
+        let name = p.start();
+        let _guard = p.expected_syntax_name("name");
+        p.expect_with_no_skip(TokenKind::Ident);
+        name.complete(p, NodeKind::VarDef);
+
+        if !p.at(TokenKind::Comma) {
+            break;
+        }
+        p.bump();
+    }
+
+    p.expect_with_no_skip(TokenKind::Colon);
+    p.expect_with_no_skip(TokenKind::Equals);
+
+    expr::parse_expr(p, "value");
+
+    p.expect_with_no_skip(TokenKind::Semicolon);
+
+    m.complete(p, NodeKind::Destructure)
+}
+
+/// directives that can only appear at the global scope, e.g. `#if`
+pub(crate) fn parse_global_directive(p: &mut Parser) {
+    assert!(p.at(TokenKind::Hash));
+
+    if p.at_ahead(1, TokenSet::new([TokenKind::If])) {
+        parse_static_if(p);
+    } else if p.at_ahead(1, TokenSet::new([TokenKind::Ident])) {
+        parse_directive_decl(p);
+    } else {
+        let m = p.start();
+        p.bump();
+
+        let _guard = p.expected_syntax_name("`if` or directive name");
+        p.error_with_recovery_set(TokenSet::NONE);
+
+        m.complete(p, NodeKind::Error);
+    }
+}
+
+/// directives like `#assert size_of(Foo) == 8, "message";`
+///
+/// the arguments aren't surrounded by parentheses, and which directives
+/// exist is decided after parsing
+fn parse_directive_decl(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::Hash));
+
+    let m = p.start();
+    p.bump();
+    p.bump();
+
+    loop {
+        expr::parse_expr(p, "directive argument");
+
+        if !p.at(TokenKind::Comma) {
+            break;
+        }
+        p.bump();
+    }
+
+    p.expect_with_no_skip(TokenKind::Semicolon);
+
+    m.complete(p, NodeKind::DirectiveDecl)
+}
+
+fn parse_static_if(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::Hash));
+
+    let m = p.start();
+    p.bump();
+    p.bump();
+
+    expr::parse_expr_with_recovery_set(p, "condition", TokenSet::new([TokenKind::LBrace]));
+
+    if p.at(TokenKind::LBrace) {
+        parse_decl_block(p);
+    } else {
+        let _guard = p.expected_syntax_name("#if body");
+        p.error_with_recovery_set(TokenSet::NONE);
+    }
+
+    if p.at(TokenKind::Else) {
+        let else_m = p.start();
+        p.bump();
+
+        if p.at(TokenKind::Hash) && p.at_ahead(1, TokenSet::new([TokenKind::If])) {
+            parse_static_if(p);
+        } else if p.at(TokenKind::LBrace) {
+            parse_decl_block(p);
+        } else {
+            let _guard = p.expected_syntax_name("else body");
+            p.error_with_recovery_set(TokenSet::NONE);
+        }
+
+        else_m.complete(p, NodeKind::StaticElse);
+    }
+
+    m.complete(p, NodeKind::StaticIf)
+}
+
+fn parse_decl_block(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::LBrace));
+
+    let m = p.start();
+    p.bump();
+
+    while !p.at(TokenKind::RBrace) && !p.at_eof() {
+        super::parse_global(p);
+    }
+
+    p.expect(TokenKind::RBrace);
+
+    m.complete(p, NodeKind::DeclBlock)
+}
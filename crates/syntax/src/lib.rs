@@ -54,9 +54,13 @@ pub enum NodeKind {
     IndexExpr, // the entire expression of indexing. e.g. `my_array[6]`
     Index,     // the actual index. `6` in `my_array[6]`
     Source,
+    SliceExpr,  // `my_array[1..4]`, either bound can be left out
+    SliceStart, // `1` in `my_array[1..4]`
+    SliceEnd,   // `4` in `my_array[1..4]`
     Distinct,
     ComptimeExpr,
     ParenExpr,
+    TupleExpr, // `(a, b)`
     Block,
     IfExpr,
     ElseBranch,
@@ -67,15 +71,20 @@ pub enum NodeKind {
     IntLiteral,
     FloatLiteral,
     BoolLiteral,
+    NullLiteral,
+    UninitLiteral, // `---`, leaves a local uninitialized
     CharLiteral,
     StringLiteral,
     CastExpr,
     RefExpr,
     DerefExpr,
+    UnwrapExpr,   // `foo.?`
+    OptionalDecl, // `?i32`
     BinaryExpr,
     UnaryExpr,
-    Binding, // `x :: 5`
-    VarDef,  // `x := 5`
+    Binding,     // `x :: 5`
+    VarDef,      // `x := 5`
+    Destructure, // `x, y := foo`
     Assign,
     ExprStmt,
     ReturnStmt, // todo: change these to void expressions
@@ -85,11 +94,20 @@ pub enum NodeKind {
     Lambda,
     ParamList,
     Param,
+    VarArgs,       // `..`
+    CaptureList,   // `[x, y]`, the locals a closure captures by value
     StructDecl,    // `struct { foo: i32 }`
+    UnionDecl,     // `union { foo: i32, bar: f32 }`
+    InterfaceDecl, // `interface { foo: () -> i32 }`
     MemberDecl,    // `foo: i32`
     StructLiteral, // `My_Struct { foo: 123 }`
     MemberLiteral, // `foo: 123`
     ImportExpr,
+    Directive,     // `#os` or `#def(DEBUG)`
+    DirectiveDecl, // `#assert FOO > 2, "message";`
+    StaticIf,      // `#if #os == "linux" { ... }`
+    StaticElse,    // `else { ... }` or `else #if ... { ... }`
+    DeclBlock,     // `{ foo :: 5; }`
     Ty,
     Path,
     Comment,
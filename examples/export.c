@@ -0,0 +1,25 @@
+// links against the object file made from `export.capy`
+#include <stdio.h>
+
+#include "export.h"
+
+int main(void) {
+    // sets up capy's globals, this must be called first
+    capy_init();
+
+    greet("C");
+
+    Particle p = make_particle(7);
+    printf("%.*s weighs %d\n", (int)p.name.len, p.name.ptr, p.mass);
+
+    for (int i = 0; i < 4; i++) {
+        step(&p, 0.5f);
+    }
+    printf("pos = (%.2f, %.2f), vel = (%.2f, %.2f)\n", p.pos.x, p.pos.y, p.vel.x, p.vel.y);
+
+    int32_t nums[] = {1, 2, 3, 4, 5};
+    capy_slice_i32 slice = { .len = 5, .ptr = nums };
+    printf("sum = %lld\n", (long long)sum(slice));
+
+    return 0;
+}